@@ -7,6 +7,8 @@ pub enum DistanceKind {
     Dot,
     Hamming,
     Jaccard,
+    Cos,
+    L1,
 }
 
 #[derive(
@@ -1,4 +1,6 @@
 use crate::distance::*;
+use crate::scalar::ScalarLike;
+use crate::search::Pointer;
 use crate::vector::*;
 use base_macros::Alter;
 use serde::{Deserialize, Serialize};
@@ -54,6 +56,8 @@ pub enum VbaseError {
     InvalidSearchOptions { reason: String },
 }
 
+pub type VbaseMultiError = VbaseError;
+
 #[must_use]
 #[derive(Debug, Clone, Error, Serialize, Deserialize)]
 pub enum ListError {
@@ -93,6 +97,79 @@ pub enum StartError {
     NotExist,
 }
 
+#[must_use]
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
+pub enum OpenError {
+    #[error("Index layout {layout} is newer than the supported layout {supported}.")]
+    NewerLayout { layout: u32, supported: u32 },
+    #[error("Vectors of segment {id} are stored in layout {layout}, which cannot be read.")]
+    UnreadableSegment { id: NonZeroU128, layout: u32 },
+    #[error("The index was interrupted while replaying its log.")]
+    InterruptedReplay,
+}
+
+#[must_use]
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
+pub enum ReplayError {
+    #[error("Index not found.")]
+    NotExist,
+    #[error("Invalid vector.")]
+    InvalidVector,
+    #[error("The index is not at the expected position of the log.")]
+    Conflict,
+}
+
+#[must_use]
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
+pub enum SnapshotError {
+    #[error("Index not found.")]
+    NotExist,
+    #[error("Failed to write the snapshot: {reason}.")]
+    Write { reason: String },
+}
+
+#[must_use]
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
+pub enum RestoreError {
+    #[error("Index not found.")]
+    NotExist,
+    #[error("Failed to read the snapshot: {reason}.")]
+    Read { reason: String },
+    #[error("The snapshot was taken from an index with different options.")]
+    IncompatibleOptions,
+}
+
+#[must_use]
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
+pub enum UpgradeError {
+    #[error("Index not found.")]
+    NotExist,
+    #[error("Failed to convert the index: {reason}.")]
+    Write { reason: String },
+    #[error("The index can not be converted to these options.")]
+    IncompatibleOptions,
+}
+
+#[must_use]
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
+pub enum VerifyError {
+    #[error("Index not found.")]
+    NotExist,
+}
+
+#[must_use]
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
+pub enum AttachError {
+    #[error("Index not found.")]
+    NotExist,
+    #[error("Failed to read the prebuilt index: {reason}.")]
+    Read { reason: String },
+    #[error("The prebuilt index was built with different options.")]
+    IncompatibleOptions,
+    #[error("Label {label} of the prebuilt index is not mapped to a row.")]
+    UnknownLabel { label: i64 },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 #[serde(deny_unknown_fields)]
 #[validate(schema(function = "IndexOptions::validate_self"))]
@@ -107,8 +184,16 @@ impl IndexOptions {
     fn validate_self(&self) -> Result<(), ValidationError> {
         match &self.indexing {
             IndexingOptions::Flat(FlatIndexingOptions { quantization }) => {
+                if quantization.is_some() && self.vector.d == DistanceKind::L1 {
+                    return Err(ValidationError::new(
+                        "quantization is not supported for l1 distance",
+                    ));
+                }
                 if quantization.is_some()
-                    && !matches!(self.vector.v, VectorKind::Vecf32 | VectorKind::Vecf16)
+                    && !matches!(
+                        self.vector.v,
+                        VectorKind::Vecf32 | VectorKind::Vecf16 | VectorKind::Vecbf16
+                    )
                 {
                     return Err(ValidationError::new(
                         "quantization is only supported for dense vectors",
@@ -116,13 +201,31 @@ impl IndexOptions {
                 }
             }
             IndexingOptions::Ivf(IvfIndexingOptions { quantization, .. }) => {
-                if !matches!(self.vector.v, VectorKind::Vecf32 | VectorKind::Vecf16) {
+                if self.vector.d == DistanceKind::L1 {
+                    return Err(ValidationError::new("ivf is not supported for l1 distance"));
+                }
+                if !matches!(
+                    (self.vector.v, self.vector.d),
+                    (
+                        VectorKind::Vecf32
+                            | VectorKind::Vecf16
+                            | VectorKind::Vecbf16
+                            | VectorKind::SVecf32,
+                        _
+                    ) | (
+                        VectorKind::BVector,
+                        DistanceKind::Hamming | DistanceKind::Jaccard
+                    )
+                ) {
                     return Err(ValidationError::new(
-                        "ivf is only supported for dense vectors",
+                        "ivf is only supported for dense vectors, sparse vectors and binary vectors with hamming or jaccard distance",
                     ));
                 }
                 if quantization.is_some()
-                    && !matches!(self.vector.v, VectorKind::Vecf32 | VectorKind::Vecf16)
+                    && !matches!(
+                        self.vector.v,
+                        VectorKind::Vecf32 | VectorKind::Vecf16 | VectorKind::Vecbf16
+                    )
                 {
                     return Err(ValidationError::new(
                         "quantization is only supported for dense vectors",
@@ -130,8 +233,33 @@ impl IndexOptions {
                 }
             }
             IndexingOptions::Hnsw(HnswIndexingOptions { quantization, .. }) => {
+                if quantization.is_some() && self.vector.d == DistanceKind::L1 {
+                    return Err(ValidationError::new(
+                        "quantization is not supported for l1 distance",
+                    ));
+                }
+                if quantization.is_some()
+                    && !matches!(
+                        self.vector.v,
+                        VectorKind::Vecf32 | VectorKind::Vecf16 | VectorKind::Vecbf16
+                    )
+                {
+                    return Err(ValidationError::new(
+                        "quantization is only supported for dense vectors",
+                    ));
+                }
+            }
+            IndexingOptions::Vamana(VamanaIndexingOptions { quantization, .. }) => {
+                if quantization.is_some() && self.vector.d == DistanceKind::L1 {
+                    return Err(ValidationError::new(
+                        "quantization is not supported for l1 distance",
+                    ));
+                }
                 if quantization.is_some()
-                    && !matches!(self.vector.v, VectorKind::Vecf32 | VectorKind::Vecf16)
+                    && !matches!(
+                        self.vector.v,
+                        VectorKind::Vecf32 | VectorKind::Vecf16 | VectorKind::Vecbf16
+                    )
                 {
                     return Err(ValidationError::new(
                         "quantization is only supported for dense vectors",
@@ -144,9 +272,12 @@ impl IndexOptions {
                         "sparse_inverted_index is only supported for sparse vectors",
                     ));
                 }
-                if !matches!(self.vector.d, DistanceKind::Dot) {
+                if !matches!(
+                    self.vector.d,
+                    DistanceKind::L2 | DistanceKind::Dot | DistanceKind::Cos
+                ) {
                     return Err(ValidationError::new(
-                        "sparse_inverted_index is only supported for dot distance",
+                        "sparse_inverted_index is only supported for l2, dot and cos distance",
                     ));
                 }
             }
@@ -177,23 +308,78 @@ pub struct VectorOptions {
     pub v: VectorKind,
     #[serde(rename = "distance")]
     pub d: DistanceKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weights: Option<Vec<f32>>,
 }
 
 impl VectorOptions {
     pub fn validate_self(&self) -> Result<(), ValidationError> {
+        if let Some(weights) = self.weights.as_ref() {
+            if !matches!(
+                self.v,
+                VectorKind::Vecf32 | VectorKind::Vecf16 | VectorKind::Vecbf16
+            ) || self.d != DistanceKind::L2
+            {
+                return Err(ValidationError::new(
+                    "weights are only supported for dense vectors with l2 distance",
+                ));
+            }
+            if weights.len() != self.dims as usize {
+                return Err(ValidationError::new(
+                    "the number of weights does not match dimensions",
+                ));
+            }
+            if !weights.iter().all(|w| w.is_finite() && *w >= 0.0) {
+                return Err(ValidationError::new(
+                    "weights must be finite and non-negative",
+                ));
+            }
+        }
         match (self.v, self.d, self.dims) {
             (VectorKind::Vecf32, DistanceKind::L2, 1..65536) => Ok(()),
             (VectorKind::Vecf32, DistanceKind::Dot, 1..65536) => Ok(()),
+            (VectorKind::Vecf32, DistanceKind::Cos, 1..65536) => Ok(()),
+            (VectorKind::Vecf32, DistanceKind::L1, 1..65536) => Ok(()),
             (VectorKind::Vecf16, DistanceKind::L2, 1..65536) => Ok(()),
             (VectorKind::Vecf16, DistanceKind::Dot, 1..65536) => Ok(()),
+            (VectorKind::Vecf16, DistanceKind::Cos, 1..65536) => Ok(()),
+            (VectorKind::Vecf16, DistanceKind::L1, 1..65536) => Ok(()),
+            (VectorKind::Vecbf16, DistanceKind::L2, 1..65536) => Ok(()),
+            (VectorKind::Vecbf16, DistanceKind::Dot, 1..65536) => Ok(()),
+            (VectorKind::Vecbf16, DistanceKind::Cos, 1..65536) => Ok(()),
             (VectorKind::SVecf32, DistanceKind::L2, 1..1048576) => Ok(()),
             (VectorKind::SVecf32, DistanceKind::Dot, 1..1048576) => Ok(()),
+            (VectorKind::SVecf32, DistanceKind::Cos, 1..1048576) => Ok(()),
             (VectorKind::BVector, DistanceKind::Dot, 1..65536) => Ok(()),
             (VectorKind::BVector, DistanceKind::Hamming, 1..65536) => Ok(()),
             (VectorKind::BVector, DistanceKind::Jaccard, 1..65536) => Ok(()),
+            (VectorKind::Veci8, DistanceKind::L2, 1..65536) => Ok(()),
+            (VectorKind::Veci8, DistanceKind::Dot, 1..65536) => Ok(()),
+            (VectorKind::Veci8, DistanceKind::Cos, 1..65536) => Ok(()),
             _ => Err(ValidationError::new("not valid vector options")),
         }
     }
+    /// Scales dense vectors by the square roots of `weights`, so that l2 distances
+    /// between scaled vectors are weighted l2 distances between the original ones.
+    pub fn weigh(&self, vector: OwnedVector) -> OwnedVector {
+        fn weigh<S: ScalarLike>(mut vector: VectOwned<S>, weights: &[f32]) -> VectOwned<S> {
+            if vector.slice().len() == weights.len() {
+                for (x, w) in vector.slice_mut().iter_mut().zip(weights) {
+                    *x = S::from_f32(x.to_f32() * w.sqrt());
+                }
+            }
+            vector
+        }
+        let Some(weights) = self.weights.as_deref() else {
+            return vector;
+        };
+        match vector {
+            OwnedVector::Vecf32(x) => OwnedVector::Vecf32(weigh(x, weights)),
+            OwnedVector::Vecf16(x) => OwnedVector::Vecf16(weigh(x, weights)),
+            OwnedVector::Vecbf16(x) => OwnedVector::Vecbf16(weigh(x, weights)),
+            vector => vector,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Validate, Alter)]
@@ -282,6 +468,7 @@ pub enum IndexingOptions {
     Flat(FlatIndexingOptions),
     Ivf(IvfIndexingOptions),
     Hnsw(HnswIndexingOptions),
+    Vamana(VamanaIndexingOptions),
     SparseInvertedIndex(SparseInvertedIndexIndexingOptions),
 }
 
@@ -304,6 +491,18 @@ impl IndexingOptions {
         };
         x
     }
+    pub fn unwrap_vamana(self) -> VamanaIndexingOptions {
+        let IndexingOptions::Vamana(x) = self else {
+            unreachable!()
+        };
+        x
+    }
+    pub fn unwrap_sparse_inverted_index(self) -> SparseInvertedIndexIndexingOptions {
+        let IndexingOptions::SparseInvertedIndex(x) = self else {
+            unreachable!()
+        };
+        x
+    }
 }
 
 impl Default for IndexingOptions {
@@ -318,6 +517,7 @@ impl Validate for IndexingOptions {
             Self::Flat(x) => x.validate(),
             Self::Ivf(x) => x.validate(),
             Self::Hnsw(x) => x.validate(),
+            Self::Vamana(x) => x.validate(),
             Self::SparseInvertedIndex(x) => x.validate(),
         }
     }
@@ -325,11 +525,22 @@ impl Validate for IndexingOptions {
 
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 #[serde(deny_unknown_fields)]
-pub struct SparseInvertedIndexIndexingOptions {}
+pub struct SparseInvertedIndexIndexingOptions {
+    #[serde(default = "SparseInvertedIndexIndexingOptions::default_quantized_weights")]
+    pub quantized_weights: bool,
+}
+
+impl SparseInvertedIndexIndexingOptions {
+    fn default_quantized_weights() -> bool {
+        false
+    }
+}
 
 impl Default for SparseInvertedIndexIndexingOptions {
     fn default() -> Self {
-        Self {}
+        Self {
+            quantized_weights: Self::default_quantized_weights(),
+        }
     }
 }
 
@@ -422,6 +633,46 @@ impl Default for HnswIndexingOptions {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct VamanaIndexingOptions {
+    #[serde(default = "VamanaIndexingOptions::default_degree")]
+    #[validate(range(min = 4, max = 256))]
+    pub degree: u32,
+    #[serde(default = "VamanaIndexingOptions::default_build_search_list_size")]
+    #[validate(range(min = 10, max = 2000))]
+    pub build_search_list_size: u32,
+    #[serde(default = "VamanaIndexingOptions::default_alpha")]
+    #[validate(range(min = 1.0, max = 2.0))]
+    pub alpha: f32,
+    #[serde(default)]
+    #[validate(nested)]
+    pub quantization: Option<QuantizationOptions>,
+}
+
+impl VamanaIndexingOptions {
+    fn default_degree() -> u32 {
+        64
+    }
+    fn default_build_search_list_size() -> u32 {
+        100
+    }
+    fn default_alpha() -> f32 {
+        1.2
+    }
+}
+
+impl Default for VamanaIndexingOptions {
+    fn default() -> Self {
+        Self {
+            degree: Self::default_degree(),
+            build_search_list_size: Self::default_build_search_list_size(),
+            alpha: Self::default_alpha(),
+            quantization: Default::default(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "snake_case")]
@@ -478,6 +729,8 @@ pub struct ProductQuantizationOptions {
     pub ratio: u32,
     #[serde(default = "ProductQuantizationOptions::default_bits")]
     pub bits: u32,
+    #[serde(default = "ProductQuantizationOptions::default_opq")]
+    pub opq: bool,
 }
 
 impl ProductQuantizationOptions {
@@ -487,6 +740,9 @@ impl ProductQuantizationOptions {
     fn default_bits() -> u32 {
         8
     }
+    fn default_opq() -> bool {
+        false
+    }
     fn validate_self(&self) -> Result<(), ValidationError> {
         match self.bits {
             1 | 2 | 4 | 8 => Ok(()),
@@ -500,19 +756,30 @@ impl Default for ProductQuantizationOptions {
         Self {
             ratio: Self::default_ratio(),
             bits: Self::default_bits(),
+            opq: Self::default_opq(),
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 #[serde(deny_unknown_fields)]
-pub struct RabitqQuantizationOptions {}
+pub struct RabitqQuantizationOptions {
+    #[serde(default = "RabitqQuantizationOptions::default_bits")]
+    #[validate(range(min = 1, max = 8))]
+    pub bits: u32,
+}
 
-impl RabitqQuantizationOptions {}
+impl RabitqQuantizationOptions {
+    fn default_bits() -> u32 {
+        1
+    }
+}
 
 impl Default for RabitqQuantizationOptions {
     fn default() -> Self {
-        Self {}
+        Self {
+            bits: Self::default_bits(),
+        }
     }
 }
 
@@ -537,6 +804,31 @@ pub struct SearchOptions {
     #[serde(default = "SearchOptions::default_hnsw_ef_search")]
     #[validate(range(min = 1, max = 65535))]
     pub hnsw_ef_search: u32,
+    #[serde(default = "SearchOptions::default_hnsw_filtered_traversal")]
+    pub hnsw_filtered_traversal: bool,
+    #[serde(default = "SearchOptions::default_hnsw_brute_force_selectivity")]
+    #[validate(range(min = 0.0, max = 1.0))]
+    pub hnsw_brute_force_selectivity: f32,
+    #[serde(default = "SearchOptions::default_vamana_search_list_size")]
+    #[validate(range(min = 1, max = 65535))]
+    pub vamana_search_list_size: u32,
+    #[serde(default = "SearchOptions::default_sparse_inverted_index_pruning_factor")]
+    #[validate(range(min = 1.0, max = 16.0))]
+    pub sparse_inverted_index_pruning_factor: f32,
+    #[serde(default = "SearchOptions::default_sparse_inverted_index_rerank_size")]
+    #[validate(range(min = 0, max = 65535))]
+    pub sparse_inverted_index_rerank_size: u32,
+    #[serde(default = "SearchOptions::default_maxsim_token_limit")]
+    #[validate(range(min = 1, max = 65535))]
+    pub maxsim_token_limit: u32,
+    #[serde(default = "SearchOptions::default_maxsim_refine")]
+    #[validate(range(min = 0, max = 65535))]
+    pub maxsim_refine: u32,
+    #[serde(default = "SearchOptions::default_radius")]
+    pub radius: f32,
+    #[serde(default = "SearchOptions::default_limit")]
+    #[validate(range(min = 1))]
+    pub limit: u32,
 }
 
 impl SearchOptions {
@@ -561,6 +853,33 @@ impl SearchOptions {
     pub const fn default_hnsw_ef_search() -> u32 {
         100
     }
+    pub const fn default_hnsw_filtered_traversal() -> bool {
+        true
+    }
+    pub const fn default_hnsw_brute_force_selectivity() -> f32 {
+        0.02
+    }
+    pub const fn default_vamana_search_list_size() -> u32 {
+        100
+    }
+    pub const fn default_sparse_inverted_index_pruning_factor() -> f32 {
+        1.0
+    }
+    pub const fn default_sparse_inverted_index_rerank_size() -> u32 {
+        0
+    }
+    pub const fn default_maxsim_token_limit() -> u32 {
+        100
+    }
+    pub const fn default_maxsim_refine() -> u32 {
+        100
+    }
+    pub const fn default_radius() -> f32 {
+        f32::INFINITY
+    }
+    pub const fn default_limit() -> u32 {
+        u32::MAX
+    }
 }
 
 impl Default for SearchOptions {
@@ -573,6 +892,16 @@ impl Default for SearchOptions {
             rq_fast_scan: Self::default_rq_fast_scan(),
             ivf_nprobe: Self::default_ivf_nprobe(),
             hnsw_ef_search: Self::default_hnsw_ef_search(),
+            hnsw_filtered_traversal: Self::default_hnsw_filtered_traversal(),
+            hnsw_brute_force_selectivity: Self::default_hnsw_brute_force_selectivity(),
+            vamana_search_list_size: Self::default_vamana_search_list_size(),
+            sparse_inverted_index_pruning_factor:
+                Self::default_sparse_inverted_index_pruning_factor(),
+            sparse_inverted_index_rerank_size: Self::default_sparse_inverted_index_rerank_size(),
+            maxsim_token_limit: Self::default_maxsim_token_limit(),
+            maxsim_refine: Self::default_maxsim_refine(),
+            radius: Self::default_radius(),
+            limit: Self::default_limit(),
         }
     }
 }
@@ -582,6 +911,23 @@ pub struct IndexStat {
     pub indexing: bool,
     pub segments: Vec<SegmentStat>,
     pub options: IndexOptions,
+    #[serde(default)]
+    pub position: Option<LogPosition>,
+}
+
+/// Position in the log of writes that is kept in the pages of an index relation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogPosition {
+    /// Identifies the log, so that a rebuilt relation is not mistaken for the old one.
+    pub log: u64,
+    pub block: u32,
+    pub offset: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LogRecord {
+    Insert(Vec<(OwnedVector, Pointer)>),
+    Delete(Vec<Pointer>),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -592,6 +938,15 @@ pub struct SegmentStat {
     pub size: u64,
 }
 
+/// A check done by verifying an index, which passed if `error` is `None`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyCheck {
+    /// The checked file or directory, relative to the index directory.
+    pub location: String,
+    pub kind: String,
+    pub error: Option<String>,
+}
+
 pub trait Alter {
     fn alter(&mut self, key: &[&str], value: &str) -> Result<(), AlterError>;
 }
@@ -1,17 +1,29 @@
 mod bvect_dot;
 mod bvect_hamming;
 mod bvect_jaccard;
+mod svect_cos;
 mod svect_dot;
 mod svect_l2;
+mod veci8_cos;
+mod veci8_dot;
+mod veci8_l2;
+mod vect_cos;
 mod vect_dot;
+mod vect_l1;
 mod vect_l2;
 
 pub use bvect_dot::BVectorDot;
 pub use bvect_hamming::BVectorHamming;
 pub use bvect_jaccard::BVectorJaccard;
+pub use svect_cos::SVectCos;
 pub use svect_dot::SVectDot;
 pub use svect_l2::SVectL2;
+pub use veci8_cos::Veci8Cos;
+pub use veci8_dot::Veci8Dot;
+pub use veci8_l2::Veci8L2;
+pub use vect_cos::VectCos;
 pub use vect_dot::VectDot;
+pub use vect_l1::VectL1;
 pub use vect_l2::VectL2;
 
 use crate::distance::*;
@@ -21,6 +33,11 @@ pub trait Operator: Copy + 'static + Send + Sync {
     type Vector: VectorOwned;
 
     fn distance(lhs: Borrowed<'_, Self>, rhs: Borrowed<'_, Self>) -> Distance;
+
+    /// Maps a vector to the form that is stored in indexes, e.g. normalizing it for cosine distance.
+    fn canonicalize(vector: Self::Vector) -> Self::Vector {
+        vector
+    }
 }
 
 pub type Borrowed<'a, T> = <<T as Operator>::Vector as VectorOwned>::Borrowed<'a>;
@@ -0,0 +1,20 @@
+use crate::distance::*;
+use crate::operator::*;
+use crate::scalar::ScalarLike;
+use crate::vector::*;
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SVectCos<S>(std::convert::Infallible, PhantomData<fn(S) -> S>);
+
+impl<S: ScalarLike> Operator for SVectCos<S> {
+    type Vector = SVectOwned<S>;
+
+    fn distance(lhs: Borrowed<'_, Self>, rhs: Borrowed<'_, Self>) -> Distance {
+        lhs.operator_cos(rhs)
+    }
+
+    fn canonicalize(vector: SVectOwned<S>) -> SVectOwned<S> {
+        vector.as_borrowed().function_normalize()
+    }
+}
@@ -0,0 +1,18 @@
+use crate::distance::*;
+use crate::operator::*;
+use crate::vector::*;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Veci8Cos {}
+
+impl Operator for Veci8Cos {
+    type Vector = Veci8Owned;
+
+    fn distance(lhs: Borrowed<'_, Self>, rhs: Borrowed<'_, Self>) -> Distance {
+        lhs.operator_cos(rhs)
+    }
+
+    fn canonicalize(vector: Veci8Owned) -> Veci8Owned {
+        vector.as_borrowed().function_normalize()
+    }
+}
@@ -0,0 +1,14 @@
+use crate::distance::*;
+use crate::operator::*;
+use crate::vector::*;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Veci8Dot {}
+
+impl Operator for Veci8Dot {
+    type Vector = Veci8Owned;
+
+    fn distance(lhs: Borrowed<'_, Self>, rhs: Borrowed<'_, Self>) -> Distance {
+        lhs.operator_dot(rhs)
+    }
+}
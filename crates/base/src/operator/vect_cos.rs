@@ -0,0 +1,20 @@
+use crate::distance::*;
+use crate::operator::*;
+use crate::scalar::ScalarLike;
+use crate::vector::*;
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub struct VectCos<S>(std::convert::Infallible, PhantomData<fn(S) -> S>);
+
+impl<S: ScalarLike> Operator for VectCos<S> {
+    type Vector = VectOwned<S>;
+
+    fn distance(lhs: VectBorrowed<'_, S>, rhs: VectBorrowed<'_, S>) -> Distance {
+        lhs.operator_cos(rhs)
+    }
+
+    fn canonicalize(vector: VectOwned<S>) -> VectOwned<S> {
+        vector.as_borrowed().function_normalize()
+    }
+}
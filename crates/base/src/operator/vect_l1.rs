@@ -0,0 +1,16 @@
+use crate::distance::*;
+use crate::operator::*;
+use crate::scalar::ScalarLike;
+use crate::vector::*;
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub struct VectL1<S>(std::convert::Infallible, PhantomData<fn(S) -> S>);
+
+impl<S: ScalarLike> Operator for VectL1<S> {
+    type Vector = VectOwned<S>;
+
+    fn distance(lhs: VectBorrowed<'_, S>, rhs: VectBorrowed<'_, S>) -> Distance {
+        Distance::from(S::reduce_sum_of_d1(lhs.slice(), rhs.slice()))
+    }
+}
@@ -25,6 +25,7 @@ unsafe impl Pod for i64 {}
 unsafe impl Pod for i128 {}
 unsafe impl Pod for isize {}
 
+unsafe impl Pod for half::bf16 {}
 unsafe impl Pod for half::f16 {}
 unsafe impl Pod for f32 {}
 unsafe impl Pod for f64 {}
@@ -124,6 +124,18 @@ impl ScalarLike for f16 {
         reduce_sum_of_d2::reduce_sum_of_d2(lhs, rhs)
     }
 
+    // FIXME: add manually-implemented SIMD version
+    #[detect::multiversion(v4, v3, v2, neon, fallback)]
+    fn reduce_sum_of_d1(lhs: &[f16], rhs: &[f16]) -> f32 {
+        assert_eq!(lhs.len(), rhs.len());
+        let n = lhs.len();
+        let mut d1 = 0.0f32;
+        for i in 0..n {
+            d1 += (lhs[i].to_f32() - rhs[i].to_f32()).abs();
+        }
+        d1
+    }
+
     #[inline(always)]
     fn reduce_sum_of_sparse_xy(lidx: &[u32], lval: &[f16], ridx: &[u32], rval: &[f16]) -> f32 {
         reduce_sum_of_sparse_xy::reduce_sum_of_sparse_xy(lidx, lval, ridx, rval)
@@ -97,6 +97,17 @@ impl ScalarLike for f32 {
         reduce_sum_of_d2::reduce_sum_of_d2(lhs, rhs)
     }
 
+    #[detect::multiversion(v4, v3, v2, neon, fallback)]
+    fn reduce_sum_of_d1(lhs: &[f32], rhs: &[f32]) -> f32 {
+        assert_eq!(lhs.len(), rhs.len());
+        let n = lhs.len();
+        let mut d1 = 0.0f32;
+        for i in 0..n {
+            d1 += (lhs[i] - rhs[i]).abs();
+        }
+        d1
+    }
+
     #[inline(always)]
     fn reduce_sum_of_sparse_xy(lidx: &[u32], lval: &[f32], ridx: &[u32], rval: &[f32]) -> f32 {
         reduce_sum_of_sparse_xy::reduce_sum_of_sparse_xy(lidx, lval, ridx, rval)
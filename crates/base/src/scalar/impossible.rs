@@ -83,6 +83,10 @@ impl ScalarLike for Impossible {
         unimplemented!()
     }
 
+    fn reduce_sum_of_d1(_lhs: &[Self], _rhs: &[Self]) -> f32 {
+        unimplemented!()
+    }
+
     fn reduce_sum_of_sparse_xy(
         _lidx: &[u32],
         _lval: &[Self],
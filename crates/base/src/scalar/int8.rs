@@ -0,0 +1,138 @@
+#[inline(always)]
+pub fn reduce_sum_of_xy(lhs: &[i8], rhs: &[i8]) -> i32 {
+    reduce_sum_of_xy::reduce_sum_of_xy(lhs, rhs)
+}
+
+mod reduce_sum_of_xy {
+    #[cfg(target_arch = "x86_64")]
+    #[detect::target_cpu(enable = "v4")]
+    unsafe fn reduce_sum_of_xy_v4(lhs: &[i8], rhs: &[i8]) -> i32 {
+        assert!(lhs.len() == rhs.len());
+        unsafe {
+            use std::arch::x86_64::*;
+            let mut n = lhs.len();
+            let mut a = lhs.as_ptr();
+            let mut b = rhs.as_ptr();
+            let mut xy = _mm512_setzero_si512();
+            while n >= 32 {
+                let x = _mm512_cvtepi8_epi16(_mm256_loadu_si256(a.cast()));
+                let y = _mm512_cvtepi8_epi16(_mm256_loadu_si256(b.cast()));
+                a = a.add(32);
+                b = b.add(32);
+                n -= 32;
+                xy = _mm512_add_epi32(xy, _mm512_madd_epi16(x, y));
+            }
+            if n > 0 {
+                let mask = _bzhi_u32(0xffffffff, n as u32);
+                let x = _mm512_cvtepi8_epi16(_mm256_maskz_loadu_epi8(mask, a.cast()));
+                let y = _mm512_cvtepi8_epi16(_mm256_maskz_loadu_epi8(mask, b.cast()));
+                xy = _mm512_add_epi32(xy, _mm512_madd_epi16(x, y));
+            }
+            _mm512_reduce_add_epi32(xy)
+        }
+    }
+
+    #[cfg(all(target_arch = "x86_64", test))]
+    #[test]
+    fn reduce_sum_of_xy_v4_test() {
+        detect::init();
+        if !detect::v4::detect() {
+            println!("test {} ... skipped (v4)", module_path!());
+            return;
+        }
+        for _ in 0..256 {
+            let n = 4016;
+            let lhs = (0..n).map(|_| rand::random::<i8>()).collect::<Vec<_>>();
+            let rhs = (0..n).map(|_| rand::random::<i8>()).collect::<Vec<_>>();
+            for z in 3984..4016 {
+                let lhs = &lhs[..z];
+                let rhs = &rhs[..z];
+                let specialized = unsafe { reduce_sum_of_xy_v4(lhs, rhs) };
+                let fallback = unsafe { reduce_sum_of_xy_fallback(lhs, rhs) };
+                assert_eq!(specialized, fallback);
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[detect::target_cpu(enable = "v3")]
+    unsafe fn reduce_sum_of_xy_v3(lhs: &[i8], rhs: &[i8]) -> i32 {
+        use crate::scalar::emulate::emulate_mm256_reduce_add_epi32;
+        assert!(lhs.len() == rhs.len());
+        unsafe {
+            use std::arch::x86_64::*;
+            let mut n = lhs.len();
+            let mut a = lhs.as_ptr();
+            let mut b = rhs.as_ptr();
+            let mut xy = _mm256_setzero_si256();
+            while n >= 16 {
+                let x = _mm256_cvtepi8_epi16(_mm_loadu_si128(a.cast()));
+                let y = _mm256_cvtepi8_epi16(_mm_loadu_si128(b.cast()));
+                a = a.add(16);
+                b = b.add(16);
+                n -= 16;
+                xy = _mm256_add_epi32(xy, _mm256_madd_epi16(x, y));
+            }
+            let mut xy = emulate_mm256_reduce_add_epi32(xy);
+            while n > 0 {
+                let x = a.read() as i32;
+                let y = b.read() as i32;
+                a = a.add(1);
+                b = b.add(1);
+                n -= 1;
+                xy += x * y;
+            }
+            xy
+        }
+    }
+
+    #[cfg(all(target_arch = "x86_64", test))]
+    #[test]
+    fn reduce_sum_of_xy_v3_test() {
+        detect::init();
+        if !detect::v3::detect() {
+            println!("test {} ... skipped (v3)", module_path!());
+            return;
+        }
+        for _ in 0..256 {
+            let n = 4016;
+            let lhs = (0..n).map(|_| rand::random::<i8>()).collect::<Vec<_>>();
+            let rhs = (0..n).map(|_| rand::random::<i8>()).collect::<Vec<_>>();
+            for z in 3984..4016 {
+                let lhs = &lhs[..z];
+                let rhs = &rhs[..z];
+                let specialized = unsafe { reduce_sum_of_xy_v3(lhs, rhs) };
+                let fallback = unsafe { reduce_sum_of_xy_fallback(lhs, rhs) };
+                assert_eq!(specialized, fallback);
+            }
+        }
+    }
+
+    #[detect::multiversion(v4 = import, v3 = import, v2, neon, fallback = export)]
+    pub fn reduce_sum_of_xy(lhs: &[i8], rhs: &[i8]) -> i32 {
+        assert_eq!(lhs.len(), rhs.len());
+        let n = lhs.len();
+        let mut xy = 0;
+        for i in 0..n {
+            xy += lhs[i] as i32 * rhs[i] as i32;
+        }
+        xy
+    }
+}
+
+#[inline(always)]
+pub fn reduce_sum_of_x(this: &[i8]) -> i32 {
+    reduce_sum_of_x::reduce_sum_of_x(this)
+}
+
+mod reduce_sum_of_x {
+    #[detect::multiversion(v4, v3, v2, neon, fallback)]
+    pub fn reduce_sum_of_x(this: &[i8]) -> i32 {
+        let n = this.len();
+        let mut x = 0;
+        for i in 0..n {
+            x += this[i] as i32;
+        }
+        x
+    }
+}
@@ -1,8 +1,10 @@
+mod bf16;
 pub mod bit;
 pub mod emulate;
 mod f16;
 mod f32;
 pub mod impossible;
+pub mod int8;
 
 pub trait ScalarLike:
     Copy
@@ -38,6 +40,7 @@ pub trait ScalarLike:
 
     fn reduce_sum_of_xy(lhs: &[Self], rhs: &[Self]) -> f32;
     fn reduce_sum_of_d2(lhs: &[Self], rhs: &[Self]) -> f32;
+    fn reduce_sum_of_d1(lhs: &[Self], rhs: &[Self]) -> f32;
 
     fn reduce_sum_of_sparse_xy(lidx: &[u32], lval: &[Self], ridx: &[u32], rval: &[Self]) -> f32;
     fn reduce_sum_of_sparse_d2(lidx: &[u32], lval: &[Self], ridx: &[u32], rval: &[Self]) -> f32;
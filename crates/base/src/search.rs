@@ -67,6 +67,28 @@ pub struct Element {
     pub payload: AlwaysEqual<Payload>,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AllowList {
+    pointers: Vec<Pointer>,
+}
+
+impl AllowList {
+    pub fn new(mut pointers: Vec<Pointer>) -> Self {
+        pointers.sort_unstable();
+        pointers.dedup();
+        Self { pointers }
+    }
+    pub fn len(&self) -> usize {
+        self.pointers.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.pointers.is_empty()
+    }
+    pub fn check(&self, payload: Payload) -> bool {
+        self.pointers.binary_search(&payload.pointer()).is_ok()
+    }
+}
+
 pub trait Vectors<V: VectorOwned> {
     fn dims(&self) -> u32;
     fn len(&self) -> u32;
@@ -1,13 +1,15 @@
 pub mod bvect;
 pub mod svect;
+pub mod veci8;
 pub mod vect;
 
 pub use bvect::{BVectBorrowed, BVectOwned, BVECTOR_WIDTH};
 pub use svect::{SVectBorrowed, SVectOwned};
+pub use veci8::{Veci8Borrowed, Veci8Owned};
 pub use vect::{VectBorrowed, VectOwned};
 
 use crate::distance::Distance;
-use half::f16;
+use half::{bf16, f16};
 use serde::{Deserialize, Serialize};
 use std::ops::RangeBounds;
 
@@ -16,8 +18,10 @@ use std::ops::RangeBounds;
 pub enum VectorKind {
     Vecf32,
     Vecf16,
+    Vecbf16,
     SVecf32,
     BVector,
+    Veci8,
 }
 
 pub trait VectorOwned: Clone + Serialize + for<'a> Deserialize<'a> + 'static {
@@ -68,8 +72,10 @@ pub trait VectorBorrowed: Copy + PartialEq + PartialOrd {
 pub enum OwnedVector {
     Vecf32(VectOwned<f32>),
     Vecf16(VectOwned<f16>),
+    Vecbf16(VectOwned<bf16>),
     SVecf32(SVectOwned<f32>),
     BVector(BVectOwned),
+    Veci8(Veci8Owned),
 }
 
 impl OwnedVector {
@@ -77,8 +83,10 @@ impl OwnedVector {
         match self {
             OwnedVector::Vecf32(x) => BorrowedVector::Vecf32(x.as_borrowed()),
             OwnedVector::Vecf16(x) => BorrowedVector::Vecf16(x.as_borrowed()),
+            OwnedVector::Vecbf16(x) => BorrowedVector::Vecbf16(x.as_borrowed()),
             OwnedVector::SVecf32(x) => BorrowedVector::SVecf32(x.as_borrowed()),
             OwnedVector::BVector(x) => BorrowedVector::BVector(x.as_borrowed()),
+            OwnedVector::Veci8(x) => BorrowedVector::Veci8(x.as_borrowed()),
         }
     }
 }
@@ -99,8 +107,10 @@ impl PartialOrd for OwnedVector {
 pub enum BorrowedVector<'a> {
     Vecf32(VectBorrowed<'a, f32>),
     Vecf16(VectBorrowed<'a, f16>),
+    Vecbf16(VectBorrowed<'a, bf16>),
     SVecf32(SVectBorrowed<'a, f32>),
     BVector(BVectBorrowed<'a>),
+    Veci8(Veci8Borrowed<'a>),
 }
 
 impl PartialEq for BorrowedVector<'_> {
@@ -109,8 +119,10 @@ impl PartialEq for BorrowedVector<'_> {
         match (self, other) {
             (Vecf32(lhs), Vecf32(rhs)) => lhs == rhs,
             (Vecf16(lhs), Vecf16(rhs)) => lhs == rhs,
+            (Vecbf16(lhs), Vecbf16(rhs)) => lhs == rhs,
             (SVecf32(lhs), SVecf32(rhs)) => lhs == rhs,
             (BVector(lhs), BVector(rhs)) => lhs == rhs,
+            (Veci8(lhs), Veci8(rhs)) => lhs == rhs,
             _ => false,
         }
     }
@@ -122,8 +134,10 @@ impl PartialOrd for BorrowedVector<'_> {
         match (self, other) {
             (Vecf32(lhs), Vecf32(rhs)) => lhs.partial_cmp(rhs),
             (Vecf16(lhs), Vecf16(rhs)) => lhs.partial_cmp(rhs),
+            (Vecbf16(lhs), Vecbf16(rhs)) => lhs.partial_cmp(rhs),
             (SVecf32(lhs), SVecf32(rhs)) => lhs.partial_cmp(rhs),
             (BVector(lhs), BVector(rhs)) => lhs.partial_cmp(rhs),
+            (Veci8(lhs), Veci8(rhs)) => lhs.partial_cmp(rhs),
             _ => None,
         }
     }
@@ -0,0 +1,358 @@
+use super::{VectorBorrowed, VectorOwned};
+use crate::distance::Distance;
+use crate::scalar::{int8, ScalarLike};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::ops::RangeBounds;
+
+// The value of the `i`-th dimension is `data[i] * alpha + offset`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Veci8Owned {
+    data: Vec<i8>,
+    alpha: f32,
+    offset: f32,
+    // sum of `data`
+    sum: f32,
+    // l2 norm of the dequantized vector
+    l2_norm: f32,
+}
+
+impl Veci8Owned {
+    #[inline(always)]
+    pub fn new(data: Vec<i8>, alpha: f32, offset: f32) -> Self {
+        Self::new_checked(data, alpha, offset).expect("invalid data")
+    }
+
+    #[inline(always)]
+    pub fn new_checked(data: Vec<i8>, alpha: f32, offset: f32) -> Option<Self> {
+        if !(1..=65535).contains(&data.len()) {
+            return None;
+        }
+        if !alpha.is_finite() || !offset.is_finite() {
+            return None;
+        }
+        let (sum, l2_norm) = metadata(&data, alpha, offset);
+        Some(unsafe { Self::new_unchecked(data, alpha, offset, sum, l2_norm) })
+    }
+
+    /// # Safety
+    ///
+    /// * `data.len()` must be in `1..=65535`.
+    /// * `sum` and `l2_norm` must be computed from `data`, `alpha` and `offset`.
+    #[inline(always)]
+    pub unsafe fn new_unchecked(
+        data: Vec<i8>,
+        alpha: f32,
+        offset: f32,
+        sum: f32,
+        l2_norm: f32,
+    ) -> Self {
+        Self {
+            data,
+            alpha,
+            offset,
+            sum,
+            l2_norm,
+        }
+    }
+
+    /// Quantizes a real vector, mapping its range onto the full range of `i8`.
+    pub fn from_f32(vector: &[f32]) -> Self {
+        let (min, max) = f32::reduce_min_max_of_x(vector);
+        let alpha = (max - min) / 255.0;
+        let offset = (max + min) / 2.0;
+        let data = if alpha > 0.0 {
+            vector
+                .iter()
+                .map(|&x| ((x - offset) / alpha).round().clamp(-128.0, 127.0) as i8)
+                .collect()
+        } else {
+            vec![0; vector.len()]
+        };
+        Self::new(data, alpha, offset)
+    }
+}
+
+impl VectorOwned for Veci8Owned {
+    type Borrowed<'a> = Veci8Borrowed<'a>;
+
+    #[inline(always)]
+    fn as_borrowed(&self) -> Veci8Borrowed<'_> {
+        Veci8Borrowed {
+            data: &self.data,
+            alpha: self.alpha,
+            offset: self.offset,
+            sum: self.sum,
+            l2_norm: self.l2_norm,
+        }
+    }
+
+    #[inline(always)]
+    fn zero(dims: u32) -> Self {
+        Self::new(vec![0; dims as usize], 0.0, 0.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Veci8Borrowed<'a> {
+    data: &'a [i8],
+    alpha: f32,
+    offset: f32,
+    sum: f32,
+    l2_norm: f32,
+}
+
+impl<'a> Veci8Borrowed<'a> {
+    #[inline(always)]
+    pub fn new(data: &'a [i8], alpha: f32, offset: f32) -> Self {
+        Self::new_checked(data, alpha, offset).expect("invalid data")
+    }
+
+    #[inline(always)]
+    pub fn new_checked(data: &'a [i8], alpha: f32, offset: f32) -> Option<Self> {
+        if !(1..=65535).contains(&data.len()) {
+            return None;
+        }
+        if !alpha.is_finite() || !offset.is_finite() {
+            return None;
+        }
+        let (sum, l2_norm) = metadata(data, alpha, offset);
+        Some(unsafe { Self::new_unchecked(data, alpha, offset, sum, l2_norm) })
+    }
+
+    /// # Safety
+    ///
+    /// * `data.len()` must be in `1..=65535`.
+    /// * `sum` and `l2_norm` must be computed from `data`, `alpha` and `offset`.
+    #[inline(always)]
+    pub unsafe fn new_unchecked(
+        data: &'a [i8],
+        alpha: f32,
+        offset: f32,
+        sum: f32,
+        l2_norm: f32,
+    ) -> Self {
+        Self {
+            data,
+            alpha,
+            offset,
+            sum,
+            l2_norm,
+        }
+    }
+
+    #[inline(always)]
+    pub fn data(&self) -> &'a [i8] {
+        self.data
+    }
+
+    #[inline(always)]
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    #[inline(always)]
+    pub fn offset(&self) -> f32 {
+        self.offset
+    }
+
+    #[inline(always)]
+    pub fn sum(&self) -> f32 {
+        self.sum
+    }
+
+    #[inline(always)]
+    pub fn l2_norm(&self) -> f32 {
+        self.l2_norm
+    }
+
+    pub fn to_f32(&self) -> Vec<f32> {
+        self.data
+            .iter()
+            .map(|&x| x as f32 * self.alpha + self.offset)
+            .collect()
+    }
+
+    #[inline(always)]
+    fn dot(self, rhs: Self) -> f32 {
+        assert_eq!(self.data.len(), rhs.data.len());
+        let n = self.data.len() as f32;
+        let xy = int8::reduce_sum_of_xy(self.data, rhs.data) as f32;
+        self.alpha * rhs.alpha * xy
+            + self.alpha * rhs.offset * self.sum
+            + rhs.alpha * self.offset * rhs.sum
+            + n * self.offset * rhs.offset
+    }
+}
+
+impl<'a> VectorBorrowed for Veci8Borrowed<'a> {
+    type Owned = Veci8Owned;
+
+    #[inline(always)]
+    fn dims(&self) -> u32 {
+        self.data.len() as u32
+    }
+
+    #[inline(always)]
+    fn own(&self) -> Veci8Owned {
+        Veci8Owned {
+            data: self.data.to_vec(),
+            alpha: self.alpha,
+            offset: self.offset,
+            sum: self.sum,
+            l2_norm: self.l2_norm,
+        }
+    }
+
+    #[inline(always)]
+    fn norm(&self) -> f32 {
+        self.l2_norm
+    }
+
+    #[inline(always)]
+    fn operator_dot(self, rhs: Self) -> Distance {
+        Distance::from(-self.dot(rhs))
+    }
+
+    #[inline(always)]
+    fn operator_l2(self, rhs: Self) -> Distance {
+        let x2 = self.l2_norm * self.l2_norm;
+        let y2 = rhs.l2_norm * rhs.l2_norm;
+        Distance::from((x2 + y2 - 2.0 * self.dot(rhs)).max(0.0))
+    }
+
+    #[inline(always)]
+    fn operator_cos(self, rhs: Self) -> Distance {
+        Distance::from(1.0 - self.dot(rhs) / (self.l2_norm * rhs.l2_norm))
+    }
+
+    #[inline(always)]
+    fn operator_hamming(self, _: Self) -> Distance {
+        unimplemented!()
+    }
+
+    #[inline(always)]
+    fn operator_jaccard(self, _: Self) -> Distance {
+        unimplemented!()
+    }
+
+    #[inline(always)]
+    fn function_normalize(&self) -> Veci8Owned {
+        let l = self.l2_norm;
+        if l == 0.0 {
+            return self.own();
+        }
+        Veci8Owned::new(self.data.to_vec(), self.alpha / l, self.offset / l)
+    }
+
+    fn operator_add(&self, rhs: Self) -> Self::Owned {
+        let x = self.to_f32();
+        let y = rhs.to_f32();
+        Veci8Owned::from_f32(&f32::vector_add(&x, &y))
+    }
+
+    fn operator_sub(&self, rhs: Self) -> Self::Owned {
+        let x = self.to_f32();
+        let y = rhs.to_f32();
+        Veci8Owned::from_f32(&f32::vector_sub(&x, &y))
+    }
+
+    fn operator_mul(&self, rhs: Self) -> Self::Owned {
+        let x = self.to_f32();
+        let y = rhs.to_f32();
+        Veci8Owned::from_f32(&f32::vector_mul(&x, &y))
+    }
+
+    fn operator_and(&self, _: Self) -> Self::Owned {
+        unimplemented!()
+    }
+
+    fn operator_or(&self, _: Self) -> Self::Owned {
+        unimplemented!()
+    }
+
+    fn operator_xor(&self, _: Self) -> Self::Owned {
+        unimplemented!()
+    }
+
+    #[inline(always)]
+    fn subvector(&self, bounds: impl RangeBounds<u32>) -> Option<Self::Owned> {
+        let start_bound = bounds.start_bound().map(|x| *x as usize);
+        let end_bound = bounds.end_bound().map(|x| *x as usize);
+        let slice = self.data.get((start_bound, end_bound))?;
+        if slice.is_empty() {
+            return None;
+        }
+        Self::Owned::new_checked(slice.to_vec(), self.alpha, self.offset)
+    }
+}
+
+impl<'a> PartialEq for Veci8Borrowed<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        if self.data.len() != other.data.len() {
+            return false;
+        }
+        let n = self.data.len();
+        for i in 0..n {
+            let l = self.data[i] as f32 * self.alpha + self.offset;
+            let r = other.data[i] as f32 * other.alpha + other.offset;
+            if l != r {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl<'a> PartialOrd for Veci8Borrowed<'a> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.data.len() != other.data.len() {
+            return None;
+        }
+        let n = self.data.len();
+        for i in 0..n {
+            let l = self.data[i] as f32 * self.alpha + self.offset;
+            let r = other.data[i] as f32 * other.alpha + other.offset;
+            match PartialOrd::partial_cmp(&l, &r)? {
+                Ordering::Less => return Some(Ordering::Less),
+                Ordering::Equal => continue,
+                Ordering::Greater => return Some(Ordering::Greater),
+            }
+        }
+        Some(Ordering::Equal)
+    }
+}
+
+fn metadata(data: &[i8], alpha: f32, offset: f32) -> (f32, f32) {
+    let n = data.len() as f32;
+    let sum = int8::reduce_sum_of_x(data) as f32;
+    let x2 = int8::reduce_sum_of_xy(data, data) as f32;
+    let l2 = alpha * alpha * x2 + 2.0 * alpha * offset * sum + n * offset * offset;
+    (sum, l2.max(0.0).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn veci8_distance_test() {
+        let x = (0..100)
+            .map(|_| rand::random::<f32>() - 0.5)
+            .collect::<Vec<_>>();
+        let y = (0..100)
+            .map(|_| rand::random::<f32>() - 0.5)
+            .collect::<Vec<_>>();
+        let a = Veci8Owned::from_f32(&x);
+        let b = Veci8Owned::from_f32(&y);
+        let (a, b) = (a.as_borrowed(), b.as_borrowed());
+        let (p, q) = (a.to_f32(), b.to_f32());
+        let dot = f32::reduce_sum_of_xy(&p, &q);
+        let l2 = f32::reduce_sum_of_d2(&p, &q);
+        assert!((a.operator_dot(b).to_f32() + dot).abs() < 1e-3);
+        assert!((a.operator_l2(b).to_f32() - l2).abs() < 1e-3);
+        for i in 0..100 {
+            assert!((p[i] - x[i]).abs() <= a.alpha() / 2.0 + 1e-6);
+        }
+    }
+}
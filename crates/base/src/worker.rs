@@ -2,6 +2,7 @@ use crate::distance::Distance;
 use crate::index::*;
 use crate::search::*;
 use crate::vector::*;
+use std::path::PathBuf;
 
 pub trait WorkerOperations {
     fn create(
@@ -18,20 +19,59 @@ pub trait WorkerOperations {
         vector: OwnedVector,
         pointer: Pointer,
     ) -> Result<(), InsertError>;
+    fn insert_many(
+        &self,
+        handle: Handle,
+        vectors: Vec<(OwnedVector, Pointer)>,
+    ) -> Result<(), InsertError>;
+    /// Inserts rows found by the scan of a build, which go to the shadow index while reindexing.
+    fn build(
+        &self,
+        handle: Handle,
+        vectors: Vec<(OwnedVector, Pointer)>,
+    ) -> Result<(), InsertError>;
     fn delete(&self, handle: Handle, pointer: Pointer) -> Result<(), DeleteError>;
+    fn replay(
+        &self,
+        handle: Handle,
+        expected: Option<LogPosition>,
+        records: Vec<LogRecord>,
+        next: LogPosition,
+    ) -> Result<(), ReplayError>;
+    fn snapshot(&self, handle: Handle, dest: PathBuf) -> Result<(), SnapshotError>;
+    fn restore(&self, handle: Handle, source: PathBuf) -> Result<(), RestoreError>;
+    fn attach(
+        &self,
+        handle: Handle,
+        source: PathBuf,
+        labels: Vec<(i64, Pointer)>,
+    ) -> Result<(), AttachError>;
+    /// Converts an index stored by an older version to the options it's declared with now.
+    fn upgrade(&self, handle: Handle, options: IndexOptions) -> Result<(), UpgradeError>;
     fn view_vbase(&self, handle: Handle) -> Result<impl ViewVbaseOperations, VbaseError>;
     fn view_list(&self, handle: Handle) -> Result<impl ViewListOperations, ListError>;
     fn stat(&self, handle: Handle) -> Result<IndexStat, StatError>;
+    fn verify(&self, handle: Handle) -> Result<Vec<VerifyCheck>, VerifyError>;
     fn alter(&self, handle: Handle, key: &str, value: &str) -> Result<(), AlterError>;
     fn stop(&self, handle: Handle) -> Result<(), StopError>;
     fn start(&self, handle: Handle) -> Result<(), StartError>;
 }
 
 pub trait ViewVbaseOperations {
+    /// Maps a query to the space that vectors are indexed in, which `vbase` expects.
+    fn preprocess(&self, vector: OwnedVector) -> OwnedVector;
     fn vbase<'a>(
         &'a self,
         vector: &'a OwnedVector,
         opts: &'a SearchOptions,
+        filter: Option<&'a AllowList>,
+    ) -> Result<Box<dyn Iterator<Item = (Distance, Pointer)> + 'a>, VbaseError>;
+    /// Searches documents made of multiple vectors by MaxSim, which expects preprocessed vectors.
+    fn vbase_multi<'a>(
+        &'a self,
+        vectors: &'a [OwnedVector],
+        opts: &'a SearchOptions,
+        filter: Option<&'a AllowList>,
     ) -> Result<Box<dyn Iterator<Item = (Distance, Pointer)> + 'a>, VbaseError>;
 }
 
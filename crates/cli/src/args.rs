@@ -15,8 +15,10 @@ fn vec_type_from_str(s: &str) -> Result<VectorKind, ArgumentParseError> {
     match s.trim() {
         "Vecf32" => Ok(VectorKind::Vecf32),
         "Vecf16" => Ok(VectorKind::Vecf16),
+        "Vecbf16" => Ok(VectorKind::Vecbf16),
         "SVecf32" => Ok(VectorKind::SVecf32),
         "BVector" => Ok(VectorKind::BVector),
+        "Veci8" => Ok(VectorKind::Veci8),
         _ => Err(ArgumentParseError),
     }
 }
@@ -24,7 +26,9 @@ fn vec_type_from_str(s: &str) -> Result<VectorKind, ArgumentParseError> {
 fn distance_from_str(s: &str) -> Result<DistanceKind, ArgumentParseError> {
     match s.trim() {
         "L2" => Ok(DistanceKind::L2),
+        "L1" => Ok(DistanceKind::L1),
         "Dot" => Ok(DistanceKind::Dot),
+        "Cos" => Ok(DistanceKind::Cos),
         "Jaccard" => Ok(DistanceKind::Jaccard),
         "Hamming" => Ok(DistanceKind::Hamming),
         _ => Err(ArgumentParseError),
@@ -38,6 +42,7 @@ pub enum SubCommandEnum {
     Build(BuildArguments),
     Create(CreateArguments),
     Query(QueryArguments),
+    Verify(VerifyArguments),
 }
 
 #[derive(FromArgs, Debug, PartialEq)]
@@ -48,7 +53,7 @@ pub struct CreateArguments {
     #[argh(option)]
     dim: u32,
 
-    /// vector type: [`Vecf32`, `Vecf16`, `SVecf32`, `BVector`, `Veci8`]
+    /// vector type: [`Vecf32`, `Vecf16`, `Vecbf16`, `SVecf32`, `BVector`, `Veci8`]
     #[argh(option, default = "String::from(\"Vecf32\")")]
     vim_type: String,
 
@@ -84,6 +89,7 @@ impl CreateArguments {
                 dims: self.dim,
                 v: vec_type_from_str(&self.vim_type)?,
                 d: distance_from_str(&self.distance)?,
+                weights: None,
             },
             indexing,
         };
@@ -126,6 +132,14 @@ pub struct QueryArguments {
     /// HNSW ef search
     #[argh(option, default = "100")]
     pub ef: u32,
+
+    /// vamana search list size
+    #[argh(option, default = "100")]
+    pub search_list_size: u32,
+
+    /// sparse inverted index pruning factor
+    #[argh(option, default = "1.0")]
+    pub pruning_factor: f32,
 }
 
 impl QueryArguments {
@@ -137,7 +151,16 @@ impl QueryArguments {
             pq_fast_scan: false,
             rq_fast_scan: true,
             hnsw_ef_search: self.ef,
+            hnsw_filtered_traversal: true,
+            hnsw_brute_force_selectivity: 0.02,
             ivf_nprobe: self.probe,
+            vamana_search_list_size: self.search_list_size,
+            sparse_inverted_index_pruning_factor: self.pruning_factor,
+            sparse_inverted_index_rerank_size: 0,
+            maxsim_token_limit: 100,
+            maxsim_refine: 100,
+            radius: f32::INFINITY,
+            limit: self.top_k as u32,
         }
     }
 }
@@ -164,6 +187,11 @@ pub struct BuildArguments {
     pub timeout_seconds: u64,
 }
 
+#[derive(FromArgs, Debug, PartialEq)]
+/// verify the index without opening it
+#[argh(subcommand, name = "verify")]
+pub struct VerifyArguments {}
+
 #[derive(FromArgs, Debug)]
 /// `pgvecto.rs` CLI
 pub struct Arguments {
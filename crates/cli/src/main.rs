@@ -1,7 +1,7 @@
 #![allow(clippy::needless_range_loop)]
 use base::search::Pointer;
 use base::worker::ViewVbaseOperations;
-use log::{debug, info, warn};
+use log::{debug, error, info, warn};
 use service::Instance;
 use std::cmp::min;
 use std::fs;
@@ -46,7 +46,7 @@ fn main() {
 
     match args.cmd {
         SubCommandEnum::Add(add) => {
-            let instance = Instance::open(path);
+            let instance = Instance::open(path).expect("failed to open instance");
             instance.refresh();
             let dim = instance.stat().options.vector.dims as usize;
             let mut count = 0;
@@ -83,7 +83,7 @@ fn main() {
             info!("{count} records have been added to the index");
         }
         SubCommandEnum::Build(build) => {
-            let instance = Instance::open(path);
+            let instance = Instance::open(path).expect("failed to open instance");
             if let Some(num) = build.threads {
                 if let Err(err) = instance.alter("optimizing.optimizing_threads", &num.to_string())
                 {
@@ -118,8 +118,27 @@ fn main() {
             std::mem::forget(instance);
             info!("index has been saved");
         }
+        SubCommandEnum::Verify(_) => {
+            // pointers are labels given by `add`, so any is valid
+            let checks = Instance::verify_dir(&path, &|_| true);
+            let mut failed = 0;
+            for check in checks.iter() {
+                match &check.error {
+                    None => debug!("{} ({}): ok", check.location, check.kind),
+                    Some(e) => {
+                        error!("{} ({}): {e}", check.location, check.kind);
+                        failed += 1;
+                    }
+                }
+            }
+            if failed != 0 {
+                error!("{failed} of {} checks failed", checks.len());
+                std::process::exit(1);
+            }
+            info!("{} checks passed", checks.len());
+        }
         SubCommandEnum::Query(query) => {
-            let instance = Instance::open(path);
+            let instance = Instance::open(path).expect("failed to open instance");
             let query_file =
                 PathBuf::from_str(&query.query).expect("failed to parse the query file path");
             let truth_file =
@@ -133,9 +152,9 @@ fn main() {
             let mut total_time = 0f64;
             for _ in 0..query.epoch {
                 for (i, vec) in queries.iter().enumerate() {
-                    let owned_vec = convert_to_owned_vec(vec);
+                    let owned_vec = view.preprocess(convert_to_owned_vec(vec));
                     let start_time = Instant::now();
-                    match view.vbase(&owned_vec, &search_opt) {
+                    match view.vbase(&owned_vec, &search_opt, None) {
                         Ok(iter) => {
                             let ans = iter
                                 .take(query.top_k)
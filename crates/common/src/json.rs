@@ -16,6 +16,10 @@ impl<T: for<'a> Deserialize<'a>> Json<T> {
         let x = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
         Self(x)
     }
+    pub fn try_open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let x = serde_json::from_slice(&std::fs::read(path)?)?;
+        Ok(Self(x))
+    }
 }
 
 impl<T> AsRef<T> for Json<T> {
@@ -57,6 +57,33 @@ where
             _mmap: mmap,
         }
     }
+    /// Opens the array as `open` does, but returns an error instead of panicking if the file is
+    /// not of the length recorded in it.
+    pub fn try_open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new().read(true).open(path)?;
+        let size = file.metadata()?.len() as usize;
+        let trailer = 4096 + size_of::<Information>();
+        let invalid =
+            |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+        if size < trailer {
+            return Err(invalid(format!("the file is truncated to {size} bytes")));
+        }
+        let info = read_information(&file);
+        let expected = (info.len.checked_mul(size_of::<T>())).and_then(|x| x.checked_add(trailer));
+        if expected != Some(size) {
+            return Err(invalid(format!(
+                "the file is of {size} bytes, which does not hold {} elements",
+                info.len
+            )));
+        }
+        let mmap = unsafe { read_mmap(&file, info.len * size_of::<T>()) };
+        let outp = unsafe { std::slice::from_raw_parts(mmap.as_ptr() as *const T, info.len) };
+        Ok(Self {
+            info,
+            outp,
+            _mmap: mmap,
+        })
+    }
     pub fn len(&self) -> usize {
         self.info.len
     }
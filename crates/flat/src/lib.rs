@@ -1,6 +1,7 @@
 #![allow(clippy::len_without_is_empty)]
 
 use base::always_equal::AlwaysEqual;
+use base::distance::Distance;
 use base::index::*;
 use base::operator::*;
 use base::search::*;
@@ -43,23 +44,32 @@ impl<O: OperatorFlat, Q: Quantizer<O>> Flat<O, Q> {
         &'a self,
         vector: Borrowed<'a, O>,
         opts: &'a SearchOptions,
+        filter: Option<&'a AllowList>,
     ) -> Box<dyn Iterator<Item = Element> + 'a> {
         let mut heap = Q::flat_rerank_start();
         let lut = self
             .quantization
             .flat_rerank_preprocess(self.quantization.project(vector).as_borrowed(), opts);
-        self.quantization
-            .flat_rerank_continue(&lut, 0..self.storage.len(), &mut heap);
+        self.quantization.flat_rerank_continue(
+            &lut,
+            0..self.storage.len(),
+            |u| filter.map_or(true, |f| f.check(self.payload(u))),
+            &mut heap,
+        );
         let mut reranker = self.quantization.flat_rerank_break(
             heap,
             move |u| (O::distance(vector, self.storage.vector(u)), ()),
             opts,
         );
+        let radius = Distance::from_f32(opts.radius);
         Box::new(std::iter::from_fn(move || {
-            reranker.pop().map(|(dis_u, u, ())| Element {
-                distance: dis_u,
-                payload: AlwaysEqual(self.payload(u)),
-            })
+            reranker
+                .pop()
+                .filter(|&(dis_u, ..)| dis_u <= radius)
+                .map(|(dis_u, u, ())| Element {
+                    distance: dis_u,
+                    payload: AlwaysEqual(self.payload(u)),
+                })
         }))
     }
 
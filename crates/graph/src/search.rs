@@ -131,6 +131,7 @@ pub fn vbase_internal<'a, G, E, T>(
     visited: &'a VisitedPool,
     s: u32,
     mut reranker: G,
+    filter: impl Fn(u32) -> bool + 'a,
 ) -> impl Iterator<Item = (Distance, u32, T)> + 'a
 where
     G: RerankerPush + RerankerPop<(E, T)> + 'a,
@@ -142,7 +143,7 @@ where
         visited.mark(s);
         reranker.push(s);
     }
-    std::iter::from_fn(move || {
+    std::iter::from_fn(move || loop {
         let (dis_u, u, (outs_u, pay_u)) = reranker.pop()?;
         for v in outs_u {
             if !visited.check(v) {
@@ -151,7 +152,55 @@ where
             visited.mark(v);
             reranker.push(v);
         }
-        Some((dis_u, u, pay_u))
+        if filter(u) {
+            return Some((dis_u, u, pay_u));
+        }
+    })
+}
+
+/// Like `vbase_internal`, but only vertices passing the filter are pushed.
+///
+/// If a neighbour is filtered out, its own neighbours are visited instead, so that
+/// the graph stays connected under restrictive filters.
+pub fn vbase_filtered_internal<'a, G, E, F, T>(
+    visited: &'a VisitedPool,
+    s: u32,
+    mut reranker: G,
+    read_outs: impl Fn(u32) -> F + 'a,
+    filter: impl Fn(u32) -> bool + 'a,
+) -> impl Iterator<Item = (Distance, u32, T)> + 'a
+where
+    G: RerankerPush + RerankerPop<(E, T)> + 'a,
+    E: Iterator<Item = u32>,
+    F: Iterator<Item = u32>,
+    T: 'a,
+{
+    let mut visited = visited.fetch_guard_checker();
+    {
+        visited.mark(s);
+        reranker.push(s);
+    }
+    std::iter::from_fn(move || loop {
+        let (dis_u, u, (outs_u, pay_u)) = reranker.pop()?;
+        for v in outs_u {
+            if !visited.check(v) {
+                continue;
+            }
+            visited.mark(v);
+            if filter(v) {
+                reranker.push(v);
+                continue;
+            }
+            for w in read_outs(v) {
+                if visited.check(w) && filter(w) {
+                    visited.mark(w);
+                    reranker.push(w);
+                }
+            }
+        }
+        if filter(u) {
+            return Some((dis_u, u, pay_u));
+        }
     })
 }
 
@@ -159,14 +208,59 @@ pub fn vbase_generic<'a, G, E, T>(
     visited: &'a VisitedPool,
     s: u32,
     reranker: G,
+    filter: impl Fn(u32) -> bool + 'a,
     ef_search: u32,
+    radius: Distance,
 ) -> impl Iterator<Item = (Distance, u32, T)> + 'a
 where
     G: RerankerPush + RerankerPop<(E, T)> + 'a,
     E: Iterator<Item = u32>,
     T: 'a,
 {
-    let mut iter = vbase_internal(visited, s, reranker);
+    let iter = vbase_internal(visited, s, reranker, filter);
+    vbase_stages(iter, ef_search, radius)
+}
+
+pub fn vbase_filtered<'a, G, E, F, T>(
+    visited: &'a VisitedPool,
+    s: u32,
+    reranker: G,
+    read_outs: impl Fn(u32) -> F + 'a,
+    filter: impl Fn(u32) -> bool + 'a,
+    ef_search: u32,
+    radius: Distance,
+) -> impl Iterator<Item = (Distance, u32, T)> + 'a
+where
+    G: RerankerPush + RerankerPop<(E, T)> + 'a,
+    E: Iterator<Item = u32>,
+    F: Iterator<Item = u32>,
+    T: 'a,
+{
+    let iter = vbase_filtered_internal(visited, s, reranker, read_outs, filter);
+    vbase_stages(iter, ef_search, radius)
+}
+
+/// Scans every vertex passing the filter.
+pub fn vbase_brute_force<'a>(
+    n: u32,
+    dist: impl Fn(u32) -> Distance + 'a,
+    filter: impl Fn(u32) -> bool + 'a,
+    radius: Distance,
+) -> impl Iterator<Item = (Distance, u32)> + 'a {
+    let mut result = (0..n)
+        .filter(|&u| filter(u))
+        .map(|u| (dist(u), u))
+        .filter(|&(dis_u, _)| dis_u <= radius)
+        .collect::<Vec<_>>();
+    result.sort_unstable();
+    result.into_iter()
+}
+
+fn vbase_stages<'a, T: 'a>(
+    mut iter: impl Iterator<Item = (Distance, u32, T)> + 'a,
+    ef_search: u32,
+    radius: Distance,
+) -> impl Iterator<Item = (Distance, u32, T)> + 'a {
     let mut results = Results::new(ef_search as _);
     let mut stage1 = Vec::new();
     for (dis_u, u, pay_u) in &mut iter {
@@ -178,9 +272,23 @@ where
             break;
         }
     }
+    stage1.retain(|x| x.0 <= radius);
     stage1.sort_unstable_by_key(|x| x.0);
     let mut stage1 = stage1.into_iter().peekable();
-    let mut stage2 = iter.peekable();
+    // stop expanding once `ef_search` vertices in a row fall outside the radius
+    let mut misses = 0_u32;
+    let mut stage2 = std::iter::from_fn(move || loop {
+        if misses >= ef_search {
+            return None;
+        }
+        let (dis_u, u, pay_u) = iter.next()?;
+        if dis_u <= radius {
+            misses = 0;
+            return Some((dis_u, u, pay_u));
+        }
+        misses += 1;
+    })
+    .peekable();
     std::iter::from_fn(move || {
         if stage1.peek().is_none() {
             return stage2.next();
@@ -68,10 +68,38 @@ impl<O: OperatorHnsw, Q: Quantizer<O>> Hnsw<O, Q> {
         &'a self,
         vector: Borrowed<'a, O>,
         opts: &'a SearchOptions,
+        filter: Option<&'a AllowList>,
     ) -> Box<dyn Iterator<Item = Element> + 'a> {
         let Some(s) = self.s else {
             return Box::new(std::iter::empty());
         };
+        let radius = Distance::from_f32(opts.radius);
+        if let Some(f) = filter {
+            // the allow-list covers the whole table, so entries of this segment are counted, but
+            // only until the selectivity is known to be above the threshold
+            let threshold = (opts.hnsw_brute_force_selectivity as f64 * self.len() as f64).ceil();
+            let threshold = threshold as usize;
+            let selective = f.len() < threshold
+                || (0..self.len())
+                    .filter(|&u| f.check(self.payload(u)))
+                    .take(threshold)
+                    .count()
+                    < threshold;
+            if selective {
+                return Box::new(
+                    graph::search::vbase_brute_force(
+                        self.len(),
+                        move |u| O::distance(self.storage.vector(u), vector),
+                        move |u| f.check(self.payload(u)),
+                        radius,
+                    )
+                    .map(|(dis_u, u)| Element {
+                        distance: dis_u,
+                        payload: AlwaysEqual(self.payload(u)),
+                    }),
+                );
+            }
+        }
         let projected_vector = self.quantization.project(vector);
         let lut = self.quantization.preprocess(projected_vector.as_borrowed());
         let s = fast_search(
@@ -86,14 +114,29 @@ impl<O: OperatorHnsw, Q: Quantizer<O>> Hnsw<O, Q> {
                 (base_outs(self, u), ()),
             )
         });
-        Box::new(
-            graph::search::vbase_generic(&self.visited, s, reranker, opts.hnsw_ef_search).map(
-                |(dis_u, u, ())| Element {
-                    distance: dis_u,
-                    payload: AlwaysEqual(self.payload(u)),
-                },
-            ),
-        )
+        let iter: Box<dyn Iterator<Item = _>> = match filter {
+            Some(f) if opts.hnsw_filtered_traversal => Box::new(graph::search::vbase_filtered(
+                &self.visited,
+                s,
+                reranker,
+                move |u| base_outs(self, u),
+                move |u| f.check(self.payload(u)),
+                opts.hnsw_ef_search,
+                radius,
+            )),
+            _ => Box::new(graph::search::vbase_generic(
+                &self.visited,
+                s,
+                reranker,
+                move |u| filter.map_or(true, |f| f.check(self.payload(u))),
+                opts.hnsw_ef_search,
+                radius,
+            )),
+        };
+        Box::new(iter.map(|(dis_u, u, ())| Element {
+            distance: dis_u,
+            payload: AlwaysEqual(self.payload(u)),
+        }))
     }
 
     pub fn dims(&self) -> u32 {
@@ -318,6 +361,66 @@ fn open<O: OperatorHnsw, Q: Quantizer<O>>(path: impl AsRef<Path>) -> Hnsw<O, Q>
     }
 }
 
+/// Checks that each vertex has at most as many out-neighbors as its hierarchies hold, and only
+/// refers to vertices of the graph.
+pub fn verify(path: impl AsRef<Path>, n: u32) -> Result<(), String> {
+    let path = path.as_ref();
+    let m = *Json::<u32>::try_open(path.join("m")).map_err(|e| format!("m: {e}"))?;
+    if m < 2 {
+        return Err(format!("m: {m} is less than 2"));
+    }
+    let open = |name: &str| {
+        MmapArray::<u32>::try_open(path.join(name)).map_err(|e| format!("{name}: {e}"))
+    };
+    let base_graph_outs = open("base_graph_outs")?;
+    let hyper_graph_outs = open("hyper_graph_outs")?;
+    let hyper_len = (0..n)
+        .map(|u| (hierarchy_for_a_vertex(m, u) as usize - 1) * m as usize)
+        .sum::<usize>();
+    for (name, outs, len) in [
+        (
+            "base_graph_outs",
+            &base_graph_outs,
+            n as usize * 2 * m as usize,
+        ),
+        ("hyper_graph_outs", &hyper_graph_outs, hyper_len),
+    ] {
+        if outs.len() != len {
+            return Err(format!(
+                "{name}: {} edges, but {len} are expected for {n} vertices",
+                outs.len()
+            ));
+        }
+    }
+    let check = |name: &str, outs: &[u32], u: u32, level: u8| {
+        let padding = outs.iter().position(|&v| v == u32::MAX);
+        let (edges, padding) = outs.split_at(padding.unwrap_or(outs.len()));
+        if let Some(v) = edges.iter().find(|&&v| v >= n) {
+            return Err(format!(
+                "{name}: vertex {u} at level {level} refers to vertex {v} of {n}"
+            ));
+        }
+        if padding.iter().any(|&v| v != u32::MAX) {
+            return Err(format!(
+                "{name}: vertex {u} at level {level} has edges after the padding"
+            ));
+        }
+        Ok(())
+    };
+    let mut offset = 0;
+    for u in 0..n {
+        let start = u as usize * 2 * m as usize;
+        let outs = &base_graph_outs[start..start + 2 * m as usize];
+        check("base_graph_outs", outs, u, 0)?;
+        for level in 1..hierarchy_for_a_vertex(m, u) {
+            let outs = &hyper_graph_outs[offset..offset + m as usize];
+            check("hyper_graph_outs", outs, u, level)?;
+            offset += m as usize;
+        }
+    }
+    Ok(())
+}
+
 fn fast_search<E>(
     dist: impl Fn(u32) -> Distance,
     read_outs: impl Fn(u32, u8) -> E,
@@ -680,3 +783,90 @@ fn start(n: u32, m: u32) -> Option<u32> {
     }
     Some(shift as u32 - 1)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::distance::DistanceKind;
+    use base::vector::{VectBorrowed, VectOwned, VectorKind};
+    use quantization::trivial::TrivialQuantizer;
+    use rand::Rng;
+
+    const DIMS: u32 = 16;
+
+    struct Points(Vec<VectOwned<f32>>);
+
+    impl Vectors<VectOwned<f32>> for Points {
+        fn dims(&self) -> u32 {
+            DIMS
+        }
+        fn len(&self) -> u32 {
+            self.0.len() as u32
+        }
+        fn vector(&self, i: u32) -> VectBorrowed<'_, f32> {
+            self.0[i as usize].as_borrowed()
+        }
+    }
+
+    impl Collection for Points {
+        fn payload(&self, i: u32) -> Payload {
+            Payload::new(Pointer::new(i as u64), 0)
+        }
+    }
+
+    #[test]
+    fn filtered_traversal_test() {
+        let mut rng = rand::thread_rng();
+        let n = 3000;
+        let points = Points(
+            (0..n)
+                .map(|_| VectOwned::new((0..DIMS).map(|_| rng.gen_range(-1.0..1.0)).collect()))
+                .collect(),
+        );
+        let path = std::env::temp_dir().join(format!("hnsw_{}", rng.gen::<u64>()));
+        let options = IndexOptions {
+            vector: VectorOptions {
+                dims: DIMS,
+                v: VectorKind::Vecf32,
+                d: DistanceKind::L2,
+                weights: None,
+            },
+            indexing: IndexingOptions::Hnsw(HnswIndexingOptions::default()),
+        };
+        let hnsw = stoppable_rayon::ThreadPoolBuilder::new()
+            .build_scoped(|pool| {
+                pool.install(|| {
+                    from_nothing::<VectL2<f32>, TrivialQuantizer<VectL2<f32>>>(
+                        &path, options, &points,
+                    )
+                })
+            })
+            .unwrap()
+            .unwrap();
+        let search = |opts: &SearchOptions, filter: &AllowList, query: &VectOwned<f32>| {
+            hnsw.vbase(query.as_borrowed(), opts, Some(filter))
+                .take(10)
+                .map(|x| x.payload.0.pointer())
+                .collect::<Vec<_>>()
+        };
+        let brute_force = SearchOptions {
+            hnsw_brute_force_selectivity: 1.0,
+            ..Default::default()
+        };
+        // rows of other segments don't count towards the selectivity of this one
+        for (step, others, min_hits) in [(20, 0, 180), (100, 0, 200), (100, 10 * n, 200)] {
+            let pointers = (0..n).step_by(step).chain(n..n + others);
+            let filter = AllowList::new(pointers.map(Pointer::new).collect());
+            let mut hits = 0;
+            for _ in 0..20 {
+                let query = VectOwned::new((0..DIMS).map(|_| rng.gen_range(-1.0..1.0)).collect());
+                let truth = search(&brute_force, &filter, &query);
+                let result = search(&SearchOptions::default(), &filter, &query);
+                assert!(result.iter().all(|&p| filter.check(Payload::new(p, 0))));
+                hits += result.iter().filter(|p| truth.contains(p)).count();
+            }
+            assert!(hits >= min_hits, "recall: {}", hits as f64 / 200.0);
+        }
+        std::fs::remove_dir_all(path).unwrap();
+    }
+}
@@ -0,0 +1,71 @@
+//! Sealed indexes built offline by `pyvectors` or the CLI, installed as sealed segments.
+//!
+//! Payloads of such indexes hold labels instead of pointers, so `payloads` is rewritten, while
+//! other files are hard-linked, or copied if the index is on another file system.
+
+use crate::layout::SegmentLayout;
+use base::index::*;
+use base::search::*;
+use common::mmap_array::MmapArray;
+use std::io;
+use std::path::Path;
+
+/// Reads the options of a prebuilt index, written to `.index_options` by `pyvectors`. A sealed
+/// segment of an index created by the CLI takes the options of the index.
+pub fn options(source: &Path) -> io::Result<IndexOptions> {
+    let options = match std::fs::read(source.join(".index_options")) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            std::fs::read(source.join("../../options"))?
+        }
+        result => result?,
+    };
+    serde_json::from_slice(&options).map_err(io::Error::other)
+}
+
+/// Returns whether the prebuilt index is readable by this version. Indexes written by
+/// `pyvectors` have no `layout` file, and are assumed to be.
+pub fn is_current(source: &Path) -> io::Result<bool> {
+    let path = source.join("layout");
+    Ok(!path.try_exists()? || SegmentLayout::read(path) == SegmentLayout::CURRENT)
+}
+
+pub fn payloads(source: &Path) -> io::Result<MmapArray<Payload>> {
+    let path = source.join("payloads");
+    std::fs::metadata(&path)?;
+    Ok(MmapArray::open(path))
+}
+
+/// Materializes a prebuilt index as a sealed segment at `path`, with payloads replaced.
+pub fn install(
+    source: &Path,
+    path: &Path,
+    payloads: impl Iterator<Item = Payload>,
+) -> io::Result<()> {
+    std::fs::create_dir(path)?;
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        // written anew, or not used by segments
+        let skipped = [".index_options", "layout", "payloads", "checksums"];
+        if skipped.iter().any(|x| name.to_str() == Some(x)) {
+            continue;
+        }
+        link_or_copy(&entry.path(), &path.join(name))?;
+    }
+    MmapArray::create(path.join("payloads"), payloads);
+    SegmentLayout::CURRENT.write(path.join("layout"));
+    crate::verify::write_checksums(path)
+}
+
+fn link_or_copy(src: &Path, dst: &Path) -> io::Result<()> {
+    if std::fs::metadata(src)?.is_dir() {
+        std::fs::create_dir(dst)?;
+        for entry in std::fs::read_dir(src)? {
+            let entry = entry?;
+            link_or_copy(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+    } else if std::fs::hard_link(src, dst).is_err() {
+        std::fs::copy(src, dst)?;
+    }
+    Ok(())
+}
@@ -4,25 +4,27 @@ use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 pub struct Delete {
+    path: PathBuf,
     version: DashMap<Pointer, u64>,
     wal: Mutex<FileWal>,
 }
 
 impl Delete {
     pub fn create(path: PathBuf) -> Arc<Self> {
-        let wal = FileWal::create(path);
+        let wal = FileWal::create(&path);
         let version = DashMap::new();
         Arc::new(Self {
+            path,
             version,
             wal: wal.into(),
         })
     }
     pub fn open(path: PathBuf) -> Arc<Self> {
-        let mut wal = FileWal::open(path);
+        let mut wal = FileWal::open(&path);
         let version = DashMap::<Pointer, u64>::new();
         while let Some(log) = wal.read() {
             let log = bincode::deserialize::<Log>(&log).unwrap();
@@ -38,6 +40,7 @@ impl Delete {
         }
         wal.truncate();
         Arc::new(Self {
+            path,
             version,
             wal: wal.into(),
         })
@@ -72,9 +75,15 @@ impl Delete {
     pub fn flush(&self) {
         self.wal.lock().sync_all();
     }
+    pub fn snapshot(&self, path: &Path) -> std::io::Result<()> {
+        let mut wal = self.wal.lock();
+        wal.sync_all();
+        std::fs::copy(&self.path, path)?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct Log {
-    key: Pointer,
+pub(crate) struct Log {
+    pub(crate) key: Pointer,
 }
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Versions of the on-disk layouts of a sealed segment, written to its `layout` file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentLayout {
+    pub indexing: u32,
+    pub quantization: u32,
+    pub storage: u32,
+}
+
+impl SegmentLayout {
+    pub const CURRENT: Self = Self {
+        indexing: indexing::LAYOUT,
+        quantization: quantization::LAYOUT,
+        storage: storage::LAYOUT,
+    };
+    /// Layout of segments written before layouts were recorded.
+    pub const LEGACY: Self = Self {
+        indexing: 1,
+        quantization: 1,
+        storage: 1,
+    };
+
+    /// Layout of segments whose indexes are to be rebuilt from their stored vectors, such as
+    /// segments of an index whose distance is converted.
+    pub const UNINDEXED: Self = Self {
+        indexing: 0,
+        quantization: 0,
+        storage: storage::LAYOUT,
+    };
+
+    pub fn read(path: impl AsRef<Path>) -> Self {
+        if !path.as_ref().try_exists().unwrap() {
+            return Self::LEGACY;
+        }
+        serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap()
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) {
+        std::fs::write(path, serde_json::to_string(self).unwrap()).unwrap();
+    }
+
+    /// Returns whether the stored vectors and payloads can be read, so that the segment can be
+    /// rebuilt from them.
+    pub fn is_rebuildable(&self) -> bool {
+        self.storage == Self::CURRENT.storage
+    }
+}
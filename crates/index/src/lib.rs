@@ -1,14 +1,20 @@
 #![allow(clippy::len_without_is_empty)]
 
+pub mod attach;
 pub mod delete;
+pub mod layout;
 pub mod optimizing;
 pub mod segment;
+pub mod snapshot;
+pub mod verify;
 
 mod utils;
 
 use self::delete::Delete;
+use self::layout::SegmentLayout;
 use self::segment::growing::GrowingSegment;
 use self::segment::sealed::SealedSegment;
+use self::segment::stored::StoredSegment;
 use crate::optimizing::Optimizing;
 use crate::utils::tournament_tree::LoserTree;
 use arc_swap::ArcSwap;
@@ -31,7 +37,7 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::convert::Infallible;
 use std::num::NonZeroU128;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::time::Instant;
@@ -86,6 +92,9 @@ impl<O: Op> Index<O> {
         let startup = FileAtomic::create(
             path.join("startup"),
             IndexStartup {
+                layout: IndexStartup::LAYOUT,
+                position: None,
+                replaying: false,
                 sealed_segment_ids: HashSet::new(),
                 growing_segment_ids: HashSet::new(),
                 alterable_options: alterable_options.clone(),
@@ -102,9 +111,12 @@ impl<O: Op> Index<O> {
             protect: Mutex::new(IndexProtect {
                 startup,
                 sealed_segments: HashMap::new(),
+                stored_segments: HashMap::new(),
                 read_segments: HashMap::new(),
                 write_segment: None,
                 alterable_options: alterable_options.clone(),
+                position: None,
+                replaying: false,
                 sealed_counter: NonZeroU128::new(1).unwrap(),
                 growing_counter: NonZeroU128::new(1).unwrap(),
             }),
@@ -112,6 +124,7 @@ impl<O: Op> Index<O> {
                 options: options.clone(),
                 alterable_options: alterable_options.clone(),
                 sealed_segments: HashMap::new(),
+                stored_segments: HashMap::new(),
                 read_segments: HashMap::new(),
                 delete: delete.clone(),
                 write_segment: None,
@@ -120,17 +133,45 @@ impl<O: Op> Index<O> {
             instant_written: AtomicCell::new(Instant::now()),
             check_deleted: AtomicCell::new(false),
             optimizing: Mutex::new(None),
-            _tracker: Arc::new(IndexTracker { path }),
+            _tracker: Arc::new(IndexTracker::new(path)),
         });
         Ok(index)
     }
 
-    pub fn open(path: PathBuf) -> Arc<Self> {
+    pub fn open(path: PathBuf) -> Result<Arc<Self>, OpenError> {
         let options =
             serde_json::from_slice::<IndexOptions>(&std::fs::read(path.join("options")).unwrap())
                 .unwrap();
-        let tracker = Arc::new(IndexTracker { path: path.clone() });
         let startup = FileAtomic::<IndexStartup>::open(path.join("startup"));
+        if startup.get().layout > IndexStartup::LAYOUT {
+            return Err(OpenError::NewerLayout {
+                layout: startup.get().layout,
+                supported: IndexStartup::LAYOUT,
+            });
+        }
+        if startup.get().replaying {
+            return Err(OpenError::InterruptedReplay);
+        }
+        // segments in an outdated layout are rebuilt from their stored vectors in background
+        let mut outdated = HashSet::new();
+        for &id in startup.get().sealed_segment_ids.iter() {
+            let layout = SegmentLayout::read(
+                path.join("sealed_segments")
+                    .join(id.to_string())
+                    .join("layout"),
+            );
+            if layout == SegmentLayout::CURRENT {
+                continue;
+            }
+            if !layout.is_rebuildable() {
+                return Err(OpenError::UnreadableSegment {
+                    id,
+                    layout: layout.storage,
+                });
+            }
+            outdated.insert(id);
+        }
+        let tracker = Arc::new(IndexTracker::new(path.clone()));
         let alterable_options = startup.get().alterable_options.clone();
         clean(
             path.join("sealed_segments"),
@@ -152,6 +193,7 @@ impl<O: Op> Index<O> {
             .get()
             .sealed_segment_ids
             .iter()
+            .filter(|id| !outdated.contains(id))
             .map(|&id| {
                 (
                     id,
@@ -164,6 +206,20 @@ impl<O: Op> Index<O> {
                 )
             })
             .collect::<HashMap<_, _>>();
+        let stored_segments = outdated
+            .iter()
+            .map(|&id| {
+                log::warn!("Rebuild segment {id} of index {path:?} written in an outdated layout.");
+                (
+                    id,
+                    StoredSegment::<O>::open(
+                        tracker.clone(),
+                        path.join("sealed_segments").join(id.to_string()),
+                        id,
+                    ),
+                )
+            })
+            .collect::<HashMap<_, _>>();
         let read_segments = startup
             .get()
             .growing_segment_ids
@@ -181,15 +237,18 @@ impl<O: Op> Index<O> {
             })
             .collect::<HashMap<_, _>>();
         let delete = Delete::open(path.join("delete"));
-        Arc::new(Index {
+        Ok(Arc::new(Index {
             path: path.clone(),
             options: options.clone(),
             delete: delete.clone(),
             protect: Mutex::new(IndexProtect {
                 sealed_segments: sealed_segments.clone(),
+                stored_segments: stored_segments.clone(),
                 read_segments: read_segments.clone(),
                 write_segment: None,
                 alterable_options: alterable_options.clone(),
+                position: startup.get().position,
+                replaying: false,
                 sealed_counter: startup.get().sealed_counter,
                 growing_counter: startup.get().growing_counter,
                 startup,
@@ -199,6 +258,7 @@ impl<O: Op> Index<O> {
                 alterable_options: alterable_options.clone(),
                 delete: delete.clone(),
                 sealed_segments,
+                stored_segments,
                 read_segments,
                 write_segment: None,
             })),
@@ -207,7 +267,7 @@ impl<O: Op> Index<O> {
             check_deleted: AtomicCell::new(false),
             optimizing: Mutex::new(None),
             _tracker: tracker,
-        })
+        }))
     }
     pub fn options(&self) -> &IndexOptions {
         &self.options
@@ -245,6 +305,7 @@ impl<O: Op> Index<O> {
             self.path.join("wal").join(write_segment_id.to_string()),
             write_segment_id,
             protect.alterable_options.segment.max_growing_segment_size as usize,
+            &self.options,
         );
         sync_dir(self.path.join("wal"));
         protect.write_segment = Some((write_segment_id, write_segment));
@@ -267,13 +328,18 @@ impl<O: Op> Index<O> {
     pub fn stat(&self) -> IndexStat {
         let view = self.view();
         IndexStat {
-            indexing: self.instant_indexed.load() < self.instant_written.load(),
+            indexing: self.instant_indexed.load() < self.instant_written.load()
+                || !view.stored_segments.is_empty(),
             options: self.options().clone(),
+            position: self.protect.lock().position,
             segments: {
                 let mut segments = Vec::new();
                 for sealed_segment in view.sealed_segments.values() {
                     segments.push(sealed_segment.stat_sealed());
                 }
+                for stored_segment in view.stored_segments.values() {
+                    segments.push(stored_segment.stat_stored());
+                }
                 for read_segment in view.read_segments.values() {
                     segments.push(read_segment.stat_read());
                 }
@@ -284,6 +350,134 @@ impl<O: Op> Index<O> {
             },
         }
     }
+    pub fn position(&self) -> Option<LogPosition> {
+        self.protect.lock().position
+    }
+    /// Records the position in the log of the relation, with `replaying` set while the records
+    /// after it are being replayed, so that an interrupted replay is detected on open.
+    pub fn set_position(&self, position: Option<LogPosition>, replaying: bool) {
+        let mut protect = self.protect.lock();
+        protect.position = position;
+        protect.replaying = replaying;
+        protect.maintain(self.options.clone(), self.delete.clone(), &self.view);
+    }
+    /// Writes a self-contained copy of the index to `dest`, which is opened as an index after
+    /// `snapshot::restore`.
+    pub fn snapshot(&self, dest: &Path) -> Result<(), SnapshotError> {
+        let error = |e: std::io::Error| SnapshotError::Write {
+            reason: e.to_string(),
+        };
+        // segments are neither sealed nor merged while the lock is held
+        let protect = self.protect.lock();
+        std::fs::create_dir(dest).map_err(error)?;
+        let result = (|| -> std::io::Result<()> {
+            std::fs::copy(self.path.join("options"), dest.join("options"))?;
+            std::fs::create_dir(dest.join("sealed_segments"))?;
+            let sealed_segment_ids = protect.sealed_segments.keys();
+            let stored_segment_ids = protect.stored_segments.keys();
+            for id in sealed_segment_ids.chain(stored_segment_ids) {
+                snapshot::link_dir(
+                    &self.path.join("sealed_segments").join(id.to_string()),
+                    &dest.join("sealed_segments").join(id.to_string()),
+                )?;
+            }
+            std::fs::create_dir(dest.join("wal"))?;
+            let read_segments = protect.read_segments.iter();
+            let write_segment = protect.write_segment.iter().map(|(id, x)| (id, x));
+            for (id, segment) in read_segments.chain(write_segment) {
+                segment.snapshot(&dest.join("wal").join(id.to_string()));
+            }
+            self.delete.snapshot(&dest.join("delete"))?;
+            let read_segment_ids = protect.read_segments.keys().copied();
+            let write_segment_id = protect.write_segment.as_ref().map(|(id, _)| *id);
+            let sealed_segment_ids = protect.sealed_segments.keys();
+            let stored_segment_ids = protect.stored_segments.keys();
+            FileAtomic::create(
+                dest.join("startup"),
+                IndexStartup {
+                    layout: IndexStartup::LAYOUT,
+                    position: protect.position,
+                    replaying: protect.replaying,
+                    sealed_segment_ids: sealed_segment_ids
+                        .chain(stored_segment_ids)
+                        .copied()
+                        .collect(),
+                    growing_segment_ids: read_segment_ids.chain(write_segment_id).collect(),
+                    alterable_options: protect.alterable_options.clone(),
+                    sealed_counter: protect.sealed_counter,
+                    growing_counter: protect.growing_counter,
+                },
+            );
+            sync_walk_from_dir(dest);
+            Ok(())
+        })();
+        if let Err(e) = result {
+            let _ = std::fs::remove_dir_all(dest);
+            return Err(error(e));
+        }
+        Ok(())
+    }
+    /// Installs a sealed index built offline as a sealed segment. Its payloads hold labels,
+    /// which are mapped to pointers by `labels`. Rows of the pointers that are already in the
+    /// index are deleted, so that they are returned only once.
+    pub fn attach(&self, source: &Path, labels: &[(i64, Pointer)]) -> Result<(), AttachError> {
+        let error = |e: std::io::Error| AttachError::Read {
+            reason: e.to_string(),
+        };
+        let options = attach::options(source).map_err(error)?;
+        if serde_json::to_value(&options).ok() != serde_json::to_value(&self.options).ok() {
+            return Err(AttachError::IncompatibleOptions);
+        }
+        if !attach::is_current(source).map_err(error)? {
+            return Err(AttachError::Read {
+                reason: "it is written in an outdated layout".to_string(),
+            });
+        }
+        let mut labels = labels.to_vec();
+        labels.sort_unstable();
+        let mut payloads = Vec::new();
+        for payload in attach::payloads(source).map_err(error)?.iter() {
+            let label = payload.pointer().as_u64() as i64;
+            let Ok(i) = labels.binary_search_by_key(&label, |&(label, _)| label) else {
+                return Err(AttachError::UnknownLabel { label });
+            };
+            // the version after the existing rows of the pointer are deleted
+            let pointer = labels[i].1;
+            payloads.push(Payload::new(pointer, self.delete.version(pointer) + 1));
+        }
+        let mut pointers = payloads.iter().map(|p| p.pointer()).collect::<Vec<_>>();
+        pointers.sort_unstable();
+        pointers.dedup();
+        let id;
+        {
+            let mut protect = self.protect.lock();
+            id = protect.sealed_counter;
+            protect.sealed_counter = protect.sealed_counter.checked_add(1).unwrap();
+            protect.maintain(self.options.clone(), self.delete.clone(), &self.view);
+        }
+        let path = self.path.join("sealed_segments").join(id.to_string());
+        if let Err(e) = attach::install(source, &path, payloads.into_iter()) {
+            let _ = std::fs::remove_dir_all(&path);
+            return Err(error(e));
+        }
+        sync_walk_from_dir(&path);
+        sync_dir(self.path.join("sealed_segments"));
+        let segment = SealedSegment::open(self._tracker.clone(), path, id, self.options.clone());
+        let mut protect = self.protect.lock();
+        for pointer in pointers {
+            self.delete.delete(pointer);
+        }
+        self.delete.flush();
+        self.check_deleted.store(false);
+        protect.sealed_segments.insert(id, segment);
+        protect.maintain(self.options.clone(), self.delete.clone(), &self.view);
+        Ok(())
+    }
+    pub fn verify(&self, valid: &dyn Fn(Pointer) -> bool) -> Vec<VerifyCheck> {
+        // segments are neither sealed nor merged while the lock is held
+        let _protect = self.protect.lock();
+        verify::verify::<O>(&self.path, valid)
+    }
     pub fn delete(&self, p: Pointer) -> Result<(), DeleteError> {
         self.delete.delete(p);
         self.check_deleted.store(false);
@@ -311,8 +505,12 @@ impl<O: Op> Index<O> {
     pub fn check_existing(&self, payload: Payload) -> bool {
         self.delete.check(payload)
     }
-    pub fn wait(&self) -> Arc<IndexTracker> {
-        Arc::clone(&self._tracker)
+    /// Returns a receiver that is disconnected once the index is no longer used and its
+    /// directory is removed.
+    pub fn wait(&self) -> std::sync::mpsc::Receiver<Infallible> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self._tracker.watchers.lock().push(sender);
+        receiver
     }
     pub fn create_sealed_segment(
         &self,
@@ -339,7 +537,9 @@ impl<O: Op> Index<O> {
         {
             let mut protect = self.protect.lock();
             for sealed_segment_id in sealed_segment_ids {
-                if protect.sealed_segments.contains_key(sealed_segment_id) {
+                if protect.sealed_segments.contains_key(sealed_segment_id)
+                    || protect.stored_segments.contains_key(sealed_segment_id)
+                {
                     continue;
                 }
                 return None;
@@ -352,6 +552,7 @@ impl<O: Op> Index<O> {
             }
             for sealed_segment_id in sealed_segment_ids {
                 protect.sealed_segments.remove(sealed_segment_id);
+                protect.stored_segments.remove(sealed_segment_id);
             }
             for growing_segment_id in growing_segment_ids {
                 protect.read_segments.remove(growing_segment_id);
@@ -363,9 +564,20 @@ impl<O: Op> Index<O> {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct IndexTracker {
     path: PathBuf,
+    // dropped after the directory is removed
+    watchers: Mutex<Vec<std::sync::mpsc::Sender<Infallible>>>,
+}
+
+impl IndexTracker {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            watchers: Mutex::new(Vec::new()),
+        }
+    }
 }
 
 impl Drop for IndexTracker {
@@ -379,6 +591,7 @@ pub struct IndexView<O: Op> {
     pub alterable_options: IndexAlterableOptions,
     pub delete: Arc<Delete>,
     pub sealed_segments: HashMap<NonZeroU128, Arc<SealedSegment<O>>>,
+    pub stored_segments: HashMap<NonZeroU128, Arc<StoredSegment<O>>>,
     pub read_segments: HashMap<NonZeroU128, Arc<GrowingSegment<O>>>,
     pub write_segment: Option<(NonZeroU128, Arc<GrowingSegment<O>>)>,
 }
@@ -388,6 +601,7 @@ impl<O: Op> IndexView<O> {
         &'a self,
         vector: Borrowed<'a, O>,
         opts: &'a SearchOptions,
+        filter: Option<&'a AllowList>,
     ) -> Result<impl Iterator<Item = (Distance, Pointer)> + 'a, VbaseError> {
         if self.options.vector.dims != vector.dims() {
             return Err(VbaseError::InvalidVector);
@@ -398,18 +612,23 @@ impl<O: Op> IndexView<O> {
             });
         }
 
-        let n = self.sealed_segments.len() + self.read_segments.len() + 1;
+        let n =
+            self.sealed_segments.len() + self.stored_segments.len() + self.read_segments.len() + 1;
         let mut iterators = Vec::with_capacity(n);
         for (_, sealed) in self.sealed_segments.iter() {
-            let stage2 = sealed.vbase(vector, opts);
+            let stage2 = sealed.vbase(vector, opts, filter);
+            iterators.push(stage2);
+        }
+        for (_, stored) in self.stored_segments.iter() {
+            let stage2 = stored.vbase(vector, opts, filter);
             iterators.push(stage2);
         }
         for (_, read) in self.read_segments.iter() {
-            let stage2 = read.vbase(vector, opts);
+            let stage2 = read.vbase(vector, opts, filter);
             iterators.push(stage2);
         }
         if let Some((_, write)) = &self.write_segment {
-            let stage2 = write.vbase(vector, opts);
+            let stage2 = write.vbase(vector, opts, filter);
             iterators.push(stage2);
         }
         let loser = LoserTree::new(iterators);
@@ -421,11 +640,117 @@ impl<O: Op> IndexView<O> {
             }
         }))
     }
+    /// Searches documents made of multiple vectors sharing a pointer, ranked by MaxSim, i.e.
+    /// the sum over query vectors of the distance to the nearest vector of the document.
+    /// All refined candidates are returned in order, so `LIMIT` is left to the executor.
+    pub fn vbase_multi(
+        &self,
+        vectors: &[Borrowed<'_, O>],
+        opts: &SearchOptions,
+        filter: Option<&AllowList>,
+    ) -> Result<impl Iterator<Item = (Distance, Pointer)>, VbaseError> {
+        if vectors.is_empty() || vectors.iter().any(|v| self.options.vector.dims != v.dims()) {
+            return Err(VbaseError::InvalidVector);
+        }
+        if let Err(err) = opts.validate() {
+            return Err(VbaseError::InvalidSearchOptions {
+                reason: err.to_string(),
+            });
+        }
+
+        let n = vectors.len();
+        let token_opts = SearchOptions {
+            radius: f32::INFINITY,
+            limit: opts.maxsim_token_limit,
+            ..opts.clone()
+        };
+        // stage 1: approximate scores, assuming that a document whose vectors are not
+        // retrieved for a query vector is as far as the furthest retrieved one
+        let mut found = HashMap::<Pointer, Vec<Option<f32>>>::new();
+        let mut furthest = Vec::with_capacity(n);
+        for (i, vector) in vectors.iter().enumerate() {
+            let vector = vector.own();
+            let mut last = 0.0f32;
+            let results = self.vbase(vector.as_borrowed(), &token_opts, filter)?;
+            for (distance, pointer) in results.take(opts.maxsim_token_limit as usize) {
+                let slot = &mut found.entry(pointer).or_insert_with(|| vec![None; n])[i];
+                if slot.is_none() {
+                    *slot = Some(distance.to_f32());
+                }
+                last = distance.to_f32();
+            }
+            furthest.push(last);
+        }
+        let mut candidates = found
+            .into_iter()
+            .map(|(pointer, distances)| {
+                let approximate = std::iter::zip(distances, &furthest)
+                    .map(|(d, f)| d.unwrap_or(*f))
+                    .sum::<f32>();
+                (approximate, pointer)
+            })
+            .collect::<Vec<_>>();
+        let refine = opts.maxsim_refine.max(opts.limit) as usize;
+        if candidates.len() > refine {
+            candidates.select_nth_unstable_by(refine, |a, b| a.0.total_cmp(&b.0));
+            candidates.truncate(refine);
+        }
+        // stage 2: exact scores over all vectors of the candidates
+        let mut exact = candidates
+            .into_iter()
+            .map(|(_, pointer)| (pointer, vec![Distance::INFINITY; n]))
+            .collect::<HashMap<_, _>>();
+        let pointers = exact.keys().copied().collect::<Vec<_>>();
+        let mut update = |payload: Payload, vector: Borrowed<'_, O>| {
+            if !self.delete.check(payload) {
+                return;
+            }
+            if let Some(nearest) = exact.get_mut(&payload.pointer()) {
+                for (nearest, &query) in std::iter::zip(nearest.iter_mut(), vectors) {
+                    *nearest = std::cmp::min(*nearest, O::distance(query, vector));
+                }
+            }
+        };
+        for sealed in self.sealed_segments.values() {
+            for &pointer in pointers.iter() {
+                for i in sealed.lookup(pointer) {
+                    update(sealed.payload(i), sealed.vector(i));
+                }
+            }
+        }
+        let growing_segments = self
+            .read_segments
+            .values()
+            .chain(self.write_segment.iter().map(|(_, x)| x));
+        for growing in growing_segments {
+            for i in 0..growing.len() {
+                update(growing.payload(i), growing.vector(i));
+            }
+        }
+        for stored in self.stored_segments.values() {
+            for i in 0..stored.len() {
+                update(stored.payload(i), stored.vector(i));
+            }
+        }
+        let radius = opts.radius;
+        let mut result = exact
+            .into_iter()
+            .map(|(pointer, nearest)| (nearest.iter().map(|d| d.to_f32()).sum::<f32>(), pointer))
+            .filter(|&(distance, _)| distance.is_finite() && distance <= radius)
+            .map(|(distance, pointer)| (Distance::from_f32(distance), pointer))
+            .collect::<Vec<_>>();
+        result.sort_unstable();
+        Ok(result.into_iter())
+    }
     pub fn list(&self) -> Result<impl Iterator<Item = Pointer> + '_, ListError> {
         let sealed_segments = self
             .sealed_segments
             .values()
             .flat_map(|x| (0..x.len()).map(|i| x.payload(i)));
+        let stored_segments = self
+            .stored_segments
+            .values()
+            .flat_map(|x| (0..x.len()).map(|i| x.payload(i)));
         let read_segments = self
             .read_segments
             .values()
@@ -436,6 +761,7 @@ impl<O: Op> IndexView<O> {
             .map(|(_, x)| x)
             .flat_map(|x| (0..x.len()).map(|i| x.payload(i)));
         let iter = sealed_segments
+            .chain(stored_segments)
             .chain(read_segments)
             .chain(write_segments)
             .filter(|p| self.delete.check(*p))
@@ -454,7 +780,8 @@ impl<O: Op> IndexView<O> {
         let payload = Payload::new(pointer, self.delete.version(pointer));
         if let Some((_, segment)) = self.write_segment.as_ref() {
             use crate::segment::growing::GrowingSegmentInsertError;
-            if let Err(GrowingSegmentInsertError) = segment.insert(vector, payload) {
+            if let Err(GrowingSegmentInsertError) = segment.insert(O::canonicalize(vector), payload)
+            {
                 return Ok(Err(OutdatedError));
             }
             Ok(Ok(()))
@@ -462,6 +789,27 @@ impl<O: Op> IndexView<O> {
             Ok(Err(OutdatedError))
         }
     }
+    pub fn insert_many(&self, vectors: &[(O::Vector, Pointer)]) -> Result<usize, InsertError> {
+        for (vector, _) in vectors {
+            if self.options.vector.dims != vector.as_borrowed().dims() {
+                return Err(InsertError::InvalidVector);
+            }
+        }
+
+        let Some((_, segment)) = self.write_segment.as_ref() else {
+            return Ok(0);
+        };
+        for (i, (vector, pointer)) in vectors.iter().enumerate() {
+            use crate::segment::growing::GrowingSegmentInsertError;
+            let payload = Payload::new(*pointer, self.delete.version(*pointer));
+            if let Err(GrowingSegmentInsertError) =
+                segment.insert(O::canonicalize(vector.clone()), payload)
+            {
+                return Ok(i);
+            }
+        }
+        Ok(vectors.len())
+    }
     pub fn flush(&self) -> Result<(), FlushError> {
         self.delete.flush();
         if let Some((_, write)) = &self.write_segment {
@@ -473,6 +821,13 @@ impl<O: Op> IndexView<O> {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct IndexStartup {
+    // 0 for indexes created before layouts were recorded
+    #[serde(default)]
+    layout: u32,
+    #[serde(default)]
+    position: Option<LogPosition>,
+    #[serde(default)]
+    replaying: bool,
     sealed_segment_ids: HashSet<NonZeroU128>,
     growing_segment_ids: HashSet<NonZeroU128>,
     alterable_options: IndexAlterableOptions,
@@ -480,12 +835,19 @@ struct IndexStartup {
     growing_counter: NonZeroU128,
 }
 
+impl IndexStartup {
+    const LAYOUT: u32 = 1;
+}
+
 struct IndexProtect<O: Op> {
     startup: FileAtomic<IndexStartup>,
     sealed_segments: HashMap<NonZeroU128, Arc<SealedSegment<O>>>,
+    stored_segments: HashMap<NonZeroU128, Arc<StoredSegment<O>>>,
     read_segments: HashMap<NonZeroU128, Arc<GrowingSegment<O>>>,
     write_segment: Option<(NonZeroU128, Arc<GrowingSegment<O>>)>,
     alterable_options: IndexAlterableOptions,
+    position: Option<LogPosition>,
+    replaying: bool,
     sealed_counter: NonZeroU128,
     growing_counter: NonZeroU128,
 }
@@ -502,14 +864,23 @@ impl<O: Op> IndexProtect<O> {
             alterable_options: self.alterable_options.clone(),
             delete,
             sealed_segments: self.sealed_segments.clone(),
+            stored_segments: self.stored_segments.clone(),
             read_segments: self.read_segments.clone(),
             write_segment: self.write_segment.clone(),
         });
         let read_segment_ids = self.read_segments.keys().copied();
         let write_segment_id = self.write_segment.as_ref().map(|(id, _)| *id);
         let growing_segment_ids = read_segment_ids.chain(write_segment_id).collect();
-        let sealed_segment_ids = self.sealed_segments.keys().copied().collect();
+        let sealed_segment_ids = self.sealed_segments.keys();
+        let stored_segment_ids = self.stored_segments.keys();
+        let sealed_segment_ids = sealed_segment_ids
+            .chain(stored_segment_ids)
+            .copied()
+            .collect();
         self.startup.set(IndexStartup {
+            layout: IndexStartup::LAYOUT,
+            position: self.position,
+            replaying: self.replaying,
             sealed_segment_ids,
             growing_segment_ids,
             alterable_options: self.alterable_options.clone(),
@@ -519,3 +890,441 @@ impl<O: Op> IndexProtect<O> {
         swap.swap(view);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::distance::DistanceKind;
+    use rand::Rng;
+
+    const DIMS: u32 = 8;
+
+    #[test]
+    fn vbase_multi_test() {
+        let mut rng = rand::thread_rng();
+        let mut random = |n: usize| {
+            (0..n)
+                .map(|_| VectOwned::new((0..DIMS).map(|_| rng.gen_range(-1.0..1.0)).collect()))
+                .collect::<Vec<_>>()
+        };
+        let documents = (0..300).map(|i| random(1 + i % 8)).collect::<Vec<_>>();
+        let query = random(4);
+        let path = std::env::temp_dir().join(format!("index_{}", rand::random::<u64>()));
+        let options = IndexOptions {
+            vector: VectorOptions {
+                dims: DIMS,
+                v: VectorKind::Vecf32,
+                d: DistanceKind::Dot,
+                weights: None,
+            },
+            indexing: IndexingOptions::Flat(Default::default()),
+        };
+        let index =
+            Index::<VectDot<f32>>::create(path, options, IndexAlterableOptions::default()).unwrap();
+        index.refresh();
+        let view = index.view();
+        for (i, document) in documents.iter().enumerate() {
+            for vector in document {
+                view.insert(vector.clone(), Pointer::new(i as u64))
+                    .unwrap()
+                    .unwrap();
+            }
+        }
+        let maxsim = |document: &[VectOwned<f32>]| {
+            query
+                .iter()
+                .map(|q| {
+                    let distances = document
+                        .iter()
+                        .map(|d| VectDot::<f32>::distance(q.as_borrowed(), d.as_borrowed()));
+                    distances.min().unwrap().to_f32()
+                })
+                .sum::<f32>()
+        };
+        let mut expected = documents
+            .iter()
+            .enumerate()
+            .map(|(i, document)| (maxsim(document), Pointer::new(i as u64)))
+            .collect::<Vec<_>>();
+        expected.sort_by(|a, b| a.0.total_cmp(&b.0));
+        expected.truncate(10);
+        let vectors = query.iter().map(|q| q.as_borrowed()).collect::<Vec<_>>();
+        let exhaustive = SearchOptions {
+            maxsim_token_limit: 65535,
+            maxsim_refine: 65535,
+            limit: 10,
+            ..Default::default()
+        };
+        let result = view
+            .vbase_multi(&vectors, &exhaustive, None)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(result.len(), documents.len());
+        for ((distance, _), (expected, _)) in std::iter::zip(&result, &expected) {
+            assert!((distance.to_f32() - expected).abs() < 1e-4);
+        }
+        let approximate = SearchOptions {
+            limit: 10,
+            ..Default::default()
+        };
+        let hits = view
+            .vbase_multi(&vectors, &approximate, None)
+            .unwrap()
+            .take(10)
+            .filter(|(_, pointer)| expected.iter().any(|(_, p)| p == pointer))
+            .count();
+        assert!(hits >= 9, "hits: {hits}");
+    }
+
+    #[test]
+    fn rebuild_outdated_segment_test() {
+        let mut rng = rand::thread_rng();
+        let mut random = || VectOwned::new((0..DIMS).map(|_| rng.gen_range(-1.0..1.0)).collect());
+        let vectors = (0..200).map(|_| random()).collect::<Vec<VectOwned<f32>>>();
+        let query = random();
+        let path = std::env::temp_dir().join(format!("index_{}", rand::random::<u64>()));
+        let options = IndexOptions {
+            vector: VectorOptions {
+                dims: DIMS,
+                v: VectorKind::Vecf32,
+                d: DistanceKind::L2,
+                weights: None,
+            },
+            indexing: IndexingOptions::Flat(Default::default()),
+        };
+        let opts = SearchOptions {
+            limit: 10,
+            ..Default::default()
+        };
+        let search = |index: &Index<VectL2<f32>>| {
+            let view = index.view();
+            let result = view.vbase(query.as_borrowed(), &opts, None).unwrap();
+            result.collect::<Vec<_>>()
+        };
+        let wait = |index: &Arc<Index<VectL2<f32>>>| {
+            index.start();
+            while index.stat().indexing {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+            index.stop();
+        };
+        let kinds = |index: &Index<VectL2<f32>>| {
+            let stat = index.stat();
+            stat.segments
+                .into_iter()
+                .map(|s| s.r#type)
+                .collect::<Vec<_>>()
+        };
+        let index =
+            Index::<VectL2<f32>>::create(path.clone(), options, IndexAlterableOptions::default())
+                .unwrap();
+        index.refresh();
+        let view = index.view();
+        for (i, vector) in vectors.iter().enumerate() {
+            view.insert(vector.clone(), Pointer::new(i as u64))
+                .unwrap()
+                .unwrap();
+        }
+        index.seal(view.write_segment.as_ref().unwrap().0);
+        wait(&index);
+        assert_eq!(kinds(&index), ["sealed"]);
+        let expected = search(&index);
+        let id = *index.view().sealed_segments.keys().next().unwrap();
+        let layout = path
+            .join("sealed_segments")
+            .join(id.to_string())
+            .join("layout");
+        assert_eq!(SegmentLayout::read(&layout), SegmentLayout::CURRENT);
+        let outdated = SegmentLayout {
+            indexing: SegmentLayout::CURRENT.indexing - 1,
+            ..SegmentLayout::CURRENT
+        };
+        outdated.write(&layout);
+        drop(view);
+        close(index, &path);
+
+        let index = Index::<VectL2<f32>>::open(path.clone()).unwrap();
+        assert_eq!(kinds(&index), ["stored"]);
+        assert!(index.stat().indexing);
+        assert_eq!(search(&index), expected);
+        wait(&index);
+        assert_eq!(kinds(&index), ["sealed"]);
+        assert_eq!(search(&index), expected);
+        let id = *index.view().sealed_segments.keys().next().unwrap();
+        let layout = path
+            .join("sealed_segments")
+            .join(id.to_string())
+            .join("layout");
+        assert_eq!(SegmentLayout::read(&layout), SegmentLayout::CURRENT);
+
+        let unreadable = SegmentLayout {
+            storage: SegmentLayout::CURRENT.storage + 1,
+            ..SegmentLayout::CURRENT
+        };
+        unreadable.write(&layout);
+        close(index, &path);
+        assert!(matches!(
+            Index::<VectL2<f32>>::open(path.clone()),
+            Err(OpenError::UnreadableSegment { .. })
+        ));
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn snapshot_test() {
+        let mut rng = rand::thread_rng();
+        let mut random = || VectOwned::new((0..DIMS).map(|_| rng.gen_range(-1.0..1.0)).collect());
+        let vectors = (0..300).map(|_| random()).collect::<Vec<VectOwned<f32>>>();
+        let query = random();
+        let temp = std::env::temp_dir();
+        let path = temp.join(format!("index_{}", rand::random::<u64>()));
+        let dest = temp.join(format!("snapshot_{}", rand::random::<u64>()));
+        let restored = temp.join(format!("index_{}", rand::random::<u64>()));
+        let options = IndexOptions {
+            vector: VectorOptions {
+                dims: DIMS,
+                v: VectorKind::Vecf32,
+                d: DistanceKind::L2,
+                weights: None,
+            },
+            indexing: IndexingOptions::Flat(Default::default()),
+        };
+        let opts = SearchOptions {
+            limit: 300,
+            ..Default::default()
+        };
+        let search = |index: &Index<VectL2<f32>>| {
+            let view = index.view();
+            let result = view.vbase(query.as_borrowed(), &opts, None).unwrap();
+            result.map(|(_, p)| p).collect::<Vec<_>>()
+        };
+        let index =
+            Index::<VectL2<f32>>::create(path, options, IndexAlterableOptions::default()).unwrap();
+        let insert = |range: std::ops::Range<usize>| {
+            index.refresh();
+            let view = index.view();
+            for i in range {
+                view.insert(vectors[i].clone(), Pointer::new(i as u64))
+                    .unwrap()
+                    .unwrap();
+            }
+            view.write_segment.as_ref().unwrap().0
+        };
+        // a sealed segment, a read segment and the write segment
+        index.seal(insert(0..100));
+        index.start();
+        while index.stat().indexing {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        index.stop();
+        index.seal(insert(100..200));
+        insert(200..300);
+        index.delete(Pointer::new(7)).unwrap();
+        let expected = search(&index);
+        assert_eq!(expected.len(), 299);
+        index.snapshot(&dest).unwrap();
+        assert!(matches!(
+            index.snapshot(&dest),
+            Err(SnapshotError::Write { .. })
+        ));
+        // changes after the snapshot are not in it
+        index.delete(Pointer::new(8)).unwrap();
+
+        snapshot::restore(&dest, &restored).unwrap();
+        let restored = Index::<VectL2<f32>>::open(restored).unwrap();
+        assert_eq!(search(&restored), expected);
+        let kinds = restored.stat().segments.into_iter().map(|s| s.r#type);
+        assert_eq!(
+            kinds.collect::<HashSet<_>>(),
+            HashSet::from(["sealed".to_string(), "growing".to_string()])
+        );
+        std::fs::remove_dir_all(dest).unwrap();
+    }
+
+    #[test]
+    fn attach_test() {
+        let mut rng = rand::thread_rng();
+        let mut random = || VectOwned::new((0..DIMS).map(|_| rng.gen_range(-1.0..1.0)).collect());
+        let vectors = (0..200).map(|_| random()).collect::<Vec<VectOwned<f32>>>();
+        let query = random();
+        let temp = std::env::temp_dir();
+        let options = |d| IndexOptions {
+            vector: VectorOptions {
+                dims: DIMS,
+                v: VectorKind::Vecf32,
+                d,
+                weights: None,
+            },
+            indexing: IndexingOptions::Flat(Default::default()),
+        };
+        let create = |d| {
+            let path = temp.join(format!("index_{}", rand::random::<u64>()));
+            Index::<VectL2<f32>>::create(path, options(d), IndexAlterableOptions::default())
+                .unwrap()
+        };
+        let insert = |index: &Arc<Index<VectL2<f32>>>, offset: u64| {
+            index.refresh();
+            let view = index.view();
+            for (i, vector) in vectors.iter().enumerate() {
+                view.insert(vector.clone(), Pointer::new(offset + i as u64))
+                    .unwrap()
+                    .unwrap();
+            }
+            view.write_segment.as_ref().unwrap().0
+        };
+        // a sealed segment of an index built by the CLI, whose pointers are labels
+        let prebuilt = create(DistanceKind::L2);
+        prebuilt.seal(insert(&prebuilt, 0));
+        prebuilt.start();
+        while prebuilt.stat().indexing {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        prebuilt.stop();
+        let id = *prebuilt.view().sealed_segments.keys().next().unwrap();
+        let source = prebuilt.path.join("sealed_segments").join(id.to_string());
+
+        // rows are already in the index, as they are after `CREATE INDEX`
+        let index = create(DistanceKind::L2);
+        insert(&index, 1000);
+        let mut labels = (0..200)
+            .map(|i| (i, Pointer::new(1000 + i as u64)))
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            index.attach(&source, &labels[1..]),
+            Err(AttachError::UnknownLabel { label: 0 })
+        ));
+        labels.reverse();
+        index.attach(&source, &labels).unwrap();
+        let opts = SearchOptions {
+            limit: 300,
+            ..Default::default()
+        };
+        let view = index.view();
+        let result = view.vbase(query.as_borrowed(), &opts, None).unwrap();
+        let mut pointers = result.map(|(_, p)| p.as_u64()).collect::<Vec<_>>();
+        pointers.sort_unstable();
+        assert_eq!(pointers, (1000..1200).collect::<Vec<_>>());
+        assert_eq!(view.sealed_segments.len(), 1);
+
+        let other = create(DistanceKind::Dot);
+        assert!(matches!(
+            other.attach(&source, &labels),
+            Err(AttachError::IncompatibleOptions)
+        ));
+    }
+
+    #[test]
+    fn verify_test() {
+        let mut rng = rand::thread_rng();
+        let mut random = || VectOwned::new((0..DIMS).map(|_| rng.gen_range(-1.0..1.0)).collect());
+        let vectors = (0..250).map(|_| random()).collect::<Vec<VectOwned<f32>>>();
+        let path = std::env::temp_dir().join(format!("index_{}", rand::random::<u64>()));
+        let options = IndexOptions {
+            vector: VectorOptions {
+                dims: DIMS,
+                v: VectorKind::Vecf32,
+                d: DistanceKind::L2,
+                weights: None,
+            },
+            indexing: IndexingOptions::Hnsw(Default::default()),
+        };
+        let index =
+            Index::<VectL2<f32>>::create(path.clone(), options, IndexAlterableOptions::default())
+                .unwrap();
+        let insert = |range: std::ops::Range<usize>| {
+            index.refresh();
+            let view = index.view();
+            for i in range {
+                view.insert(vectors[i].clone(), Pointer::new(i as u64))
+                    .unwrap()
+                    .unwrap();
+            }
+            view.write_segment.as_ref().unwrap().0
+        };
+        index.seal(insert(0..200));
+        index.start();
+        while index.stat().indexing {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        index.stop();
+        let write = insert(200..250);
+        index.delete(Pointer::new(3)).unwrap();
+        let sealed = *index.view().sealed_segments.keys().next().unwrap();
+        let failures = |checks: Vec<VerifyCheck>| {
+            checks
+                .into_iter()
+                .filter_map(|c| Some((c.location, c.kind, c.error?)))
+                .collect::<Vec<_>>()
+        };
+
+        let checks = index.verify(&|_| true);
+        let kinds = checks
+            .iter()
+            .map(|c| c.kind.as_str())
+            .collect::<HashSet<_>>();
+        for kind in ["checksum", "payloads", "hnsw_graph", "wal"] {
+            assert!(kinds.contains(kind), "{kind} is not checked");
+        }
+        assert_eq!(failures(checks), []);
+        let failed = failures(index.verify(&|p| p.as_u64() != 5));
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, format!("sealed_segments/{sealed}/payloads"));
+        assert!(failed[0].2.starts_with("1 invalid payloads"));
+        close(index, &path);
+
+        let outs = path
+            .join("sealed_segments")
+            .join(sealed.to_string())
+            .join("base_graph_outs");
+        let mut bytes = std::fs::read(&outs).unwrap();
+        bytes[0..4].copy_from_slice(&1000_u32.to_ne_bytes());
+        std::fs::write(&outs, bytes).unwrap();
+        let record = [0xff_u8; 3];
+        let mut wal = std::fs::OpenOptions::new()
+            .append(true)
+            .open(path.join("wal").join(write.to_string()))
+            .unwrap();
+        std::io::Write::write_all(&mut wal, &crc32fast::hash(&record).to_ne_bytes()).unwrap();
+        std::io::Write::write_all(&mut wal, &3_u32.to_ne_bytes()).unwrap();
+        std::io::Write::write_all(&mut wal, &record).unwrap();
+        let failed = failures(verify::verify::<VectL2<f32>>(&path, &|_| true));
+        let failed = failed
+            .iter()
+            .map(|(location, kind, _)| (location.as_str(), kind.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            failed,
+            [
+                (
+                    format!("sealed_segments/{sealed}/base_graph_outs").as_str(),
+                    "checksum"
+                ),
+                (format!("sealed_segments/{sealed}").as_str(), "hnsw_graph"),
+                (format!("wal/{write}").as_str(), "wal"),
+            ]
+        );
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    /// Drops an index but keeps its directory, which is removed along with the index.
+    fn close<O: Op>(index: Arc<Index<O>>, path: &Path) {
+        let kept = path.with_extension("kept");
+        copy_dir(path, &kept);
+        let removed = index.wait();
+        drop(index);
+        let _ = removed.recv();
+        std::fs::rename(&kept, path).unwrap();
+    }
+
+    fn copy_dir(from: &Path, to: &Path) {
+        std::fs::create_dir(to).unwrap();
+        for entry in std::fs::read_dir(from).unwrap() {
+            let entry = entry.unwrap();
+            if entry.file_type().unwrap().is_dir() {
+                copy_dir(&entry.path(), &to.join(entry.file_name()));
+            } else {
+                std::fs::copy(entry.path(), to.join(entry.file_name())).unwrap();
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::delete::Delete;
 use crate::Op;
-use crate::{GrowingSegment, SealedSegment};
+use crate::{GrowingSegment, SealedSegment, StoredSegment};
 use base::index::IndexOptions;
 use base::operator::Borrowed;
 use base::search::*;
@@ -11,6 +11,7 @@ use std::sync::Arc;
 
 pub struct IndexSource<V, O: Op> {
     pub(super) sealed: Option<Arc<SealedSegment<O>>>,
+    pub(super) stored: Option<Arc<StoredSegment<O>>>,
     pub(super) growing: Vec<Arc<GrowingSegment<O>>>,
     pub(super) dims: u32,
     pub(super) delete: Arc<Delete>,
@@ -21,11 +22,13 @@ impl<O: Op> IndexSource<O::Vector, O> {
     pub fn new(
         options: IndexOptions,
         sealed: Option<Arc<SealedSegment<O>>>,
+        stored: Option<Arc<StoredSegment<O>>>,
         growing: Vec<Arc<GrowingSegment<O>>>,
         delete: Arc<Delete>,
     ) -> Self {
         IndexSource {
             sealed,
+            stored,
             growing,
             dims: options.vector.dims,
             delete,
@@ -41,6 +44,7 @@ impl<O: Op> Vectors<O::Vector> for IndexSource<O::Vector, O> {
 
     fn len(&self) -> u32 {
         self.sealed.iter().map(|x| x.len()).sum::<u32>()
+            + self.stored.iter().map(|x| x.len()).sum::<u32>()
             + self.growing.iter().map(|x| x.len()).sum::<u32>()
     }
 
@@ -51,6 +55,12 @@ impl<O: Op> Vectors<O::Vector> for IndexSource<O::Vector, O> {
             }
             index -= x.len();
         }
+        for x in self.stored.iter() {
+            if index < x.len() {
+                return x.vector(index);
+            }
+            index -= x.len();
+        }
         for x in self.growing.iter() {
             if index < x.len() {
                 return x.vector(index);
@@ -69,6 +79,12 @@ impl<O: Op> Collection for IndexSource<O::Vector, O> {
             }
             index -= x.len();
         }
+        for x in self.stored.iter() {
+            if index < x.len() {
+                return x.payload(index);
+            }
+            index -= x.len();
+        }
         for x in self.growing.iter() {
             if index < x.len() {
                 return x.payload(index);
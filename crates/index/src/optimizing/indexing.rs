@@ -8,8 +8,12 @@ pub fn scan<O: Op>(
     capacity: u32,
     delete_threshold: f64,
 ) -> Option<IndexSource<O::Vector, O>> {
-    let (sealed, growing) = 'a: {
+    let (sealed, stored, growing) = 'a: {
         let protect = index.protect.lock();
+        // approach 0: rebuild a segment written in an outdated layout
+        if let Some(stored_segment) = protect.stored_segments.values().next() {
+            break 'a (None, Some(stored_segment.clone()), Vec::new());
+        }
         // approach 1: merge small segments to a big segment
         {
             let mut counter = 0u64;
@@ -35,7 +39,7 @@ pub fn scan<O: Op>(
                 delta_segments
             };
             if !delta_segments.is_empty() {
-                break 'a (base_segment, delta_segments);
+                break 'a (base_segment, None, delta_segments);
             }
         }
         // approach 2: merge small segments
@@ -56,7 +60,7 @@ pub fn scan<O: Op>(
                 delta_segments
             };
             if !delta_segments.is_empty() {
-                break 'a (None, delta_segments);
+                break 'a (None, None, delta_segments);
             }
         }
         // approach 3: vacuum sealed segment
@@ -71,7 +75,7 @@ pub fn scan<O: Op>(
                 }
                 let value = counter as f64 / sealed_segment.len() as f64;
                 if value >= delete_threshold {
-                    break 'a (Some(sealed_segment.clone()), Vec::new());
+                    break 'a (Some(sealed_segment.clone()), None, Vec::new());
                 }
             }
             index.set_check_deleted_flag();
@@ -81,6 +85,7 @@ pub fn scan<O: Op>(
     Some(IndexSource::new(
         index.options().clone(),
         sealed.clone(),
+        stored.clone(),
         growing.clone(),
         index.delete.clone(),
     ))
@@ -89,7 +94,9 @@ pub fn scan<O: Op>(
 pub fn make<O: Op>(index: Arc<Index<O>>, source: IndexSource<O::Vector, O>) {
     let _ = index.create_sealed_segment(
         &source,
-        &source.sealed.iter().map(|x| x.id()).collect::<Vec<_>>(),
+        &(source.sealed.iter().map(|x| x.id()))
+            .chain(source.stored.iter().map(|x| x.id()))
+            .collect::<Vec<_>>(),
         &source.growing.iter().map(|x| x.id()).collect::<Vec<_>>(),
     );
 }
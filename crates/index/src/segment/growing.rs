@@ -2,17 +2,20 @@ use crate::utils::file_wal::FileWal;
 use crate::IndexTracker;
 use crate::Op;
 use base::always_equal::AlwaysEqual;
+use base::distance::Distance;
 use base::index::*;
 use base::operator::*;
 use base::search::*;
 use base::vector::*;
-use parking_lot::Mutex;
+use graph::visited::VisitedPool;
+use parking_lot::{Mutex, RwLock};
+use quantization::reranker::graph::GraphReranker;
 use serde::{Deserialize, Serialize};
 use std::cell::UnsafeCell;
 use std::fmt::Debug;
 use std::mem::MaybeUninit;
 use std::num::NonZeroU128;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use thiserror::Error;
@@ -21,12 +24,20 @@ use thiserror::Error;
 #[error("`GrowingSegment` is read-only.")]
 pub struct GrowingSegmentInsertError;
 
+const GRAPH_M: u32 = 16;
+const GRAPH_EF_CONSTRUCTION: u32 = 64;
+const GRAPH_THRESHOLD: usize = 1024;
+
 pub struct GrowingSegment<O: Op> {
     id: NonZeroU128,
     vec: Vec<MaybeUninit<UnsafeCell<Log<O>>>>,
     wal: Mutex<FileWal>,
     len: AtomicUsize,
     pro: Mutex<Protect>,
+    // vectors are linked only if the index is approximate, otherwise it's always scanned
+    approximate: bool,
+    graph: Vec<RwLock<Vec<(Distance, u32)>>>,
+    visited: VisitedPool,
     _growing_segment_tracker: GrowingSegmentTracker,
     _index_tracker: Arc<IndexTracker>,
 }
@@ -45,8 +56,10 @@ impl<O: Op> GrowingSegment<O> {
         path: PathBuf,
         id: NonZeroU128,
         capacity: usize,
+        options: &IndexOptions,
     ) -> Arc<Self> {
         let wal = FileWal::create(&path);
+        let approximate = approximate(options);
         Arc::new(Self {
             id,
             vec: unsafe {
@@ -60,6 +73,9 @@ impl<O: Op> GrowingSegment<O> {
                 inflight: 0,
                 capacity,
             }),
+            approximate,
+            graph: (0..capacity).map(|_| RwLock::new(Vec::new())).collect(),
+            visited: VisitedPool::new(capacity as u32),
             _growing_segment_tracker: GrowingSegmentTracker { path },
             _index_tracker: index_tracker,
         })
@@ -69,7 +85,7 @@ impl<O: Op> GrowingSegment<O> {
         index_tracker: Arc<IndexTracker>,
         path: PathBuf,
         id: NonZeroU128,
-        _: IndexOptions,
+        options: IndexOptions,
     ) -> Arc<Self> {
         let mut wal = FileWal::open(&path);
         let mut vec = Vec::new();
@@ -79,7 +95,7 @@ impl<O: Op> GrowingSegment<O> {
         }
         wal.truncate();
         let n = vec.len();
-        Arc::new(Self {
+        let segment = Self {
             id,
             vec,
             wal: { Mutex::new(wal) },
@@ -88,9 +104,30 @@ impl<O: Op> GrowingSegment<O> {
                 inflight: n,
                 capacity: n,
             }),
+            approximate: approximate(&options),
+            graph: (0..n).map(|_| RwLock::new(Vec::new())).collect(),
+            visited: VisitedPool::new(n as u32),
             _growing_segment_tracker: GrowingSegmentTracker { path },
             _index_tracker: index_tracker,
-        })
+        };
+        if !segment.approximate {
+            return Arc::new(segment);
+        }
+        // vertices are linked in parallel, as concurrent inserts do
+        let next = AtomicUsize::new(0);
+        let threads = std::thread::available_parallelism().map_or(1, |x| x.get());
+        std::thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= n {
+                        break;
+                    }
+                    segment.link(i as u32);
+                });
+            }
+        });
+        Arc::new(segment)
     }
 
     pub fn id(&self) -> NonZeroU128 {
@@ -129,6 +166,17 @@ impl<O: Op> GrowingSegment<O> {
         self.wal.lock().sync_all();
     }
 
+    /// Writes the logs of all inserted vectors to a new WAL file, instead of copying the WAL
+    /// file, which may lag behind `len`.
+    pub fn snapshot(&self, path: &Path) {
+        let mut wal = FileWal::create(path);
+        for i in 0..self.len.load(Ordering::Acquire) {
+            let log = unsafe { &*self.vec[i].assume_init_ref().get().cast_const() };
+            wal.write(&bincode::serialize::<Log<O>>(log).unwrap());
+        }
+        wal.sync_all();
+    }
+
     pub fn insert(
         &self,
         vector: O::Vector,
@@ -154,9 +202,42 @@ impl<O: Op> GrowingSegment<O> {
         self.wal
             .lock()
             .write(&bincode::serialize::<Log<O>>(&log).unwrap());
+        if self.approximate {
+            self.link(i as u32);
+        }
         Ok(())
     }
 
+    fn link(&self, u: u32) {
+        if u == 0 {
+            return;
+        }
+        let dist = |x: u32, y: u32| O::distance(self.vector(x), self.vector(y));
+        let mut visited = self.visited.fetch_guard();
+        let scope = graph::search::search(
+            |v| dist(u, v),
+            |v| outs(&self.graph, v),
+            &mut visited,
+            0,
+            GRAPH_EF_CONSTRUCTION,
+        );
+        let mut edges = Vec::new();
+        graph::prune::prune(dist, u, &mut edges, &scope, GRAPH_M);
+        self.graph[u as usize].write().clone_from(&edges);
+        for (dis_v, v) in edges {
+            let mut lock = self.graph[v as usize].write();
+            if lock.iter().any(|(_, k)| *k == u) {
+                continue;
+            }
+            if lock.len() < GRAPH_M as usize {
+                let (Ok(index) | Err(index)) = lock.binary_search(&(dis_v, u));
+                lock.insert(index, (dis_v, u));
+                continue;
+            }
+            graph::prune::prune(dist, v, &mut lock, &[(dis_v, u)], GRAPH_M);
+        }
+    }
+
     pub fn len(&self) -> u32 {
         self.len.load(Ordering::Acquire) as u32
     }
@@ -202,13 +283,43 @@ impl<O: Op> GrowingSegment<O> {
     pub fn vbase<'a>(
         &'a self,
         vector: Borrowed<'a, O>,
-        _opts: &SearchOptions,
+        opts: &'a SearchOptions,
+        filter: Option<&'a AllowList>,
     ) -> Box<dyn Iterator<Item = Element> + 'a> {
         let n = self.len.load(Ordering::Acquire);
+        if self.approximate && n > GRAPH_THRESHOLD {
+            let reranker = GraphReranker::new(move |u| {
+                (
+                    O::distance(vector, self.vector(u)),
+                    (outs(&self.graph, u), ()),
+                )
+            });
+            return Box::new(
+                graph::search::vbase_generic(
+                    &self.visited,
+                    0,
+                    reranker,
+                    move |u| filter.map_or(true, |f| f.check(self.payload(u))),
+                    opts.hnsw_ef_search,
+                    Distance::from_f32(opts.radius),
+                )
+                .map(|(distance, u, ())| Element {
+                    distance,
+                    payload: AlwaysEqual(self.payload(u)),
+                }),
+            );
+        }
+        let radius = Distance::from_f32(opts.radius);
         let mut result = Vec::new();
         for i in 0..n {
             let log = unsafe { &*self.vec[i].assume_init_ref().get().cast_const() };
+            if !filter.map_or(true, |f| f.check(log.payload)) {
+                continue;
+            }
             let distance = O::distance(vector, log.vector.as_borrowed());
+            if distance > radius {
+                continue;
+            }
             result.push(Element {
                 distance,
                 payload: AlwaysEqual(log.payload),
@@ -233,10 +344,30 @@ impl<O: Op> Drop for GrowingSegment<O> {
     }
 }
 
+/// Growing segments are searched by the graph only if sealed segments are also approximate, so
+/// that exact indexes return exact results.
+fn approximate(options: &IndexOptions) -> bool {
+    matches!(
+        options.indexing,
+        IndexingOptions::Hnsw(_) | IndexingOptions::Vamana(_)
+    )
+}
+
+/// Copies neighbours out of the lock, so that none is held while they are visited.
+fn outs(graph: &[RwLock<Vec<(Distance, u32)>>], u: u32) -> impl Iterator<Item = u32> {
+    let mut outs = [0u32; GRAPH_M as usize];
+    let lock = graph[u as usize].read();
+    for (o, &(_, v)) in outs.iter_mut().zip(lock.iter()) {
+        *o = v;
+    }
+    let n = std::cmp::min(lock.len(), GRAPH_M as usize);
+    outs.into_iter().take(n)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct Log<O: Op> {
-    vector: O::Vector,
-    payload: Payload,
+pub(crate) struct Log<O: Op> {
+    pub(crate) vector: O::Vector,
+    pub(crate) payload: Payload,
 }
 
 #[derive(Debug, Clone)]
@@ -255,3 +386,101 @@ impl Drop for GrowingSegmentTracker {
         std::fs::remove_file(&self.path).unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const DIMS: usize = 16;
+
+    #[test]
+    fn graph_test() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut random = || VectOwned::new((0..DIMS).map(|_| rng.gen_range(-1.0..1.0)).collect());
+        let vectors = (0..3000).map(|_| random()).collect::<Vec<VectOwned<f32>>>();
+        let queries = (0..20).map(|_| random()).collect::<Vec<VectOwned<f32>>>();
+        let dir = std::env::temp_dir().join(format!("growing_{}", rand::random::<u64>()));
+        std::fs::create_dir(&dir).unwrap();
+        let tracker = Arc::new(IndexTracker::new(dir.clone()));
+        let id = NonZeroU128::new(1).unwrap();
+        let options = |indexing| IndexOptions {
+            vector: VectorOptions {
+                dims: DIMS as u32,
+                v: VectorKind::Vecf32,
+                d: base::distance::DistanceKind::L2,
+                weights: None,
+            },
+            indexing,
+        };
+        let hnsw = options(IndexingOptions::Hnsw(Default::default()));
+        let segment = GrowingSegment::<VectL2<f32>>::create(
+            tracker.clone(),
+            dir.join("wal"),
+            id,
+            vectors.len(),
+            &hnsw,
+        );
+        let opts = SearchOptions::default();
+        // returns the recall of the top 10 results, as a number of hits of all queries
+        let recall = |segment: &GrowingSegment<VectL2<f32>>| {
+            let n = segment.len() as usize;
+            let mut hits = 0;
+            for query in queries.iter() {
+                let mut truth = (0..n)
+                    .map(|i| {
+                        (
+                            VectL2::distance(query.as_borrowed(), vectors[i].as_borrowed()),
+                            i,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                truth.sort_unstable();
+                let truth = truth[..10]
+                    .iter()
+                    .map(|&(_, i)| i as u64)
+                    .collect::<Vec<_>>();
+                let result = segment.vbase(query.as_borrowed(), &opts, None);
+                let result = result.take(10).map(|e| e.payload.0.pointer().as_u64());
+                hits += result.filter(|p| truth.contains(p)).count();
+            }
+            hits
+        };
+        let insert = |segment: &GrowingSegment<VectL2<f32>>, range: std::ops::Range<usize>| {
+            for i in range {
+                let payload = Payload::new(Pointer::new(i as u64), 0);
+                segment.insert(vectors[i].clone(), payload).unwrap();
+            }
+        };
+
+        // below the threshold, vectors are scanned
+        insert(&segment, 0..GRAPH_THRESHOLD);
+        assert_eq!(recall(&segment), 200);
+        // above the threshold, the graph is searched
+        insert(&segment, GRAPH_THRESHOLD..vectors.len());
+        assert!(segment.is_full());
+        let hits = recall(&segment);
+        assert!(hits >= 180, "recall: {}", hits as f64 / 200.0);
+
+        // the graph is linked again from the WAL
+        segment.snapshot(&dir.join("copy"));
+        let copy = GrowingSegment::<VectL2<f32>>::open(tracker.clone(), dir.join("copy"), id, hnsw);
+        assert_eq!(copy.len(), segment.len());
+        assert!((0..copy.len()).all(|u| !copy.graph[u as usize].read().is_empty()));
+        let hits = recall(&copy);
+        assert!(hits >= 180, "recall: {}", hits as f64 / 200.0);
+
+        // segments of exact indexes are always scanned
+        let flat = GrowingSegment::<VectL2<f32>>::create(
+            tracker,
+            dir.join("flat"),
+            id,
+            vectors.len(),
+            &options(IndexingOptions::Flat(Default::default())),
+        );
+        insert(&flat, 0..vectors.len());
+        assert_eq!(recall(&flat), 200);
+        assert!((0..flat.len()).all(|u| flat.graph[u as usize].read().is_empty()));
+    }
+}
@@ -1,2 +1,3 @@
 pub mod growing;
 pub mod sealed;
+pub mod stored;
@@ -1,4 +1,6 @@
+use crate::layout::SegmentLayout;
 use crate::utils::dir_ops::dir_size;
+use crate::verify::write_checksums;
 use crate::IndexTracker;
 use crate::Op;
 use base::index::*;
@@ -11,6 +13,7 @@ use std::fmt::Debug;
 use std::num::NonZeroU128;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::OnceLock;
 use std::time::Duration;
 use std::time::Instant;
 
@@ -19,6 +22,7 @@ pub struct SealedSegment<O: Op> {
     path: PathBuf,
     indexing: SealedIndexing<O>,
     deletes: AtomicCell<(Instant, u32)>,
+    pointers: OnceLock<Vec<(Pointer, u32)>>,
     _sealed_segment_tracker: SealedSegmentTracker,
     _index_tracker: Arc<IndexTracker>,
 }
@@ -40,11 +44,14 @@ impl<O: Op> SealedSegment<O> {
         source: &(impl Vectors<O::Vector> + Collection + Source + Sync),
     ) -> Arc<Self> {
         let indexing = SealedIndexing::create(&path, options, source);
+        SegmentLayout::CURRENT.write(path.join("layout"));
+        write_checksums(&path).unwrap();
         Arc::new(Self {
             id,
             path: path.clone(),
             indexing,
             deletes: AtomicCell::new((Instant::now(), 0)),
+            pointers: OnceLock::new(),
             _sealed_segment_tracker: SealedSegmentTracker { path },
             _index_tracker: index_tracker,
         })
@@ -62,6 +69,7 @@ impl<O: Op> SealedSegment<O> {
             path: path.clone(),
             indexing,
             deletes: AtomicCell::new((Instant::now(), 0)),
+            pointers: OnceLock::new(),
             _sealed_segment_tracker: SealedSegmentTracker { path },
             _index_tracker: index_tracker,
         })
@@ -84,8 +92,9 @@ impl<O: Op> SealedSegment<O> {
         &'a self,
         vector: Borrowed<'a, O>,
         opts: &'a SearchOptions,
+        filter: Option<&'a AllowList>,
     ) -> Box<dyn Iterator<Item = Element> + 'a> {
-        self.indexing.vbase(vector, opts)
+        self.indexing.vbase(vector, opts, filter)
     }
 
     pub fn len(&self) -> u32 {
@@ -100,6 +109,22 @@ impl<O: Op> SealedSegment<O> {
         self.indexing.payload(i)
     }
 
+    /// Returns ids of all vectors inserted with the pointer, building a lookup table on first use.
+    pub fn lookup(&self, pointer: Pointer) -> impl Iterator<Item = u32> + '_ {
+        let pointers = self.pointers.get_or_init(|| {
+            let mut pointers = (0..self.len())
+                .map(|i| (self.payload(i).pointer(), i))
+                .collect::<Vec<_>>();
+            pointers.sort_unstable();
+            pointers
+        });
+        let start = pointers.partition_point(|&(p, _)| p < pointer);
+        pointers[start..]
+            .iter()
+            .take_while(move |&&(p, _)| p == pointer)
+            .map(|&(_, i)| i)
+    }
+
     pub fn inspect(&self, d: Duration, check: impl Fn(u64) -> bool) -> Result<u32, u32> {
         let (t, c) = self.deletes.load();
         if t.elapsed() > d {
@@ -123,7 +148,7 @@ impl<O: Op> SealedSegment<O> {
 
 #[derive(Debug, Clone)]
 pub struct SealedSegmentTracker {
-    path: PathBuf,
+    pub(super) path: PathBuf,
 }
 
 impl Drop for SealedSegmentTracker {
@@ -0,0 +1,101 @@
+use super::sealed::SealedSegmentTracker;
+use crate::utils::dir_ops::dir_size;
+use crate::IndexTracker;
+use crate::Op;
+use base::always_equal::AlwaysEqual;
+use base::distance::Distance;
+use base::index::*;
+use base::operator::*;
+use base::search::*;
+use common::mmap_array::MmapArray;
+use std::fmt::Debug;
+use std::num::NonZeroU128;
+use std::path::PathBuf;
+use std::sync::Arc;
+use storage::OperatorStorage;
+use storage::Storage;
+
+/// A sealed segment written in an outdated layout, opened only by its stored vectors and
+/// payloads. It's searched by brute force until the optimizer rebuilds it.
+pub struct StoredSegment<O: Op> {
+    id: NonZeroU128,
+    path: PathBuf,
+    storage: <O as OperatorStorage>::Storage,
+    payloads: MmapArray<Payload>,
+    _sealed_segment_tracker: SealedSegmentTracker,
+    _index_tracker: Arc<IndexTracker>,
+}
+
+impl<O: Op> Debug for StoredSegment<O> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoredSegment")
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl<O: Op> StoredSegment<O> {
+    pub fn open(index_tracker: Arc<IndexTracker>, path: PathBuf, id: NonZeroU128) -> Arc<Self> {
+        let storage = O::Storage::open(path.join("storage"));
+        let payloads = MmapArray::open(path.join("payloads"));
+        Arc::new(Self {
+            id,
+            path: path.clone(),
+            storage,
+            payloads,
+            _sealed_segment_tracker: SealedSegmentTracker { path },
+            _index_tracker: index_tracker,
+        })
+    }
+
+    pub fn id(&self) -> NonZeroU128 {
+        self.id
+    }
+
+    pub fn stat_stored(&self) -> SegmentStat {
+        SegmentStat {
+            id: self.id,
+            r#type: "stored".to_string(),
+            length: self.len() as usize,
+            size: dir_size(&self.path).unwrap(),
+        }
+    }
+
+    pub fn vbase<'a>(
+        &'a self,
+        vector: Borrowed<'a, O>,
+        opts: &'a SearchOptions,
+        filter: Option<&'a AllowList>,
+    ) -> Box<dyn Iterator<Item = Element> + 'a> {
+        let radius = Distance::from_f32(opts.radius);
+        let mut result = Vec::new();
+        for i in 0..self.len() {
+            let payload = self.payload(i);
+            if !filter.map_or(true, |f| f.check(payload)) {
+                continue;
+            }
+            let distance = O::distance(vector, self.vector(i));
+            if distance > radius {
+                continue;
+            }
+            result.push(Element {
+                distance,
+                payload: AlwaysEqual(payload),
+            });
+        }
+        result.sort_unstable();
+        Box::new(result.into_iter())
+    }
+
+    pub fn len(&self) -> u32 {
+        self.storage.len()
+    }
+
+    pub fn vector(&self, i: u32) -> Borrowed<'_, O> {
+        self.storage.vector(i)
+    }
+
+    pub fn payload(&self, i: u32) -> Payload {
+        self.payloads[i as usize]
+    }
+}
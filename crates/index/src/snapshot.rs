@@ -0,0 +1,77 @@
+//! Self-contained copies of index directories.
+//!
+//! Files of sealed segments are never written after the segment is created, so they are
+//! hard-linked instead of copied. Snapshots must be on the same file system as the index.
+
+use crate::layout::SegmentLayout;
+use base::index::*;
+use common::dir_ops::sync_walk_from_dir;
+use std::io;
+use std::path::Path;
+
+/// Reads the options of the index that a snapshot was taken from.
+pub fn options(source: &Path) -> io::Result<IndexOptions> {
+    let options = std::fs::read(source.join("options"))?;
+    serde_json::from_slice(&options).map_err(io::Error::other)
+}
+
+/// Materializes a snapshot as an index directory at `path`, which is then opened as an index.
+pub fn restore(source: &Path, path: &Path) -> io::Result<()> {
+    std::fs::create_dir(path)?;
+    std::fs::copy(source.join("options"), path.join("options"))?;
+    std::fs::copy(source.join("delete"), path.join("delete"))?;
+    copy_dir(&source.join("startup"), &path.join("startup"))?;
+    copy_dir(&source.join("wal"), &path.join("wal"))?;
+    link_dir(
+        &source.join("sealed_segments"),
+        &path.join("sealed_segments"),
+    )?;
+    sync_walk_from_dir(path);
+    Ok(())
+}
+
+/// Rewrites the options of a snapshot, whose sealed segments are then rebuilt from their stored
+/// vectors once it's opened, as segments written in an outdated layout are.
+pub fn convert(path: &Path, options: &IndexOptions) -> io::Result<()> {
+    let options = serde_json::to_vec(options).map_err(io::Error::other)?;
+    std::fs::write(path.join("options"), options)?;
+    for entry in std::fs::read_dir(path.join("sealed_segments"))? {
+        let layout = entry?.path().join("layout");
+        if !SegmentLayout::read(&layout).is_rebuildable() {
+            continue;
+        }
+        // the file is hard-linked, so it's replaced instead of written in place
+        if layout.try_exists()? {
+            std::fs::remove_file(&layout)?;
+        }
+        SegmentLayout::UNINDEXED.write(&layout);
+    }
+    sync_walk_from_dir(path);
+    Ok(())
+}
+
+pub(crate) fn link_dir(src: &Path, dst: &Path) -> io::Result<()> {
+    std::fs::create_dir(dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            link_dir(&entry.path(), &dst.join(entry.file_name()))?;
+        } else {
+            std::fs::hard_link(entry.path(), dst.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+fn copy_dir(src: &Path, dst: &Path) -> io::Result<()> {
+    std::fs::create_dir(dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &dst.join(entry.file_name()))?;
+        } else {
+            std::fs::copy(entry.path(), dst.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
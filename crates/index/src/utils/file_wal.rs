@@ -113,6 +113,28 @@ impl FileWal {
     }
 }
 
+/// Reads records of a wal without opening it for writing. As `FileWal::read` does, reading stops
+/// at the first torn or corrupted record, and the bytes from it are left out.
+pub fn inspect(path: impl AsRef<Path>) -> std::io::Result<Vec<Vec<u8>>> {
+    use byteorder::ByteOrder;
+    let bytes = std::fs::read(path)?;
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while let Some(header) = bytes.get(offset..offset + 8) {
+        let crc = N::read_u32(&header[0..4]);
+        let len = N::read_u32(&header[4..8]) as usize;
+        let Some(data) = bytes.get(offset + 8..offset + 8 + len) else {
+            break;
+        };
+        if crc32(data) != crc {
+            break;
+        }
+        records.push(data.to_vec());
+        offset += 8 + len;
+    }
+    Ok(records)
+}
+
 #[derive(Debug, Clone, Copy)]
 enum WalStatus {
     Read,
@@ -0,0 +1,261 @@
+//! Integrity checks of index directories, which report problems instead of panicking.
+
+use crate::delete;
+use crate::layout::SegmentLayout;
+use crate::segment::growing;
+use crate::utils::file_wal;
+use crate::{IndexStartup, Op};
+use base::index::*;
+use base::search::*;
+use base::vector::*;
+use common::mmap_array::MmapArray;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Checksum {
+    len: u64,
+    crc: u32,
+}
+
+fn checksum(path: &Path) -> io::Result<Checksum> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0u8; 1 << 20];
+    let mut len = 0;
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        len += n as u64;
+    }
+    let crc = hasher.finalize();
+    Ok(Checksum { len, crc })
+}
+
+fn files(path: &Path, prefix: &str, result: &mut Vec<String>) -> io::Result<()> {
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let name = format!("{prefix}{}", entry.file_name().to_string_lossy());
+        if entry.file_type()?.is_dir() {
+            files(&entry.path(), &format!("{name}/"), result)?;
+        } else if name != "checksums" {
+            result.push(name);
+        }
+    }
+    Ok(())
+}
+
+/// Records lengths and checksums of files of a sealed segment to `checksums`.
+pub(crate) fn write_checksums(path: &Path) -> io::Result<()> {
+    let mut names = Vec::new();
+    files(path, "", &mut names)?;
+    let mut checksums = BTreeMap::new();
+    for name in names {
+        checksums.insert(name.clone(), checksum(&path.join(name))?);
+    }
+    let checksums = serde_json::to_string(&checksums).map_err(io::Error::other)?;
+    std::fs::write(path.join("checksums"), checksums)
+}
+
+fn read_json<T: for<'a> Deserialize<'a>>(path: &Path) -> Result<T, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    serde_json::from_slice(&bytes).map_err(|e| e.to_string())
+}
+
+#[derive(Default)]
+struct Report {
+    checks: Vec<VerifyCheck>,
+}
+
+impl Report {
+    fn check(&mut self, location: &str, kind: &str, result: Result<(), String>) -> bool {
+        let passed = result.is_ok();
+        self.checks.push(VerifyCheck {
+            location: location.to_string(),
+            kind: kind.to_string(),
+            error: result.err(),
+        });
+        passed
+    }
+}
+
+/// Checks payloads, reporting the number of invalid ones and the first of them.
+fn check_payloads(
+    payloads: impl Iterator<Item = Payload>,
+    versions: &HashMap<Pointer, u64>,
+    valid: &dyn Fn(Pointer) -> bool,
+) -> Result<(), String> {
+    let mut first = None;
+    let mut count = 0_usize;
+    for (i, payload) in payloads.enumerate() {
+        let pointer = payload.pointer();
+        let version = versions.get(&pointer).copied().unwrap_or(0);
+        let error = if !valid(pointer) {
+            format!("pointer {} of vector {i} is invalid", pointer.as_u64())
+        } else if payload.time() > version {
+            format!("vector {i} is newer than the deletions of its pointer")
+        } else {
+            continue;
+        };
+        first.get_or_insert(error);
+        count += 1;
+    }
+    match first {
+        None => Ok(()),
+        Some(first) => Err(format!("{count} invalid payloads, and {first}")),
+    }
+}
+
+/// Verifies the index directory at `path`. Pointers of payloads are checked by `valid`.
+///
+/// Files are only read, so the index may be verified while it's used, as long as its segments
+/// are neither sealed nor merged meanwhile. Records at the tail of logs that are torn are
+/// skipped, as they are when the index is opened.
+pub fn verify<O: Op>(path: &Path, valid: &dyn Fn(Pointer) -> bool) -> Vec<VerifyCheck> {
+    let mut report = Report::default();
+    let options = read_json::<IndexOptions>(&path.join("options"));
+    let options = match options {
+        Ok(options) => options,
+        Err(e) => {
+            report.check("options", "options", Err(e));
+            return report.checks;
+        }
+    };
+    report.check("options", "options", Ok(()));
+    let startup = read_json::<IndexStartup>(&path.join("startup").join("0"));
+    let startup = match startup {
+        Ok(startup) if startup.layout > IndexStartup::LAYOUT => {
+            let layout = startup.layout;
+            report.check(
+                "startup",
+                "startup",
+                Err(format!("layout {layout} is newer")),
+            );
+            return report.checks;
+        }
+        Ok(startup) => startup,
+        Err(e) => {
+            report.check("startup", "startup", Err(e));
+            return report.checks;
+        }
+    };
+    let result = if startup.replaying {
+        Err("replaying the log of the relation was interrupted".to_string())
+    } else {
+        Ok(())
+    };
+    report.check("startup", "startup", result);
+
+    let mut versions = HashMap::<Pointer, u64>::new();
+    let result = file_wal::inspect(path.join("delete")).map_err(|e| e.to_string());
+    let result = result.and_then(|records| {
+        for (i, record) in records.iter().enumerate() {
+            let log = bincode::deserialize::<delete::Log>(record)
+                .map_err(|e| format!("record {i} is invalid: {e}"))?;
+            *versions.entry(log.key).or_default() += 1;
+        }
+        Ok(())
+    });
+    report.check("delete", "wal", result);
+
+    let mut sealed_segment_ids = startup.sealed_segment_ids.iter().collect::<Vec<_>>();
+    sealed_segment_ids.sort();
+    for id in sealed_segment_ids {
+        let location = format!("sealed_segments/{id}");
+        let path = path.join("sealed_segments").join(id.to_string());
+        let layout = if path.join("layout").try_exists().unwrap_or(false) {
+            read_json::<SegmentLayout>(&path.join("layout"))
+        } else {
+            Ok(SegmentLayout::LEGACY)
+        };
+        let result = match layout {
+            Ok(layout) if layout == SegmentLayout::CURRENT => Ok(()),
+            // rebuilt from its stored vectors once the index is opened, so it's not checked
+            Ok(layout) if layout.is_rebuildable() => {
+                report.check(&location, "layout", Ok(()));
+                continue;
+            }
+            Ok(layout) => Err(format!("layout {layout:?} is unreadable")),
+            Err(e) => Err(e),
+        };
+        if !report.check(&location, "layout", result) {
+            continue;
+        }
+        if path.join("checksums").try_exists().unwrap_or(false) {
+            match read_json::<BTreeMap<String, Checksum>>(&path.join("checksums")) {
+                Ok(checksums) => {
+                    for (name, expected) in checksums {
+                        let result = match checksum(&path.join(&name)) {
+                            Ok(actual) if actual.len != expected.len => Err(format!(
+                                "the file is of {} bytes, but {} bytes are expected",
+                                actual.len, expected.len
+                            )),
+                            Ok(actual) if actual.crc != expected.crc => {
+                                Err("the checksum does not match".to_string())
+                            }
+                            Ok(_) => Ok(()),
+                            Err(e) => Err(e.to_string()),
+                        };
+                        report.check(&format!("{location}/{name}"), "checksum", result);
+                    }
+                }
+                Err(e) => {
+                    report.check(&format!("{location}/checksums"), "checksum", Err(e));
+                }
+            }
+        }
+        let payloads = match MmapArray::<Payload>::try_open(path.join("payloads")) {
+            Ok(payloads) => payloads,
+            Err(e) => {
+                report.check(
+                    &format!("{location}/payloads"),
+                    "payloads",
+                    Err(e.to_string()),
+                );
+                continue;
+            }
+        };
+        let result = check_payloads(payloads.iter().copied(), &versions, valid);
+        report.check(&format!("{location}/payloads"), "payloads", result);
+        let Ok(n) = u32::try_from(payloads.len()) else {
+            continue;
+        };
+        if let Some((kind, result)) = indexing::verify(&path, &options.indexing, n) {
+            report.check(&location, kind, result);
+        }
+    }
+
+    let mut growing_segment_ids = startup.growing_segment_ids.iter().collect::<Vec<_>>();
+    growing_segment_ids.sort();
+    for id in growing_segment_ids {
+        let location = format!("wal/{id}");
+        let records = match file_wal::inspect(path.join("wal").join(id.to_string())) {
+            Ok(records) => records,
+            Err(e) => {
+                report.check(&location, "wal", Err(e.to_string()));
+                continue;
+            }
+        };
+        let mut payloads = Vec::with_capacity(records.len());
+        let result = records.iter().enumerate().try_for_each(|(i, record)| {
+            let log = bincode::deserialize::<growing::Log<O>>(record)
+                .map_err(|e| format!("record {i} is invalid: {e}"))?;
+            let dims = log.vector.as_borrowed().dims();
+            if dims != options.vector.dims {
+                return Err(format!("record {i} is a vector of {dims} dimensions"));
+            }
+            payloads.push(log.payload);
+            Ok(())
+        });
+        if report.check(&location, "wal", result) {
+            let result = check_payloads(payloads.into_iter(), &versions, valid);
+            report.check(&location, "payloads", result);
+        }
+    }
+    report.checks
+}
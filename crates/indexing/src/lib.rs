@@ -3,11 +3,18 @@ pub mod sealed;
 use quantization::rabitq::OperatorRabitqQuantization;
 pub use sealed::SealedIndexing;
 
+use base::index::IndexingOptions;
 use base::operator::Operator;
 use ivf::operator::OperatorIvf;
 use quantization::product::OperatorProductQuantization;
 use quantization::scalar::OperatorScalarQuantization;
 use sparse_inverted_index::operator::OperatorSparseInvertedIndex;
+use std::path::Path;
+
+/// Version of the on-disk layout of sealed indexing, bumped whenever any algorithm changes it.
+///
+/// * 2: block-max summaries and quantized weights in the sparse inverted index.
+pub const LAYOUT: u32 = 2;
 
 pub trait OperatorIndexing
 where
@@ -30,3 +37,17 @@ where
     Self: OperatorRabitqQuantization,
 {
 }
+
+/// Checks invariants of the files of a sealed indexing of `n` vectors, with the name of the check,
+/// for indexings that have any.
+pub fn verify(
+    path: impl AsRef<Path>,
+    options: &IndexingOptions,
+    n: u32,
+) -> Option<(&'static str, Result<(), String>)> {
+    match options {
+        IndexingOptions::Hnsw(_) => Some(("hnsw_graph", hnsw::verify(path, n))),
+        IndexingOptions::Ivf(_) => Some(("ivf_offsets", ivf::verify(path, n))),
+        _ => None,
+    }
+}
@@ -12,6 +12,7 @@ use quantization::trivial::TrivialQuantizer;
 use sparse_inverted_index::SparseInvertedIndex;
 use std::any::Any;
 use std::path::Path;
+use vamana::Vamana;
 
 pub enum SealedIndexing<O: OperatorIndexing> {
     Flat(Flat<O, TrivialQuantizer<O>>),
@@ -26,6 +27,10 @@ pub enum SealedIndexing<O: OperatorIndexing> {
     HnswSq(Hnsw<O, ScalarQuantizer<O>>),
     HnswPq(Hnsw<O, ProductQuantizer<O>>),
     HnswRq(Hnsw<O, RabitqQuantizer<O>>),
+    Vamana(Vamana<O, TrivialQuantizer<O>>),
+    VamanaSq(Vamana<O, ScalarQuantizer<O>>),
+    VamanaPq(Vamana<O, ProductQuantizer<O>>),
+    VamanaRq(Vamana<O, RabitqQuantizer<O>>),
     SparseInvertedIndex(SparseInvertedIndex<O>),
 }
 
@@ -78,6 +83,20 @@ impl<O: OperatorIndexing> SealedIndexing<O> {
                     Self::HnswRq(Hnsw::create(path, options, source))
                 }
             },
+            IndexingOptions::Vamana(VamanaIndexingOptions {
+                ref quantization, ..
+            }) => match quantization {
+                None => Self::Vamana(Vamana::create(path, options, source)),
+                Some(QuantizationOptions::Scalar(_)) => {
+                    Self::VamanaSq(Vamana::create(path, options, source))
+                }
+                Some(QuantizationOptions::Product(_)) => {
+                    Self::VamanaPq(Vamana::create(path, options, source))
+                }
+                Some(QuantizationOptions::Rabitq(_)) => {
+                    Self::VamanaRq(Vamana::create(path, options, source))
+                }
+            },
             IndexingOptions::SparseInvertedIndex(_) => {
                 Self::SparseInvertedIndex(SparseInvertedIndex::create(path, options, source))
             }
@@ -110,6 +129,14 @@ impl<O: OperatorIndexing> SealedIndexing<O> {
                 Some(QuantizationOptions::Product(_)) => Self::HnswPq(Hnsw::open(path)),
                 Some(QuantizationOptions::Rabitq(_)) => Self::HnswRq(Hnsw::open(path)),
             },
+            IndexingOptions::Vamana(VamanaIndexingOptions {
+                ref quantization, ..
+            }) => match quantization {
+                None => Self::Vamana(Vamana::open(path)),
+                Some(QuantizationOptions::Scalar(_)) => Self::VamanaSq(Vamana::open(path)),
+                Some(QuantizationOptions::Product(_)) => Self::VamanaPq(Vamana::open(path)),
+                Some(QuantizationOptions::Rabitq(_)) => Self::VamanaRq(Vamana::open(path)),
+            },
             IndexingOptions::SparseInvertedIndex(_) => {
                 Self::SparseInvertedIndex(SparseInvertedIndex::open(path))
             }
@@ -120,21 +147,26 @@ impl<O: OperatorIndexing> SealedIndexing<O> {
         &'a self,
         vector: Borrowed<'a, O>,
         opts: &'a SearchOptions,
+        filter: Option<&'a AllowList>,
     ) -> Box<dyn Iterator<Item = Element> + 'a> {
         match self {
-            SealedIndexing::Flat(x) => x.vbase(vector, opts),
-            SealedIndexing::FlatPq(x) => x.vbase(vector, opts),
-            SealedIndexing::FlatSq(x) => x.vbase(vector, opts),
-            SealedIndexing::FlatRq(x) => x.vbase(vector, opts),
-            SealedIndexing::Ivf(x) => x.vbase(vector, opts),
-            SealedIndexing::IvfPq(x) => x.vbase(vector, opts),
-            SealedIndexing::IvfSq(x) => x.vbase(vector, opts),
-            SealedIndexing::IvfRq(x) => x.vbase(vector, opts),
-            SealedIndexing::Hnsw(x) => x.vbase(vector, opts),
-            SealedIndexing::HnswPq(x) => x.vbase(vector, opts),
-            SealedIndexing::HnswSq(x) => x.vbase(vector, opts),
-            SealedIndexing::HnswRq(x) => x.vbase(vector, opts),
-            SealedIndexing::SparseInvertedIndex(x) => x.vbase(vector, opts),
+            SealedIndexing::Flat(x) => x.vbase(vector, opts, filter),
+            SealedIndexing::FlatPq(x) => x.vbase(vector, opts, filter),
+            SealedIndexing::FlatSq(x) => x.vbase(vector, opts, filter),
+            SealedIndexing::FlatRq(x) => x.vbase(vector, opts, filter),
+            SealedIndexing::Ivf(x) => x.vbase(vector, opts, filter),
+            SealedIndexing::IvfPq(x) => x.vbase(vector, opts, filter),
+            SealedIndexing::IvfSq(x) => x.vbase(vector, opts, filter),
+            SealedIndexing::IvfRq(x) => x.vbase(vector, opts, filter),
+            SealedIndexing::Hnsw(x) => x.vbase(vector, opts, filter),
+            SealedIndexing::HnswPq(x) => x.vbase(vector, opts, filter),
+            SealedIndexing::HnswSq(x) => x.vbase(vector, opts, filter),
+            SealedIndexing::HnswRq(x) => x.vbase(vector, opts, filter),
+            SealedIndexing::Vamana(x) => x.vbase(vector, opts, filter),
+            SealedIndexing::VamanaSq(x) => x.vbase(vector, opts, filter),
+            SealedIndexing::VamanaPq(x) => x.vbase(vector, opts, filter),
+            SealedIndexing::VamanaRq(x) => x.vbase(vector, opts, filter),
+            SealedIndexing::SparseInvertedIndex(x) => x.vbase(vector, opts, filter),
         }
     }
 
@@ -152,6 +184,10 @@ impl<O: OperatorIndexing> SealedIndexing<O> {
             SealedIndexing::HnswPq(x) => x,
             SealedIndexing::HnswSq(x) => x,
             SealedIndexing::HnswRq(x) => x,
+            SealedIndexing::Vamana(x) => x,
+            SealedIndexing::VamanaSq(x) => x,
+            SealedIndexing::VamanaPq(x) => x,
+            SealedIndexing::VamanaRq(x) => x,
             SealedIndexing::SparseInvertedIndex(x) => x,
         }
     }
@@ -172,6 +208,10 @@ impl<O: OperatorIndexing> Vectors<O::Vector> for SealedIndexing<O> {
             SealedIndexing::HnswPq(x) => x.dims(),
             SealedIndexing::HnswSq(x) => x.dims(),
             SealedIndexing::HnswRq(x) => x.dims(),
+            SealedIndexing::Vamana(x) => x.dims(),
+            SealedIndexing::VamanaSq(x) => x.dims(),
+            SealedIndexing::VamanaPq(x) => x.dims(),
+            SealedIndexing::VamanaRq(x) => x.dims(),
             SealedIndexing::SparseInvertedIndex(x) => x.dims(),
         }
     }
@@ -190,6 +230,10 @@ impl<O: OperatorIndexing> Vectors<O::Vector> for SealedIndexing<O> {
             SealedIndexing::HnswPq(x) => x.len(),
             SealedIndexing::HnswSq(x) => x.len(),
             SealedIndexing::HnswRq(x) => x.len(),
+            SealedIndexing::Vamana(x) => x.len(),
+            SealedIndexing::VamanaSq(x) => x.len(),
+            SealedIndexing::VamanaPq(x) => x.len(),
+            SealedIndexing::VamanaRq(x) => x.len(),
             SealedIndexing::SparseInvertedIndex(x) => x.len(),
         }
     }
@@ -208,6 +252,10 @@ impl<O: OperatorIndexing> Vectors<O::Vector> for SealedIndexing<O> {
             SealedIndexing::HnswSq(x) => x.vector(i),
             SealedIndexing::HnswPq(x) => x.vector(i),
             SealedIndexing::HnswRq(x) => x.vector(i),
+            SealedIndexing::Vamana(x) => x.vector(i),
+            SealedIndexing::VamanaSq(x) => x.vector(i),
+            SealedIndexing::VamanaPq(x) => x.vector(i),
+            SealedIndexing::VamanaRq(x) => x.vector(i),
             SealedIndexing::SparseInvertedIndex(x) => x.vector(i),
         }
     }
@@ -228,6 +276,10 @@ impl<O: OperatorIndexing> Collection for SealedIndexing<O> {
             SealedIndexing::HnswPq(x) => x.payload(i),
             SealedIndexing::HnswSq(x) => x.payload(i),
             SealedIndexing::HnswRq(x) => x.payload(i),
+            SealedIndexing::Vamana(x) => x.payload(i),
+            SealedIndexing::VamanaSq(x) => x.payload(i),
+            SealedIndexing::VamanaPq(x) => x.payload(i),
+            SealedIndexing::VamanaRq(x) => x.payload(i),
             SealedIndexing::SparseInvertedIndex(x) => x.payload(i),
         }
     }
@@ -4,6 +4,7 @@
 pub mod operator;
 
 use base::always_equal::AlwaysEqual;
+use base::distance::{Distance, DistanceKind};
 use base::index::*;
 use base::operator::*;
 use base::search::*;
@@ -12,10 +13,6 @@ use base::vector::VectorOwned;
 use common::json::Json;
 use common::mmap_array::MmapArray;
 use common::remap::RemappedCollection;
-use common::vec2::Vec2;
-use k_means::k_means;
-use k_means::k_means_lookup;
-use k_means::k_means_lookup_many;
 use operator::OperatorIvf as Op;
 use quantization::quantizer::Quantizer;
 use quantization::Quantization;
@@ -31,7 +28,7 @@ pub struct Ivf<O: Op, Q: Quantizer<O>> {
     quantization: Quantization<O, Q>,
     payloads: MmapArray<Payload>,
     offsets: Json<Vec<u32>>,
-    projected_centroids: Json<Vec2<<O as Op>::Scalar>>,
+    projected_centroids: Json<O::Centroids>,
     is_residual: Json<bool>,
 }
 
@@ -69,13 +66,11 @@ impl<O: Op, Q: Quantizer<O>> Ivf<O, Q> {
         &'a self,
         vector: Borrowed<'a, O>,
         opts: &'a SearchOptions,
+        filter: Option<&'a AllowList>,
     ) -> Box<dyn Iterator<Item = Element> + 'a> {
         let projected_vector = self.quantization.project(vector);
         let lists = select(
-            k_means_lookup_many(
-                O::interpret(projected_vector.as_borrowed()),
-                &self.projected_centroids,
-            ),
+            O::lookup_many(projected_vector.as_borrowed(), &self.projected_centroids),
             opts.ivf_nprobe as usize,
         );
         let mut heap = Q::flat_rerank_start();
@@ -92,29 +87,36 @@ impl<O: Op, Q: Quantizer<O>> Ivf<O, Q> {
                 lut
             } else {
                 &self.quantization.flat_rerank_preprocess(
-                    O::residual(
-                        projected_vector.as_borrowed(),
-                        &self.projected_centroids[(i,)],
-                    )
-                    .as_borrowed(),
+                    O::residual(projected_vector.as_borrowed(), &self.projected_centroids, i)
+                        .as_borrowed(),
                     opts,
                 )
             };
             let start = self.offsets[i];
             let end = self.offsets[i + 1];
-            self.quantization
-                .flat_rerank_continue(lut, start..end, &mut heap);
+            self.quantization.flat_rerank_continue(
+                lut,
+                start..end,
+                |u| filter.map_or(true, |f| f.check(self.payload(u))),
+                &mut heap,
+            );
         }
         let mut reranker = self.quantization.flat_rerank_break(
             heap,
             move |u| (O::distance(vector, self.storage.vector(u)), ()),
             opts,
         );
+        // posting lists are chosen by `ivf_nprobe` only and are not pruned by the radius;
+        // the radius is applied as a post-filter on the reranked candidates
+        let radius = Distance::from_f32(opts.radius);
         Box::new(std::iter::from_fn(move || {
-            reranker.pop().map(|(dis_u, u, ())| Element {
-                distance: dis_u,
-                payload: AlwaysEqual(self.payload(u)),
-            })
+            reranker
+                .pop()
+                .filter(|&(dis_u, ..)| dis_u <= radius)
+                .map(|(dis_u, u, ())| Element {
+                    distance: dis_u,
+                    payload: AlwaysEqual(self.payload(u)),
+                })
         }))
     }
 }
@@ -131,13 +133,13 @@ fn from_nothing<O: Op, Q: Quantizer<O>>(
         residual_quantization,
         quantization: quantization_options,
     } = options.indexing.clone().unwrap_ivf();
-    let samples = O::sample(collection, nlist);
+    let spherical_centroids = spherical_centroids || options.vector.d == DistanceKind::Cos;
     rayon::check();
-    let centroids = k_means(nlist as usize, samples, spherical_centroids, 10, false);
+    let centroids = O::train(collection, nlist, spherical_centroids);
     rayon::check();
     let fa = (0..collection.len())
         .into_par_iter()
-        .map(|i| k_means_lookup(O::interpret(collection.vector(i)), &centroids))
+        .map(|i| O::lookup(collection.vector(i), &centroids))
         .collect::<Vec<_>>();
     let ls = (0..collection.len())
         .into_par_iter()
@@ -179,8 +181,8 @@ fn from_nothing<O: Op, Q: Quantizer<O>>(
         &collection,
         |vector| {
             if is_residual {
-                let target = k_means_lookup(O::interpret(vector), &centroids);
-                O::residual(vector, &centroids[(target,)])
+                let target = O::lookup(vector, &centroids);
+                O::residual(vector, &centroids, target)
             } else {
                 vector.own()
             }
@@ -191,14 +193,10 @@ fn from_nothing<O: Op, Q: Quantizer<O>>(
         (0..collection.len()).map(|i| collection.payload(i)),
     );
     let offsets = Json::create(path.as_ref().join("offsets"), offsets);
-    let projected_centroids = Json::create(path.as_ref().join("projected_centroids"), {
-        let mut projected_centroids = Vec2::zeros(centroids.shape());
-        for i in 0..centroids.shape_0() {
-            projected_centroids[(i,)]
-                .copy_from_slice(&O::project(quantization.quantizer(), &centroids[(i,)]));
-        }
-        projected_centroids
-    });
+    let projected_centroids = Json::create(
+        path.as_ref().join("projected_centroids"),
+        O::project(quantization.quantizer(), &centroids),
+    );
     let is_residual = Json::create(path.as_ref().join("is_residual"), is_residual);
     Ivf {
         storage,
@@ -227,6 +225,22 @@ fn open<O: Op, Q: Quantizer<O>>(path: impl AsRef<Path>) -> Ivf<O, Q> {
     }
 }
 
+/// Checks that `offsets` divides the vectors into lists.
+pub fn verify(path: impl AsRef<Path>, n: u32) -> Result<(), String> {
+    let offsets = Json::<Vec<u32>>::try_open(path.as_ref().join("offsets"))
+        .map_err(|e| format!("offsets: {e}"))?;
+    if offsets.first() != Some(&0) {
+        return Err("offsets: the first list does not start at 0".to_string());
+    }
+    if let Some(i) = offsets.windows(2).position(|w| w[0] > w[1]) {
+        return Err(format!("offsets: list {i} ends before it starts"));
+    }
+    if offsets.last() != Some(&n) {
+        return Err(format!("offsets: the last list does not end at {n}"));
+    }
+    Ok(())
+}
+
 fn select(mut lists: Vec<(f32, usize)>, n: usize) -> Vec<(f32, usize)> {
     if lists.is_empty() || n == 0 {
         return Vec::new();
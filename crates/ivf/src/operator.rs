@@ -4,143 +4,221 @@ use base::scalar::ScalarLike;
 use base::search::Vectors;
 use base::vector::*;
 use common::vec2::Vec2;
+use half::f16;
+use k_means::binary::{k_modes, k_modes_lookup};
+use k_means::sparse::{k_means_sparse, k_means_sparse_lookup, k_means_sparse_lookup_many};
+use k_means::{k_means, k_means_lookup, k_means_lookup_many};
 use quantization::quantizer::Quantizer;
+use serde::{Deserialize, Serialize};
 use storage::OperatorStorage;
 
+/// The number of terms kept in a centroid of sparse vectors.
+const SPARSE_CENTROID_TERMS: usize = 1024;
+
 pub trait OperatorIvf: OperatorStorage {
-    type Scalar: ScalarLike;
-    fn sample(vectors: &impl Vectors<Self::Vector>, nlist: u32) -> Vec2<Self::Scalar>;
-    fn interpret(vector: Borrowed<'_, Self>) -> &[Self::Scalar];
-    fn project<Q: Quantizer<Self>>(quantizer: &Q, slice: &[Self::Scalar]) -> Vec<Self::Scalar>;
+    type Centroids: Serialize + for<'a> Deserialize<'a> + Send + Sync;
+    fn train(
+        vectors: &impl Vectors<Self::Vector>,
+        nlist: u32,
+        is_spherical: bool,
+    ) -> Self::Centroids;
+    fn lookup(vector: Borrowed<'_, Self>, centroids: &Self::Centroids) -> usize;
+    fn lookup_many(vector: Borrowed<'_, Self>, centroids: &Self::Centroids) -> Vec<(f32, usize)>;
+    fn project<Q: Quantizer<Self>>(quantizer: &Q, centroids: &Self::Centroids) -> Self::Centroids;
     const SUPPORT_RESIDUAL: bool;
-    fn residual(lhs: Borrowed<'_, Self>, rhs: &[Self::Scalar]) -> Self::Vector;
+    fn residual(lhs: Borrowed<'_, Self>, centroids: &Self::Centroids, i: usize) -> Self::Vector;
 }
 
-impl OperatorIvf for BVectorDot {
-    type Scalar = Impossible;
-    fn sample(_: &impl Vectors<Self::Vector>, _: u32) -> Vec2<Self::Scalar> {
-        unimplemented!()
-    }
-    fn interpret(_: Borrowed<'_, Self>) -> &[Self::Scalar] {
-        unimplemented!()
-    }
-    fn project<Q: Quantizer<Self>>(_: &Q, _: &[Self::Scalar]) -> Vec<Self::Scalar> {
-        unimplemented!()
-    }
-    const SUPPORT_RESIDUAL: bool = false;
-    fn residual(_lhs: Borrowed<'_, Self>, _rhs: &[Self::Scalar]) -> Self::Vector {
-        unimplemented!()
-    }
+fn sample<V: VectorOwned>(vectors: &impl Vectors<V>, m: u32) -> Vec<V> {
+    let m = std::cmp::min(vectors.len(), m);
+    let f = base::rand::sample_u32(&mut rand::thread_rng(), vectors.len(), m);
+    f.into_iter().map(|i| vectors.vector(i).own()).collect()
 }
 
-impl OperatorIvf for BVectorJaccard {
-    type Scalar = Impossible;
-    fn sample(_: &impl Vectors<Self::Vector>, _: u32) -> Vec2<Self::Scalar> {
-        unimplemented!()
-    }
-    fn interpret(_: Borrowed<'_, Self>) -> &[Self::Scalar] {
-        unimplemented!()
-    }
-    fn project<Q: Quantizer<Self>>(_: &Q, _: &[Self::Scalar]) -> Vec<Self::Scalar> {
-        unimplemented!()
-    }
-    const SUPPORT_RESIDUAL: bool = false;
-    fn residual(_lhs: Borrowed<'_, Self>, _rhs: &[Self::Scalar]) -> Self::Vector {
-        unimplemented!()
-    }
+fn project_dense<S: ScalarLike, O: Operator<Vector = VectOwned<S>>, Q: Quantizer<O>>(
+    quantizer: &Q,
+    centroids: &Vec2<S>,
+) -> Vec2<S> {
+    let mut projected = Vec2::zeros(centroids.shape());
+    for i in 0..centroids.shape_0() {
+        projected[(i,)].copy_from_slice(
+            quantizer
+                .project(VectBorrowed::new(&centroids[(i,)]))
+                .slice(),
+        );
+    }
+    projected
 }
 
-impl OperatorIvf for BVectorHamming {
-    type Scalar = Impossible;
-    fn sample(_: &impl Vectors<Self::Vector>, _: u32) -> Vec2<Self::Scalar> {
-        unimplemented!()
-    }
-    fn interpret(_: Borrowed<'_, Self>) -> &[Self::Scalar] {
-        unimplemented!()
-    }
-    fn project<Q: Quantizer<Self>>(_: &Q, _: &[Self::Scalar]) -> Vec<Self::Scalar> {
-        unimplemented!()
-    }
-    const SUPPORT_RESIDUAL: bool = false;
-    fn residual(_lhs: Borrowed<'_, Self>, _rhs: &[Self::Scalar]) -> Self::Vector {
-        unimplemented!()
-    }
+macro_rules! impl_operator_ivf_dense {
+    ($t:ident, $residual:literal) => {
+        impl<S: ScalarLike> OperatorIvf for $t<S> {
+            type Centroids = Vec2<S>;
+            fn train(
+                vectors: &impl Vectors<Self::Vector>,
+                nlist: u32,
+                is_spherical: bool,
+            ) -> Self::Centroids {
+                let samples = common::sample::sample(
+                    vectors.len(),
+                    nlist.saturating_mul(256).min(1 << 20),
+                    vectors.dims(),
+                    |i| vectors.vector(i).slice(),
+                );
+                k_means(nlist as usize, samples, is_spherical, 10, false)
+            }
+            fn lookup(vector: Borrowed<'_, Self>, centroids: &Self::Centroids) -> usize {
+                k_means_lookup(vector.slice(), centroids)
+            }
+            fn lookup_many(
+                vector: Borrowed<'_, Self>,
+                centroids: &Self::Centroids,
+            ) -> Vec<(f32, usize)> {
+                k_means_lookup_many(vector.slice(), centroids)
+            }
+            fn project<Q: Quantizer<Self>>(
+                quantizer: &Q,
+                centroids: &Self::Centroids,
+            ) -> Self::Centroids {
+                project_dense(quantizer, centroids)
+            }
+            const SUPPORT_RESIDUAL: bool = $residual;
+            fn residual(
+                lhs: Borrowed<'_, Self>,
+                centroids: &Self::Centroids,
+                i: usize,
+            ) -> Self::Vector {
+                if Self::SUPPORT_RESIDUAL {
+                    lhs.operator_sub(VectBorrowed::new(&centroids[(i,)]))
+                } else {
+                    unimplemented!()
+                }
+            }
+        }
+    };
 }
 
-impl OperatorIvf for SVectDot<f32> {
-    type Scalar = Impossible;
-    fn sample(_: &impl Vectors<Self::Vector>, _: u32) -> Vec2<Self::Scalar> {
-        unimplemented!()
-    }
-    fn interpret(_: Borrowed<'_, Self>) -> &[Self::Scalar] {
-        unimplemented!()
-    }
-    fn project<Q: Quantizer<Self>>(_: &Q, _: &[Self::Scalar]) -> Vec<Self::Scalar> {
-        unimplemented!()
-    }
-    const SUPPORT_RESIDUAL: bool = false;
-    fn residual(_lhs: Borrowed<'_, Self>, _rhs: &[Self::Scalar]) -> Self::Vector {
-        unimplemented!()
-    }
-}
+impl_operator_ivf_dense!(VectDot, false);
+impl_operator_ivf_dense!(VectCos, false);
+impl_operator_ivf_dense!(VectL2, true);
 
-impl OperatorIvf for SVectL2<f32> {
-    type Scalar = Impossible;
-    fn sample(_: &impl Vectors<Self::Vector>, _: u32) -> Vec2<Self::Scalar> {
-        unimplemented!()
-    }
-    fn interpret(_: Borrowed<'_, Self>) -> &[Self::Scalar] {
-        unimplemented!()
-    }
-    fn project<Q: Quantizer<Self>>(_: &Q, _: &[Self::Scalar]) -> Vec<Self::Scalar> {
-        unimplemented!()
-    }
-    const SUPPORT_RESIDUAL: bool = false;
-    fn residual(_lhs: Borrowed<'_, Self>, _rhs: &[Self::Scalar]) -> Self::Vector {
-        unimplemented!()
-    }
+macro_rules! impl_operator_ivf_sparse {
+    ($t:ty, $spherical:literal) => {
+        impl OperatorIvf for $t {
+            type Centroids = Vec<SVectOwned<f32>>;
+            fn train(
+                vectors: &impl Vectors<Self::Vector>,
+                nlist: u32,
+                is_spherical: bool,
+            ) -> Self::Centroids {
+                let samples = sample(vectors, nlist.saturating_mul(64).min(1 << 18));
+                k_means_sparse(
+                    nlist as usize,
+                    vectors.dims(),
+                    samples,
+                    SPARSE_CENTROID_TERMS,
+                    $spherical || is_spherical,
+                    10,
+                )
+            }
+            fn lookup(vector: Borrowed<'_, Self>, centroids: &Self::Centroids) -> usize {
+                k_means_sparse_lookup(vector, centroids)
+            }
+            fn lookup_many(
+                vector: Borrowed<'_, Self>,
+                centroids: &Self::Centroids,
+            ) -> Vec<(f32, usize)> {
+                k_means_sparse_lookup_many(vector, centroids)
+            }
+            fn project<Q: Quantizer<Self>>(_: &Q, centroids: &Self::Centroids) -> Self::Centroids {
+                centroids.clone()
+            }
+            const SUPPORT_RESIDUAL: bool = false;
+            fn residual(_: Borrowed<'_, Self>, _: &Self::Centroids, _: usize) -> Self::Vector {
+                unimplemented!()
+            }
+        }
+    };
 }
 
-impl<S: ScalarLike> OperatorIvf for VectDot<S> {
-    type Scalar = S;
-    fn sample(vectors: &impl Vectors<Self::Vector>, nlist: u32) -> Vec2<Self::Scalar> {
-        common::sample::sample(
-            vectors.len(),
-            nlist.saturating_mul(256).min(1 << 20),
-            vectors.dims(),
-            |i| vectors.vector(i).slice(),
-        )
-    }
-    fn interpret(x: Borrowed<'_, Self>) -> &[Self::Scalar] {
-        x.slice()
-    }
-    fn project<Q: Quantizer<Self>>(quantizer: &Q, centroid: &[Self::Scalar]) -> Vec<Self::Scalar> {
-        quantizer.project(VectBorrowed::new(centroid)).into_vec()
-    }
-    const SUPPORT_RESIDUAL: bool = false;
-    fn residual(_lhs: Borrowed<'_, Self>, _rhs: &[S]) -> Self::Vector {
-        unimplemented!()
-    }
+impl_operator_ivf_sparse!(SVectDot<f32>, true);
+impl_operator_ivf_sparse!(SVectCos<f32>, true);
+impl_operator_ivf_sparse!(SVectL2<f32>, false);
+
+macro_rules! impl_operator_ivf_binary {
+    ($t:ty) => {
+        impl OperatorIvf for $t {
+            type Centroids = Vec<BVectOwned>;
+            fn train(vectors: &impl Vectors<Self::Vector>, nlist: u32, _: bool) -> Self::Centroids {
+                let samples = sample(vectors, nlist.saturating_mul(256).min(1 << 20));
+                k_modes(
+                    nlist as usize,
+                    vectors.dims(),
+                    samples,
+                    |lhs, rhs| Self::distance(lhs, rhs).to_f32(),
+                    10,
+                )
+            }
+            fn lookup(vector: Borrowed<'_, Self>, centroids: &Self::Centroids) -> usize {
+                k_modes_lookup(vector, centroids, |lhs, rhs| {
+                    Self::distance(lhs, rhs).to_f32()
+                })
+            }
+            fn lookup_many(
+                vector: Borrowed<'_, Self>,
+                centroids: &Self::Centroids,
+            ) -> Vec<(f32, usize)> {
+                centroids
+                    .iter()
+                    .enumerate()
+                    .map(|(i, centroid)| {
+                        (Self::distance(vector, centroid.as_borrowed()).to_f32(), i)
+                    })
+                    .collect()
+            }
+            fn project<Q: Quantizer<Self>>(_: &Q, centroids: &Self::Centroids) -> Self::Centroids {
+                centroids.clone()
+            }
+            const SUPPORT_RESIDUAL: bool = false;
+            fn residual(_: Borrowed<'_, Self>, _: &Self::Centroids, _: usize) -> Self::Vector {
+                unimplemented!()
+            }
+        }
+    };
 }
 
-impl<S: ScalarLike> OperatorIvf for VectL2<S> {
-    type Scalar = S;
-    fn sample(vectors: &impl Vectors<Self::Vector>, nlist: u32) -> Vec2<Self::Scalar> {
-        common::sample::sample(
-            vectors.len(),
-            nlist.saturating_mul(256).min(1 << 20),
-            vectors.dims(),
-            |i| vectors.vector(i).slice(),
-        )
-    }
-    fn interpret(x: Borrowed<'_, Self>) -> &[Self::Scalar] {
-        x.slice()
-    }
-    fn project<Q: Quantizer<Self>>(quantizer: &Q, vector: &[Self::Scalar]) -> Vec<Self::Scalar> {
-        quantizer.project(VectBorrowed::new(vector)).into_vec()
-    }
-    const SUPPORT_RESIDUAL: bool = true;
-    fn residual(lhs: Borrowed<'_, Self>, rhs: &[S]) -> Self::Vector {
-        lhs.operator_sub(VectBorrowed::new(rhs))
-    }
+impl_operator_ivf_binary!(BVectorHamming);
+impl_operator_ivf_binary!(BVectorJaccard);
+
+macro_rules! unimpl_operator_ivf {
+    ($t:ty) => {
+        impl OperatorIvf for $t {
+            type Centroids = Vec2<Impossible>;
+            fn train(_: &impl Vectors<Self::Vector>, _: u32, _: bool) -> Self::Centroids {
+                unimplemented!()
+            }
+            fn lookup(_: Borrowed<'_, Self>, _: &Self::Centroids) -> usize {
+                unimplemented!()
+            }
+            fn lookup_many(_: Borrowed<'_, Self>, _: &Self::Centroids) -> Vec<(f32, usize)> {
+                unimplemented!()
+            }
+            fn project<Q: Quantizer<Self>>(_: &Q, _: &Self::Centroids) -> Self::Centroids {
+                unimplemented!()
+            }
+            const SUPPORT_RESIDUAL: bool = false;
+            fn residual(_: Borrowed<'_, Self>, _: &Self::Centroids, _: usize) -> Self::Vector {
+                unimplemented!()
+            }
+        }
+    };
 }
+
+unimpl_operator_ivf!(BVectorDot);
+
+unimpl_operator_ivf!(Veci8Cos);
+unimpl_operator_ivf!(Veci8Dot);
+unimpl_operator_ivf!(Veci8L2);
+
+unimpl_operator_ivf!(VectL1<f32>);
+unimpl_operator_ivf!(VectL1<f16>);
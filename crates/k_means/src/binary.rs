@@ -0,0 +1,164 @@
+use base::vector::{BVectBorrowed, BVectOwned, VectorOwned, BVECTOR_WIDTH};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use stoppable_rayon as rayon;
+
+/// K-modes for binary vectors. A bit of a centroid is set if it is set in the
+/// majority of the members of the cluster.
+pub fn k_modes(
+    c: usize,
+    dims: u32,
+    samples: Vec<BVectOwned>,
+    distance: impl Fn(BVectBorrowed<'_>, BVectBorrowed<'_>) -> f32 + Sync,
+    iterations: usize,
+) -> Vec<BVectOwned> {
+    k_modes_with_rng(
+        c,
+        dims,
+        samples,
+        distance,
+        iterations,
+        &mut StdRng::from_entropy(),
+    )
+}
+
+fn k_modes_with_rng(
+    c: usize,
+    dims: u32,
+    samples: Vec<BVectOwned>,
+    distance: impl Fn(BVectBorrowed<'_>, BVectBorrowed<'_>) -> f32 + Sync,
+    iterations: usize,
+    rng: &mut StdRng,
+) -> Vec<BVectOwned> {
+    assert!(c > 0);
+    let n = samples.len();
+    if n <= c {
+        let mut centroids = samples;
+        while centroids.len() < c {
+            let mut data = (0..dims.div_ceil(BVECTOR_WIDTH))
+                .map(|_| rng.gen::<u64>())
+                .collect::<Vec<_>>();
+            if dims % BVECTOR_WIDTH != 0 {
+                *data.last_mut().unwrap() &= (1 << (dims % BVECTOR_WIDTH)) - 1;
+            }
+            centroids.push(BVectOwned::new(dims, data));
+        }
+        return centroids;
+    }
+    let mut centroids = rand::seq::index::sample(rng, n, c)
+        .into_iter()
+        .map(|i| samples[i].clone())
+        .collect::<Vec<_>>();
+    let assign_all = |centroids: &[BVectOwned]| {
+        (0..n)
+            .into_par_iter()
+            .map(|i| k_modes_lookup(samples[i].as_borrowed(), centroids, &distance))
+            .collect::<Vec<_>>()
+    };
+    let mut assign = assign_all(&centroids);
+    for _ in 0..iterations {
+        rayon::check();
+        let mut members = vec![Vec::new(); c];
+        for i in 0..n {
+            members[assign[i]].push(i);
+        }
+        centroids = (0..c)
+            .into_par_iter()
+            .map(|j| {
+                let mut count = vec![0_u32; dims as usize];
+                for &i in members[j].iter() {
+                    for (k, bit) in samples[i].as_borrowed().iter().enumerate() {
+                        count[k] += bit as u32;
+                    }
+                }
+                let mut data = vec![0_u64; dims.div_ceil(BVECTOR_WIDTH) as usize];
+                for k in 0..dims as usize {
+                    if 2 * count[k] as usize > members[j].len() {
+                        data[k / BVECTOR_WIDTH as usize] |= 1 << (k % BVECTOR_WIDTH as usize);
+                    }
+                }
+                BVectOwned::new(dims, data)
+            })
+            .collect();
+        for j in 0..c {
+            if members[j].is_empty() {
+                centroids[j] = samples[rng.gen_range(0..n)].clone();
+            }
+        }
+        let new_assign = assign_all(&centroids);
+        let stable = new_assign == assign;
+        assign = new_assign;
+        if stable {
+            break;
+        }
+    }
+    centroids
+}
+
+pub fn k_modes_lookup(
+    vector: BVectBorrowed<'_>,
+    centroids: &[BVectOwned],
+    distance: impl Fn(BVectBorrowed<'_>, BVectBorrowed<'_>) -> f32,
+) -> usize {
+    assert_ne!(centroids.len(), 0);
+    let mut result = (f32::INFINITY, 0);
+    for (i, centroid) in centroids.iter().enumerate() {
+        let dis = distance(vector, centroid.as_borrowed());
+        if dis <= result.0 {
+            result = (dis, i);
+        }
+    }
+    result.1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hamming(lhs: BVectBorrowed<'_>, rhs: BVectBorrowed<'_>) -> f32 {
+        lhs.iter().zip(rhs.iter()).filter(|(x, y)| x != y).count() as f32
+    }
+
+    #[test]
+    fn k_modes_test() {
+        // `k_modes` checks for cancellation, so it must run inside the pool
+        rayon::ThreadPoolBuilder::new()
+            .build_scoped(|pool| pool.install(k_modes_seeded))
+            .unwrap()
+            .unwrap();
+    }
+
+    fn k_modes_seeded() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let dims = 128;
+        let modes = (0..3)
+            .map(|_| BVectOwned::new(dims, vec![rng.gen(), rng.gen()]))
+            .collect::<Vec<_>>();
+        // every sample differs from the mode of its cluster in a few bits
+        let mut samples = Vec::new();
+        let mut labels = Vec::new();
+        for _ in 0..40 {
+            for (label, mode) in modes.iter().enumerate() {
+                let mut data = mode.as_borrowed().data().to_vec();
+                for _ in 0..8 {
+                    let k = rng.gen_range(0..dims as usize);
+                    data[k / BVECTOR_WIDTH as usize] ^= 1 << (k % BVECTOR_WIDTH as usize);
+                }
+                samples.push(BVectOwned::new(dims, data));
+                labels.push(label);
+            }
+        }
+        let centroids = k_modes_with_rng(3, dims, samples.clone(), hamming, 20, &mut rng);
+        let mut cluster_to_centroid = [None; 3];
+        for (sample, &label) in samples.iter().zip(labels.iter()) {
+            let j = k_modes_lookup(sample.as_borrowed(), &centroids, hamming);
+            assert_eq!(*cluster_to_centroid[label].get_or_insert(j), j);
+        }
+        // the majority of the bits of the members recovers the modes
+        for (label, mode) in modes.iter().enumerate() {
+            let centroid = &centroids[cluster_to_centroid[label].unwrap()];
+            assert_eq!(centroid.as_borrowed().data(), mode.as_borrowed().data());
+        }
+    }
+}
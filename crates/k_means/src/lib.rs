@@ -1,8 +1,10 @@
 #![allow(clippy::needless_range_loop)]
 
+pub mod binary;
 pub mod kmeans1d;
 pub mod lloyd;
 pub mod quick_centers;
+pub mod sparse;
 
 use base::scalar::*;
 use common::vec2::Vec2;
@@ -0,0 +1,284 @@
+use base::scalar::ScalarLike;
+use base::vector::{SVectBorrowed, SVectOwned, VectorBorrowed, VectorOwned};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::collections::BTreeMap;
+use stoppable_rayon as rayon;
+
+/// K-means for sparse vectors. Every centroid keeps at most `terms` terms with the
+/// largest magnitudes, so centroids stay sparse. If `is_spherical` is set, samples and
+/// centroids are normalized.
+pub fn k_means_sparse(
+    c: usize,
+    dims: u32,
+    samples: Vec<SVectOwned<f32>>,
+    terms: usize,
+    is_spherical: bool,
+    iterations: usize,
+) -> Vec<SVectOwned<f32>> {
+    k_means_sparse_with_rng(
+        c,
+        dims,
+        samples,
+        terms,
+        is_spherical,
+        iterations,
+        &mut StdRng::from_entropy(),
+    )
+}
+
+fn k_means_sparse_with_rng(
+    c: usize,
+    dims: u32,
+    samples: Vec<SVectOwned<f32>>,
+    terms: usize,
+    is_spherical: bool,
+    iterations: usize,
+    rng: &mut StdRng,
+) -> Vec<SVectOwned<f32>> {
+    assert!(c > 0);
+    assert!(terms > 0);
+    let samples = if is_spherical {
+        samples
+            .into_iter()
+            .filter(|x| !x.indexes().is_empty())
+            .map(|x| x.as_borrowed().function_normalize())
+            .collect::<Vec<_>>()
+    } else {
+        samples
+    };
+    let n = samples.len();
+    if n <= c {
+        let mut centroids = samples
+            .into_iter()
+            .map(|x| {
+                truncate(
+                    dims,
+                    x.indexes().iter().copied().zip(x.values().iter().copied()),
+                    terms,
+                    is_spherical,
+                )
+            })
+            .collect::<Vec<_>>();
+        while centroids.len() < c {
+            let index = rng.gen_range(0..dims);
+            centroids.push(SVectOwned::new(dims, vec![index], vec![1.0]));
+        }
+        return centroids;
+    }
+    let mut centroids = rand::seq::index::sample(rng, n, c)
+        .into_iter()
+        .map(|i| samples[i].clone())
+        .collect::<Vec<_>>();
+    let mut assign = assign_all(&samples, &centroids);
+    for _ in 0..iterations {
+        rayon::check();
+        let mut members = vec![Vec::new(); c];
+        for i in 0..n {
+            members[assign[i]].push(i);
+        }
+        centroids = (0..c)
+            .into_par_iter()
+            .map(|j| {
+                let mut sum = BTreeMap::<u32, f32>::new();
+                for &i in members[j].iter() {
+                    let sample = samples[i].as_borrowed();
+                    for (&index, &value) in sample.indexes().iter().zip(sample.values()) {
+                        *sum.entry(index).or_default() += value;
+                    }
+                }
+                let count = members[j].len().max(1) as f32;
+                let mean = sum.into_iter().map(|(index, value)| (index, value / count));
+                truncate(dims, mean, terms, is_spherical)
+            })
+            .collect();
+        for j in 0..c {
+            // a spherical centroid is undefined if its members cancel each other out
+            let empty =
+                members[j].is_empty() || (is_spherical && centroids[j].indexes().is_empty());
+            if empty {
+                centroids[j] = samples[rng.gen_range(0..n)].clone();
+            }
+        }
+        let new_assign = assign_all(&samples, &centroids);
+        let stable = new_assign == assign;
+        assign = new_assign;
+        if stable {
+            break;
+        }
+    }
+    centroids
+}
+
+pub fn k_means_sparse_lookup(
+    vector: SVectBorrowed<'_, f32>,
+    centroids: &[SVectOwned<f32>],
+) -> usize {
+    assert_ne!(centroids.len(), 0);
+    let mut result = (f32::INFINITY, 0);
+    for (dis, i) in k_means_sparse_lookup_many(vector, centroids) {
+        if dis <= result.0 {
+            result = (dis, i);
+        }
+    }
+    result.1
+}
+
+/// Returns squared l2 distances to centroids. For normalized centroids of spherical k-means,
+/// they are ordered as negative dot products are.
+pub fn k_means_sparse_lookup_many(
+    vector: SVectBorrowed<'_, f32>,
+    centroids: &[SVectOwned<f32>],
+) -> Vec<(f32, usize)> {
+    assert_ne!(centroids.len(), 0);
+    let mut seq = Vec::new();
+    for (i, centroid) in centroids.iter().enumerate() {
+        let centroid = centroid.as_borrowed();
+        let d2 = f32::reduce_sum_of_sparse_d2(
+            vector.indexes(),
+            vector.values(),
+            centroid.indexes(),
+            centroid.values(),
+        );
+        seq.push((d2, i));
+    }
+    seq
+}
+
+fn assign_all(samples: &[SVectOwned<f32>], centroids: &[SVectOwned<f32>]) -> Vec<usize> {
+    (0..samples.len())
+        .into_par_iter()
+        .map(|i| k_means_sparse_lookup(samples[i].as_borrowed(), centroids))
+        .collect()
+}
+
+fn truncate(
+    dims: u32,
+    terms: impl Iterator<Item = (u32, f32)>,
+    n: usize,
+    is_spherical: bool,
+) -> SVectOwned<f32> {
+    let mut terms = terms.filter(|&(_, v)| v != 0.0).collect::<Vec<_>>();
+    if terms.len() > n {
+        terms.select_nth_unstable_by(n - 1, |(_, x), (_, y)| f32::total_cmp(&y.abs(), &x.abs()));
+        terms.truncate(n);
+        terms.sort_unstable_by_key(|&(i, _)| i);
+    }
+    let (indexes, values) = terms.into_iter().unzip::<_, _, Vec<_>, Vec<_>>();
+    let vector = SVectOwned::new(dims, indexes, values);
+    if !is_spherical || vector.indexes().is_empty() {
+        vector
+    } else {
+        vector.as_borrowed().function_normalize()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Returns samples of clusters, each of which is made of vectors of `terms` with
+    /// values near `value`, and the index of the cluster of every sample.
+    fn clusters(rng: &mut StdRng, specs: &[([u32; 2], f32)]) -> (Vec<SVectOwned<f32>>, Vec<usize>) {
+        let mut samples = Vec::new();
+        let mut labels = Vec::new();
+        for _ in 0..40 {
+            for (label, &(terms, value)) in specs.iter().enumerate() {
+                let values = terms
+                    .iter()
+                    .map(|_| value * rng.gen_range(0.95..1.05))
+                    .collect();
+                samples.push(SVectOwned::new(16, terms.to_vec(), values));
+                labels.push(label);
+            }
+        }
+        (samples, labels)
+    }
+
+    fn check_assignment(
+        samples: &[SVectOwned<f32>],
+        labels: &[usize],
+        centroids: &[SVectOwned<f32>],
+    ) -> Vec<usize> {
+        let assign = assign_all(samples, centroids);
+        let mut cluster_to_centroid = vec![None; centroids.len()];
+        for (&label, &j) in labels.iter().zip(assign.iter()) {
+            assert_eq!(*cluster_to_centroid[label].get_or_insert(j), j);
+        }
+        let mut targets = cluster_to_centroid.iter().flatten().collect::<Vec<_>>();
+        targets.sort();
+        targets.dedup();
+        assert_eq!(targets.len(), centroids.len());
+        assign
+    }
+
+    #[test]
+    fn l2_test() {
+        // `k_means_sparse` checks for cancellation, so it must run inside the pool
+        rayon::ThreadPoolBuilder::new()
+            .build_scoped(|pool| pool.install(l2))
+            .unwrap()
+            .unwrap();
+    }
+
+    fn l2() {
+        let mut rng = StdRng::seed_from_u64(7);
+        // the first and the third clusters point at the same direction
+        let specs = [([0, 1], 10.0), ([2, 3], 1.0), ([0, 1], 1.0)];
+        let (samples, labels) = clusters(&mut rng, &specs);
+        let centroids = k_means_sparse_with_rng(3, 16, samples.clone(), 4, false, 20, &mut rng);
+        let assign = check_assignment(&samples, &labels, &centroids);
+        // converged centroids are means of their members
+        for (j, centroid) in centroids.iter().enumerate() {
+            let members = (0..samples.len())
+                .filter(|&i| assign[i] == j)
+                .collect::<Vec<_>>();
+            let mut mean = BTreeMap::<u32, f32>::new();
+            for &i in members.iter() {
+                let sample = samples[i].as_borrowed();
+                for (&index, &value) in sample.indexes().iter().zip(sample.values()) {
+                    *mean.entry(index).or_default() += value / members.len() as f32;
+                }
+            }
+            let (indexes, values) = mean.into_iter().unzip::<_, _, Vec<_>, Vec<_>>();
+            let d2 = f32::reduce_sum_of_sparse_d2(
+                &indexes,
+                &values,
+                centroid.indexes(),
+                centroid.values(),
+            );
+            assert!(d2 < 1e-4, "centroid {j} is {d2} away from the mean");
+        }
+    }
+
+    #[test]
+    fn spherical_test() {
+        // `k_means_sparse` checks for cancellation, so it must run inside the pool
+        rayon::ThreadPoolBuilder::new()
+            .build_scoped(|pool| pool.install(spherical))
+            .unwrap()
+            .unwrap();
+    }
+
+    fn spherical() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let specs = [([0, 1], 10.0), ([2, 3], 1.0), ([4, 5], 5.0)];
+        let (samples, labels) = clusters(&mut rng, &specs);
+        let centroids = k_means_sparse_with_rng(3, 16, samples.clone(), 4, true, 20, &mut rng);
+        let assign = check_assignment(&samples, &labels, &centroids);
+        // centroids are normalized and point at the directions of their clusters
+        for (label, &(terms, _)) in specs.iter().enumerate() {
+            let i = labels.iter().position(|&x| x == label).unwrap();
+            let centroid = centroids[assign[i]].as_borrowed();
+            let direction = [std::f32::consts::FRAC_1_SQRT_2; 2];
+            let d2 = f32::reduce_sum_of_sparse_d2(
+                &terms,
+                &direction,
+                centroid.indexes(),
+                centroid.values(),
+            );
+            assert!(d2 < 1e-3, "centroid of cluster {label} is {d2} away");
+        }
+    }
+}
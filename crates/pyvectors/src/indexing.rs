@@ -9,6 +9,7 @@ use std::path::Path;
 #[allow(dead_code)]
 pub enum Indexing {
     Vecf32Dot(indexing::SealedIndexing<VectDot<f32>>),
+    Vecf32Cos(indexing::SealedIndexing<VectCos<f32>>),
     Vecf32L2(indexing::SealedIndexing<VectL2<f32>>),
     Vecf16Dot(indexing::SealedIndexing<VectDot<f16>>),
     Vecf16L2(indexing::SealedIndexing<VectL2<f16>>),
@@ -69,6 +70,27 @@ impl Indexing {
                     .unwrap()
                     .unwrap(),
             ),
+            (VectorKind::Vecf32, DistanceKind::Cos) => Self::Vecf32Cos(
+                stoppable_rayon::ThreadPoolBuilder::new()
+                    .build_scoped(|pool| {
+                        pool.install(|| {
+                            let x = indexing::SealedIndexing::create(
+                                &path,
+                                index_options.clone(),
+                                &source,
+                            );
+                            // write options
+                            std::fs::write(
+                                path.join(".index_options"),
+                                serde_json::to_string(&index_options).unwrap(),
+                            )
+                            .unwrap();
+                            x
+                        })
+                    })
+                    .unwrap()
+                    .unwrap(),
+            ),
             _ => unimplemented!(),
         }
     }
@@ -84,6 +106,9 @@ impl Indexing {
             (VectorKind::Vecf32, DistanceKind::Dot) => {
                 Self::Vecf32Dot(indexing::SealedIndexing::open(path, index_options))
             }
+            (VectorKind::Vecf32, DistanceKind::Cos) => {
+                Self::Vecf32Cos(indexing::SealedIndexing::open(path, index_options))
+            }
             _ => unimplemented!(),
         }
     }
@@ -93,15 +118,20 @@ impl Indexing {
         opts: &'a SearchOptions,
     ) -> impl Iterator<Item = (Distance, Pointer)> + 'a {
         match (self, vector) {
-            (Self::Vecf32L2(x), BorrowedVector::Vecf32(vector)) => x.vbase(vector, opts),
-            (Self::Vecf32Dot(x), BorrowedVector::Vecf32(vector)) => x.vbase(vector, opts),
-            (Self::Vecf16Dot(x), BorrowedVector::Vecf16(vector)) => x.vbase(vector, opts),
-            (Self::Vecf16L2(x), BorrowedVector::Vecf16(vector)) => x.vbase(vector, opts),
-            (Self::BVectorDot(x), BorrowedVector::BVector(vector)) => x.vbase(vector, opts),
-            (Self::BVectorHamming(x), BorrowedVector::BVector(vector)) => x.vbase(vector, opts),
-            (Self::BVectorJaccard(x), BorrowedVector::BVector(vector)) => x.vbase(vector, opts),
-            (Self::SVecf32Dot(x), BorrowedVector::SVecf32(vector)) => x.vbase(vector, opts),
-            (Self::SVecf32L2(x), BorrowedVector::SVecf32(vector)) => x.vbase(vector, opts),
+            (Self::Vecf32L2(x), BorrowedVector::Vecf32(vector)) => x.vbase(vector, opts, None),
+            (Self::Vecf32Dot(x), BorrowedVector::Vecf32(vector)) => x.vbase(vector, opts, None),
+            (Self::Vecf32Cos(x), BorrowedVector::Vecf32(vector)) => x.vbase(vector, opts, None),
+            (Self::Vecf16Dot(x), BorrowedVector::Vecf16(vector)) => x.vbase(vector, opts, None),
+            (Self::Vecf16L2(x), BorrowedVector::Vecf16(vector)) => x.vbase(vector, opts, None),
+            (Self::BVectorDot(x), BorrowedVector::BVector(vector)) => x.vbase(vector, opts, None),
+            (Self::BVectorHamming(x), BorrowedVector::BVector(vector)) => {
+                x.vbase(vector, opts, None)
+            }
+            (Self::BVectorJaccard(x), BorrowedVector::BVector(vector)) => {
+                x.vbase(vector, opts, None)
+            }
+            (Self::SVecf32Dot(x), BorrowedVector::SVecf32(vector)) => x.vbase(vector, opts, None),
+            (Self::SVecf32L2(x), BorrowedVector::SVecf32(vector)) => x.vbase(vector, opts, None),
             _ => panic!("invalid vector type"),
         }
         .map(|Element { distance, payload }| (distance, payload.0.pointer()))
@@ -109,6 +139,7 @@ impl Indexing {
     pub fn dims(&self) -> u32 {
         match self {
             Indexing::Vecf32Dot(x) => x.dims(),
+            Indexing::Vecf32Cos(x) => x.dims(),
             Indexing::Vecf32L2(x) => x.dims(),
             Indexing::Vecf16Dot(x) => x.dims(),
             Indexing::Vecf16L2(x) => x.dims(),
@@ -7,7 +7,7 @@ use base::index::*;
 use base::search::Vectors;
 use base::vector::{BorrowedVector, VectorKind};
 use dataset::Dataset;
-use ndarray::{Array1, Array2};
+use ndarray::{Array1, Array2, ArrayView2};
 use numpy::{IntoPyArray, PyArray2, PyReadonlyArray1, PyReadonlyArray2};
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
@@ -47,17 +47,25 @@ impl Indexing {
             v: VectorKind::Vecf32,
             d: match distance {
                 "dot" => DistanceKind::Dot,
+                "cos" => DistanceKind::Cos,
                 "l2" => DistanceKind::L2,
                 "hamming" => DistanceKind::Hamming,
                 "jaccard" => DistanceKind::Jaccard,
                 _ => unimplemented!("distance type {distance} is not implemented"),
             },
+            weights: None,
         };
         vector_options.validate().expect("not valid vector options");
         // dataset
         let dataset = dataset.as_array();
         assert!(dataset.dim().1 == dims as usize, "bad dataset");
-        let dataset = Dataset::new(dataset);
+        let normalized;
+        let dataset = if vector_options.d == DistanceKind::Cos {
+            normalized = normalize(dataset);
+            Dataset::new(normalized.view())
+        } else {
+            Dataset::new(dataset)
+        };
         let source = WithLabels::new(
             dataset,
             labels.as_slice().expect("memory is non continuous"),
@@ -115,3 +123,12 @@ impl Indexing {
         (d.into_pyarray_bound(py), l.into_pyarray_bound(py))
     }
 }
+
+fn normalize(dataset: ArrayView2<'_, f32>) -> Array2<f32> {
+    let mut result = dataset.as_standard_layout().into_owned();
+    for mut row in result.rows_mut() {
+        let l = row.dot(&row).sqrt();
+        row.mapv_inplace(|x| x / l);
+    }
+    result
+}
@@ -30,6 +30,11 @@ use std::ops::Range;
 use std::path::Path;
 use stoppable_rayon as rayon;
 
+/// Version of the on-disk layout of quantizers, bumped whenever it changes.
+///
+/// * 2: OPQ rotations and multi-bit RaBitQ codes.
+pub const LAYOUT: u32 = 2;
+
 pub struct Quantization<O, Q> {
     quantizer: Json<Q>,
     codes: MmapArray<u8>,
@@ -138,6 +143,7 @@ impl<O: Operator, Q: Quantizer<O>> Quantization<O, Q> {
         &self,
         frlut: &Result<Q::FLut, Q::Lut>,
         range: Range<u32>,
+        filter: impl Fn(u32) -> bool,
         heap: &mut Q::FlatRerankVec,
     ) {
         Q::flat_rerank_continue(
@@ -156,6 +162,7 @@ impl<O: Operator, Q: Quantizer<O>> Quantization<O, Q> {
             },
             frlut,
             range,
+            filter,
             heap,
         )
     }
@@ -15,11 +15,14 @@ use base::operator::*;
 use base::scalar::impossible::Impossible;
 use base::scalar::ScalarLike;
 use base::search::*;
+use base::vector::VectOwned;
 use base::vector::VectorBorrowed;
 use base::vector::VectorOwned;
 use common::sample::sample;
 use common::vec2::Vec2;
+use half::f16;
 use k_means::k_means;
+use k_means::k_means_lookup;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use serde::Deserialize;
 use serde::Serialize;
@@ -33,6 +36,8 @@ pub struct ProductQuantizer<O: OperatorProductQuantization> {
     dims: u32,
     ratio: u32,
     bits: u32,
+    #[serde(default)]
+    projection: Option<Vec<Vec<O::Scalar>>>,
     centroids: Vec2<O::Scalar>,
     tcentroids: Vec2<O::Scalar>,
 }
@@ -52,23 +57,32 @@ impl<O: OperatorProductQuantization> Quantizer<O> for ProductQuantizer<O> {
         };
         let ratio = options.ratio;
         let bits = options.bits;
-        let points = (0..dims.div_ceil(ratio))
-            .into_par_iter()
-            .map(|p| {
-                let subdims = std::cmp::min(ratio, dims - ratio * p);
-                let start = p * ratio;
-                let end = start + subdims;
-                let subsamples = sample(vectors.len(), 65536, end - start, |i| {
-                    O::subslice(
-                        transform(vectors.vector(i)).as_borrowed(),
-                        start,
-                        end - start,
-                    )
-                    .to_vec()
-                });
-                k_means(1 << bits, subsamples, false, 25, true)
-            })
-            .collect::<Vec<_>>();
+        let (projection, points) = if options.opq {
+            let samples = sample(vectors.len(), 8192, dims, |i| {
+                O::subslice(transform(vectors.vector(i)).as_borrowed(), 0, dims).to_vec()
+            });
+            let (projection, points) = opq(ratio, bits, &samples);
+            (Some(projection), points)
+        } else {
+            let points = (0..dims.div_ceil(ratio))
+                .into_par_iter()
+                .map(|p| {
+                    let subdims = std::cmp::min(ratio, dims - ratio * p);
+                    let start = p * ratio;
+                    let end = start + subdims;
+                    let subsamples = sample(vectors.len(), 65536, end - start, |i| {
+                        O::subslice(
+                            transform(vectors.vector(i)).as_borrowed(),
+                            start,
+                            end - start,
+                        )
+                        .to_vec()
+                    });
+                    k_means(1 << bits, subsamples, false, 25, true)
+                })
+                .collect::<Vec<_>>();
+            (None, points)
+        };
         let mut centroids = Vec2::zeros((1 << bits, dims as usize));
         for i in 0..dims.div_ceil(ratio) {
             let subdims = std::cmp::min(ratio, dims - ratio * i);
@@ -87,6 +101,7 @@ impl<O: OperatorProductQuantization> Quantizer<O> for ProductQuantizer<O> {
             dims,
             ratio,
             bits,
+            projection,
             centroids,
             tcentroids,
         }
@@ -142,7 +157,11 @@ impl<O: OperatorProductQuantization> Quantizer<O> for ProductQuantizer<O> {
     }
 
     fn project(&self, vector: Borrowed<'_, O>) -> O::Vector {
-        vector.own()
+        if let Some(projection) = self.projection.as_ref() {
+            O::project(projection, vector)
+        } else {
+            vector.own()
+        }
     }
 
     type Lut = Vec<f32>;
@@ -194,6 +213,7 @@ impl<O: OperatorProductQuantization> Quantizer<O> for ProductQuantizer<O> {
         locate_1: impl Fn(u32) -> C,
         frlut: &Result<Self::FLut, Self::Lut>,
         range: Range<u32>,
+        filter: impl Fn(u32) -> bool,
         heap: &mut Vec<(Reverse<Distance>, AlwaysEqual<u32>)>,
     ) where
         C: AsRef<[u8]>,
@@ -223,7 +243,7 @@ impl<O: OperatorProductQuantization> Quantizer<O> for ProductQuantizer<O> {
                 if let Some(i) = left {
                     let r = self.fscan_process(flut, locate_1(i).as_ref());
                     for j in 0..32 {
-                        if range.contains(&(i * 32 + j)) {
+                        if range.contains(&(i * 32 + j)) && filter(i * 32 + j) {
                             heap.push((Reverse(r[j as usize]), AlwaysEqual(i * 32 + j)));
                         }
                     }
@@ -231,20 +251,22 @@ impl<O: OperatorProductQuantization> Quantizer<O> for ProductQuantizer<O> {
                 for i in main {
                     let r = self.fscan_process(flut, locate_1(i).as_ref());
                     for j in 0..32 {
-                        heap.push((Reverse(r[j as usize]), AlwaysEqual(i * 32 + j)));
+                        if filter(i * 32 + j) {
+                            heap.push((Reverse(r[j as usize]), AlwaysEqual(i * 32 + j)));
+                        }
                     }
                 }
                 if let Some(i) = right {
                     let r = self.fscan_process(flut, locate_1(i).as_ref());
                     for j in 0..32 {
-                        if range.contains(&(i * 32 + j)) {
+                        if range.contains(&(i * 32 + j)) && filter(i * 32 + j) {
                             heap.push((Reverse(r[j as usize]), AlwaysEqual(i * 32 + j)));
                         }
                     }
                 }
             }
             Err(lut) => {
-                for j in range {
+                for j in range.filter(|&j| filter(j)) {
                     let r = O::process(self.dims, self.ratio, self.bits, lut, locate_0(j).as_ref());
                     heap.push((Reverse(r), AlwaysEqual(j)));
                 }
@@ -261,7 +283,7 @@ impl<O: OperatorProductQuantization> Quantizer<O> for ProductQuantizer<O> {
     where
         R: Fn(u32) -> (Distance, T) + 'a,
     {
-        WindowFlatReranker::new(heap, rerank, opts.pq_rerank_size)
+        WindowFlatReranker::new(heap, rerank, opts.pq_rerank_size, opts.limit)
     }
 
     fn graph_rerank<'a, T, R, C>(
@@ -285,6 +307,7 @@ impl<O: OperatorProductQuantization> Quantizer<O> for ProductQuantizer<O> {
 pub trait OperatorProductQuantization: Operator {
     type Scalar: ScalarLike;
     fn subslice(vector: Borrowed<'_, Self>, start: u32, len: u32) -> &[Self::Scalar];
+    fn project(projection: &[Vec<Self::Scalar>], vector: Borrowed<'_, Self>) -> Self::Vector;
     fn code(
         dims: u32,
         ratio: u32,
@@ -316,6 +339,12 @@ impl<S: ScalarLike> OperatorProductQuantization for VectDot<S> {
     fn subslice(vector: Borrowed<'_, Self>, start: u32, len: u32) -> &[Self::Scalar] {
         &vector.slice()[start as usize..][..len as usize]
     }
+    fn project(projection: &[Vec<Self::Scalar>], vector: Borrowed<'_, Self>) -> Self::Vector {
+        let slice = (0..projection.len())
+            .map(|i| S::from_f32(S::reduce_sum_of_xy(&projection[i], vector.slice())))
+            .collect();
+        VectOwned::new(slice)
+    }
     fn code(
         dims: u32,
         ratio: u32,
@@ -511,11 +540,79 @@ impl<S: ScalarLike> OperatorProductQuantization for VectDot<S> {
     }
 }
 
+impl<S: ScalarLike> OperatorProductQuantization for VectCos<S> {
+    type Scalar = S;
+    fn subslice(vector: Borrowed<'_, Self>, start: u32, len: u32) -> &[Self::Scalar] {
+        &vector.slice()[start as usize..][..len as usize]
+    }
+    fn project(projection: &[Vec<Self::Scalar>], vector: Borrowed<'_, Self>) -> Self::Vector {
+        <VectDot<S> as OperatorProductQuantization>::project(projection, vector)
+    }
+    fn code(
+        dims: u32,
+        ratio: u32,
+        bits: u32,
+        centroids: &Vec2<S>,
+        vector: Borrowed<'_, Self>,
+    ) -> Vec<u8> {
+        <VectDot<S> as OperatorProductQuantization>::code(dims, ratio, bits, centroids, vector)
+    }
+
+    fn preprocess(
+        dims: u32,
+        ratio: u32,
+        bits: u32,
+        tcentroids: &Vec2<S>,
+        vector: Borrowed<'_, Self>,
+    ) -> Vec<f32> {
+        let vector = vector.function_normalize();
+        <VectDot<S> as OperatorProductQuantization>::preprocess(
+            dims,
+            ratio,
+            bits,
+            tcentroids,
+            vector.as_borrowed(),
+        )
+    }
+    fn process(dims: u32, ratio: u32, bits: u32, lut: &[f32], code: &[u8]) -> Distance {
+        let dot =
+            <VectDot<S> as OperatorProductQuantization>::process(dims, ratio, bits, lut, code);
+        Distance::from(1.0 + dot.to_f32())
+    }
+
+    fn fscan_preprocess(
+        dims: u32,
+        ratio: u32,
+        bits: u32,
+        centroids: &Vec2<S>,
+        vector: Borrowed<'_, Self>,
+    ) -> (u32, f32, f32, Vec<u8>) {
+        let vector = vector.function_normalize();
+        <VectDot<S> as OperatorProductQuantization>::fscan_preprocess(
+            dims,
+            ratio,
+            bits,
+            centroids,
+            vector.as_borrowed(),
+        )
+    }
+    fn fscan_process(flut: &(u32, f32, f32, Vec<u8>), codes: &[u8]) -> [Distance; 32] {
+        <VectDot<S> as OperatorProductQuantization>::fscan_process(flut, codes)
+            .map(|dot| Distance::from(1.0 + dot.to_f32()))
+    }
+}
+
 impl<S: ScalarLike> OperatorProductQuantization for VectL2<S> {
     type Scalar = S;
     fn subslice(vector: Borrowed<'_, Self>, start: u32, len: u32) -> &[Self::Scalar] {
         &vector.slice()[start as usize..][..len as usize]
     }
+    fn project(projection: &[Vec<Self::Scalar>], vector: Borrowed<'_, Self>) -> Self::Vector {
+        let slice = (0..projection.len())
+            .map(|i| S::from_f32(S::reduce_sum_of_xy(&projection[i], vector.slice())))
+            .collect();
+        VectOwned::new(slice)
+    }
     fn code(
         dims: u32,
         ratio: u32,
@@ -711,6 +808,59 @@ impl<S: ScalarLike> OperatorProductQuantization for VectL2<S> {
     }
 }
 
+/// Trains a rotation alternately with the codebooks, returning the rows of the
+/// rotation and the codebooks of the rotated subspaces.
+fn opq<S: ScalarLike>(ratio: u32, bits: u32, samples: &Vec2<S>) -> (Vec<Vec<S>>, Vec<Vec2<S>>) {
+    use nalgebra::DMatrix;
+    const ITERATIONS: usize = 4;
+    let (n, dims) = samples.shape();
+    let ratio = ratio as usize;
+    let x = DMatrix::from_fn(n, dims, |i, j| samples[(i, j)].to_f32());
+    let train = |xr: &DMatrix<f32>, iterations: usize| {
+        (0..dims.div_ceil(ratio))
+            .into_par_iter()
+            .map(|p| {
+                let start = p * ratio;
+                let end = std::cmp::min(start + ratio, dims);
+                let mut subsamples = Vec2::zeros((n, end - start));
+                for i in 0..n {
+                    for j in start..end {
+                        subsamples[(i, j - start)] = S::from_f32(xr[(i, j)]);
+                    }
+                }
+                k_means(1 << bits, subsamples, false, iterations, true)
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut r = DMatrix::<f32>::identity(dims, dims);
+    for _ in 0..ITERATIONS {
+        let xr = &x * &r;
+        let points = train(&xr, 10);
+        // reconstruct samples with the codebooks, then fit the rotation to them
+        let mut y = DMatrix::<f32>::zeros(n, dims);
+        for (p, centroids) in points.iter().enumerate() {
+            let start = p * ratio;
+            let end = std::cmp::min(start + ratio, dims);
+            for i in 0..n {
+                let left = (start..end)
+                    .map(|j| S::from_f32(xr[(i, j)]))
+                    .collect::<Vec<_>>();
+                let target = k_means_lookup(&left, centroids);
+                for j in start..end {
+                    y[(i, j)] = centroids[(target, j - start)].to_f32();
+                }
+            }
+        }
+        let svd = (x.transpose() * y).svd(true, true);
+        r = svd.u.unwrap() * svd.v_t.unwrap();
+    }
+    let points = train(&(&x * &r), 25);
+    let projection = (0..dims)
+        .map(|i| (0..dims).map(|j| S::from_f32(r[(j, i)])).collect())
+        .collect();
+    (projection, points)
+}
+
 macro_rules! unimpl_operator_product_quantization {
     ($t:ty) => {
         impl OperatorProductQuantization for $t {
@@ -718,6 +868,9 @@ macro_rules! unimpl_operator_product_quantization {
             fn subslice(_: Borrowed<'_, Self>, _: u32, _: u32) -> &[Self::Scalar] {
                 unimplemented!()
             }
+            fn project(_: &[Vec<Self::Scalar>], _: Borrowed<'_, Self>) -> Self::Vector {
+                unimplemented!()
+            }
             fn code(
                 _: u32,
                 _: u32,
@@ -761,5 +914,83 @@ unimpl_operator_product_quantization!(BVectorDot);
 unimpl_operator_product_quantization!(BVectorHamming);
 unimpl_operator_product_quantization!(BVectorJaccard);
 
+unimpl_operator_product_quantization!(VectL1<f32>);
+unimpl_operator_product_quantization!(VectL1<f16>);
+
+unimpl_operator_product_quantization!(SVectCos<f32>);
 unimpl_operator_product_quantization!(SVectDot<f32>);
 unimpl_operator_product_quantization!(SVectL2<f32>);
+
+unimpl_operator_product_quantization!(Veci8Cos);
+unimpl_operator_product_quantization!(Veci8Dot);
+unimpl_operator_product_quantization!(Veci8L2);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha12Rng;
+    use rand_distr::StandardNormal;
+
+    #[test]
+    fn opq_test() {
+        // `k_means` checks for cancellation, so it must run inside the pool
+        stoppable_rayon::ThreadPoolBuilder::new()
+            .build_scoped(|pool| pool.install(opq))
+            .unwrap()
+            .unwrap();
+    }
+
+    fn opq() {
+        let (n, dims, ratio, bits) = (2048, 16, 2, 4);
+        let mut rng = ChaCha12Rng::from_seed([7; 32]);
+        // variance is concentrated in the first dimensions, then mixed by a rotation
+        let rotation = {
+            let matrix =
+                nalgebra::DMatrix::<f32>::from_fn(dims, dims, |_, _| rng.sample(StandardNormal));
+            matrix.qr().q()
+        };
+        let mut samples = Vec2::<f32>::zeros((n, dims));
+        for i in 0..n {
+            let z = nalgebra::DVector::<f32>::from_fn(dims, |j, _| {
+                rng.sample::<f32, _>(StandardNormal) * 8.0 / (j + 1) as f32
+            });
+            samples[(i,)].copy_from_slice((&rotation * z).as_slice());
+        }
+        let loss = |projection: Option<&[Vec<f32>]>, points: &[Vec2<f32>]| {
+            let mut loss = 0.0f32;
+            for i in 0..n {
+                let x = match projection {
+                    Some(p) => (0..dims)
+                        .map(|j| f32::reduce_sum_of_xy(&p[j], &samples[(i,)]))
+                        .collect::<Vec<_>>(),
+                    None => samples[(i,)].to_vec(),
+                };
+                for (p, centroids) in points.iter().enumerate() {
+                    let sub = &x[p * ratio..][..ratio];
+                    let target = k_means_lookup(sub, centroids);
+                    loss += f32::reduce_sum_of_d2(sub, &centroids[(target,)]);
+                }
+            }
+            loss
+        };
+        let plain = (0..dims / ratio)
+            .map(|p| {
+                let mut subsamples = Vec2::zeros((n, ratio));
+                for i in 0..n {
+                    subsamples[(i,)].copy_from_slice(&samples[(i,)][p * ratio..][..ratio]);
+                }
+                k_means(1 << bits, subsamples, false, 25, true)
+            })
+            .collect::<Vec<_>>();
+        let (projection, points) = super::opq(ratio as u32, bits, &samples);
+        for i in 0..dims {
+            for j in 0..dims {
+                let dot = f32::reduce_sum_of_xy(&projection[i], &projection[j]);
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((dot - expected).abs() < 1e-3, "not a rotation");
+            }
+        }
+        assert!(loss(Some(&projection), &points) < loss(None, &plain));
+    }
+}
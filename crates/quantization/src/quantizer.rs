@@ -47,6 +47,7 @@ pub trait Quantizer<O: Operator>:
         locate_1: impl Fn(u32) -> C,
         frlut: &Result<Self::FLut, Self::Lut>,
         range: Range<u32>,
+        filter: impl Fn(u32) -> bool,
         heap: &mut Self::FlatRerankVec,
     ) where
         C: AsRef<[u8]>;
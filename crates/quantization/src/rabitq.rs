@@ -2,6 +2,7 @@ use crate::fast_scan::b4::fast_scan_b4;
 use crate::fast_scan::b4::pack;
 use crate::quantizer::Quantizer;
 use crate::reranker::error::ErrorFlatReranker;
+use crate::reranker::error::ErrorGraphReranker;
 use crate::reranker::graph_2::Graph2Reranker;
 use crate::utils::InfiniteByteChunks;
 use base::always_equal::AlwaysEqual;
@@ -14,6 +15,7 @@ use base::search::*;
 use base::vector::VectOwned;
 use base::vector::VectorBorrowed;
 use base::vector::VectorOwned;
+use half::f16;
 use serde::{Deserialize, Serialize};
 use std::cmp::Reverse;
 use std::marker::PhantomData;
@@ -23,6 +25,8 @@ use std::ops::Range;
 #[serde(bound = "")]
 pub struct RabitqQuantizer<O: OperatorRabitqQuantization> {
     dims: u32,
+    #[serde(default = "default_bits")]
+    bits: u32,
     projection: Vec<Vec<O::Scalar>>,
     _maker: PhantomData<O>,
 }
@@ -30,11 +34,17 @@ pub struct RabitqQuantizer<O: OperatorRabitqQuantization> {
 impl<O: OperatorRabitqQuantization> Quantizer<O> for RabitqQuantizer<O> {
     fn train(
         vector_options: VectorOptions,
-        _: Option<QuantizationOptions>,
+        options: Option<QuantizationOptions>,
         _: &(impl Vectors<O::Vector> + Sync),
         _: impl Fn(Borrowed<'_, O>) -> O::Vector + Copy + Sync,
     ) -> Self {
         let dims = vector_options.dims;
+        let options = if let Some(QuantizationOptions::Rabitq(x)) = options {
+            x
+        } else {
+            panic!("inconsistent parameters: options and generics")
+        };
+        let bits = options.bits;
         let projection = {
             use nalgebra::{DMatrix, QR};
             use rand::{Rng, SeedableRng};
@@ -55,6 +65,7 @@ impl<O: OperatorRabitqQuantization> Quantizer<O> for RabitqQuantizer<O> {
         };
         Self {
             dims,
+            bits,
             projection,
             _maker: PhantomData,
         }
@@ -62,25 +73,29 @@ impl<O: OperatorRabitqQuantization> Quantizer<O> for RabitqQuantizer<O> {
 
     fn encode(&self, vector: Borrowed<'_, O>) -> Vec<u8> {
         let dims = self.dims;
-        let (a, b, c, d, e) = O::code(vector);
-        let mut result = Vec::with_capacity(size_of::<f32>() * 4);
+        let (a, b, c, d, e) = O::code(self.bits, vector);
+        let mut result = Vec::with_capacity(self.code_size() as usize);
         result.extend(a.to_ne_bytes());
         result.extend(b.to_ne_bytes());
         result.extend(c.to_ne_bytes());
         result.extend(d.to_ne_bytes());
-        for x in InfiniteByteChunks::<_, 64>::new(e.into_iter()).take(dims.div_ceil(64) as usize) {
-            let mut r = 0_u64;
-            for i in 0..64 {
-                r |= (x[i] as u64) << i;
+        // bit planes, from the most significant one, which is the sign
+        for p in (0..self.bits).rev() {
+            let plane = e.iter().map(|&x| (x >> p) & 1);
+            for x in InfiniteByteChunks::<_, 64>::new(plane).take(dims.div_ceil(64) as usize) {
+                let mut r = 0_u64;
+                for i in 0..64 {
+                    r |= (x[i] as u64) << i;
+                }
+                result.extend(r.to_ne_bytes().into_iter());
             }
-            result.extend(r.to_ne_bytes().into_iter());
         }
         result
     }
 
     fn fscan_encode(&self, vectors: [O::Vector; 32]) -> Vec<u8> {
         let dims = self.dims;
-        let coded = vectors.map(|vector| O::code(vector.as_borrowed()));
+        let coded = vectors.map(|vector| O::code(1, vector.as_borrowed()));
         let codes = coded.clone().map(|(_, _, _, _, e)| {
             InfiniteByteChunks::new(e.into_iter())
                 .map(|[b0, b1, b2, b3]| b0 | b1 << 1 | b2 << 2 | b3 << 3)
@@ -105,7 +120,7 @@ impl<O: OperatorRabitqQuantization> Quantizer<O> for RabitqQuantizer<O> {
     }
 
     fn code_size(&self) -> u32 {
-        size_of::<f32>() as u32 * 4 + size_of::<u64>() as u32 * self.dims.div_ceil(64)
+        size_of::<f32>() as u32 * 4 + size_of::<u64>() as u32 * self.dims.div_ceil(64) * self.bits
     }
 
     fn fcode_size(&self) -> u32 {
@@ -162,6 +177,7 @@ impl<O: OperatorRabitqQuantization> Quantizer<O> for RabitqQuantizer<O> {
         locate_1: impl Fn(u32) -> C,
         frlut: &Result<Self::FLut, Self::Lut>,
         range: Range<u32>,
+        filter: impl Fn(u32) -> bool,
         heap: &mut Self::FlatRerankVec,
     ) where
         C: AsRef<[u8]>,
@@ -193,7 +209,7 @@ impl<O: OperatorRabitqQuantization> Quantizer<O> for RabitqQuantizer<O> {
                     let c = parses_codes(c.as_ref());
                     let r = O::fscan_process_lowerbound(self.dims, flut, c, 1.9);
                     for j in 0..32 {
-                        if range.contains(&(i * 32 + j)) {
+                        if range.contains(&(i * 32 + j)) && filter(i * 32 + j) {
                             heap.push((Reverse(r[j as usize]), AlwaysEqual(i * 32 + j)));
                         }
                     }
@@ -203,7 +219,9 @@ impl<O: OperatorRabitqQuantization> Quantizer<O> for RabitqQuantizer<O> {
                     let c = parses_codes(c.as_ref());
                     let r = O::fscan_process_lowerbound(self.dims, flut, c, 1.9);
                     for j in 0..32 {
-                        heap.push((Reverse(r[j as usize]), AlwaysEqual(i * 32 + j)));
+                        if filter(i * 32 + j) {
+                            heap.push((Reverse(r[j as usize]), AlwaysEqual(i * 32 + j)));
+                        }
                     }
                 }
                 if let Some(i) = right {
@@ -211,14 +229,14 @@ impl<O: OperatorRabitqQuantization> Quantizer<O> for RabitqQuantizer<O> {
                     let c = parses_codes(c.as_ref());
                     let r = O::fscan_process_lowerbound(self.dims, flut, c, 1.9);
                     for j in 0..32 {
-                        if range.contains(&(i * 32 + j)) {
+                        if range.contains(&(i * 32 + j)) && filter(i * 32 + j) {
                             heap.push((Reverse(r[j as usize]), AlwaysEqual(i * 32 + j)));
                         }
                     }
                 }
             }
             Err(lut) => {
-                for j in range {
+                for j in range.filter(|&j| filter(j)) {
                     let c = locate_0(j);
                     let c = parse_code(c.as_ref());
                     let r = O::process_lowerbound(lut, c, 1.9);
@@ -251,17 +269,51 @@ impl<O: OperatorRabitqQuantization> Quantizer<O> for RabitqQuantizer<O> {
         R: Fn(u32) -> (Distance, T) + 'a,
         C: AsRef<[u8]>,
     {
-        Graph2Reranker::new(
-            move |u| O::process(&lut, parse_code(locate(u).as_ref())),
-            rerank,
-        )
+        if self.bits == 1 {
+            GraphRerankers::Rough(Graph2Reranker::new(
+                move |u| O::process(&lut, parse_code(locate(u).as_ref())),
+                rerank,
+            ))
+        } else {
+            GraphRerankers::Lowerbound(ErrorGraphReranker::new(
+                move |u| O::process_lowerbound(&lut, parse_code(locate(u).as_ref()), 1.9),
+                rerank,
+            ))
+        }
+    }
+}
+
+fn default_bits() -> u32 {
+    1
+}
+
+enum GraphRerankers<A, B> {
+    Rough(A),
+    Lowerbound(B),
+}
+
+impl<T, A: RerankerPop<T>, B: RerankerPop<T>> RerankerPop<T> for GraphRerankers<A, B> {
+    fn pop(&mut self) -> Option<(Distance, u32, T)> {
+        match self {
+            GraphRerankers::Rough(x) => x.pop(),
+            GraphRerankers::Lowerbound(x) => x.pop(),
+        }
+    }
+}
+
+impl<A: RerankerPush, B: RerankerPush> RerankerPush for GraphRerankers<A, B> {
+    fn push(&mut self, u: u32) {
+        match self {
+            GraphRerankers::Rough(x) => x.push(u),
+            GraphRerankers::Lowerbound(x) => x.push(u),
+        }
     }
 }
 
 pub trait OperatorRabitqQuantization: Operator {
     type Scalar: ScalarLike;
 
-    fn code(vector: Borrowed<'_, Self>) -> (f32, f32, f32, f32, Vec<u8>);
+    fn code(bits: u32, vector: Borrowed<'_, Self>) -> (f32, f32, f32, f32, Vec<u8>);
 
     fn project(projection: &[Vec<Self::Scalar>], vector: Borrowed<'_, Self>) -> Self::Vector;
 
@@ -292,32 +344,8 @@ pub trait OperatorRabitqQuantization: Operator {
 impl<S: ScalarLike> OperatorRabitqQuantization for VectL2<S> {
     type Scalar = S;
 
-    fn code(vector: Borrowed<'_, Self>) -> (f32, f32, f32, f32, Vec<u8>) {
-        let dims = vector.dims();
-        let vector = vector.slice();
-        let sum_of_abs_x = S::reduce_sum_of_abs_x(vector);
-        let sum_of_x2 = S::reduce_sum_of_x2(vector);
-        let dis_u = sum_of_x2.sqrt();
-        let x0 = sum_of_abs_x / (sum_of_x2 * (dims as f32)).sqrt();
-        let x_x0 = dis_u / x0;
-        let fac_norm = (dims as f32).sqrt();
-        let max_x1 = 1.0f32 / (dims as f32 - 1.0).sqrt();
-        let factor_err = 2.0f32 * max_x1 * (x_x0 * x_x0 - dis_u * dis_u).sqrt();
-        let factor_ip = -2.0f32 / fac_norm * x_x0;
-        let cnt_pos = vector
-            .iter()
-            .map(|x| x.scalar_is_sign_positive() as i32)
-            .sum::<i32>();
-        let cnt_neg = vector
-            .iter()
-            .map(|x| x.scalar_is_sign_negative() as i32)
-            .sum::<i32>();
-        let factor_ppc = factor_ip * (cnt_pos - cnt_neg) as f32;
-        let mut code = Vec::new();
-        for i in 0..dims {
-            code.push(vector[i as usize].scalar_is_sign_positive() as u8);
-        }
-        (sum_of_x2, factor_ppc, factor_ip, factor_err, code)
+    fn code(bits: u32, vector: Borrowed<'_, Self>) -> (f32, f32, f32, f32, Vec<u8>) {
+        code(bits, vector.slice())
     }
 
     fn project(projection: &[Vec<Self::Scalar>], vector: Borrowed<'_, Self>) -> Self::Vector {
@@ -348,11 +376,8 @@ impl<S: ScalarLike> OperatorRabitqQuantization for VectL2<S> {
         (dis_u_2, factor_ppc, factor_ip, _, t): (f32, f32, f32, f32, &[u64]),
     ) -> Distance {
         let &(dis_v_2, b, k, qvector_sum, ref s) = lut;
-        let value = asymmetric_binary_dot_product(t, s) as u16;
-        let rough = dis_u_2
-            + dis_v_2
-            + b * factor_ppc
-            + ((2.0 * value as f32) - qvector_sum) * factor_ip * k;
+        let value = asymmetric_centered_dot_product(t, s, qvector_sum);
+        let rough = dis_u_2 + dis_v_2 + b * factor_ppc + value * factor_ip * k;
         Distance::from_f32(rough)
     }
 
@@ -362,12 +387,10 @@ impl<S: ScalarLike> OperatorRabitqQuantization for VectL2<S> {
         epsilon: f32,
     ) -> Distance {
         let &(dis_v_2, b, k, qvector_sum, ref s) = lut;
-        let value = asymmetric_binary_dot_product(t, s) as u16;
-        let rough = dis_u_2
-            + dis_v_2
-            + b * factor_ppc
-            + ((2.0 * value as f32) - qvector_sum) * factor_ip * k;
-        let err = factor_err * dis_v_2.sqrt();
+        let value = asymmetric_centered_dot_product(t, s, qvector_sum);
+        let rough = dis_u_2 + dis_v_2 + b * factor_ppc + value * factor_ip * k;
+        // the quantization of the query adds an error of about `|u| k / sqrt(12)` to `<u, v>`
+        let err = (factor_err * factor_err * dis_v_2 + dis_u_2 * k * k / 3.0).sqrt();
         Distance::from_f32(rough - epsilon * err)
     }
 
@@ -437,32 +460,8 @@ impl<S: ScalarLike> OperatorRabitqQuantization for VectL2<S> {
 impl<S: ScalarLike> OperatorRabitqQuantization for VectDot<S> {
     type Scalar = S;
 
-    fn code(vector: Borrowed<'_, Self>) -> (f32, f32, f32, f32, Vec<u8>) {
-        let dims = vector.dims();
-        let vector = vector.slice();
-        let sum_of_abs_x = S::reduce_sum_of_abs_x(vector);
-        let sum_of_x2 = S::reduce_sum_of_x2(vector);
-        let dis_u = sum_of_x2.sqrt();
-        let x0 = sum_of_abs_x / (sum_of_x2 * (dims as f32)).sqrt();
-        let x_x0 = dis_u / x0;
-        let fac_norm = (dims as f32).sqrt();
-        let max_x1 = 1.0f32 / (dims as f32 - 1.0).sqrt();
-        let factor_err = 2.0f32 * max_x1 * (x_x0 * x_x0 - dis_u * dis_u).sqrt();
-        let factor_ip = -2.0f32 / fac_norm * x_x0;
-        let cnt_pos = vector
-            .iter()
-            .map(|x| x.scalar_is_sign_positive() as i32)
-            .sum::<i32>();
-        let cnt_neg = vector
-            .iter()
-            .map(|x| x.scalar_is_sign_negative() as i32)
-            .sum::<i32>();
-        let factor_ppc = factor_ip * (cnt_pos - cnt_neg) as f32;
-        let mut code = Vec::new();
-        for i in 0..dims {
-            code.push(vector[i as usize].scalar_is_sign_positive() as u8);
-        }
-        (sum_of_x2, factor_ppc, factor_ip, factor_err, code)
+    fn code(bits: u32, vector: Borrowed<'_, Self>) -> (f32, f32, f32, f32, Vec<u8>) {
+        code(bits, vector.slice())
     }
 
     fn project(projection: &[Vec<Self::Scalar>], vector: Borrowed<'_, Self>) -> Self::Vector {
@@ -493,22 +492,20 @@ impl<S: ScalarLike> OperatorRabitqQuantization for VectDot<S> {
         (_, factor_ppc, factor_ip, _, t): (f32, f32, f32, f32, &[u64]),
     ) -> Distance {
         let &(_, b, k, qvector_sum, ref s) = lut;
-        let value = asymmetric_binary_dot_product(t, s) as u16;
-        let rough =
-            0.5 * b * factor_ppc + 0.5 * ((2.0 * value as f32) - qvector_sum) * factor_ip * k;
+        let value = asymmetric_centered_dot_product(t, s, qvector_sum);
+        let rough = 0.5 * b * factor_ppc + 0.5 * value * factor_ip * k;
         Distance::from_f32(rough)
     }
 
     fn process_lowerbound(
         lut: &Self::Lut,
-        (_, factor_ppc, factor_ip, factor_err, t): (f32, f32, f32, f32, &[u64]),
+        (dis_u_2, factor_ppc, factor_ip, factor_err, t): (f32, f32, f32, f32, &[u64]),
         epsilon: f32,
     ) -> Distance {
         let &(dis_v_2, b, k, qvector_sum, ref s) = lut;
-        let value = asymmetric_binary_dot_product(t, s) as u16;
-        let rough =
-            0.5 * b * factor_ppc + 0.5 * ((2.0 * value as f32) - qvector_sum) * factor_ip * k;
-        let err = 0.5 * factor_err * dis_v_2.sqrt();
+        let value = asymmetric_centered_dot_product(t, s, qvector_sum);
+        let rough = 0.5 * b * factor_ppc + 0.5 * value * factor_ip * k;
+        let err = 0.5 * (factor_err * factor_err * dis_v_2 + dis_u_2 * k * k / 3.0).sqrt();
         Distance::from_f32(rough - epsilon * err)
     }
 
@@ -565,12 +562,74 @@ impl<S: ScalarLike> OperatorRabitqQuantization for VectDot<S> {
     }
 }
 
+impl<S: ScalarLike> OperatorRabitqQuantization for VectCos<S> {
+    type Scalar = S;
+
+    fn code(bits: u32, vector: Borrowed<'_, Self>) -> (f32, f32, f32, f32, Vec<u8>) {
+        <VectDot<S> as OperatorRabitqQuantization>::code(bits, vector)
+    }
+
+    fn project(projection: &[Vec<Self::Scalar>], vector: Borrowed<'_, Self>) -> Self::Vector {
+        <VectDot<S> as OperatorRabitqQuantization>::project(projection, vector)
+    }
+
+    type Lut = <VectDot<S> as OperatorRabitqQuantization>::Lut;
+
+    fn preprocess(vector: Borrowed<'_, Self>) -> Self::Lut {
+        let vector = vector.function_normalize();
+        <VectDot<S> as OperatorRabitqQuantization>::preprocess(vector.as_borrowed())
+    }
+
+    fn process(lut: &Self::Lut, code: (f32, f32, f32, f32, &[u64])) -> Distance {
+        let dot = <VectDot<S> as OperatorRabitqQuantization>::process(lut, code);
+        Distance::from_f32(1.0 + dot.to_f32())
+    }
+
+    fn process_lowerbound(
+        lut: &Self::Lut,
+        code: (f32, f32, f32, f32, &[u64]),
+        epsilon: f32,
+    ) -> Distance {
+        let dot =
+            <VectDot<S> as OperatorRabitqQuantization>::process_lowerbound(lut, code, epsilon);
+        Distance::from_f32(1.0 + dot.to_f32())
+    }
+
+    type FLut = <VectDot<S> as OperatorRabitqQuantization>::FLut;
+
+    fn fscan_preprocess(vector: Borrowed<'_, Self>) -> Self::FLut {
+        let vector = vector.function_normalize();
+        <VectDot<S> as OperatorRabitqQuantization>::fscan_preprocess(vector.as_borrowed())
+    }
+
+    fn fscan_process(
+        dims: u32,
+        lut: &Self::FLut,
+        code: (&[f32; 32], &[f32; 32], &[f32; 32], &[f32; 32], &[u8]),
+    ) -> [Distance; 32] {
+        <VectDot<S> as OperatorRabitqQuantization>::fscan_process(dims, lut, code)
+            .map(|dot| Distance::from_f32(1.0 + dot.to_f32()))
+    }
+
+    fn fscan_process_lowerbound(
+        dims: u32,
+        lut: &Self::FLut,
+        code: (&[f32; 32], &[f32; 32], &[f32; 32], &[f32; 32], &[u8]),
+        epsilon: f32,
+    ) -> [Distance; 32] {
+        <VectDot<S> as OperatorRabitqQuantization>::fscan_process_lowerbound(
+            dims, lut, code, epsilon,
+        )
+        .map(|dot| Distance::from_f32(1.0 + dot.to_f32()))
+    }
+}
+
 macro_rules! unimpl_operator_rabitq_quantization {
     ($t:ty) => {
         impl OperatorRabitqQuantization for $t {
             type Scalar = Impossible;
 
-            fn code(_: Borrowed<'_, Self>) -> (f32, f32, f32, f32, Vec<u8>) {
+            fn code(_: u32, _: Borrowed<'_, Self>) -> (f32, f32, f32, f32, Vec<u8>) {
                 unimplemented!()
             }
 
@@ -620,9 +679,79 @@ unimpl_operator_rabitq_quantization!(BVectorDot);
 unimpl_operator_rabitq_quantization!(BVectorHamming);
 unimpl_operator_rabitq_quantization!(BVectorJaccard);
 
+unimpl_operator_rabitq_quantization!(VectL1<f32>);
+unimpl_operator_rabitq_quantization!(VectL1<f16>);
+
+unimpl_operator_rabitq_quantization!(SVectCos<f32>);
 unimpl_operator_rabitq_quantization!(SVectDot<f32>);
 unimpl_operator_rabitq_quantization!(SVectL2<f32>);
 
+unimpl_operator_rabitq_quantization!(Veci8Cos);
+unimpl_operator_rabitq_quantization!(Veci8Dot);
+unimpl_operator_rabitq_quantization!(Veci8L2);
+
+fn code<S: ScalarLike>(bits: u32, vector: &[S]) -> (f32, f32, f32, f32, Vec<u8>) {
+    let dims = vector.len();
+    let sum_of_x2 = S::reduce_sum_of_x2(vector);
+    let dis_u = sum_of_x2.sqrt();
+    let abs_o = vector
+        .iter()
+        .map(|x| x.to_f32().abs() / dis_u)
+        .collect::<Vec<_>>();
+    // the code of a dimension is a sign and a magnitude `m` in `[0, 2^(bits - 1))`,
+    // which stands for `(m + 0.5)` times the sign
+    let levels = 1_u32 << (bits - 1);
+    let magnitude = |t: f32| {
+        abs_o
+            .iter()
+            .map(|&x| ((t * x) as u32).min(levels - 1))
+            .collect::<Vec<_>>()
+    };
+    let cosine = |m: &[u32]| {
+        let mut ip_y_o = 0.0f32;
+        let mut norm_y_2 = 0.0f32;
+        for i in 0..dims {
+            let y = m[i] as f32 + 0.5;
+            ip_y_o += y * abs_o[i];
+            norm_y_2 += y * y;
+        }
+        (ip_y_o, ip_y_o / norm_y_2.sqrt())
+    };
+    let mut m = vec![0_u32; dims];
+    if levels > 1 {
+        // search for the rescaling factor that makes the code closest to the vector
+        const STEPS: u32 = 64;
+        let max_abs_o = abs_o.iter().copied().fold(0.0f32, f32::max);
+        let mut best = cosine(&m).1;
+        for step in 1..=STEPS {
+            let t = levels as f32 * step as f32 / (STEPS as f32 * max_abs_o);
+            let candidate = magnitude(t);
+            let cos = cosine(&candidate).1;
+            if cos > best {
+                best = cos;
+                m = candidate;
+            }
+        }
+    }
+    let (ip_y_o, cos) = cosine(&m);
+    let max_x1 = 1.0f32 / (dims as f32 - 1.0).sqrt();
+    let factor_err = 2.0f32 * max_x1 * dis_u * (1.0 / (cos * cos) - 1.0).sqrt();
+    let factor_ip = -dis_u / ip_y_o;
+    let mut sum_of_y = 0.0f32;
+    let mut code = Vec::with_capacity(dims);
+    for i in 0..dims {
+        if vector[i].scalar_is_sign_positive() {
+            sum_of_y += m[i] as f32 + 0.5;
+            code.push((levels + m[i]) as u8);
+        } else {
+            sum_of_y -= m[i] as f32 + 0.5;
+            code.push((levels - 1 - m[i]) as u8);
+        }
+    }
+    let factor_ppc = factor_ip * 2.0 * sum_of_y;
+    (sum_of_x2, factor_ppc, factor_ip, factor_err, code)
+}
+
 fn parse_code(code: &[u8]) -> (f32, f32, f32, f32, &[u64]) {
     assert!(code.len() > size_of::<f32>() * 4, "length is incorrect");
     assert!(code.len() % size_of::<u64>() == 0, "length is incorrect");
@@ -700,6 +829,21 @@ fn binarize(vector: &[u8]) -> (Vec<u64>, Vec<u64>, Vec<u64>, Vec<u64>) {
     (t0, t1, t2, t3)
 }
 
+/// Computes `2 <c, q> - (2^bits - 1) sum(q)`, where `c` is a code of `bits` planes
+/// and `q` is the quantized query.
+fn asymmetric_centered_dot_product(
+    x: &[u64],
+    y: &(Vec<u64>, Vec<u64>, Vec<u64>, Vec<u64>),
+    qvector_sum: f32,
+) -> f32 {
+    let words = y.0.len();
+    let bits = x.len() / words;
+    let value = x.chunks_exact(words).fold(0_u64, |acc, plane| {
+        (acc << 1) + asymmetric_binary_dot_product(plane, y) as u64
+    });
+    2.0 * value as f32 - ((1_u64 << bits) - 1) as f32 * qvector_sum
+}
+
 #[detect::multiversion(v2, fallback)]
 fn asymmetric_binary_dot_product(x: &[u64], y: &(Vec<u64>, Vec<u64>, Vec<u64>, Vec<u64>)) -> u32 {
     assert_eq!(x.len(), y.0.len());
@@ -722,3 +866,57 @@ fn asymmetric_binary_dot_product(x: &[u64], y: &(Vec<u64>, Vec<u64>, Vec<u64>, V
     }
     (t0 << 0) + (t1 << 1) + (t2 << 2) + (t3 << 3)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha12Rng;
+
+    #[test]
+    fn rabitq_multibit_error_test() {
+        type O = VectL2<f32>;
+        let dims = 256;
+        let mut rng = ChaCha12Rng::from_seed([7; 32]);
+        let mut sample = || {
+            let x = (0..dims).map(|_| rng.gen_range(-1.0f32..1.0)).collect();
+            VectOwned::<f32>::new(x)
+        };
+        let pairs = (0..200).map(|_| (sample(), sample())).collect::<Vec<_>>();
+        let mut errors = Vec::new();
+        for bits in [1, 2, 4, 8] {
+            let quantizer = RabitqQuantizer::<O> {
+                dims,
+                bits,
+                projection: Vec::new(),
+                _maker: PhantomData,
+            };
+            let mut error = 0.0f32;
+            let mut violations = 0;
+            for (x, q) in pairs.iter() {
+                // codes are read as `u64`, so they must be aligned
+                let code = quantizer.encode(x.as_borrowed());
+                let code = code
+                    .chunks_exact(8)
+                    .map(|c| u64::from_ne_bytes(c.try_into().unwrap()))
+                    .collect::<Vec<_>>();
+                let code = unsafe {
+                    std::slice::from_raw_parts(code.as_ptr().cast::<u8>(), code.len() * 8)
+                };
+                let lut = quantizer.preprocess(q.as_borrowed());
+                let exact = O::distance(x.as_borrowed(), q.as_borrowed()).to_f32();
+                let rough = quantizer.process(&lut, code, q.as_borrowed()).to_f32();
+                let lowerbound = O::process_lowerbound(&lut, parse_code(code), 1.9).to_f32();
+                violations += (lowerbound > exact) as usize;
+                error += (rough - exact).abs() / exact;
+            }
+            // the lowerbound holds with high probability
+            assert!(violations * 10 < pairs.len(), "bits = {bits}");
+            errors.push(error / pairs.len() as f32);
+        }
+        assert!(
+            errors.windows(2).all(|w| w[1] < w[0]),
+            "errors = {errors:?}"
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use base::always_equal::AlwaysEqual;
 use base::distance::Distance;
 use base::search::RerankerPop;
+use base::search::RerankerPush;
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
 
@@ -40,3 +41,49 @@ where
         Some((dist, u, pay_u))
     }
 }
+
+pub struct ErrorGraphReranker<T, F, R> {
+    compute: F,
+    rerank: R,
+    heap: BinaryHeap<(Reverse<Distance>, AlwaysEqual<u32>)>,
+    cache: BinaryHeap<(Reverse<Distance>, AlwaysEqual<u32>, AlwaysEqual<T>)>,
+}
+
+impl<T, F, R> ErrorGraphReranker<T, F, R> {
+    pub fn new(compute: F, rerank: R) -> Self {
+        Self {
+            compute,
+            rerank,
+            heap: BinaryHeap::new(),
+            cache: BinaryHeap::new(),
+        }
+    }
+}
+
+impl<T, F, R> RerankerPop<T> for ErrorGraphReranker<T, F, R>
+where
+    R: Fn(u32) -> (Distance, T),
+{
+    fn pop(&mut self) -> Option<(Distance, u32, T)> {
+        while !self.heap.is_empty()
+            && self.heap.peek().map(|x| x.0) > self.cache.peek().map(|x| x.0)
+        {
+            let (_, AlwaysEqual(u)) = self.heap.pop().unwrap();
+            let (dis_u, pay_u) = (self.rerank)(u);
+            self.cache
+                .push((Reverse(dis_u), AlwaysEqual(u), AlwaysEqual(pay_u)));
+        }
+        let (Reverse(dist), AlwaysEqual(u), AlwaysEqual(pay_u)) = self.cache.pop()?;
+        Some((dist, u, pay_u))
+    }
+}
+
+impl<T, F, R> RerankerPush for ErrorGraphReranker<T, F, R>
+where
+    F: Fn(u32) -> Distance,
+{
+    fn push(&mut self, u: u32) {
+        let lowerbound_u = (self.compute)(u);
+        self.heap.push((Reverse(lowerbound_u), AlwaysEqual(u)));
+    }
+}
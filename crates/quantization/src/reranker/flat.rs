@@ -4,10 +4,114 @@ use base::search::*;
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
 
+/// A max-heap that is built from the greatest `limit` elements first, and from the others
+/// once they are all popped, so that a search with a known limit doesn't heapify everything.
+pub struct PrefetchHeap<E> {
+    heap: BinaryHeap<E>,
+    rest: Vec<E>,
+}
+
+impl<E: Ord> PrefetchHeap<E> {
+    pub fn new(mut elements: Vec<E>, limit: u32) -> Self {
+        let limit = limit as usize;
+        let rest = if limit < elements.len() {
+            elements.select_nth_unstable_by(limit, |a, b| b.cmp(a));
+            elements.split_off(limit)
+        } else {
+            Vec::new()
+        };
+        Self {
+            heap: elements.into(),
+            rest,
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty() && self.rest.is_empty()
+    }
+    pub fn pop(&mut self) -> Option<E> {
+        if self.heap.is_empty() {
+            self.heap = std::mem::take(&mut self.rest).into();
+        }
+        self.heap.pop()
+    }
+}
+
+/// A reranker that keeps the nearest `limit` candidates in a bounded heap while the candidates
+/// are scanned, and only reranks the others if more than `limit` results are popped.
+pub struct TopKFlatReranker<T, R> {
+    rerank: R,
+    heap: BinaryHeap<(Reverse<Distance>, AlwaysEqual<u32>, AlwaysEqual<T>)>,
+    rest: Vec<u32>,
+}
+
+impl<T, R> TopKFlatReranker<T, R>
+where
+    R: Fn(u32) -> (Distance, T),
+{
+    pub fn new(candidates: Vec<u32>, rerank: R, limit: u32) -> Self {
+        let limit = limit as usize;
+        if candidates.len() <= limit {
+            let heap = candidates
+                .into_iter()
+                .map(|u| {
+                    let (dis_u, pay_u) = rerank(u);
+                    (Reverse(dis_u), AlwaysEqual(u), AlwaysEqual(pay_u))
+                })
+                .collect();
+            return Self {
+                rerank,
+                heap,
+                rest: Vec::new(),
+            };
+        }
+        let mut top = BinaryHeap::<(Distance, AlwaysEqual<u32>, AlwaysEqual<T>)>::with_capacity(
+            limit.saturating_add(1),
+        );
+        let mut rest = Vec::with_capacity(candidates.len() - limit);
+        for u in candidates {
+            let (dis_u, pay_u) = rerank(u);
+            if top.len() < limit {
+                top.push((dis_u, AlwaysEqual(u), AlwaysEqual(pay_u)));
+            } else if top.peek().is_some_and(|&(dis_v, ..)| dis_u < dis_v) {
+                let (_, AlwaysEqual(v), _) = top.pop().unwrap();
+                top.push((dis_u, AlwaysEqual(u), AlwaysEqual(pay_u)));
+                rest.push(v);
+            } else {
+                rest.push(u);
+            }
+        }
+        let heap = top
+            .into_iter()
+            .map(|(dis_u, u, pay_u)| (Reverse(dis_u), u, pay_u))
+            .collect();
+        Self { rerank, heap, rest }
+    }
+}
+
+impl<T, R> RerankerPop<T> for TopKFlatReranker<T, R>
+where
+    R: Fn(u32) -> (Distance, T),
+{
+    fn pop(&mut self) -> Option<(Distance, u32, T)> {
+        if self.heap.is_empty() && !self.rest.is_empty() {
+            // every candidate left is not nearer than the popped ones
+            self.heap = std::mem::take(&mut self.rest)
+                .into_iter()
+                .map(|u| {
+                    let (dis_u, pay_u) = (self.rerank)(u);
+                    (Reverse(dis_u), AlwaysEqual(u), AlwaysEqual(pay_u))
+                })
+                .collect();
+        }
+        let (Reverse(dis_u), AlwaysEqual(u), AlwaysEqual(pay_u)) = self.heap.pop()?;
+        Some((dis_u, u, pay_u))
+    }
+}
+
 pub struct WindowFlatReranker<T, R> {
     rerank: R,
     size: u32,
-    heap: BinaryHeap<(Reverse<Distance>, AlwaysEqual<u32>)>,
+    heap: PrefetchHeap<(Reverse<Distance>, AlwaysEqual<u32>)>,
     cache: BinaryHeap<(Reverse<Distance>, AlwaysEqual<u32>, AlwaysEqual<T>)>,
 }
 
@@ -15,9 +119,14 @@ impl<T, R> WindowFlatReranker<T, R>
 where
     R: Fn(u32) -> (Distance, T),
 {
-    pub fn new(heap: Vec<(Reverse<Distance>, AlwaysEqual<u32>)>, rerank: R, size: u32) -> Self {
+    pub fn new(
+        heap: Vec<(Reverse<Distance>, AlwaysEqual<u32>)>,
+        rerank: R,
+        size: u32,
+        limit: u32,
+    ) -> Self {
         Self {
-            heap: heap.into(),
+            heap: PrefetchHeap::new(heap, limit.saturating_add(size)),
             rerank,
             size: size.max(1),
             cache: BinaryHeap::new(),
@@ -40,3 +149,30 @@ where
         Some((dis_u, u, pay_u))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha12Rng;
+
+    #[test]
+    fn top_k_flat_reranker_test() {
+        let mut rng = ChaCha12Rng::from_seed([7; 32]);
+        let mut candidates = (0..1000u32).collect::<Vec<_>>();
+        candidates.shuffle(&mut rng);
+        let rerank = |u: u32| (Distance::from_f32(u as f32), u);
+        for limit in [0, 1, 10, 999, 1000, u32::MAX] {
+            let mut reranker = TopKFlatReranker::new(candidates.clone(), rerank, limit);
+            // results past the limit are still returned, in order
+            let popped = std::iter::from_fn(|| reranker.pop())
+                .map(|(_, u, pay_u)| {
+                    assert_eq!(u, pay_u);
+                    u
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(popped, (0..1000u32).collect::<Vec<_>>());
+        }
+    }
+}
@@ -18,6 +18,7 @@ use base::search::RerankerPop;
 use base::search::RerankerPush;
 use base::search::Vectors;
 use base::vector::*;
+use half::f16;
 use rayon::iter::IntoParallelIterator;
 use rayon::iter::ParallelIterator;
 use serde::Deserialize;
@@ -218,7 +219,7 @@ impl<O: OperatorScalarQuantization> Quantizer<O> for ScalarQuantizer<O> {
     where
         R: Fn(u32) -> (Distance, T) + 'a,
     {
-        WindowFlatReranker::new(heap, rerank, opts.sq_rerank_size)
+        WindowFlatReranker::new(heap, rerank, opts.sq_rerank_size, opts.limit)
     }
 
     fn flat_rerank_continue<C>(
@@ -227,6 +228,7 @@ impl<O: OperatorScalarQuantization> Quantizer<O> for ScalarQuantizer<O> {
         locate_1: impl Fn(u32) -> C,
         frlut: &Result<Self::FLut, Self::Lut>,
         range: Range<u32>,
+        filter: impl Fn(u32) -> bool,
         heap: &mut Vec<(Reverse<Distance>, AlwaysEqual<u32>)>,
     ) where
         C: AsRef<[u8]>,
@@ -256,7 +258,7 @@ impl<O: OperatorScalarQuantization> Quantizer<O> for ScalarQuantizer<O> {
                 if let Some(i) = left {
                     let r = self.fscan_process(flut, locate_1(i).as_ref());
                     for j in 0..32 {
-                        if range.contains(&(i * 32 + j)) {
+                        if range.contains(&(i * 32 + j)) && filter(i * 32 + j) {
                             heap.push((Reverse(r[j as usize]), AlwaysEqual(i * 32 + j)));
                         }
                     }
@@ -264,20 +266,22 @@ impl<O: OperatorScalarQuantization> Quantizer<O> for ScalarQuantizer<O> {
                 for i in main {
                     let r = self.fscan_process(flut, locate_1(i).as_ref());
                     for j in 0..32 {
-                        heap.push((Reverse(r[j as usize]), AlwaysEqual(i * 32 + j)));
+                        if filter(i * 32 + j) {
+                            heap.push((Reverse(r[j as usize]), AlwaysEqual(i * 32 + j)));
+                        }
                     }
                 }
                 if let Some(i) = right {
                     let r = self.fscan_process(flut, locate_1(i).as_ref());
                     for j in 0..32 {
-                        if range.contains(&(i * 32 + j)) {
+                        if range.contains(&(i * 32 + j)) && filter(i * 32 + j) {
                             heap.push((Reverse(r[j as usize]), AlwaysEqual(i * 32 + j)));
                         }
                     }
                 }
             }
             Err(lut) => {
-                for j in range {
+                for j in range.filter(|&j| filter(j)) {
                     let r = O::process(self.dims, self.bits, lut, locate_0(j).as_ref());
                     heap.push((Reverse(r), AlwaysEqual(j)));
                 }
@@ -425,6 +429,55 @@ impl<S: ScalarLike> OperatorScalarQuantization for VectDot<S> {
     }
 }
 
+impl<S: ScalarLike> OperatorScalarQuantization for VectCos<S> {
+    type Scalar = S;
+    fn get(vector: Borrowed<'_, Self>, i: u32) -> Self::Scalar {
+        vector.slice()[i as usize]
+    }
+
+    fn preprocess(
+        dims: u32,
+        bits: u32,
+        min: &[f32],
+        max: &[f32],
+        vector: Borrowed<'_, Self>,
+    ) -> Vec<f32> {
+        let vector = vector.function_normalize();
+        <VectDot<S> as OperatorScalarQuantization>::preprocess(
+            dims,
+            bits,
+            min,
+            max,
+            vector.as_borrowed(),
+        )
+    }
+    fn process(dims: u32, bits: u32, lut: &[f32], code: &[u8]) -> Distance {
+        let dot = <VectDot<S> as OperatorScalarQuantization>::process(dims, bits, lut, code);
+        Distance::from(1.0 + dot.to_f32())
+    }
+
+    fn fscan_preprocess(
+        dims: u32,
+        bits: u32,
+        min: &[f32],
+        max: &[f32],
+        vector: Borrowed<'_, Self>,
+    ) -> (u32, f32, f32, Vec<u8>) {
+        let vector = vector.function_normalize();
+        <VectDot<S> as OperatorScalarQuantization>::fscan_preprocess(
+            dims,
+            bits,
+            min,
+            max,
+            vector.as_borrowed(),
+        )
+    }
+    fn fscan_process(flut: &(u32, f32, f32, Vec<u8>), codes: &[u8]) -> [Distance; 32] {
+        <VectDot<S> as OperatorScalarQuantization>::fscan_process(flut, codes)
+            .map(|dot| Distance::from(1.0 + dot.to_f32()))
+    }
+}
+
 impl<S: ScalarLike> OperatorScalarQuantization for VectL2<S> {
     type Scalar = S;
     fn get(vector: Borrowed<'_, Self>, i: u32) -> Self::Scalar {
@@ -559,5 +612,13 @@ unimpl_operator_scalar_quantization!(BVectorDot);
 unimpl_operator_scalar_quantization!(BVectorHamming);
 unimpl_operator_scalar_quantization!(BVectorJaccard);
 
+unimpl_operator_scalar_quantization!(VectL1<f32>);
+unimpl_operator_scalar_quantization!(VectL1<f16>);
+
+unimpl_operator_scalar_quantization!(SVectCos<f32>);
 unimpl_operator_scalar_quantization!(SVectDot<f32>);
 unimpl_operator_scalar_quantization!(SVectL2<f32>);
+
+unimpl_operator_scalar_quantization!(Veci8Cos);
+unimpl_operator_scalar_quantization!(Veci8Dot);
+unimpl_operator_scalar_quantization!(Veci8L2);
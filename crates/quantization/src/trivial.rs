@@ -1,6 +1,6 @@
 use crate::quantizer::Quantizer;
+use crate::reranker::flat::TopKFlatReranker;
 use crate::reranker::graph::GraphReranker;
-use base::always_equal::AlwaysEqual;
 use base::distance::Distance;
 use base::index::*;
 use base::operator::*;
@@ -9,8 +9,6 @@ use base::vector::VectorBorrowed;
 use base::vector::VectorOwned;
 use serde::Deserialize;
 use serde::Serialize;
-use std::cmp::Reverse;
-use std::collections::BinaryHeap;
 use std::marker::PhantomData;
 use std::ops::Range;
 
@@ -92,28 +90,24 @@ impl<O: Operator> Quantizer<O> for TrivialQuantizer<O> {
         _: impl Fn(u32) -> C,
         _: &Result<Self::FLut, Self::Lut>,
         range: Range<u32>,
+        filter: impl Fn(u32) -> bool,
         heap: &mut Vec<u32>,
     ) where
         C: AsRef<[u8]>,
     {
-        heap.extend(range);
+        heap.extend(range.filter(|&u| filter(u)));
     }
 
     fn flat_rerank_break<'a, T: 'a, R>(
         &'a self,
         heap: Vec<u32>,
         rerank: R,
-        _: &SearchOptions,
+        opts: &SearchOptions,
     ) -> impl RerankerPop<T> + 'a
     where
         R: Fn(u32) -> (Distance, T) + 'a,
     {
-        heap.into_iter()
-            .map(|u| {
-                let (dis_u, pay_u) = rerank(u);
-                (Reverse(dis_u), AlwaysEqual(u), AlwaysEqual(pay_u))
-            })
-            .collect::<BinaryHeap<(Reverse<Distance>, AlwaysEqual<u32>, AlwaysEqual<T>)>>()
+        TopKFlatReranker::new(heap, rerank, opts.limit)
     }
 
     fn graph_rerank<'a, T, R, C>(
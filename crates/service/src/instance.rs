@@ -4,25 +4,37 @@ use base::operator::*;
 use base::search::*;
 use base::vector::*;
 use base::worker::*;
-use half::f16;
+use half::{bf16, f16};
 use index::Index;
-use index::IndexTracker;
 use index::IndexView;
 use index::OutdatedError;
-use std::path::PathBuf;
+use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
 use std::sync::Arc;
 
 #[derive(Clone)]
 pub enum Instance {
     Vecf32Dot(Arc<Index<VectDot<f32>>>),
+    Vecf32Cos(Arc<Index<VectCos<f32>>>),
     Vecf32L2(Arc<Index<VectL2<f32>>>),
+    Vecf32L1(Arc<Index<VectL1<f32>>>),
     Vecf16Dot(Arc<Index<VectDot<f16>>>),
+    Vecf16Cos(Arc<Index<VectCos<f16>>>),
     Vecf16L2(Arc<Index<VectL2<f16>>>),
+    Vecf16L1(Arc<Index<VectL1<f16>>>),
+    Vecbf16Dot(Arc<Index<VectDot<bf16>>>),
+    Vecbf16Cos(Arc<Index<VectCos<bf16>>>),
+    Vecbf16L2(Arc<Index<VectL2<bf16>>>),
     SVecf32Dot(Arc<Index<SVectDot<f32>>>),
+    SVecf32Cos(Arc<Index<SVectCos<f32>>>),
     SVecf32L2(Arc<Index<SVectL2<f32>>>),
     BVectorDot(Arc<Index<BVectorDot>>),
     BVectorHamming(Arc<Index<BVectorHamming>>),
     BVectorJaccard(Arc<Index<BVectorJaccard>>),
+    Veci8Dot(Arc<Index<Veci8Dot>>),
+    Veci8Cos(Arc<Index<Veci8Cos>>),
+    Veci8L2(Arc<Index<Veci8L2>>),
 }
 
 impl Instance {
@@ -36,22 +48,54 @@ impl Instance {
                 let index = Index::create(path.clone(), options, alterable_options)?;
                 Ok(Self::Vecf32Dot(index))
             }
+            (VectorKind::Vecf32, DistanceKind::Cos) => {
+                let index = Index::create(path.clone(), options, alterable_options)?;
+                Ok(Self::Vecf32Cos(index))
+            }
             (VectorKind::Vecf32, DistanceKind::L2) => {
                 let index = Index::create(path.clone(), options, alterable_options)?;
                 Ok(Self::Vecf32L2(index))
             }
+            (VectorKind::Vecf32, DistanceKind::L1) => {
+                let index = Index::create(path.clone(), options, alterable_options)?;
+                Ok(Self::Vecf32L1(index))
+            }
             (VectorKind::Vecf16, DistanceKind::Dot) => {
                 let index = Index::create(path.clone(), options, alterable_options)?;
                 Ok(Self::Vecf16Dot(index))
             }
+            (VectorKind::Vecf16, DistanceKind::Cos) => {
+                let index = Index::create(path.clone(), options, alterable_options)?;
+                Ok(Self::Vecf16Cos(index))
+            }
             (VectorKind::Vecf16, DistanceKind::L2) => {
                 let index = Index::create(path.clone(), options, alterable_options)?;
                 Ok(Self::Vecf16L2(index))
             }
+            (VectorKind::Vecf16, DistanceKind::L1) => {
+                let index = Index::create(path.clone(), options, alterable_options)?;
+                Ok(Self::Vecf16L1(index))
+            }
+            (VectorKind::Vecbf16, DistanceKind::Dot) => {
+                let index = Index::create(path.clone(), options, alterable_options)?;
+                Ok(Self::Vecbf16Dot(index))
+            }
+            (VectorKind::Vecbf16, DistanceKind::Cos) => {
+                let index = Index::create(path.clone(), options, alterable_options)?;
+                Ok(Self::Vecbf16Cos(index))
+            }
+            (VectorKind::Vecbf16, DistanceKind::L2) => {
+                let index = Index::create(path.clone(), options, alterable_options)?;
+                Ok(Self::Vecbf16L2(index))
+            }
             (VectorKind::SVecf32, DistanceKind::Dot) => {
                 let index = Index::create(path.clone(), options, alterable_options)?;
                 Ok(Self::SVecf32Dot(index))
             }
+            (VectorKind::SVecf32, DistanceKind::Cos) => {
+                let index = Index::create(path.clone(), options, alterable_options)?;
+                Ok(Self::SVecf32Cos(index))
+            }
             (VectorKind::SVecf32, DistanceKind::L2) => {
                 let index = Index::create(path.clone(), options, alterable_options)?;
                 Ok(Self::SVecf32L2(index))
@@ -68,197 +112,709 @@ impl Instance {
                 let index = Index::create(path.clone(), options, alterable_options)?;
                 Ok(Self::BVectorJaccard(index))
             }
+            (VectorKind::Veci8, DistanceKind::Dot) => {
+                let index = Index::create(path.clone(), options, alterable_options)?;
+                Ok(Self::Veci8Dot(index))
+            }
+            (VectorKind::Veci8, DistanceKind::Cos) => {
+                let index = Index::create(path.clone(), options, alterable_options)?;
+                Ok(Self::Veci8Cos(index))
+            }
+            (VectorKind::Veci8, DistanceKind::L2) => {
+                let index = Index::create(path.clone(), options, alterable_options)?;
+                Ok(Self::Veci8L2(index))
+            }
             _ => Err(CreateError::InvalidIndexOptions {
                 reason: "vector index config is not supported".to_string(),
             }),
         }
     }
-    pub fn open(path: PathBuf) -> Self {
+    pub fn open(path: PathBuf) -> Result<Self, OpenError> {
         let options =
             serde_json::from_slice::<IndexOptions>(&std::fs::read(path.join("options")).unwrap())
                 .unwrap();
         match (options.vector.v, options.vector.d) {
-            (VectorKind::Vecf32, DistanceKind::Dot) => Self::Vecf32Dot(Index::open(path)),
-            (VectorKind::Vecf32, DistanceKind::L2) => Self::Vecf32L2(Index::open(path)),
-            (VectorKind::Vecf16, DistanceKind::Dot) => Self::Vecf16Dot(Index::open(path)),
-            (VectorKind::Vecf16, DistanceKind::L2) => Self::Vecf16L2(Index::open(path)),
-            (VectorKind::SVecf32, DistanceKind::Dot) => Self::SVecf32Dot(Index::open(path)),
-            (VectorKind::SVecf32, DistanceKind::L2) => Self::SVecf32L2(Index::open(path)),
-            (VectorKind::BVector, DistanceKind::Dot) => Self::BVectorDot(Index::open(path)),
-            (VectorKind::BVector, DistanceKind::Hamming) => Self::BVectorHamming(Index::open(path)),
-            (VectorKind::BVector, DistanceKind::Jaccard) => Self::BVectorJaccard(Index::open(path)),
+            (VectorKind::Vecf32, DistanceKind::Dot) => Ok(Self::Vecf32Dot(Index::open(path)?)),
+            (VectorKind::Vecf32, DistanceKind::Cos) => Ok(Self::Vecf32Cos(Index::open(path)?)),
+            (VectorKind::Vecf32, DistanceKind::L2) => Ok(Self::Vecf32L2(Index::open(path)?)),
+            (VectorKind::Vecf32, DistanceKind::L1) => Ok(Self::Vecf32L1(Index::open(path)?)),
+            (VectorKind::Vecf16, DistanceKind::Dot) => Ok(Self::Vecf16Dot(Index::open(path)?)),
+            (VectorKind::Vecf16, DistanceKind::Cos) => Ok(Self::Vecf16Cos(Index::open(path)?)),
+            (VectorKind::Vecf16, DistanceKind::L2) => Ok(Self::Vecf16L2(Index::open(path)?)),
+            (VectorKind::Vecf16, DistanceKind::L1) => Ok(Self::Vecf16L1(Index::open(path)?)),
+            (VectorKind::Vecbf16, DistanceKind::Dot) => Ok(Self::Vecbf16Dot(Index::open(path)?)),
+            (VectorKind::Vecbf16, DistanceKind::Cos) => Ok(Self::Vecbf16Cos(Index::open(path)?)),
+            (VectorKind::Vecbf16, DistanceKind::L2) => Ok(Self::Vecbf16L2(Index::open(path)?)),
+            (VectorKind::SVecf32, DistanceKind::Dot) => Ok(Self::SVecf32Dot(Index::open(path)?)),
+            (VectorKind::SVecf32, DistanceKind::Cos) => Ok(Self::SVecf32Cos(Index::open(path)?)),
+            (VectorKind::SVecf32, DistanceKind::L2) => Ok(Self::SVecf32L2(Index::open(path)?)),
+            (VectorKind::BVector, DistanceKind::Dot) => Ok(Self::BVectorDot(Index::open(path)?)),
+            (VectorKind::BVector, DistanceKind::Hamming) => {
+                Ok(Self::BVectorHamming(Index::open(path)?))
+            }
+            (VectorKind::BVector, DistanceKind::Jaccard) => {
+                Ok(Self::BVectorJaccard(Index::open(path)?))
+            }
+            (VectorKind::Veci8, DistanceKind::Dot) => Ok(Self::Veci8Dot(Index::open(path)?)),
+            (VectorKind::Veci8, DistanceKind::Cos) => Ok(Self::Veci8Cos(Index::open(path)?)),
+            (VectorKind::Veci8, DistanceKind::L2) => Ok(Self::Veci8L2(Index::open(path)?)),
             _ => unreachable!(),
         }
     }
+    pub fn verify(&self, valid: &dyn Fn(Pointer) -> bool) -> Vec<VerifyCheck> {
+        match self {
+            Instance::Vecf32Dot(x) => x.verify(valid),
+            Instance::Vecf32Cos(x) => x.verify(valid),
+            Instance::Vecf32L2(x) => x.verify(valid),
+            Instance::Vecf32L1(x) => x.verify(valid),
+            Instance::Vecf16Dot(x) => x.verify(valid),
+            Instance::Vecf16Cos(x) => x.verify(valid),
+            Instance::Vecf16L2(x) => x.verify(valid),
+            Instance::Vecf16L1(x) => x.verify(valid),
+            Instance::Vecbf16Dot(x) => x.verify(valid),
+            Instance::Vecbf16Cos(x) => x.verify(valid),
+            Instance::Vecbf16L2(x) => x.verify(valid),
+            Instance::SVecf32Dot(x) => x.verify(valid),
+            Instance::SVecf32Cos(x) => x.verify(valid),
+            Instance::SVecf32L2(x) => x.verify(valid),
+            Instance::BVectorDot(x) => x.verify(valid),
+            Instance::BVectorHamming(x) => x.verify(valid),
+            Instance::BVectorJaccard(x) => x.verify(valid),
+            Instance::Veci8Dot(x) => x.verify(valid),
+            Instance::Veci8Cos(x) => x.verify(valid),
+            Instance::Veci8L2(x) => x.verify(valid),
+        }
+    }
+    /// Verifies the index directory at `path` without opening it, so that indexes that fail to
+    /// open can be verified.
+    pub fn verify_dir(path: &Path, valid: &dyn Fn(Pointer) -> bool) -> Vec<VerifyCheck> {
+        use index::verify::verify;
+        let options = std::fs::read(path.join("options"))
+            .map_err(|e| e.to_string())
+            .and_then(|x| serde_json::from_slice::<IndexOptions>(&x).map_err(|e| e.to_string()));
+        let error = match options {
+            Ok(options) => match (options.vector.v, options.vector.d) {
+                (VectorKind::Vecf32, DistanceKind::Dot) => {
+                    return verify::<VectDot<f32>>(path, valid)
+                }
+                (VectorKind::Vecf32, DistanceKind::Cos) => {
+                    return verify::<VectCos<f32>>(path, valid)
+                }
+                (VectorKind::Vecf32, DistanceKind::L2) => {
+                    return verify::<VectL2<f32>>(path, valid)
+                }
+                (VectorKind::Vecf32, DistanceKind::L1) => {
+                    return verify::<VectL1<f32>>(path, valid)
+                }
+                (VectorKind::Vecf16, DistanceKind::Dot) => {
+                    return verify::<VectDot<f16>>(path, valid)
+                }
+                (VectorKind::Vecf16, DistanceKind::Cos) => {
+                    return verify::<VectCos<f16>>(path, valid)
+                }
+                (VectorKind::Vecf16, DistanceKind::L2) => {
+                    return verify::<VectL2<f16>>(path, valid)
+                }
+                (VectorKind::Vecf16, DistanceKind::L1) => {
+                    return verify::<VectL1<f16>>(path, valid)
+                }
+                (VectorKind::Vecbf16, DistanceKind::Dot) => {
+                    return verify::<VectDot<bf16>>(path, valid)
+                }
+                (VectorKind::Vecbf16, DistanceKind::Cos) => {
+                    return verify::<VectCos<bf16>>(path, valid)
+                }
+                (VectorKind::Vecbf16, DistanceKind::L2) => {
+                    return verify::<VectL2<bf16>>(path, valid)
+                }
+                (VectorKind::SVecf32, DistanceKind::Dot) => {
+                    return verify::<SVectDot<f32>>(path, valid)
+                }
+                (VectorKind::SVecf32, DistanceKind::Cos) => {
+                    return verify::<SVectCos<f32>>(path, valid)
+                }
+                (VectorKind::SVecf32, DistanceKind::L2) => {
+                    return verify::<SVectL2<f32>>(path, valid)
+                }
+                (VectorKind::BVector, DistanceKind::Dot) => {
+                    return verify::<BVectorDot>(path, valid)
+                }
+                (VectorKind::BVector, DistanceKind::Hamming) => {
+                    return verify::<BVectorHamming>(path, valid)
+                }
+                (VectorKind::BVector, DistanceKind::Jaccard) => {
+                    return verify::<BVectorJaccard>(path, valid)
+                }
+                (VectorKind::Veci8, DistanceKind::Dot) => return verify::<Veci8Dot>(path, valid),
+                (VectorKind::Veci8, DistanceKind::Cos) => return verify::<Veci8Cos>(path, valid),
+                (VectorKind::Veci8, DistanceKind::L2) => return verify::<Veci8L2>(path, valid),
+                _ => "vector index config is not supported".to_string(),
+            },
+            Err(e) => e,
+        };
+        vec![VerifyCheck {
+            location: "options".to_string(),
+            kind: "options".to_string(),
+            error: Some(error),
+        }]
+    }
     pub fn refresh(&self) {
         match self {
             Instance::Vecf32Dot(x) => x.refresh(),
+            Instance::Vecf32Cos(x) => x.refresh(),
             Instance::Vecf32L2(x) => x.refresh(),
+            Instance::Vecf32L1(x) => x.refresh(),
             Instance::Vecf16Dot(x) => x.refresh(),
+            Instance::Vecf16Cos(x) => x.refresh(),
             Instance::Vecf16L2(x) => x.refresh(),
+            Instance::Vecf16L1(x) => x.refresh(),
+            Instance::Vecbf16Dot(x) => x.refresh(),
+            Instance::Vecbf16Cos(x) => x.refresh(),
+            Instance::Vecbf16L2(x) => x.refresh(),
             Instance::SVecf32Dot(x) => x.refresh(),
+            Instance::SVecf32Cos(x) => x.refresh(),
             Instance::SVecf32L2(x) => x.refresh(),
             Instance::BVectorDot(x) => x.refresh(),
             Instance::BVectorHamming(x) => x.refresh(),
             Instance::BVectorJaccard(x) => x.refresh(),
+            Instance::Veci8Dot(x) => x.refresh(),
+            Instance::Veci8Cos(x) => x.refresh(),
+            Instance::Veci8L2(x) => x.refresh(),
         }
     }
     pub fn view(&self) -> InstanceView {
         match self {
             Instance::Vecf32Dot(x) => InstanceView::Vecf32Dot(x.view()),
+            Instance::Vecf32Cos(x) => InstanceView::Vecf32Cos(x.view()),
             Instance::Vecf32L2(x) => InstanceView::Vecf32L2(x.view()),
+            Instance::Vecf32L1(x) => InstanceView::Vecf32L1(x.view()),
             Instance::Vecf16Dot(x) => InstanceView::Vecf16Dot(x.view()),
+            Instance::Vecf16Cos(x) => InstanceView::Vecf16Cos(x.view()),
             Instance::Vecf16L2(x) => InstanceView::Vecf16L2(x.view()),
+            Instance::Vecf16L1(x) => InstanceView::Vecf16L1(x.view()),
+            Instance::Vecbf16Dot(x) => InstanceView::Vecbf16Dot(x.view()),
+            Instance::Vecbf16Cos(x) => InstanceView::Vecbf16Cos(x.view()),
+            Instance::Vecbf16L2(x) => InstanceView::Vecbf16L2(x.view()),
             Instance::SVecf32Dot(x) => InstanceView::SVecf32Dot(x.view()),
+            Instance::SVecf32Cos(x) => InstanceView::SVecf32Cos(x.view()),
             Instance::SVecf32L2(x) => InstanceView::SVecf32L2(x.view()),
             Instance::BVectorDot(x) => InstanceView::BVectorDot(x.view()),
             Instance::BVectorHamming(x) => InstanceView::BVectorHamming(x.view()),
             Instance::BVectorJaccard(x) => InstanceView::BVectorJaccard(x.view()),
+            Instance::Veci8Dot(x) => InstanceView::Veci8Dot(x.view()),
+            Instance::Veci8Cos(x) => InstanceView::Veci8Cos(x.view()),
+            Instance::Veci8L2(x) => InstanceView::Veci8L2(x.view()),
         }
     }
     pub fn stat(&self) -> IndexStat {
         match self {
             Instance::Vecf32Dot(x) => x.stat(),
+            Instance::Vecf32Cos(x) => x.stat(),
             Instance::Vecf32L2(x) => x.stat(),
+            Instance::Vecf32L1(x) => x.stat(),
             Instance::Vecf16Dot(x) => x.stat(),
+            Instance::Vecf16Cos(x) => x.stat(),
             Instance::Vecf16L2(x) => x.stat(),
+            Instance::Vecf16L1(x) => x.stat(),
+            Instance::Vecbf16Dot(x) => x.stat(),
+            Instance::Vecbf16Cos(x) => x.stat(),
+            Instance::Vecbf16L2(x) => x.stat(),
             Instance::SVecf32Dot(x) => x.stat(),
+            Instance::SVecf32Cos(x) => x.stat(),
             Instance::SVecf32L2(x) => x.stat(),
             Instance::BVectorDot(x) => x.stat(),
             Instance::BVectorHamming(x) => x.stat(),
             Instance::BVectorJaccard(x) => x.stat(),
+            Instance::Veci8Dot(x) => x.stat(),
+            Instance::Veci8Cos(x) => x.stat(),
+            Instance::Veci8L2(x) => x.stat(),
+        }
+    }
+    pub fn position(&self) -> Option<LogPosition> {
+        match self {
+            Instance::Vecf32Dot(x) => x.position(),
+            Instance::Vecf32Cos(x) => x.position(),
+            Instance::Vecf32L2(x) => x.position(),
+            Instance::Vecf32L1(x) => x.position(),
+            Instance::Vecf16Dot(x) => x.position(),
+            Instance::Vecf16Cos(x) => x.position(),
+            Instance::Vecf16L2(x) => x.position(),
+            Instance::Vecf16L1(x) => x.position(),
+            Instance::Vecbf16Dot(x) => x.position(),
+            Instance::Vecbf16Cos(x) => x.position(),
+            Instance::Vecbf16L2(x) => x.position(),
+            Instance::SVecf32Dot(x) => x.position(),
+            Instance::SVecf32Cos(x) => x.position(),
+            Instance::SVecf32L2(x) => x.position(),
+            Instance::BVectorDot(x) => x.position(),
+            Instance::BVectorHamming(x) => x.position(),
+            Instance::BVectorJaccard(x) => x.position(),
+            Instance::Veci8Dot(x) => x.position(),
+            Instance::Veci8Cos(x) => x.position(),
+            Instance::Veci8L2(x) => x.position(),
+        }
+    }
+    pub fn snapshot(&self, dest: &Path) -> Result<(), SnapshotError> {
+        match self {
+            Instance::Vecf32Dot(x) => x.snapshot(dest),
+            Instance::Vecf32Cos(x) => x.snapshot(dest),
+            Instance::Vecf32L2(x) => x.snapshot(dest),
+            Instance::Vecf32L1(x) => x.snapshot(dest),
+            Instance::Vecf16Dot(x) => x.snapshot(dest),
+            Instance::Vecf16Cos(x) => x.snapshot(dest),
+            Instance::Vecf16L2(x) => x.snapshot(dest),
+            Instance::Vecf16L1(x) => x.snapshot(dest),
+            Instance::Vecbf16Dot(x) => x.snapshot(dest),
+            Instance::Vecbf16Cos(x) => x.snapshot(dest),
+            Instance::Vecbf16L2(x) => x.snapshot(dest),
+            Instance::SVecf32Dot(x) => x.snapshot(dest),
+            Instance::SVecf32Cos(x) => x.snapshot(dest),
+            Instance::SVecf32L2(x) => x.snapshot(dest),
+            Instance::BVectorDot(x) => x.snapshot(dest),
+            Instance::BVectorHamming(x) => x.snapshot(dest),
+            Instance::BVectorJaccard(x) => x.snapshot(dest),
+            Instance::Veci8Dot(x) => x.snapshot(dest),
+            Instance::Veci8Cos(x) => x.snapshot(dest),
+            Instance::Veci8L2(x) => x.snapshot(dest),
+        }
+    }
+    pub fn attach(&self, source: &Path, labels: &[(i64, Pointer)]) -> Result<(), AttachError> {
+        match self {
+            Instance::Vecf32Dot(x) => x.attach(source, labels),
+            Instance::Vecf32Cos(x) => x.attach(source, labels),
+            Instance::Vecf32L2(x) => x.attach(source, labels),
+            Instance::Vecf32L1(x) => x.attach(source, labels),
+            Instance::Vecf16Dot(x) => x.attach(source, labels),
+            Instance::Vecf16Cos(x) => x.attach(source, labels),
+            Instance::Vecf16L2(x) => x.attach(source, labels),
+            Instance::Vecf16L1(x) => x.attach(source, labels),
+            Instance::Vecbf16Dot(x) => x.attach(source, labels),
+            Instance::Vecbf16Cos(x) => x.attach(source, labels),
+            Instance::Vecbf16L2(x) => x.attach(source, labels),
+            Instance::SVecf32Dot(x) => x.attach(source, labels),
+            Instance::SVecf32Cos(x) => x.attach(source, labels),
+            Instance::SVecf32L2(x) => x.attach(source, labels),
+            Instance::BVectorDot(x) => x.attach(source, labels),
+            Instance::BVectorHamming(x) => x.attach(source, labels),
+            Instance::BVectorJaccard(x) => x.attach(source, labels),
+            Instance::Veci8Dot(x) => x.attach(source, labels),
+            Instance::Veci8Cos(x) => x.attach(source, labels),
+            Instance::Veci8L2(x) => x.attach(source, labels),
+        }
+    }
+    pub fn set_position(&self, position: Option<LogPosition>, replaying: bool) {
+        match self {
+            Instance::Vecf32Dot(x) => x.set_position(position, replaying),
+            Instance::Vecf32Cos(x) => x.set_position(position, replaying),
+            Instance::Vecf32L2(x) => x.set_position(position, replaying),
+            Instance::Vecf32L1(x) => x.set_position(position, replaying),
+            Instance::Vecf16Dot(x) => x.set_position(position, replaying),
+            Instance::Vecf16Cos(x) => x.set_position(position, replaying),
+            Instance::Vecf16L2(x) => x.set_position(position, replaying),
+            Instance::Vecf16L1(x) => x.set_position(position, replaying),
+            Instance::Vecbf16Dot(x) => x.set_position(position, replaying),
+            Instance::Vecbf16Cos(x) => x.set_position(position, replaying),
+            Instance::Vecbf16L2(x) => x.set_position(position, replaying),
+            Instance::SVecf32Dot(x) => x.set_position(position, replaying),
+            Instance::SVecf32Cos(x) => x.set_position(position, replaying),
+            Instance::SVecf32L2(x) => x.set_position(position, replaying),
+            Instance::BVectorDot(x) => x.set_position(position, replaying),
+            Instance::BVectorHamming(x) => x.set_position(position, replaying),
+            Instance::BVectorJaccard(x) => x.set_position(position, replaying),
+            Instance::Veci8Dot(x) => x.set_position(position, replaying),
+            Instance::Veci8Cos(x) => x.set_position(position, replaying),
+            Instance::Veci8L2(x) => x.set_position(position, replaying),
         }
     }
     pub fn alter(&self, key: &str, value: &str) -> Result<(), AlterError> {
         match self {
             Instance::Vecf32Dot(x) => x.alter(key, value),
+            Instance::Vecf32Cos(x) => x.alter(key, value),
             Instance::Vecf32L2(x) => x.alter(key, value),
+            Instance::Vecf32L1(x) => x.alter(key, value),
             Instance::Vecf16Dot(x) => x.alter(key, value),
+            Instance::Vecf16Cos(x) => x.alter(key, value),
             Instance::Vecf16L2(x) => x.alter(key, value),
+            Instance::Vecf16L1(x) => x.alter(key, value),
+            Instance::Vecbf16Dot(x) => x.alter(key, value),
+            Instance::Vecbf16Cos(x) => x.alter(key, value),
+            Instance::Vecbf16L2(x) => x.alter(key, value),
             Instance::SVecf32Dot(x) => x.alter(key, value),
+            Instance::SVecf32Cos(x) => x.alter(key, value),
             Instance::SVecf32L2(x) => x.alter(key, value),
             Instance::BVectorDot(x) => x.alter(key, value),
             Instance::BVectorHamming(x) => x.alter(key, value),
             Instance::BVectorJaccard(x) => x.alter(key, value),
+            Instance::Veci8Dot(x) => x.alter(key, value),
+            Instance::Veci8Cos(x) => x.alter(key, value),
+            Instance::Veci8L2(x) => x.alter(key, value),
         }
     }
     pub fn delete(&self, pointer: Pointer) -> Result<(), DeleteError> {
         match self {
             Instance::Vecf32Dot(x) => x.delete(pointer),
+            Instance::Vecf32Cos(x) => x.delete(pointer),
             Instance::Vecf32L2(x) => x.delete(pointer),
+            Instance::Vecf32L1(x) => x.delete(pointer),
             Instance::Vecf16Dot(x) => x.delete(pointer),
+            Instance::Vecf16Cos(x) => x.delete(pointer),
             Instance::Vecf16L2(x) => x.delete(pointer),
+            Instance::Vecf16L1(x) => x.delete(pointer),
+            Instance::Vecbf16Dot(x) => x.delete(pointer),
+            Instance::Vecbf16Cos(x) => x.delete(pointer),
+            Instance::Vecbf16L2(x) => x.delete(pointer),
             Instance::SVecf32Dot(x) => x.delete(pointer),
+            Instance::SVecf32Cos(x) => x.delete(pointer),
             Instance::SVecf32L2(x) => x.delete(pointer),
             Instance::BVectorDot(x) => x.delete(pointer),
             Instance::BVectorHamming(x) => x.delete(pointer),
             Instance::BVectorJaccard(x) => x.delete(pointer),
+            Instance::Veci8Dot(x) => x.delete(pointer),
+            Instance::Veci8Cos(x) => x.delete(pointer),
+            Instance::Veci8L2(x) => x.delete(pointer),
         }
     }
     pub fn start(&self) {
         match self {
             Instance::Vecf32Dot(x) => x.start(),
+            Instance::Vecf32Cos(x) => x.start(),
             Instance::Vecf32L2(x) => x.start(),
+            Instance::Vecf32L1(x) => x.start(),
             Instance::Vecf16Dot(x) => x.start(),
+            Instance::Vecf16Cos(x) => x.start(),
             Instance::Vecf16L2(x) => x.start(),
+            Instance::Vecf16L1(x) => x.start(),
+            Instance::Vecbf16Dot(x) => x.start(),
+            Instance::Vecbf16Cos(x) => x.start(),
+            Instance::Vecbf16L2(x) => x.start(),
             Instance::SVecf32Dot(x) => x.start(),
+            Instance::SVecf32Cos(x) => x.start(),
             Instance::SVecf32L2(x) => x.start(),
             Instance::BVectorDot(x) => x.start(),
             Instance::BVectorHamming(x) => x.start(),
             Instance::BVectorJaccard(x) => x.start(),
+            Instance::Veci8Dot(x) => x.start(),
+            Instance::Veci8Cos(x) => x.start(),
+            Instance::Veci8L2(x) => x.start(),
         }
     }
     pub fn stop(&self) {
         match self {
             Instance::Vecf32Dot(x) => x.stop(),
+            Instance::Vecf32Cos(x) => x.stop(),
             Instance::Vecf32L2(x) => x.stop(),
+            Instance::Vecf32L1(x) => x.stop(),
             Instance::Vecf16Dot(x) => x.stop(),
+            Instance::Vecf16Cos(x) => x.stop(),
             Instance::Vecf16L2(x) => x.stop(),
+            Instance::Vecf16L1(x) => x.stop(),
+            Instance::Vecbf16Dot(x) => x.stop(),
+            Instance::Vecbf16Cos(x) => x.stop(),
+            Instance::Vecbf16L2(x) => x.stop(),
             Instance::SVecf32Dot(x) => x.stop(),
+            Instance::SVecf32Cos(x) => x.stop(),
             Instance::SVecf32L2(x) => x.stop(),
             Instance::BVectorDot(x) => x.stop(),
             Instance::BVectorHamming(x) => x.stop(),
             Instance::BVectorJaccard(x) => x.stop(),
+            Instance::Veci8Dot(x) => x.stop(),
+            Instance::Veci8Cos(x) => x.stop(),
+            Instance::Veci8L2(x) => x.stop(),
         }
     }
-    pub fn wait(&self) -> Arc<IndexTracker> {
+    pub fn wait(&self) -> Receiver<Infallible> {
         match self {
             Instance::Vecf32Dot(x) => x.wait(),
+            Instance::Vecf32Cos(x) => x.wait(),
             Instance::Vecf32L2(x) => x.wait(),
+            Instance::Vecf32L1(x) => x.wait(),
             Instance::Vecf16Dot(x) => x.wait(),
+            Instance::Vecf16Cos(x) => x.wait(),
             Instance::Vecf16L2(x) => x.wait(),
+            Instance::Vecf16L1(x) => x.wait(),
+            Instance::Vecbf16Dot(x) => x.wait(),
+            Instance::Vecbf16Cos(x) => x.wait(),
+            Instance::Vecbf16L2(x) => x.wait(),
             Instance::SVecf32Dot(x) => x.wait(),
+            Instance::SVecf32Cos(x) => x.wait(),
             Instance::SVecf32L2(x) => x.wait(),
             Instance::BVectorDot(x) => x.wait(),
             Instance::BVectorHamming(x) => x.wait(),
             Instance::BVectorJaccard(x) => x.wait(),
+            Instance::Veci8Dot(x) => x.wait(),
+            Instance::Veci8Cos(x) => x.wait(),
+            Instance::Veci8L2(x) => x.wait(),
         }
     }
 }
 
 pub enum InstanceView {
     Vecf32Dot(Arc<IndexView<VectDot<f32>>>),
+    Vecf32Cos(Arc<IndexView<VectCos<f32>>>),
     Vecf32L2(Arc<IndexView<VectL2<f32>>>),
+    Vecf32L1(Arc<IndexView<VectL1<f32>>>),
     Vecf16Dot(Arc<IndexView<VectDot<f16>>>),
+    Vecf16Cos(Arc<IndexView<VectCos<f16>>>),
     Vecf16L2(Arc<IndexView<VectL2<f16>>>),
+    Vecf16L1(Arc<IndexView<VectL1<f16>>>),
+    Vecbf16Dot(Arc<IndexView<VectDot<bf16>>>),
+    Vecbf16Cos(Arc<IndexView<VectCos<bf16>>>),
+    Vecbf16L2(Arc<IndexView<VectL2<bf16>>>),
     SVecf32Dot(Arc<IndexView<SVectDot<f32>>>),
+    SVecf32Cos(Arc<IndexView<SVectCos<f32>>>),
     SVecf32L2(Arc<IndexView<SVectL2<f32>>>),
     BVectorDot(Arc<IndexView<BVectorDot>>),
     BVectorHamming(Arc<IndexView<BVectorHamming>>),
     BVectorJaccard(Arc<IndexView<BVectorJaccard>>),
+    Veci8Dot(Arc<IndexView<Veci8Dot>>),
+    Veci8Cos(Arc<IndexView<Veci8Cos>>),
+    Veci8L2(Arc<IndexView<Veci8L2>>),
 }
 
 impl ViewVbaseOperations for InstanceView {
+    fn preprocess(&self, vector: OwnedVector) -> OwnedVector {
+        self.options().vector.weigh(vector)
+    }
     fn vbase<'a>(
         &'a self,
         vector: &'a OwnedVector,
         opts: &'a SearchOptions,
+        filter: Option<&'a AllowList>,
     ) -> Result<Box<dyn Iterator<Item = (Distance, Pointer)> + 'a>, VbaseError> {
         match (self, vector) {
             (InstanceView::Vecf32Dot(x), OwnedVector::Vecf32(vector)) => {
-                Ok(Box::new(x.vbase(vector.as_borrowed(), opts)?))
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
+            }
+            (InstanceView::Vecf32Cos(x), OwnedVector::Vecf32(vector)) => {
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
             }
             (InstanceView::Vecf32L2(x), OwnedVector::Vecf32(vector)) => {
-                Ok(Box::new(x.vbase(vector.as_borrowed(), opts)?))
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
+            }
+            (InstanceView::Vecf32L1(x), OwnedVector::Vecf32(vector)) => {
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
             }
             (InstanceView::Vecf16Dot(x), OwnedVector::Vecf16(vector)) => {
-                Ok(Box::new(x.vbase(vector.as_borrowed(), opts)?))
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
+            }
+            (InstanceView::Vecf16Cos(x), OwnedVector::Vecf16(vector)) => {
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
             }
             (InstanceView::Vecf16L2(x), OwnedVector::Vecf16(vector)) => {
-                Ok(Box::new(x.vbase(vector.as_borrowed(), opts)?))
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
+            }
+            (InstanceView::Vecf16L1(x), OwnedVector::Vecf16(vector)) => {
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
+            }
+            (InstanceView::Vecbf16Dot(x), OwnedVector::Vecbf16(vector)) => {
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
+            }
+            (InstanceView::Vecbf16Cos(x), OwnedVector::Vecbf16(vector)) => {
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
+            }
+            (InstanceView::Vecbf16L2(x), OwnedVector::Vecbf16(vector)) => {
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
             }
             (InstanceView::SVecf32Dot(x), OwnedVector::SVecf32(vector)) => {
-                Ok(Box::new(x.vbase(vector.as_borrowed(), opts)?))
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
+            }
+            (InstanceView::SVecf32Cos(x), OwnedVector::SVecf32(vector)) => {
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
             }
             (InstanceView::SVecf32L2(x), OwnedVector::SVecf32(vector)) => {
-                Ok(Box::new(x.vbase(vector.as_borrowed(), opts)?))
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
             }
             (InstanceView::BVectorDot(x), OwnedVector::BVector(vector)) => {
-                Ok(Box::new(x.vbase(vector.as_borrowed(), opts)?))
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
             }
             (InstanceView::BVectorHamming(x), OwnedVector::BVector(vector)) => {
-                Ok(Box::new(x.vbase(vector.as_borrowed(), opts)?))
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
             }
             (InstanceView::BVectorJaccard(x), OwnedVector::BVector(vector)) => {
-                Ok(Box::new(x.vbase(vector.as_borrowed(), opts)?))
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
+            }
+            (InstanceView::Veci8Dot(x), OwnedVector::Veci8(vector)) => {
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
+            }
+            (InstanceView::Veci8Cos(x), OwnedVector::Veci8(vector)) => {
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
+            }
+            (InstanceView::Veci8L2(x), OwnedVector::Veci8(vector)) => {
+                Ok(Box::new(x.vbase(vector.as_borrowed(), opts, filter)?))
             }
             _ => Err(VbaseError::InvalidVector),
         }
     }
+    fn vbase_multi<'a>(
+        &'a self,
+        vectors: &'a [OwnedVector],
+        opts: &'a SearchOptions,
+        filter: Option<&'a AllowList>,
+    ) -> Result<Box<dyn Iterator<Item = (Distance, Pointer)> + 'a>, VbaseError> {
+        fn vecf32(vector: BorrowedVector<'_>) -> Option<VectBorrowed<'_, f32>> {
+            match vector {
+                BorrowedVector::Vecf32(vector) => Some(vector),
+                _ => None,
+            }
+        }
+        fn vecf16(vector: BorrowedVector<'_>) -> Option<VectBorrowed<'_, f16>> {
+            match vector {
+                BorrowedVector::Vecf16(vector) => Some(vector),
+                _ => None,
+            }
+        }
+        fn vecbf16(vector: BorrowedVector<'_>) -> Option<VectBorrowed<'_, bf16>> {
+            match vector {
+                BorrowedVector::Vecbf16(vector) => Some(vector),
+                _ => None,
+            }
+        }
+        fn svecf32(vector: BorrowedVector<'_>) -> Option<SVectBorrowed<'_, f32>> {
+            match vector {
+                BorrowedVector::SVecf32(vector) => Some(vector),
+                _ => None,
+            }
+        }
+        fn bvector(vector: BorrowedVector<'_>) -> Option<BVectBorrowed<'_>> {
+            match vector {
+                BorrowedVector::BVector(vector) => Some(vector),
+                _ => None,
+            }
+        }
+        fn veci8(vector: BorrowedVector<'_>) -> Option<Veci8Borrowed<'_>> {
+            match vector {
+                BorrowedVector::Veci8(vector) => Some(vector),
+                _ => None,
+            }
+        }
+        match self {
+            InstanceView::Vecf32Dot(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, vecf32)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::Vecf32Cos(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, vecf32)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::Vecf32L2(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, vecf32)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::Vecf32L1(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, vecf32)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::Vecf16Dot(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, vecf16)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::Vecf16Cos(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, vecf16)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::Vecf16L2(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, vecf16)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::Vecf16L1(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, vecf16)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::Vecbf16Dot(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, vecbf16)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::Vecbf16Cos(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, vecbf16)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::Vecbf16L2(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, vecbf16)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::SVecf32Dot(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, svecf32)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::SVecf32Cos(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, svecf32)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::SVecf32L2(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, svecf32)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::BVectorDot(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, bvector)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::BVectorHamming(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, bvector)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::BVectorJaccard(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, bvector)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::Veci8Dot(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, veci8)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::Veci8Cos(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, veci8)?,
+                opts,
+                filter,
+            )?)),
+            InstanceView::Veci8L2(x) => Ok(Box::new(x.vbase_multi(
+                &borrow_vectors(vectors, veci8)?,
+                opts,
+                filter,
+            )?)),
+        }
+    }
 }
 
 impl ViewListOperations for InstanceView {
     fn list(&self) -> Result<Box<dyn Iterator<Item = Pointer> + '_>, ListError> {
         match self {
             InstanceView::Vecf32Dot(x) => Ok(Box::new(x.list()?)),
+            InstanceView::Vecf32Cos(x) => Ok(Box::new(x.list()?)),
             InstanceView::Vecf32L2(x) => Ok(Box::new(x.list()?)),
+            InstanceView::Vecf32L1(x) => Ok(Box::new(x.list()?)),
             InstanceView::Vecf16Dot(x) => Ok(Box::new(x.list()?)),
+            InstanceView::Vecf16Cos(x) => Ok(Box::new(x.list()?)),
             InstanceView::Vecf16L2(x) => Ok(Box::new(x.list()?)),
+            InstanceView::Vecf16L1(x) => Ok(Box::new(x.list()?)),
+            InstanceView::Vecbf16Dot(x) => Ok(Box::new(x.list()?)),
+            InstanceView::Vecbf16Cos(x) => Ok(Box::new(x.list()?)),
+            InstanceView::Vecbf16L2(x) => Ok(Box::new(x.list()?)),
             InstanceView::SVecf32Dot(x) => Ok(Box::new(x.list()?)),
+            InstanceView::SVecf32Cos(x) => Ok(Box::new(x.list()?)),
             InstanceView::SVecf32L2(x) => Ok(Box::new(x.list()?)),
             InstanceView::BVectorDot(x) => Ok(Box::new(x.list()?)),
             InstanceView::BVectorHamming(x) => Ok(Box::new(x.list()?)),
             InstanceView::BVectorJaccard(x) => Ok(Box::new(x.list()?)),
+            InstanceView::Veci8Dot(x) => Ok(Box::new(x.list()?)),
+            InstanceView::Veci8Cos(x) => Ok(Box::new(x.list()?)),
+            InstanceView::Veci8L2(x) => Ok(Box::new(x.list()?)),
         }
     }
 }
@@ -269,14 +825,29 @@ impl InstanceView {
         vector: OwnedVector,
         pointer: Pointer,
     ) -> Result<Result<(), OutdatedError>, InsertError> {
+        let vector = self.options().vector.weigh(vector);
         match (self, vector) {
             (InstanceView::Vecf32Dot(x), OwnedVector::Vecf32(vector)) => x.insert(vector, pointer),
+            (InstanceView::Vecf32Cos(x), OwnedVector::Vecf32(vector)) => x.insert(vector, pointer),
             (InstanceView::Vecf32L2(x), OwnedVector::Vecf32(vector)) => x.insert(vector, pointer),
+            (InstanceView::Vecf32L1(x), OwnedVector::Vecf32(vector)) => x.insert(vector, pointer),
             (InstanceView::Vecf16Dot(x), OwnedVector::Vecf16(vector)) => x.insert(vector, pointer),
+            (InstanceView::Vecf16Cos(x), OwnedVector::Vecf16(vector)) => x.insert(vector, pointer),
             (InstanceView::Vecf16L2(x), OwnedVector::Vecf16(vector)) => x.insert(vector, pointer),
+            (InstanceView::Vecf16L1(x), OwnedVector::Vecf16(vector)) => x.insert(vector, pointer),
+            (InstanceView::Vecbf16Dot(x), OwnedVector::Vecbf16(vector)) => {
+                x.insert(vector, pointer)
+            }
+            (InstanceView::Vecbf16Cos(x), OwnedVector::Vecbf16(vector)) => {
+                x.insert(vector, pointer)
+            }
+            (InstanceView::Vecbf16L2(x), OwnedVector::Vecbf16(vector)) => x.insert(vector, pointer),
             (InstanceView::SVecf32Dot(x), OwnedVector::SVecf32(vector)) => {
                 x.insert(vector, pointer)
             }
+            (InstanceView::SVecf32Cos(x), OwnedVector::SVecf32(vector)) => {
+                x.insert(vector, pointer)
+            }
             (InstanceView::SVecf32L2(x), OwnedVector::SVecf32(vector)) => x.insert(vector, pointer),
             (InstanceView::BVectorDot(x), OwnedVector::BVector(vector)) => {
                 x.insert(vector, pointer)
@@ -287,20 +858,160 @@ impl InstanceView {
             (InstanceView::BVectorJaccard(x), OwnedVector::BVector(vector)) => {
                 x.insert(vector, pointer)
             }
+            (InstanceView::Veci8Dot(x), OwnedVector::Veci8(vector)) => x.insert(vector, pointer),
+            (InstanceView::Veci8Cos(x), OwnedVector::Veci8(vector)) => x.insert(vector, pointer),
+            (InstanceView::Veci8L2(x), OwnedVector::Veci8(vector)) => x.insert(vector, pointer),
             _ => Err(InsertError::InvalidVector),
         }
     }
+    pub fn insert_many(&self, vectors: &[(OwnedVector, Pointer)]) -> Result<usize, InsertError> {
+        let weighted;
+        let vectors = if self.options().vector.weights.is_some() {
+            weighted = vectors
+                .iter()
+                .map(|(vector, pointer)| (self.options().vector.weigh(vector.clone()), *pointer))
+                .collect::<Vec<_>>();
+            &weighted
+        } else {
+            vectors
+        };
+        let vecf32 = |vector: &OwnedVector| match vector {
+            OwnedVector::Vecf32(vector) => Some(vector.clone()),
+            _ => None,
+        };
+        let vecf16 = |vector: &OwnedVector| match vector {
+            OwnedVector::Vecf16(vector) => Some(vector.clone()),
+            _ => None,
+        };
+        let vecbf16 = |vector: &OwnedVector| match vector {
+            OwnedVector::Vecbf16(vector) => Some(vector.clone()),
+            _ => None,
+        };
+        let svecf32 = |vector: &OwnedVector| match vector {
+            OwnedVector::SVecf32(vector) => Some(vector.clone()),
+            _ => None,
+        };
+        let bvector = |vector: &OwnedVector| match vector {
+            OwnedVector::BVector(vector) => Some(vector.clone()),
+            _ => None,
+        };
+        let veci8 = |vector: &OwnedVector| match vector {
+            OwnedVector::Veci8(vector) => Some(vector.clone()),
+            _ => None,
+        };
+        match self {
+            InstanceView::Vecf32Dot(x) => x.insert_many(&unwrap_vectors(vectors, vecf32)?),
+            InstanceView::Vecf32Cos(x) => x.insert_many(&unwrap_vectors(vectors, vecf32)?),
+            InstanceView::Vecf32L2(x) => x.insert_many(&unwrap_vectors(vectors, vecf32)?),
+            InstanceView::Vecf32L1(x) => x.insert_many(&unwrap_vectors(vectors, vecf32)?),
+            InstanceView::Vecf16Dot(x) => x.insert_many(&unwrap_vectors(vectors, vecf16)?),
+            InstanceView::Vecf16Cos(x) => x.insert_many(&unwrap_vectors(vectors, vecf16)?),
+            InstanceView::Vecf16L2(x) => x.insert_many(&unwrap_vectors(vectors, vecf16)?),
+            InstanceView::Vecf16L1(x) => x.insert_many(&unwrap_vectors(vectors, vecf16)?),
+            InstanceView::Vecbf16Dot(x) => x.insert_many(&unwrap_vectors(vectors, vecbf16)?),
+            InstanceView::Vecbf16Cos(x) => x.insert_many(&unwrap_vectors(vectors, vecbf16)?),
+            InstanceView::Vecbf16L2(x) => x.insert_many(&unwrap_vectors(vectors, vecbf16)?),
+            InstanceView::SVecf32Dot(x) => x.insert_many(&unwrap_vectors(vectors, svecf32)?),
+            InstanceView::SVecf32Cos(x) => x.insert_many(&unwrap_vectors(vectors, svecf32)?),
+            InstanceView::SVecf32L2(x) => x.insert_many(&unwrap_vectors(vectors, svecf32)?),
+            InstanceView::BVectorDot(x) => x.insert_many(&unwrap_vectors(vectors, bvector)?),
+            InstanceView::BVectorHamming(x) => x.insert_many(&unwrap_vectors(vectors, bvector)?),
+            InstanceView::BVectorJaccard(x) => x.insert_many(&unwrap_vectors(vectors, bvector)?),
+            InstanceView::Veci8Dot(x) => x.insert_many(&unwrap_vectors(vectors, veci8)?),
+            InstanceView::Veci8Cos(x) => x.insert_many(&unwrap_vectors(vectors, veci8)?),
+            InstanceView::Veci8L2(x) => x.insert_many(&unwrap_vectors(vectors, veci8)?),
+        }
+    }
     pub fn flush(&self) -> Result<(), FlushError> {
         match self {
             InstanceView::Vecf32Dot(x) => x.flush(),
+            InstanceView::Vecf32Cos(x) => x.flush(),
             InstanceView::Vecf32L2(x) => x.flush(),
+            InstanceView::Vecf32L1(x) => x.flush(),
             InstanceView::Vecf16Dot(x) => x.flush(),
+            InstanceView::Vecf16Cos(x) => x.flush(),
             InstanceView::Vecf16L2(x) => x.flush(),
+            InstanceView::Vecf16L1(x) => x.flush(),
+            InstanceView::Vecbf16Dot(x) => x.flush(),
+            InstanceView::Vecbf16Cos(x) => x.flush(),
+            InstanceView::Vecbf16L2(x) => x.flush(),
             InstanceView::SVecf32Dot(x) => x.flush(),
+            InstanceView::SVecf32Cos(x) => x.flush(),
             InstanceView::SVecf32L2(x) => x.flush(),
             InstanceView::BVectorDot(x) => x.flush(),
             InstanceView::BVectorHamming(x) => x.flush(),
             InstanceView::BVectorJaccard(x) => x.flush(),
+            InstanceView::Veci8Dot(x) => x.flush(),
+            InstanceView::Veci8Cos(x) => x.flush(),
+            InstanceView::Veci8L2(x) => x.flush(),
+        }
+    }
+    pub fn options(&self) -> &IndexOptions {
+        match self {
+            InstanceView::Vecf32Dot(x) => &x.options,
+            InstanceView::Vecf32Cos(x) => &x.options,
+            InstanceView::Vecf32L2(x) => &x.options,
+            InstanceView::Vecf32L1(x) => &x.options,
+            InstanceView::Vecf16Dot(x) => &x.options,
+            InstanceView::Vecf16Cos(x) => &x.options,
+            InstanceView::Vecf16L2(x) => &x.options,
+            InstanceView::Vecf16L1(x) => &x.options,
+            InstanceView::Vecbf16Dot(x) => &x.options,
+            InstanceView::Vecbf16Cos(x) => &x.options,
+            InstanceView::Vecbf16L2(x) => &x.options,
+            InstanceView::SVecf32Dot(x) => &x.options,
+            InstanceView::SVecf32Cos(x) => &x.options,
+            InstanceView::SVecf32L2(x) => &x.options,
+            InstanceView::BVectorDot(x) => &x.options,
+            InstanceView::BVectorHamming(x) => &x.options,
+            InstanceView::BVectorJaccard(x) => &x.options,
+            InstanceView::Veci8Dot(x) => &x.options,
+            InstanceView::Veci8Cos(x) => &x.options,
+            InstanceView::Veci8L2(x) => &x.options,
         }
     }
+    pub fn sealed(&self) -> bool {
+        match self {
+            InstanceView::Vecf32Dot(x) => !x.sealed_segments.is_empty(),
+            InstanceView::Vecf32Cos(x) => !x.sealed_segments.is_empty(),
+            InstanceView::Vecf32L2(x) => !x.sealed_segments.is_empty(),
+            InstanceView::Vecf32L1(x) => !x.sealed_segments.is_empty(),
+            InstanceView::Vecf16Dot(x) => !x.sealed_segments.is_empty(),
+            InstanceView::Vecf16Cos(x) => !x.sealed_segments.is_empty(),
+            InstanceView::Vecf16L2(x) => !x.sealed_segments.is_empty(),
+            InstanceView::Vecf16L1(x) => !x.sealed_segments.is_empty(),
+            InstanceView::Vecbf16Dot(x) => !x.sealed_segments.is_empty(),
+            InstanceView::Vecbf16Cos(x) => !x.sealed_segments.is_empty(),
+            InstanceView::Vecbf16L2(x) => !x.sealed_segments.is_empty(),
+            InstanceView::SVecf32Dot(x) => !x.sealed_segments.is_empty(),
+            InstanceView::SVecf32Cos(x) => !x.sealed_segments.is_empty(),
+            InstanceView::SVecf32L2(x) => !x.sealed_segments.is_empty(),
+            InstanceView::BVectorDot(x) => !x.sealed_segments.is_empty(),
+            InstanceView::BVectorHamming(x) => !x.sealed_segments.is_empty(),
+            InstanceView::BVectorJaccard(x) => !x.sealed_segments.is_empty(),
+            InstanceView::Veci8Dot(x) => !x.sealed_segments.is_empty(),
+            InstanceView::Veci8Cos(x) => !x.sealed_segments.is_empty(),
+            InstanceView::Veci8L2(x) => !x.sealed_segments.is_empty(),
+        }
+    }
+}
+
+fn borrow_vectors<'a, V>(
+    vectors: &'a [OwnedVector],
+    f: impl Fn(BorrowedVector<'a>) -> Option<V>,
+) -> Result<Vec<V>, VbaseError> {
+    vectors
+        .iter()
+        .map(|vector| f(vector.as_borrowed()).ok_or(VbaseError::InvalidVector))
+        .collect()
+}
+
+fn unwrap_vectors<V>(
+    vectors: &[(OwnedVector, Pointer)],
+    f: impl Fn(&OwnedVector) -> Option<V>,
+) -> Result<Vec<(V, Pointer)>, InsertError> {
+    vectors
+        .iter()
+        .map(|(vector, pointer)| Ok((f(vector).ok_or(InsertError::InvalidVector)?, *pointer)))
+        .collect()
 }
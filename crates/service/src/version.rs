@@ -18,8 +18,11 @@ pub struct Version {
 }
 
 impl Version {
+    // on-disk layouts of indexes are versioned by themselves and migrated on open, so
+    // `VERSION` is only bumped if the worker directory can not be read any more
     const VERSION: u64 = 9;
-    const SOFT_VERSION: u64 = 0;
+    // 1: indexes record their layouts, so older builds must not open them
+    const SOFT_VERSION: u64 = 1;
 }
 
 impl Version {
@@ -29,7 +32,10 @@ impl Version {
             soft_version: Some(Self::SOFT_VERSION),
         };
         let contents = serde_json::to_string(&version).unwrap();
-        std::fs::write(path, contents).unwrap();
+        // it's rewritten on every start, so never leave it truncated
+        let temp = path.as_ref().with_extension("tmp");
+        std::fs::write(&temp, contents).unwrap();
+        std::fs::rename(temp, path).unwrap();
     }
     pub fn read(path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
         use VersionError::*;
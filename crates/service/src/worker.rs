@@ -1,5 +1,6 @@
 use crate::instance::*;
 use arc_swap::ArcSwap;
+use base::distance::DistanceKind;
 use base::index::*;
 use base::search::*;
 use base::vector::*;
@@ -8,7 +9,7 @@ use common::clean::clean;
 use common::dir_ops::sync_walk_from_dir;
 use common::file_atomic::FileAtomic;
 use index::OutdatedError;
-use parking_lot::Mutex;
+use parking_lot::{Condvar, Mutex, MutexGuard};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
@@ -18,6 +19,8 @@ pub struct Worker {
     path: PathBuf,
     protect: Mutex<WorkerProtect>,
     view: ArcSwap<WorkerView>,
+    replay: Mutex<()>,
+    retired: Condvar,
 }
 
 impl Worker {
@@ -28,41 +31,159 @@ impl Worker {
         let indexes = HashMap::new();
         let view = Arc::new(WorkerView {
             indexes: indexes.clone(),
+            shadows: HashMap::new(),
         });
-        let protect = WorkerProtect { startup, indexes };
+        let protect = WorkerProtect {
+            startup,
+            indexes,
+            shadows: HashMap::new(),
+            flipped: HashSet::new(),
+            scanned: HashSet::new(),
+            retiring: HashMap::new(),
+        };
         sync_walk_from_dir(&path);
         Arc::new(Worker {
             path,
             protect: Mutex::new(protect),
             view: ArcSwap::new(view),
+            replay: Mutex::new(()),
+            retired: Condvar::new(),
         })
     }
     pub fn open(path: PathBuf) -> Arc<Self> {
         let startup = FileAtomic::<WorkerStartup>::open(path.join("startup"));
+        let flipped = startup.get().flipped.clone();
+        let scanned = startup.get().scanned.clone();
         clean(
             path.join("indexes"),
-            startup.get().indexes.iter().map(|s| s.to_string()),
+            (startup
+                .get()
+                .indexes
+                .iter()
+                .map(|&s| dir(&flipped, s, false)))
+            .chain(scanned.iter().map(|&s| dir(&flipped, s, true))),
         );
         let mut indexes = HashMap::new();
         for &id in startup.get().indexes.iter() {
-            let path = path.join("indexes").join(id.to_string());
-            let index = Instance::open(path);
+            let path = path.join("indexes").join(dir(&flipped, id, false));
+            let index = match Instance::open(path) {
+                Ok(index) => index,
+                Err(e) => {
+                    log::error!("Failed to open index {id}, it should be rebuilt: {e}");
+                    continue;
+                }
+            };
             index.start();
             indexes.insert(id, index);
         }
+        let mut shadows = HashMap::new();
+        for &id in scanned.iter().filter(|id| indexes.contains_key(id)) {
+            let path = path.join("indexes").join(dir(&flipped, id, true));
+            let shadow = match Instance::open(path) {
+                Ok(shadow) => shadow,
+                Err(e) => {
+                    log::error!("Failed to open the shadow index of {id}, it's dropped: {e}");
+                    continue;
+                }
+            };
+            shadow.start();
+            shadows.insert(id, shadow);
+        }
         let view = Arc::new(WorkerView {
             indexes: indexes.clone(),
+            shadows: shadows.clone(),
         });
-        let protect = WorkerProtect { startup, indexes };
+        let protect = WorkerProtect {
+            startup,
+            indexes,
+            shadows,
+            flipped,
+            scanned,
+            retiring: HashMap::new(),
+        };
         Arc::new(Worker {
             path,
             protect: Mutex::new(protect),
             view: ArcSwap::new(view),
+            replay: Mutex::new(()),
+            retired: Condvar::new(),
         })
     }
     fn view(&self) -> Arc<WorkerView> {
         self.view.load_full()
     }
+    /// Swaps in shadow indexes whose build is complete, which is when the rows of the build
+    /// scan are inserted and the first sealed segment is built, or nothing is left to index.
+    /// It's called periodically by the background worker.
+    pub fn promote(&self) {
+        let built = self
+            .view()
+            .shadows
+            .iter()
+            .filter(|(_, shadow)| shadow.view().sealed() || !shadow.stat().indexing)
+            .map(|(&handle, _)| handle)
+            .collect::<Vec<_>>();
+        for handle in built {
+            let mut protect = self.protect.lock();
+            // the shadow index may be dropped or replaced by another reindex meanwhile
+            if !protect.scanned.contains(&handle) {
+                continue;
+            }
+            let index = protect.swap(handle);
+            protect.maintain(&self.view);
+            drop(protect);
+            if let Some(index) = index {
+                self.retire(handle, index);
+            }
+        }
+    }
+    /// Stops an index that is removed from the view and waits until it's no longer used, after
+    /// which its directory is removed. The caller holds no view of the worker.
+    fn retire(&self, handle: Handle, index: Instance) {
+        index.stop();
+        let retired = index.wait();
+        drop(index);
+        // it's disconnected once the directory is removed
+        let _ = retired.recv();
+        let mut protect = self.protect.lock();
+        if let Some(count) = protect.retiring.get_mut(&handle) {
+            *count -= 1;
+            if *count == 0 {
+                protect.retiring.remove(&handle);
+            }
+        }
+        self.retired.notify_all();
+    }
+    /// Waits until directories of retired indexes of the handle are removed, so that they can be
+    /// reused.
+    fn settle(&self, protect: &mut MutexGuard<'_, WorkerProtect>, handle: Handle) {
+        self.retired
+            .wait_while(protect, |protect| protect.retiring.contains_key(&handle));
+    }
+    /// Opens the directory of the shadow index and promotes it, retiring the old index.
+    fn install(
+        &self,
+        mut protect: MutexGuard<'_, WorkerProtect>,
+        handle: Handle,
+        path: PathBuf,
+    ) -> Result<(), String> {
+        let shadow = match Instance::open(path.clone()) {
+            Ok(shadow) => shadow,
+            Err(e) => {
+                std::fs::remove_dir_all(&path).unwrap();
+                return Err(e.to_string());
+            }
+        };
+        shadow.start();
+        protect.shadows.insert(handle, shadow);
+        let index = protect.swap(handle);
+        protect.maintain(&self.view);
+        drop(protect);
+        if let Some(index) = index {
+            self.retire(handle, index);
+        }
+        Ok(())
+    }
 }
 
 impl WorkerOperations for Worker {
@@ -74,44 +195,33 @@ impl WorkerOperations for Worker {
     ) -> Result<(), CreateError> {
         use std::collections::hash_map::Entry;
         let mut protect = self.protect.lock();
+        // reindex: an unfinished shadow index is replaced
+        if let Some(shadow) = protect.unshadow(handle) {
+            protect.maintain(&self.view);
+            MutexGuard::unlocked(&mut protect, || self.retire(handle, shadow));
+        }
+        self.settle(&mut protect, handle);
+        let indexes = self.path.join("indexes");
+        let path = indexes.join(dir(&protect.flipped, handle, false));
+        let shadow_path = indexes.join(dir(&protect.flipped, handle, true));
         match protect.indexes.entry(handle) {
             Entry::Vacant(o) => {
-                let index = Instance::create(
-                    self.path.join("indexes").join(handle.to_string()),
-                    options,
-                    alterable_options,
-                )?;
+                // left by an index that failed to open
+                if path.try_exists().unwrap() {
+                    std::fs::remove_dir_all(&path).unwrap();
+                }
+                let index = Instance::create(path, options, alterable_options)?;
                 index.start();
                 o.insert(index);
                 protect.maintain(&self.view);
                 Ok(())
             }
-            // reindex
-            Entry::Occupied(o) => {
-                {
-                    let index = o.remove();
-                    protect.maintain(&self.view);
-                    index.stop();
-                    let tracker = index.wait();
-                    drop(index);
-                    loop {
-                        if Arc::strong_count(&tracker) == 1 {
-                            break;
-                        }
-                        std::thread::sleep(std::time::Duration::from_millis(100));
-                    }
-                    drop(tracker);
-                }
-                {
-                    let index = Instance::create(
-                        self.path.join("indexes").join(handle.to_string()),
-                        options,
-                        alterable_options,
-                    )?;
-                    index.start();
-                    protect.indexes.insert(handle, index);
-                    protect.maintain(&self.view);
-                }
+            // reindex: build a shadow index and keep serving the old one until it's ready
+            Entry::Occupied(_) => {
+                let shadow = Instance::create(shadow_path, options, alterable_options)?;
+                shadow.start();
+                protect.shadows.insert(handle, shadow);
+                protect.maintain(&self.view);
                 Ok(())
             }
         }
@@ -119,17 +229,13 @@ impl WorkerOperations for Worker {
     fn drop(&self, handle: Handle) -> Result<(), DropError> {
         let mut protect = self.protect.lock();
         if let Some(index) = protect.indexes.remove(&handle) {
+            *protect.retiring.entry(handle).or_default() += 1;
+            let shadow = protect.unshadow(handle);
             protect.maintain(&self.view);
-            index.stop();
-            let tracker = index.wait();
-            drop(index);
-            loop {
-                if Arc::strong_count(&tracker) == 1 {
-                    break;
-                }
-                std::thread::sleep(std::time::Duration::from_millis(100));
+            drop(protect);
+            for index in std::iter::once(index).chain(shadow) {
+                self.retire(handle, index);
             }
-            drop(tracker);
             Ok(())
         } else {
             Err(DropError::NotExist)
@@ -138,8 +244,10 @@ impl WorkerOperations for Worker {
     fn flush(&self, handle: Handle) -> Result<(), FlushError> {
         let view = self.view();
         let instance = view.get(handle).ok_or(FlushError::NotExist)?;
-        let view = instance.view();
-        view.flush()?;
+        instance.view().flush()?;
+        if let Some(shadow) = view.shadow(handle) {
+            shadow.view().flush()?;
+        }
         Ok(())
     }
     fn insert(
@@ -150,21 +258,207 @@ impl WorkerOperations for Worker {
     ) -> Result<(), InsertError> {
         let view = self.view();
         let instance = view.get(handle).ok_or(InsertError::NotExist)?;
-        loop {
-            let view = instance.view();
-            match view.insert(vector.clone(), pointer)? {
-                Ok(()) => break,
-                Err(OutdatedError) => {
-                    instance.refresh();
+        // the old index keeps serving queries until the shadow index is promoted
+        for instance in std::iter::once(instance).chain(view.shadow(handle)) {
+            loop {
+                let view = instance.view();
+                match view.insert(vector.clone(), pointer)? {
+                    Ok(()) => break,
+                    Err(OutdatedError) => {
+                        instance.refresh();
+                    }
                 }
             }
         }
         Ok(())
     }
+    fn insert_many(
+        &self,
+        handle: Handle,
+        vectors: Vec<(OwnedVector, Pointer)>,
+    ) -> Result<(), InsertError> {
+        let view = self.view();
+        let instance = view.get(handle).ok_or(InsertError::NotExist)?;
+        for instance in std::iter::once(instance).chain(view.shadow(handle)) {
+            insert_many(instance, &vectors)?;
+        }
+        Ok(())
+    }
+    fn build(
+        &self,
+        handle: Handle,
+        vectors: Vec<(OwnedVector, Pointer)>,
+    ) -> Result<(), InsertError> {
+        let view = self.view();
+        let instance = view.get(handle).ok_or(InsertError::NotExist)?;
+        // rows of the build scan are already in the old index
+        let instance = view.shadow(handle).unwrap_or(instance);
+        insert_many(instance, &vectors)
+    }
     fn delete(&self, handle: Handle, pointer: Pointer) -> Result<(), DeleteError> {
         let view = self.view();
         let instance = view.get(handle).ok_or(DeleteError::NotExist)?;
         instance.delete(pointer)?;
+        if let Some(shadow) = view.shadow(handle) {
+            shadow.delete(pointer)?;
+        }
+        Ok(())
+    }
+    fn replay(
+        &self,
+        handle: Handle,
+        expected: Option<LogPosition>,
+        records: Vec<LogRecord>,
+        next: LogPosition,
+    ) -> Result<(), ReplayError> {
+        let _replay = self.replay.lock();
+        let view = self.view();
+        let instance = view.get(handle).ok_or(ReplayError::NotExist)?;
+        // a reindex starts a new log, which is followed by the shadow index, and the old index
+        // only takes the records after the build scan
+        let (instance, old) = match view.shadow(handle) {
+            Some(shadow) if self.protect.lock().scanned.contains(&handle) => {
+                (shadow, Some(instance))
+            }
+            Some(shadow) => (shadow, None),
+            None => (instance, None),
+        };
+        if instance.position() != expected {
+            return Err(ReplayError::Conflict);
+        }
+        if !records.is_empty() {
+            instance.set_position(expected, true);
+        }
+        let result = records.into_iter().try_for_each(|record| match record {
+            LogRecord::Insert(vectors) => std::iter::once(instance)
+                .chain(old)
+                .try_for_each(|instance| insert_many(instance, &vectors))
+                .map_err(|e| match e {
+                    InsertError::NotExist => ReplayError::NotExist,
+                    InsertError::InvalidVector => ReplayError::InvalidVector,
+                }),
+            LogRecord::Delete(pointers) => pointers.into_iter().try_for_each(|pointer| {
+                self.delete(handle, pointer)
+                    .map_err(|DeleteError::NotExist| ReplayError::NotExist)
+            }),
+        });
+        let result = result.and_then(|()| {
+            self.flush(handle)
+                .map_err(|FlushError::NotExist| ReplayError::NotExist)
+        });
+        if let Err(e) = result {
+            // the index holds a part of the records, so it's rebuilt by replaying from scratch
+            log::error!("Failed to replay the log of index {handle}: {e}");
+            // the index is retired once it's no longer used, including by this view
+            drop(view);
+            let _ = self.drop(handle);
+            return Err(e);
+        }
+        instance.set_position(Some(next), false);
+        Ok(())
+    }
+    fn snapshot(&self, handle: Handle, dest: PathBuf) -> Result<(), SnapshotError> {
+        // a snapshot is never taken in the middle of replaying the log
+        let _replay = self.replay.lock();
+        let view = self.view();
+        let instance = view.get(handle).ok_or(SnapshotError::NotExist)?;
+        instance.snapshot(&dest)
+    }
+    fn restore(&self, handle: Handle, source: PathBuf) -> Result<(), RestoreError> {
+        let error = |e: std::io::Error| RestoreError::Read {
+            reason: e.to_string(),
+        };
+        let _replay = self.replay.lock();
+        let mut protect = self.protect.lock();
+        let instance = protect.indexes.get(&handle).ok_or(RestoreError::NotExist)?;
+        let options = index::snapshot::options(&source).map_err(error)?;
+        let expected = instance.view().options().clone();
+        if serde_json::to_value(options).ok() != serde_json::to_value(expected).ok() {
+            return Err(RestoreError::IncompatibleOptions);
+        }
+        // the snapshot is opened as a shadow index and then promoted, as a reindex does
+        if let Some(shadow) = protect.unshadow(handle) {
+            protect.maintain(&self.view);
+            MutexGuard::unlocked(&mut protect, || self.retire(handle, shadow));
+        }
+        self.settle(&mut protect, handle);
+        if !protect.indexes.contains_key(&handle) {
+            return Err(RestoreError::NotExist);
+        }
+        let path = self
+            .path
+            .join("indexes")
+            .join(dir(&protect.flipped, handle, true));
+        if path.try_exists().unwrap() {
+            std::fs::remove_dir_all(&path).unwrap();
+        }
+        if let Err(e) = index::snapshot::restore(&source, &path) {
+            let _ = std::fs::remove_dir_all(&path);
+            return Err(error(e));
+        }
+        self.install(protect, handle, path)
+            .map_err(|reason| RestoreError::Read { reason })
+    }
+    fn upgrade(&self, handle: Handle, options: IndexOptions) -> Result<(), UpgradeError> {
+        let error = |reason: String| UpgradeError::Write { reason };
+        let _replay = self.replay.lock();
+        let mut protect = self.protect.lock();
+        self.settle(&mut protect, handle);
+        let instance = protect
+            .indexes
+            .get(&handle)
+            .cloned()
+            .ok_or(UpgradeError::NotExist)?;
+        let current = instance.view().options().clone();
+        if serde_json::to_value(&current).ok() == serde_json::to_value(&options).ok() {
+            return Ok(());
+        }
+        // cos indexes used to be stored as dot indexes of normalized vectors
+        let mut legacy = options.clone();
+        legacy.vector.d = DistanceKind::Dot;
+        if options.vector.d != DistanceKind::Cos
+            || serde_json::to_value(&current).ok() != serde_json::to_value(&legacy).ok()
+        {
+            return Err(UpgradeError::IncompatibleOptions);
+        }
+        // a reindex replaces the index with one of these options anyway
+        if protect.shadows.contains_key(&handle) {
+            return Ok(());
+        }
+        // the snapshot is converted and opened as a shadow index, whose segments are rebuilt
+        // from their stored vectors, and then promoted
+        let path = self
+            .path
+            .join("indexes")
+            .join(dir(&protect.flipped, handle, true));
+        if path.try_exists().unwrap() {
+            std::fs::remove_dir_all(&path).unwrap();
+        }
+        instance.snapshot(&path).map_err(|e| error(e.to_string()))?;
+        // the old index is retired once it's released
+        drop(instance);
+        if let Err(e) = index::snapshot::convert(&path, &options) {
+            std::fs::remove_dir_all(&path).unwrap();
+            return Err(error(e.to_string()));
+        }
+        self.install(protect, handle, path).map_err(error)
+    }
+    fn attach(
+        &self,
+        handle: Handle,
+        source: PathBuf,
+        labels: Vec<(i64, Pointer)>,
+    ) -> Result<(), AttachError> {
+        let view = self.view();
+        let instance = view.get(handle).ok_or(AttachError::NotExist)?;
+        // the old index keeps serving queries until the shadow index is promoted, while the
+        // shadow index takes the rows from the build scan until it's complete, or they are
+        // inserted twice
+        let scanned = self.protect.lock().scanned.contains(&handle);
+        let shadow = view.shadow(handle).filter(|_| scanned);
+        for instance in std::iter::once(instance).chain(shadow) {
+            instance.attach(&source, &labels)?;
+        }
         Ok(())
     }
     fn view_vbase(&self, handle: Handle) -> Result<impl ViewVbaseOperations, VbaseError> {
@@ -180,23 +474,57 @@ impl WorkerOperations for Worker {
     fn stat(&self, handle: Handle) -> Result<IndexStat, StatError> {
         let view = self.view();
         let instance = view.get(handle).ok_or(StatError::NotExist)?;
-        let stat = instance.stat();
+        // a reindex waits for the shadow index to be built
+        let stat = view.shadow(handle).unwrap_or(instance).stat();
         Ok(stat)
     }
+    fn verify(&self, handle: Handle) -> Result<Vec<VerifyCheck>, VerifyError> {
+        // the log of the relation is not replayed meanwhile
+        let _replay = self.replay.lock();
+        let view = self.view();
+        if let Some(instance) = view.get(handle) {
+            return Ok(instance.verify(&is_ctid));
+        }
+        // an index that failed to open is left in its directory until the worker restarts
+        let path = {
+            let protect = self.protect.lock();
+            let dir = dir(&protect.flipped, handle, false);
+            self.path.join("indexes").join(dir)
+        };
+        if !path.try_exists().unwrap_or(false) {
+            return Err(VerifyError::NotExist);
+        }
+        Ok(Instance::verify_dir(&path, &is_ctid))
+    }
     fn alter(&self, handle: Handle, key: &str, value: &str) -> Result<(), AlterError> {
         let view = self.view();
         let instance = view.get(handle).ok_or(AlterError::NotExist)?;
-        instance.alter(key, value)
+        instance.alter(key, value)?;
+        if let Some(shadow) = view.shadow(handle) {
+            shadow.alter(key, value)?;
+        }
+        Ok(())
     }
     fn stop(&self, handle: Handle) -> Result<(), StopError> {
         let view = self.view();
         let instance = view.get(handle).ok_or(StopError::NotExist)?;
+        let instance = view.shadow(handle).unwrap_or(instance);
         instance.stop();
         Ok(())
     }
     fn start(&self, handle: Handle) -> Result<(), StartError> {
         let view = self.view();
         let instance = view.get(handle).ok_or(StartError::NotExist)?;
+        if let Some(shadow) = view.shadow(handle) {
+            // a build starts the index once all rows of the scan are inserted
+            shadow.start();
+            let mut protect = self.protect.lock();
+            if protect.shadows.contains_key(&handle) {
+                protect.scanned.insert(handle);
+                protect.maintain(&self.view);
+            }
+            return Ok(());
+        }
         instance.start();
         Ok(())
     }
@@ -204,25 +532,65 @@ impl WorkerOperations for Worker {
 
 pub struct WorkerView {
     indexes: HashMap<Handle, Instance>,
+    shadows: HashMap<Handle, Instance>,
 }
 
 impl WorkerView {
     pub fn get(&self, handle: Handle) -> Option<&Instance> {
         self.indexes.get(&handle)
     }
+    pub fn shadow(&self, handle: Handle) -> Option<&Instance> {
+        self.shadows.get(&handle)
+    }
 }
 
 struct WorkerProtect {
     startup: FileAtomic<WorkerStartup>,
     indexes: HashMap<Handle, Instance>,
+    shadows: HashMap<Handle, Instance>,
+    flipped: HashSet<Handle>,
+    // shadow indexes that all rows of the build scan are inserted into
+    scanned: HashSet<Handle>,
+    // numbers of indexes that are removed from the view but still used, whose directories are
+    // removed once they're retired
+    retiring: HashMap<Handle, usize>,
 }
 
 impl WorkerProtect {
+    /// Replaces the index with its shadow index, returning the old one to be retired.
+    fn swap(&mut self, handle: Handle) -> Option<Instance> {
+        let shadow = self.shadows.remove(&handle)?;
+        self.scanned.remove(&handle);
+        if !self.flipped.remove(&handle) {
+            self.flipped.insert(handle);
+        }
+        let index = self.indexes.insert(handle, shadow)?;
+        *self.retiring.entry(handle).or_default() += 1;
+        Some(index)
+    }
+    /// Removes the shadow index, returning it to be retired.
+    fn unshadow(&mut self, handle: Handle) -> Option<Instance> {
+        let shadow = self.shadows.remove(&handle)?;
+        self.scanned.remove(&handle);
+        *self.retiring.entry(handle).or_default() += 1;
+        Some(shadow)
+    }
     fn maintain(&mut self, swap: &ArcSwap<WorkerView>) {
+        self.flipped
+            .retain(|handle| self.indexes.contains_key(handle));
+        self.scanned
+            .retain(|handle| self.shadows.contains_key(handle));
         let indexes = self.indexes.keys().copied().collect();
-        self.startup.set(WorkerStartup { indexes });
+        let flipped = self.flipped.clone();
+        let scanned = self.scanned.clone();
+        self.startup.set(WorkerStartup {
+            indexes,
+            flipped,
+            scanned,
+        });
         swap.swap(Arc::new(WorkerView {
             indexes: self.indexes.clone(),
+            shadows: self.shadows.clone(),
         }));
     }
 }
@@ -230,12 +598,311 @@ impl WorkerProtect {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct WorkerStartup {
     indexes: HashSet<Handle>,
+    // indexes whose live directory is the alternate one, after a shadow build was promoted
+    #[serde(default)]
+    flipped: HashSet<Handle>,
+    // shadow indexes whose build scan is complete, which are promoted once they're built, while
+    // others belong to a reindex that is aborted by a restart
+    #[serde(default, alias = "shadows")]
+    scanned: HashSet<Handle>,
 }
 
 impl WorkerStartup {
     pub fn new() -> Self {
         Self {
             indexes: HashSet::new(),
+            flipped: HashSet::new(),
+            scanned: HashSet::new(),
+        }
+    }
+}
+
+fn insert_many(instance: &Instance, vectors: &[(OwnedVector, Pointer)]) -> Result<(), InsertError> {
+    let mut vectors = vectors;
+    while !vectors.is_empty() {
+        let view = instance.view();
+        let n = view.insert_many(vectors)?;
+        vectors = &vectors[n..];
+        if !vectors.is_empty() {
+            instance.refresh();
+        }
+    }
+    Ok(())
+}
+
+/// Pointers are encoded from ctids, whose offset numbers start at 1.
+fn is_ctid(pointer: Pointer) -> bool {
+    let value = pointer.as_u64();
+    value >> 48 == 0 && value & 0xffff != 0
+}
+
+fn dir(flipped: &HashSet<Handle>, handle: Handle, shadow: bool) -> String {
+    if flipped.contains(&handle) != shadow {
+        format!("{handle}.1")
+    } else {
+        handle.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reindex_test() {
+        let path = std::env::temp_dir().join(format!("worker_{}", std::process::id()));
+        let handle = Handle::new(0, 1);
+        let options = IndexOptions {
+            vector: VectorOptions {
+                dims: 4,
+                v: VectorKind::Vecf32,
+                d: DistanceKind::L2,
+                weights: None,
+            },
+            indexing: IndexingOptions::Flat(Default::default()),
+        };
+        let rows = |range: std::ops::Range<u64>| {
+            range
+                .map(|i| {
+                    let vector = OwnedVector::Vecf32(VectOwned::new(vec![i as f32; 4]));
+                    (vector, Pointer::new(i))
+                })
+                .collect::<Vec<_>>()
+        };
+        let list = |worker: &Worker| {
+            let view = worker.view_list(handle).unwrap();
+            let mut pointers = view.list().unwrap().map(|p| p.as_u64()).collect::<Vec<_>>();
+            pointers.sort();
+            pointers
+        };
+        let worker = Worker::create(path.clone());
+        worker
+            .create(handle, options.clone(), IndexAlterableOptions::default())
+            .unwrap();
+        worker.build(handle, rows(0..100)).unwrap();
+
+        // a reindex, whose shadow index is empty until the rows of the build scan are inserted
+        worker
+            .create(handle, options.clone(), IndexAlterableOptions::default())
+            .unwrap();
+        worker.stop(handle).unwrap();
+        worker.stat(handle).unwrap();
+        worker.promote();
+        assert_eq!(list(&worker), (0..100).collect::<Vec<_>>());
+        worker.build(handle, rows(0..100)).unwrap();
+        let (vector, pointer) = rows(100..101).pop().unwrap();
+        worker.insert(handle, vector, pointer).unwrap();
+        assert_eq!(list(&worker), (0..101).collect::<Vec<_>>());
+        worker.start(handle).unwrap();
+
+        // the shadow index is kept by a restart
+        let startup = FileAtomic::<WorkerStartup>::open(path.join("startup"));
+        assert!(startup.get().scanned.contains(&handle));
+        let view = worker.view();
+        for instance in view.indexes.values().chain(view.shadows.values()) {
+            instance.stop();
         }
+        drop(view);
+        // dropping a worker removes the directories of its indexes, so a copy is reopened
+        let restarted = path.with_extension("restarted");
+        copy_dir(&path, &restarted);
+        drop(worker);
+        std::fs::remove_dir_all(&path).unwrap();
+        let path = restarted;
+        let worker = Worker::open(path.clone());
+        assert!(worker.view().shadow(handle).is_some());
+        while worker.view().shadow(handle).is_some() {
+            worker.promote();
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        assert_eq!(list(&worker), (0..101).collect::<Vec<_>>());
+
+        // the old index is retired once it's no longer used, and then its directory is reused
+        worker
+            .create(handle, options.clone(), IndexAlterableOptions::default())
+            .unwrap();
+        worker.stop(handle).unwrap();
+        worker.build(handle, rows(0..101)).unwrap();
+        worker.start(handle).unwrap();
+        let old = worker.view_list(handle).unwrap();
+        std::thread::scope(|scope| {
+            let promoting = scope.spawn(|| {
+                while worker.view().shadow(handle).is_some() {
+                    worker.promote();
+                    std::thread::sleep(std::time::Duration::from_millis(100));
+                }
+            });
+            std::thread::sleep(std::time::Duration::from_secs(1));
+            assert!(!promoting.is_finished());
+            drop(old);
+        });
+        worker
+            .create(handle, options, IndexAlterableOptions::default())
+            .unwrap();
+        worker.build(handle, rows(0..101)).unwrap();
+        assert_eq!(list(&worker), (0..101).collect::<Vec<_>>());
+        WorkerOperations::drop(worker.as_ref(), handle).unwrap();
+        drop(worker);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn upgrade_test() {
+        let path = std::env::temp_dir().join(format!("worker_upgrade_{}", std::process::id()));
+        let handle = Handle::new(0, 3);
+        let options = |d| IndexOptions {
+            vector: VectorOptions {
+                dims: 4,
+                v: VectorKind::Vecf32,
+                d,
+                weights: None,
+            },
+            indexing: IndexingOptions::Flat(Default::default()),
+        };
+        // vectors of legacy cos indexes are normalized before they are inserted
+        let rows = (0..100)
+            .map(|i| {
+                let theta = i as f32 / 100.0;
+                let vector = VectOwned::new(vec![theta.cos(), theta.sin(), 0.0, 0.0]);
+                (OwnedVector::Vecf32(vector), Pointer::new(i))
+            })
+            .collect::<Vec<_>>();
+        let query = OwnedVector::Vecf32(VectOwned::new(vec![2.0, 0.0, 0.0, 0.0]));
+        let nearest = |worker: &Worker| {
+            let view = worker.view_vbase(handle).unwrap();
+            let query = view.preprocess(query.clone());
+            let opts = SearchOptions::default();
+            let mut results = view.vbase(&query, &opts, None).unwrap();
+            let (distance, pointer) = results.next().unwrap();
+            (distance.to_f32(), pointer.as_u64())
+        };
+        let worker = Worker::create(path.clone());
+        worker
+            .create(handle, options(DistanceKind::Dot), Default::default())
+            .unwrap();
+        worker.build(handle, rows).unwrap();
+        worker.start(handle).unwrap();
+        let (distance, pointer) = nearest(&worker);
+        assert_eq!(pointer, 0);
+        assert!((distance + 2.0).abs() < 1e-4);
+
+        // sealed segments are rebuilt from their stored vectors
+        assert!(matches!(
+            worker.upgrade(handle, options(DistanceKind::L2)),
+            Err(UpgradeError::IncompatibleOptions)
+        ));
+        worker.upgrade(handle, options(DistanceKind::Cos)).unwrap();
+        let stat = worker.stat(handle).unwrap();
+        assert_eq!(stat.options.vector.d, DistanceKind::Cos);
+        while worker.stat(handle).unwrap().indexing {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        let (distance, pointer) = nearest(&worker);
+        assert_eq!(pointer, 0);
+        assert!(distance.abs() < 1e-4);
+        let view = worker.view_list(handle).unwrap();
+        assert_eq!(view.list().unwrap().count(), 100);
+        drop(view);
+        worker.upgrade(handle, options(DistanceKind::Cos)).unwrap();
+        drop(worker);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    fn copy_dir(from: &std::path::Path, to: &std::path::Path) {
+        std::fs::create_dir(to).unwrap();
+        for entry in std::fs::read_dir(from).unwrap() {
+            let entry = entry.unwrap();
+            if entry.file_type().unwrap().is_dir() {
+                copy_dir(&entry.path(), &to.join(entry.file_name()));
+            } else {
+                std::fs::copy(entry.path(), to.join(entry.file_name())).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn replay_restore_test() {
+        let path = std::env::temp_dir().join(format!("worker_replay_{}", std::process::id()));
+        let snapshot = path.with_extension("snapshot");
+        let handle = Handle::new(0, 2);
+        let options = IndexOptions {
+            vector: VectorOptions {
+                dims: 4,
+                v: VectorKind::Vecf32,
+                d: DistanceKind::L2,
+                weights: None,
+            },
+            indexing: IndexingOptions::Flat(Default::default()),
+        };
+        let insert = |range: std::ops::Range<u64>| {
+            LogRecord::Insert(
+                range
+                    .map(|i| {
+                        let vector = OwnedVector::Vecf32(VectOwned::new(vec![i as f32; 4]));
+                        (vector, Pointer::new(i))
+                    })
+                    .collect(),
+            )
+        };
+        let position = |block| LogPosition {
+            log: 7,
+            block,
+            offset: 24,
+        };
+        let list = |worker: &Worker| {
+            let view = worker.view_list(handle).unwrap();
+            let mut pointers = view.list().unwrap().map(|p| p.as_u64()).collect::<Vec<_>>();
+            pointers.sort();
+            pointers
+        };
+        let worker = Worker::create(path.clone());
+        worker
+            .create(handle, options, IndexAlterableOptions::default())
+            .unwrap();
+        worker
+            .replay(handle, None, vec![insert(0..10)], position(1))
+            .unwrap();
+        worker.snapshot(handle, snapshot.clone()).unwrap();
+        worker
+            .replay(
+                handle,
+                Some(position(1)),
+                vec![insert(10..20), LogRecord::Delete(vec![Pointer::new(0)])],
+                position(2),
+            )
+            .unwrap();
+
+        // a restored index is behind the log, so the records after the snapshot are replayed
+        worker.restore(handle, snapshot.clone()).unwrap();
+        assert_eq!(worker.stat(handle).unwrap().position, Some(position(1)));
+        assert_eq!(list(&worker), (0..10).collect::<Vec<_>>());
+        assert!(matches!(
+            worker.replay(handle, Some(position(2)), Vec::new(), position(2)),
+            Err(ReplayError::Conflict)
+        ));
+        worker
+            .replay(
+                handle,
+                Some(position(1)),
+                vec![insert(10..20), LogRecord::Delete(vec![Pointer::new(0)])],
+                position(2),
+            )
+            .unwrap();
+        assert_eq!(list(&worker), (1..20).collect::<Vec<_>>());
+
+        // a failed replay drops the index, which is rebuilt from the start of the log
+        let invalid = OwnedVector::Vecf32(VectOwned::new(vec![0.0; 3]));
+        assert!(matches!(
+            worker.replay(
+                handle,
+                Some(position(2)),
+                vec![LogRecord::Insert(vec![(invalid, Pointer::new(20))])],
+                position(3),
+            ),
+            Err(ReplayError::InvalidVector)
+        ));
+        assert!(matches!(worker.stat(handle), Err(StatError::NotExist)));
+        std::fs::remove_dir_all(path).unwrap();
+        std::fs::remove_dir_all(snapshot).unwrap();
     }
 }
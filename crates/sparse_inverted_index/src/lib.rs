@@ -5,28 +5,83 @@ pub mod operator;
 use self::operator::OperatorSparseInvertedIndex;
 use base::always_equal::AlwaysEqual;
 use base::distance::Distance;
-use base::index::{IndexOptions, SearchOptions};
+use base::index::{IndexOptions, SearchOptions, SparseInvertedIndexIndexingOptions};
 use base::operator::Borrowed;
+use base::pod::Pod;
 use base::scalar::ScalarLike;
-use base::search::{Collection, Element, Payload, Source, Vectors};
+use base::search::{AllowList, Collection, Element, Payload, RerankerPop, Source, Vectors};
+use base::vector::VectorBorrowed;
 use common::json::Json;
 use common::mmap_array::MmapArray;
 use common::remap::RemappedCollection;
+use quantization::reranker::flat::WindowFlatReranker;
 use storage::Storage;
 
-use std::collections::BinaryHeap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
 use std::fs::create_dir;
 use std::path::Path;
 
 const ZERO: f32 = 0.0f32;
 
-#[allow(dead_code)]
+/// Number of postings in a block of a posting list.
+const BLOCK_SIZE: usize = 128;
+
+/// Summary of a block of postings, or of a whole posting list.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Block {
+    /// The last document in the block.
+    last: u32,
+    max: f32,
+    min: f32,
+    /// The minimum squared norm of the documents in the block.
+    norm: f32,
+}
+
+unsafe impl Pod for Block {}
+
+impl Block {
+    fn new(postings: &[(u32, f32)], norms: &[f32]) -> Self {
+        Self {
+            last: postings.last().map_or(0, |&(doc, _)| doc),
+            max: postings.iter().map(|&(_, x)| x).fold(ZERO, f32::max),
+            min: postings.iter().map(|&(_, x)| x).fold(ZERO, f32::min),
+            norm: postings
+                .iter()
+                .map(|&(doc, _)| norms[doc as usize])
+                .fold(f32::INFINITY, f32::min),
+        }
+    }
+    /// Upper bound of the contribution to the score of a term with weight `q`.
+    fn bound(&self, q: f32) -> f32 {
+        (q * self.max).max(q * self.min)
+    }
+    fn quantize(&self, x: f32) -> u8 {
+        if self.max > self.min {
+            ((x - self.min) / (self.max - self.min) * 255.0).round() as u8
+        } else {
+            0
+        }
+    }
+    fn dequantize(&self, x: u8) -> f32 {
+        self.min + x as f32 * ((self.max - self.min) / 255.0)
+    }
+}
+
 pub struct SparseInvertedIndex<O: OperatorSparseInvertedIndex> {
     storage: O::Storage,
     payloads: MmapArray<Payload>,
-    indexes: Json<Vec<u32>>,
-    offsets: Json<Vec<u32>>,
-    scores: Json<Vec<f32>>,
+    offsets: MmapArray<u32>,
+    indexes: MmapArray<u32>,
+    scores: MmapArray<f32>,
+    codes: MmapArray<u8>,
+    block_offsets: MmapArray<u32>,
+    blocks: MmapArray<Block>,
+    summaries: MmapArray<Block>,
+    norms: MmapArray<f32>,
+    order: MmapArray<u32>,
+    is_quantized: Json<bool>,
 }
 
 impl<O: OperatorSparseInvertedIndex> SparseInvertedIndex<O> {
@@ -46,28 +101,68 @@ impl<O: OperatorSparseInvertedIndex> SparseInvertedIndex<O> {
     pub fn vbase<'a>(
         &'a self,
         vector: Borrowed<'a, O>,
-        _: &'a SearchOptions,
+        opts: &'a SearchOptions,
+        filter: Option<&'a AllowList>,
     ) -> Box<dyn Iterator<Item = Element> + 'a> {
-        let mut doc_score = vec![ZERO; self.payloads.len()];
-        for (token, val) in O::to_index_vec(vector) {
-            let start = self.offsets[token as usize];
-            let end = self.offsets[token as usize + 1];
-            for i in (start as usize)..(end as usize) {
-                doc_score[self.indexes[i] as usize] += self.scores[i] * val;
-            }
+        let query = O::to_index_vec(vector)
+            .filter(|&(token, val)| {
+                val != ZERO && self.posting(token).start != self.posting(token).end
+            })
+            .collect::<Vec<_>>();
+        let check = move |i: u32| filter.map_or(true, |f| f.check(self.payloads[i as usize]));
+        let radius = Distance::from_f32(opts.radius);
+        let factor = opts.sparse_inverted_index_pruning_factor;
+        if !*self.is_quantized {
+            let k = opts.limit as usize;
+            let mut candidates = if k < self.payloads.len() {
+                Fallback::new(self.top_k(vector, &query, k, factor, radius, check))
+            } else {
+                Fallback::exhaustive(self.exhaustive(vector, &query, radius, check))
+            };
+            return Box::new(std::iter::from_fn(move || {
+                candidates
+                    .next(|| self.exhaustive(vector, &query, radius, check))
+                    .map(|(distance, u)| Element {
+                        distance,
+                        payload: AlwaysEqual(self.payload(u)),
+                    })
+            }));
         }
-        let mut candidates: BinaryHeap<(Distance, AlwaysEqual<u32>)> = doc_score
-            .iter()
-            .enumerate()
-            .map(|(i, score)| (Distance::from(-score), AlwaysEqual(i as u32)))
-            .collect::<Vec<_>>()
-            .into();
-        Box::new(std::iter::from_fn(move || {
-            candidates.pop().map(|(distance, AlwaysEqual(u))| Element {
-                distance,
-                payload: AlwaysEqual(self.payload(u)),
+        // scores computed with quantized weights are rescored with the vectors
+        let k = (opts.limit as usize).saturating_add(opts.sparse_inverted_index_rerank_size as _);
+        let rerank = move |candidates: Vec<(Distance, u32)>| {
+            let heap = candidates
+                .into_iter()
+                .map(|(distance, u)| (Reverse(distance), AlwaysEqual(u)))
+                .collect();
+            WindowFlatReranker::new(
+                heap,
+                move |u| (O::distance(vector, self.storage.vector(u)), ()),
+                opts.sparse_inverted_index_rerank_size,
+                opts.limit,
+            )
+        };
+        let mut candidates = if k < self.payloads.len() {
+            Fallback::new(self.top_k(vector, &query, k, factor, Distance::INFINITY, check))
+        } else {
+            Fallback::exhaustive(self.exhaustive(vector, &query, Distance::INFINITY, check))
+        };
+        let mut reranker = rerank(candidates.take());
+        Box::new(
+            std::iter::from_fn(move || loop {
+                if let Some((distance, u, ())) = reranker.pop() {
+                    return Some(Element {
+                        distance,
+                        payload: AlwaysEqual(self.payload(u)),
+                    });
+                }
+                reranker = rerank(
+                    candidates
+                        .rest(|| self.exhaustive(vector, &query, Distance::INFINITY, check))?,
+                );
             })
-        }))
+            .filter(move |x| x.distance <= radius),
+        )
     }
 
     pub fn dims(&self) -> u32 {
@@ -85,6 +180,273 @@ impl<O: OperatorSparseInvertedIndex> SparseInvertedIndex<O> {
     pub fn payload(&self, i: u32) -> Payload {
         self.payloads[i as usize]
     }
+
+    fn posting(&self, token: u32) -> std::ops::Range<usize> {
+        self.offsets[token as usize] as usize..self.offsets[token as usize + 1] as usize
+    }
+
+    fn weight(&self, i: usize, block: usize) -> f32 {
+        if *self.is_quantized {
+            self.blocks[block].dequantize(self.codes[i])
+        } else {
+            self.scores[i]
+        }
+    }
+
+    fn bias(&self, doc: u32) -> f32 {
+        if O::NORM {
+            -0.5 * self.norms[doc as usize]
+        } else {
+            ZERO
+        }
+    }
+
+    /// Accumulates every posting of every query term.
+    fn exhaustive(
+        &self,
+        vector: Borrowed<'_, O>,
+        query: &[(u32, f32)],
+        radius: Distance,
+        check: impl Fn(u32) -> bool,
+    ) -> Vec<(Distance, u32)> {
+        let mut doc_score = (0..self.payloads.len() as u32)
+            .map(|i| self.bias(i))
+            .collect::<Vec<_>>();
+        for &(token, val) in query {
+            let first = self.block_offsets[token as usize] as usize;
+            for (j, i) in self.posting(token).enumerate() {
+                let weight = self.weight(i, first + j / BLOCK_SIZE);
+                doc_score[self.indexes[i] as usize] += weight * val;
+            }
+        }
+        let mut result = doc_score
+            .into_iter()
+            .enumerate()
+            .filter(|&(i, _)| check(i as u32))
+            .map(|(i, score)| (O::to_distance(vector, score), i as u32))
+            .filter(|&(distance, _)| distance <= radius)
+            .collect::<Vec<_>>();
+        result.sort_unstable();
+        result
+    }
+
+    /// Block-Max WAND. A document is skipped if the upper bound of its score is not
+    /// greater than the score of the `k`-th best document found so far, raised by
+    /// `factor - 1` times its magnitude, so `factor = 1.0` returns exact results.
+    fn top_k(
+        &self,
+        vector: Borrowed<'_, O>,
+        query: &[(u32, f32)],
+        k: usize,
+        factor: f32,
+        radius: Distance,
+        check: impl Fn(u32) -> bool,
+    ) -> Vec<(Distance, u32)> {
+        let mut cursors = query
+            .iter()
+            .map(|&(token, q)| Cursor {
+                pos: self.posting(token).start,
+                start: self.posting(token).start,
+                end: self.posting(token).end,
+                first: self.block_offsets[token as usize] as usize,
+                block: self.block_offsets[token as usize] as usize,
+                q,
+                bound: self.summaries[token as usize].bound(q),
+            })
+            .collect::<Vec<_>>();
+        let mut heap = BinaryHeap::<(Reverse<Distance>, AlwaysEqual<u32>)>::new();
+        let threshold = |heap: &BinaryHeap<(Reverse<Distance>, AlwaysEqual<u32>)>| match heap.peek()
+        {
+            Some(&(Reverse(score), _)) if heap.len() == k => {
+                let score = f32::from(score);
+                score + (factor - 1.0) * score.abs()
+            }
+            _ => f32::NEG_INFINITY,
+        };
+        // upper bound of the bias of any document
+        let bias = self.order.first().map_or(ZERO, |&u| self.bias(u));
+        loop {
+            cursors.sort_by_key(|c| self.doc(c));
+            let theta = threshold(&heap);
+            // find the pivot, the first document whose score may exceed the threshold
+            let mut upper = bias;
+            let mut pivot = None;
+            for (i, c) in cursors.iter().enumerate() {
+                if self.doc(c) == u32::MAX {
+                    break;
+                }
+                upper += c.bound;
+                if upper > theta {
+                    pivot = Some(i);
+                    break;
+                }
+            }
+            let Some(mut p) = pivot else {
+                break;
+            };
+            let pivot_doc = self.doc(&cursors[p]);
+            while p + 1 < cursors.len() && self.doc(&cursors[p + 1]) == pivot_doc {
+                p += 1;
+            }
+            let mut upper = ZERO;
+            let mut norm = f32::INFINITY;
+            for c in cursors[..=p].iter_mut() {
+                self.shallow_advance(c, pivot_doc);
+                upper += self.blocks[c.block].bound(c.q);
+                norm = norm.min(self.blocks[c.block].norm);
+            }
+            if O::NORM {
+                upper -= 0.5 * norm;
+            }
+            if upper > theta {
+                if self.doc(&cursors[0]) == pivot_doc {
+                    let mut score = self.bias(pivot_doc);
+                    for c in cursors[..=p].iter_mut() {
+                        score += self.weight(c.pos, c.block) * c.q;
+                        c.pos += 1;
+                    }
+                    if check(pivot_doc) && O::to_distance(vector, score) <= radius {
+                        heap.push((Reverse(Distance::from_f32(score)), AlwaysEqual(pivot_doc)));
+                        if heap.len() > k {
+                            heap.pop();
+                        }
+                    }
+                } else {
+                    for c in cursors[..p].iter_mut() {
+                        self.advance(c, pivot_doc);
+                    }
+                }
+            } else {
+                // no document before the end of any current block can exceed the threshold
+                let mut next = cursors[..=p]
+                    .iter()
+                    .map(|c| self.blocks[c.block].last.saturating_add(1))
+                    .min()
+                    .unwrap_or(u32::MAX);
+                if let Some(c) = cursors.get(p + 1) {
+                    next = next.min(self.doc(c));
+                }
+                for c in cursors[..=p].iter_mut() {
+                    self.advance(c, next);
+                }
+            }
+        }
+        let mut result = heap
+            .into_iter()
+            .map(|(Reverse(score), AlwaysEqual(u))| (O::to_distance(vector, f32::from(score)), u))
+            .collect::<Vec<_>>();
+        // documents sharing no term with the query are scored by their biases, so the
+        // best of them come first in `order`
+        let best = O::to_distance(vector, bias);
+        if best <= radius && result.iter().filter(|&&(d, _)| d <= best).count() < k {
+            let matched = |doc: u32| {
+                query.iter().any(|&(token, _)| {
+                    self.indexes[self.posting(token)]
+                        .binary_search(&doc)
+                        .is_ok()
+                })
+            };
+            result.extend(
+                self.order
+                    .iter()
+                    .copied()
+                    .filter(|&u| check(u) && !matched(u))
+                    .take(k)
+                    .map(|u| (O::to_distance(vector, self.bias(u)), u))
+                    .filter(|&(distance, _)| distance <= radius),
+            );
+        }
+        result.sort_unstable();
+        result.truncate(k);
+        result
+    }
+
+    fn doc(&self, c: &Cursor) -> u32 {
+        if c.pos < c.end {
+            self.indexes[c.pos]
+        } else {
+            u32::MAX
+        }
+    }
+
+    /// Moves the block of the cursor to the block that may contain `target`.
+    fn shallow_advance(&self, c: &mut Cursor, target: u32) {
+        let last = c.first + (c.end - c.start).div_ceil(BLOCK_SIZE) - 1;
+        while c.block < last && self.blocks[c.block].last < target {
+            c.block += 1;
+        }
+    }
+
+    /// Moves the cursor to the first posting whose document is not less than `target`.
+    fn advance(&self, c: &mut Cursor, target: u32) {
+        if self.doc(c) >= target {
+            return;
+        }
+        self.shallow_advance(c, target);
+        if self.blocks[c.block].last < target {
+            c.pos = c.end;
+            return;
+        }
+        let begin = c.start + (c.block - c.first) * BLOCK_SIZE;
+        let end = (begin + BLOCK_SIZE).min(c.end);
+        let begin = begin.max(c.pos);
+        c.pos = begin + self.indexes[begin..end].partition_point(|&doc| doc < target);
+    }
+}
+
+/// Results of a search that is limited to the first `k` documents, followed by the rest of the
+/// documents once they are exhausted, since rows of the first `k` documents may be deleted or
+/// invisible to the query.
+struct Fallback {
+    candidates: std::vec::IntoIter<(Distance, u32)>,
+    // documents that are returned, or `None` if all documents are returned
+    returned: Option<HashSet<u32>>,
+}
+
+impl Fallback {
+    fn new(candidates: Vec<(Distance, u32)>) -> Self {
+        Self {
+            returned: Some(candidates.iter().map(|&(_, u)| u).collect()),
+            candidates: candidates.into_iter(),
+        }
+    }
+    fn exhaustive(candidates: Vec<(Distance, u32)>) -> Self {
+        Self {
+            candidates: candidates.into_iter(),
+            returned: None,
+        }
+    }
+    fn next(&mut self, rest: impl FnOnce() -> Vec<(Distance, u32)>) -> Option<(Distance, u32)> {
+        if let Some(x) = self.candidates.next() {
+            return Some(x);
+        }
+        self.candidates = self.rest(rest)?.into_iter();
+        self.candidates.next()
+    }
+    /// Takes the first documents.
+    fn take(&mut self) -> Vec<(Distance, u32)> {
+        std::mem::take(&mut self.candidates).collect()
+    }
+    /// Returns the documents that are not returned yet, or `None` if all documents are returned.
+    fn rest(
+        &mut self,
+        rest: impl FnOnce() -> Vec<(Distance, u32)>,
+    ) -> Option<Vec<(Distance, u32)>> {
+        let returned = self.returned.take()?;
+        let mut rest = rest();
+        rest.retain(|(_, u)| !returned.contains(u));
+        Some(rest)
+    }
+}
+
+struct Cursor {
+    pos: usize,
+    start: usize,
+    end: usize,
+    first: usize,
+    block: usize,
+    q: f32,
+    bound: f32,
 }
 
 fn from_nothing<O: OperatorSparseInvertedIndex>(
@@ -94,67 +456,294 @@ fn from_nothing<O: OperatorSparseInvertedIndex>(
 ) -> SparseInvertedIndex<O> {
     create_dir(path.as_ref()).expect("failed to create path for inverted index");
 
+    let SparseInvertedIndexIndexingOptions { quantized_weights } =
+        opts.indexing.clone().unwrap_sparse_inverted_index();
     let mut token_collection = vec![Vec::new(); opts.vector.dims as usize];
+    let mut norms = Vec::with_capacity(collection.len() as usize);
     for i in 0..collection.len() {
-        for (token, score) in O::to_index_vec(collection.vector(i)) {
+        let vector = collection.vector(i);
+        for (token, score) in O::to_index_vec(vector) {
             token_collection[token as usize].push((i, score.to_f32()));
         }
+        norms.push(vector.norm() * vector.norm());
     }
-    let (indexes, offsets, scores) = build_compressed_matrix(token_collection);
+    let mut order = (0..collection.len()).collect::<Vec<_>>();
+    order.sort_by(|&x, &y| f32::total_cmp(&norms[x as usize], &norms[y as usize]));
 
     let storage = O::Storage::create(path.as_ref().join("storage"), collection);
     let payloads = MmapArray::create(
         path.as_ref().join("payloads"),
         (0..collection.len()).map(|i| collection.payload(i)),
     );
-    let json_index = Json::create(path.as_ref().join("indexes"), indexes);
-    let json_offset = Json::create(path.as_ref().join("offsets"), offsets);
-    let json_score = Json::create(path.as_ref().join("scores"), scores);
+    let offsets = MmapArray::create(
+        path.as_ref().join("offsets"),
+        std::iter::once(0).chain(token_collection.iter().scan(0, |last, x| {
+            *last += x.len() as u32;
+            Some(*last)
+        })),
+    );
+    let indexes = MmapArray::create(
+        path.as_ref().join("indexes"),
+        token_collection.iter().flatten().map(|&(doc, _)| doc),
+    );
+    let blocks = token_collection
+        .iter()
+        .flat_map(|x| x.chunks(BLOCK_SIZE).map(|x| Block::new(x, &norms)))
+        .collect::<Vec<_>>();
+    let scores = MmapArray::create(
+        path.as_ref().join("scores"),
+        token_collection
+            .iter()
+            .flatten()
+            .map(|&(_, score)| score)
+            .filter(|_| !quantized_weights),
+    );
+    let codes = MmapArray::create(
+        path.as_ref().join("codes"),
+        token_collection
+            .iter()
+            .flat_map(|x| x.chunks(BLOCK_SIZE))
+            .zip(blocks.iter())
+            .flat_map(|(x, block)| x.iter().map(|&(_, score)| block.quantize(score)))
+            .filter(|_| quantized_weights),
+    );
+    let block_offsets = MmapArray::create(
+        path.as_ref().join("block_offsets"),
+        std::iter::once(0).chain(token_collection.iter().scan(0, |last, x| {
+            *last += x.len().div_ceil(BLOCK_SIZE) as u32;
+            Some(*last)
+        })),
+    );
+    let blocks = MmapArray::create(path.as_ref().join("blocks"), blocks.into_iter());
+    let summaries = MmapArray::create(
+        path.as_ref().join("summaries"),
+        token_collection.iter().map(|x| Block::new(x, &norms)),
+    );
+    let norms = MmapArray::create(path.as_ref().join("norms"), norms.into_iter());
+    let order = MmapArray::create(path.as_ref().join("order"), order.into_iter());
+    let is_quantized = Json::create(path.as_ref().join("is_quantized"), quantized_weights);
     SparseInvertedIndex {
         storage,
         payloads,
-        indexes: json_index,
-        offsets: json_offset,
-        scores: json_score,
+        offsets,
+        indexes,
+        scores,
+        codes,
+        block_offsets,
+        blocks,
+        summaries,
+        norms,
+        order,
+        is_quantized,
     }
 }
 
 fn open<O: OperatorSparseInvertedIndex>(path: impl AsRef<Path>) -> SparseInvertedIndex<O> {
     let storage = O::Storage::open(path.as_ref().join("storage"));
     let payloads = MmapArray::open(path.as_ref().join("payloads"));
-    let offsets = Json::open(path.as_ref().join("offsets"));
-    let indexes = Json::open(path.as_ref().join("indexes"));
-    let scores = Json::open(path.as_ref().join("scores"));
+    let offsets = MmapArray::open(path.as_ref().join("offsets"));
+    let indexes = MmapArray::open(path.as_ref().join("indexes"));
+    let scores = MmapArray::open(path.as_ref().join("scores"));
+    let codes = MmapArray::open(path.as_ref().join("codes"));
+    let block_offsets = MmapArray::open(path.as_ref().join("block_offsets"));
+    let blocks = MmapArray::open(path.as_ref().join("blocks"));
+    let summaries = MmapArray::open(path.as_ref().join("summaries"));
+    let norms = MmapArray::open(path.as_ref().join("norms"));
+    let order = MmapArray::open(path.as_ref().join("order"));
+    let is_quantized = Json::open(path.as_ref().join("is_quantized"));
     SparseInvertedIndex {
         storage,
         payloads,
-        indexes,
         offsets,
+        indexes,
         scores,
+        codes,
+        block_offsets,
+        blocks,
+        summaries,
+        norms,
+        order,
+        is_quantized,
     }
 }
 
-fn build_compressed_matrix(
-    token_collection: Vec<Vec<(u32, f32)>>,
-) -> (Vec<u32>, Vec<u32>, Vec<f32>) {
-    let mut indexes = Vec::new();
-    let mut offsets = Vec::new();
-    let mut scores = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::distance::DistanceKind;
+    use base::index::{IndexingOptions, VectorOptions};
+    use base::operator::{Operator, SVectDot, SVectL2};
+    use base::search::Pointer;
+    use base::vector::{SVectBorrowed, SVectOwned, VectorKind, VectorOwned};
+    use rand::rngs::StdRng;
+    use rand::seq::index::sample;
+    use rand::{Rng, SeedableRng};
 
-    let mut last: u32 = 0;
-    offsets.push(0);
-    for doc_scores in token_collection.iter() {
-        if doc_scores.is_empty() {
-            offsets.push(last);
-            continue;
+    const DIMS: u32 = 1000;
+
+    struct Docs(Vec<SVectOwned<f32>>);
+
+    impl Vectors<SVectOwned<f32>> for Docs {
+        fn dims(&self) -> u32 {
+            DIMS
+        }
+        fn len(&self) -> u32 {
+            self.0.len() as u32
+        }
+        fn vector(&self, i: u32) -> SVectBorrowed<'_, f32> {
+            self.0[i as usize].as_borrowed()
+        }
+    }
+
+    impl Collection for Docs {
+        fn payload(&self, i: u32) -> Payload {
+            Payload::new(Pointer::new(i as u64), 0)
+        }
+    }
+
+    fn random(rng: &mut impl Rng, nnz: usize) -> SVectOwned<f32> {
+        let mut indexes = sample(rng, DIMS as usize, nnz)
+            .into_iter()
+            .map(|x| x as u32)
+            .collect::<Vec<_>>();
+        indexes.sort_unstable();
+        let values = (0..nnz).map(|_| rng.gen_range(-0.2..1.0)).collect();
+        SVectOwned::new(DIMS, indexes, values)
+    }
+
+    fn build<O: OperatorSparseInvertedIndex<Vector = SVectOwned<f32>>>(
+        docs: &Docs,
+        d: DistanceKind,
+        quantized_weights: bool,
+    ) -> (SparseInvertedIndex<O>, std::path::PathBuf) {
+        let path =
+            std::env::temp_dir().join(format!("sparse_inverted_index_{}", rand::random::<u64>()));
+        let options = IndexOptions {
+            vector: VectorOptions {
+                dims: DIMS,
+                v: VectorKind::SVecf32,
+                d,
+                weights: None,
+            },
+            indexing: IndexingOptions::SparseInvertedIndex(SparseInvertedIndexIndexingOptions {
+                quantized_weights,
+            }),
+        };
+        (from_nothing(&path, options, docs), path)
+    }
+
+    fn search<O: OperatorSparseInvertedIndex<Vector = SVectOwned<f32>>>(
+        index: &SparseInvertedIndex<O>,
+        query: &SVectOwned<f32>,
+        opts: &SearchOptions,
+    ) -> Vec<(f32, u32)> {
+        index
+            .vbase(query.as_borrowed(), opts, None)
+            .map(|x| (f32::from(x.distance), x.payload.0.pointer().as_u64() as u32))
+            .collect()
+    }
+
+    fn check_top_k<O: OperatorSparseInvertedIndex<Vector = SVectOwned<f32>>>(d: DistanceKind) {
+        let mut rng = rand::thread_rng();
+        let docs = Docs((0..5000).map(|_| random(&mut rng, 30)).collect());
+        let (index, path) = build::<O>(&docs, d, false);
+        let exhaustive = SearchOptions::default();
+        for (nnz, k) in [(100, 10), (100, 100), (3, 1000)] {
+            let query = random(&mut rng, nnz);
+            let exact = search(&index, &query, &exhaustive);
+            let opts = SearchOptions {
+                limit: k as u32,
+                ..Default::default()
+            };
+            let pruned = search(&index, &query, &opts);
+            assert_eq!(pruned.len(), exact.len());
+            for (&(x, _), &(y, _)) in exact.iter().zip(pruned.iter().take(k)) {
+                assert!((x - y).abs() < 1e-4, "{x} != {y}");
+            }
         }
-        for (id, score) in doc_scores {
-            indexes.push(*id);
-            scores.push(*score);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn block_max_wand_test() {
+        check_top_k::<SVectDot<f32>>(DistanceKind::Dot);
+        check_top_k::<SVectL2<f32>>(DistanceKind::L2);
+    }
+
+    #[test]
+    fn quantized_weights_test() {
+        let mut rng = rand::thread_rng();
+        let docs = Docs((0..5000).map(|_| random(&mut rng, 30)).collect());
+        let (index, path) = build::<SVectL2<f32>>(&docs, DistanceKind::L2, true);
+        let opts = SearchOptions {
+            limit: 10,
+            sparse_inverted_index_rerank_size: 100,
+            ..Default::default()
+        };
+        let mut hits = 0;
+        for _ in 0..10 {
+            let query = random(&mut rng, 100);
+            let mut exact = (0..5000)
+                .map(|i| {
+                    let distance = SVectL2::distance(query.as_borrowed(), docs.vector(i));
+                    (f32::from(distance), i)
+                })
+                .collect::<Vec<_>>();
+            exact.sort_by(|x, y| f32::total_cmp(&x.0, &y.0));
+            let mut result = search(&index, &query, &opts);
+            result.truncate(10);
+            for &(distance, i) in result.iter() {
+                let expected = SVectL2::distance(query.as_borrowed(), docs.vector(i));
+                assert_eq!(distance, f32::from(expected));
+            }
+            hits += result
+                .iter()
+                .filter(|&&(_, i)| exact[..10].iter().any(|&(_, j)| i == j))
+                .count();
         }
-        last += doc_scores.len() as u32;
-        offsets.push(last);
+        assert!(hits >= 90, "recall: {hits}%");
+        std::fs::remove_dir_all(path).unwrap();
     }
 
-    (indexes, offsets, scores)
+    #[test]
+    fn fallback_test() {
+        let mut rng = StdRng::seed_from_u64(17);
+        let docs = Docs((0..5000).map(|_| random(&mut rng, 30)).collect());
+        let query = random(&mut rng, 100);
+        let mut exact = (0..5000)
+            .map(|i| {
+                let distance = SVectL2::distance(query.as_borrowed(), docs.vector(i));
+                (f32::from(distance), i)
+            })
+            .collect::<Vec<_>>();
+        exact.sort_by(|x, y| f32::total_cmp(&x.0, &y.0));
+        // the rows of the first 10 documents are deleted
+        let deleted = exact[..10].iter().map(|&(_, i)| i).collect::<Vec<_>>();
+        let opts = SearchOptions {
+            limit: 10,
+            sparse_inverted_index_rerank_size: 100,
+            ..Default::default()
+        };
+        for quantized_weights in [false, true] {
+            let (index, path) = build::<SVectL2<f32>>(&docs, DistanceKind::L2, quantized_weights);
+            let result = index
+                .vbase(query.as_borrowed(), &opts, None)
+                .map(|x| (f32::from(x.distance), x.payload.0.pointer().as_u64() as u32))
+                .filter(|(_, i)| !deleted.contains(i))
+                .take(10)
+                .collect::<Vec<_>>();
+            assert_eq!(result.len(), 10);
+            if !quantized_weights {
+                for (&(x, _), &(y, _)) in exact[10..20].iter().zip(result.iter()) {
+                    assert!((x - y).abs() < 1e-4, "{x} != {y}");
+                }
+            }
+            let all = search(&index, &query, &opts);
+            let mut pointers = all.iter().map(|&(_, i)| i).collect::<Vec<_>>();
+            pointers.sort_unstable();
+            pointers.dedup();
+            assert_eq!(pointers.len(), docs.0.len());
+            std::fs::remove_dir_all(path).unwrap();
+        }
+    }
 }
@@ -1,10 +1,17 @@
+use base::distance::Distance;
 use base::operator::*;
 use base::scalar::ScalarLike;
+use base::vector::VectorBorrowed;
 use std::iter::{zip, Empty};
 use storage::OperatorStorage;
 
 pub trait OperatorSparseInvertedIndex: OperatorStorage {
+    /// Whether the score of a document includes `-|x|^2 / 2` besides the dot product.
+    const NORM: bool = false;
     fn to_index_vec(vec: Borrowed<'_, Self>) -> impl Iterator<Item = (u32, f32)> + '_;
+    fn to_distance(_: Borrowed<'_, Self>, score: f32) -> Distance {
+        Distance::from(-score)
+    }
 }
 
 impl<S: ScalarLike> OperatorSparseInvertedIndex for SVectDot<S> {
@@ -16,14 +23,48 @@ impl<S: ScalarLike> OperatorSparseInvertedIndex for SVectDot<S> {
     }
 }
 
+impl<S: ScalarLike> OperatorSparseInvertedIndex for SVectCos<S> {
+    fn to_index_vec(vector: Borrowed<'_, Self>) -> impl Iterator<Item = (u32, f32)> + '_ {
+        let norm = vector.norm();
+        zip(
+            vector.indexes().iter().copied(),
+            vector.values().iter().map(move |x| x.to_f32() / norm),
+        )
+    }
+    fn to_distance(_: Borrowed<'_, Self>, score: f32) -> Distance {
+        Distance::from(1.0 - score)
+    }
+}
+
 impl<S: ScalarLike> OperatorSparseInvertedIndex for SVectL2<S> {
+    const NORM: bool = true;
+    fn to_index_vec(vector: Borrowed<'_, Self>) -> impl Iterator<Item = (u32, f32)> + '_ {
+        zip(
+            vector.indexes().iter().copied(),
+            vector.values().iter().copied().map(S::to_f32),
+        )
+    }
+    fn to_distance(vector: Borrowed<'_, Self>, score: f32) -> Distance {
+        let norm = vector.norm();
+        Distance::from(norm * norm - 2.0 * score)
+    }
+}
+
+impl<S: ScalarLike> OperatorSparseInvertedIndex for VectDot<S> {
     fn to_index_vec(_: Borrowed<'_, Self>) -> impl Iterator<Item = (u32, f32)> + '_ {
         #![allow(unreachable_code)]
         unimplemented!() as Empty<(u32, f32)>
     }
 }
 
-impl<S: ScalarLike> OperatorSparseInvertedIndex for VectDot<S> {
+impl<S: ScalarLike> OperatorSparseInvertedIndex for VectCos<S> {
+    fn to_index_vec(_: Borrowed<'_, Self>) -> impl Iterator<Item = (u32, f32)> + '_ {
+        #![allow(unreachable_code)]
+        unimplemented!() as Empty<(u32, f32)>
+    }
+}
+
+impl<S: ScalarLike> OperatorSparseInvertedIndex for VectL1<S> {
     fn to_index_vec(_: Borrowed<'_, Self>) -> impl Iterator<Item = (u32, f32)> + '_ {
         #![allow(unreachable_code)]
         unimplemented!() as Empty<(u32, f32)>
@@ -51,3 +92,7 @@ macro_rules! unimpl_operator_inverted_index {
 unimpl_operator_inverted_index!(BVectorDot);
 unimpl_operator_inverted_index!(BVectorJaccard);
 unimpl_operator_inverted_index!(BVectorHamming);
+
+unimpl_operator_inverted_index!(Veci8Cos);
+unimpl_operator_inverted_index!(Veci8Dot);
+unimpl_operator_inverted_index!(Veci8L2);
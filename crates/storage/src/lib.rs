@@ -1,6 +1,7 @@
 mod bvector;
 mod svec;
 mod vec;
+mod veci8;
 
 use base::operator::*;
 use base::scalar::ScalarLike;
@@ -8,6 +9,9 @@ use base::search::*;
 use base::vector::VectorOwned;
 use std::path::Path;
 
+/// Version of the on-disk layout of storages, bumped whenever it changes.
+pub const LAYOUT: u32 = 1;
+
 pub trait Storage<V: VectorOwned>: Vectors<V> {
     fn open(path: impl AsRef<Path>) -> Self;
     fn create(path: impl AsRef<Path>, vectors: &impl Vectors<V>) -> Self;
@@ -21,6 +25,10 @@ impl<S: ScalarLike> OperatorStorage for SVectDot<S> {
     type Storage = svec::SVecStorage<S>;
 }
 
+impl<S: ScalarLike> OperatorStorage for SVectCos<S> {
+    type Storage = svec::SVecStorage<S>;
+}
+
 impl<S: ScalarLike> OperatorStorage for SVectL2<S> {
     type Storage = svec::SVecStorage<S>;
 }
@@ -29,6 +37,14 @@ impl<S: ScalarLike> OperatorStorage for VectDot<S> {
     type Storage = vec::VecStorage<S>;
 }
 
+impl<S: ScalarLike> OperatorStorage for VectCos<S> {
+    type Storage = vec::VecStorage<S>;
+}
+
+impl<S: ScalarLike> OperatorStorage for VectL1<S> {
+    type Storage = vec::VecStorage<S>;
+}
+
 impl<S: ScalarLike> OperatorStorage for VectL2<S> {
     type Storage = vec::VecStorage<S>;
 }
@@ -44,3 +60,15 @@ impl OperatorStorage for BVectorHamming {
 impl OperatorStorage for BVectorJaccard {
     type Storage = bvector::BVectorStorage;
 }
+
+impl OperatorStorage for Veci8Dot {
+    type Storage = veci8::Veci8Storage;
+}
+
+impl OperatorStorage for Veci8Cos {
+    type Storage = veci8::Veci8Storage;
+}
+
+impl OperatorStorage for Veci8L2 {
+    type Storage = veci8::Veci8Storage;
+}
@@ -0,0 +1,69 @@
+use crate::Storage;
+use base::search::*;
+use base::vector::*;
+use common::json::Json;
+use common::mmap_array::MmapArray;
+use std::path::Path;
+
+pub struct Veci8Storage {
+    dims: Json<u32>,
+    len: Json<u32>,
+    slice: MmapArray<i8>,
+    // alpha, offset, sum and l2 norm of each vector
+    metadata: MmapArray<f32>,
+}
+
+impl Vectors<Veci8Owned> for Veci8Storage {
+    fn dims(&self) -> u32 {
+        *self.dims
+    }
+
+    fn len(&self) -> u32 {
+        *self.len
+    }
+
+    fn vector(&self, i: u32) -> Veci8Borrowed<'_> {
+        let s = i as usize * *self.dims as usize;
+        let e = (i + 1) as usize * *self.dims as usize;
+        let m = &self.metadata[i as usize * 4..(i + 1) as usize * 4];
+        unsafe { Veci8Borrowed::new_unchecked(&self.slice[s..e], m[0], m[1], m[2], m[3]) }
+    }
+}
+
+impl Storage<Veci8Owned> for Veci8Storage {
+    fn create(path: impl AsRef<Path>, vectors: &impl Vectors<Veci8Owned>) -> Self {
+        std::fs::create_dir(path.as_ref()).unwrap();
+        let dims = Json::create(path.as_ref().join("dims"), vectors.dims());
+        let len = Json::create(path.as_ref().join("len"), vectors.len());
+        let slice = MmapArray::create(
+            path.as_ref().join("slice"),
+            (0..*len).flat_map(|i| vectors.vector(i).data().iter().copied()),
+        );
+        let metadata = MmapArray::create(
+            path.as_ref().join("metadata"),
+            (0..*len).flat_map(|i| {
+                let v = vectors.vector(i);
+                [v.alpha(), v.offset(), v.sum(), v.l2_norm()]
+            }),
+        );
+        Self {
+            dims,
+            len,
+            slice,
+            metadata,
+        }
+    }
+
+    fn open(path: impl AsRef<Path>) -> Self {
+        let dims = Json::open(path.as_ref().join("dims"));
+        let len = Json::open(path.as_ref().join("len"));
+        let slice = MmapArray::open(path.as_ref().join("slice"));
+        let metadata = MmapArray::open(path.as_ref().join("metadata"));
+        Self {
+            dims,
+            len,
+            slice,
+            metadata,
+        }
+    }
+}
@@ -0,0 +1,256 @@
+#![allow(clippy::len_without_is_empty)]
+
+use base::always_equal::AlwaysEqual;
+use base::distance::Distance;
+use base::index::*;
+use base::operator::*;
+use base::search::*;
+use base::vector::VectorBorrowed;
+use base::vector::VectorOwned;
+use common::json::Json;
+use common::mmap_array::MmapArray;
+use common::remap::RemappedCollection;
+use graph::visited::VisitedPool;
+use parking_lot::RwLock;
+use quantization::quantizer::Quantizer;
+use quantization::Quantization;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::fs::create_dir;
+use std::path::Path;
+use stoppable_rayon as rayon;
+use storage::OperatorStorage;
+use storage::Storage;
+
+pub trait OperatorVamana: OperatorStorage {}
+
+impl<T: OperatorStorage> OperatorVamana for T {}
+
+pub struct Vamana<O: OperatorVamana, Q: Quantizer<O>> {
+    storage: O::Storage,
+    quantization: Quantization<O, Q>,
+    payloads: MmapArray<Payload>,
+    graph_outs: MmapArray<u32>,
+    degree: Json<u32>,
+    s: Json<Option<u32>>,
+    visited: VisitedPool,
+}
+
+impl<O: OperatorVamana, Q: Quantizer<O>> Vamana<O, Q> {
+    pub fn create(
+        path: impl AsRef<Path>,
+        options: IndexOptions,
+        source: &(impl Vectors<O::Vector> + Collection + Source + Sync),
+    ) -> Self {
+        let remapped = RemappedCollection::from_source(source);
+        from_nothing(path, options, &remapped)
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Self {
+        open(path)
+    }
+
+    pub fn vbase<'a>(
+        &'a self,
+        vector: Borrowed<'a, O>,
+        opts: &'a SearchOptions,
+        filter: Option<&'a AllowList>,
+    ) -> Box<dyn Iterator<Item = Element> + 'a> {
+        let Some(s) = *self.s else {
+            return Box::new(std::iter::empty());
+        };
+        let projected_vector = self.quantization.project(vector);
+        let lut = self.quantization.preprocess(projected_vector.as_borrowed());
+        let reranker = self.quantization.graph_rerank(lut, move |u| {
+            (
+                O::distance(self.storage.vector(u), vector),
+                (outs(self, u), ()),
+            )
+        });
+        Box::new(
+            graph::search::vbase_generic(
+                &self.visited,
+                s,
+                reranker,
+                move |u| filter.map_or(true, |f| f.check(self.payload(u))),
+                opts.vamana_search_list_size,
+                Distance::from_f32(opts.radius),
+            )
+            .map(|(dis_u, u, ())| Element {
+                distance: dis_u,
+                payload: AlwaysEqual(self.payload(u)),
+            }),
+        )
+    }
+
+    pub fn dims(&self) -> u32 {
+        self.storage.dims()
+    }
+
+    pub fn len(&self) -> u32 {
+        self.storage.len()
+    }
+
+    pub fn vector(&self, i: u32) -> Borrowed<'_, O> {
+        self.storage.vector(i)
+    }
+
+    pub fn payload(&self, i: u32) -> Payload {
+        self.payloads[i as usize]
+    }
+}
+
+fn from_nothing<O: OperatorVamana, Q: Quantizer<O>>(
+    path: impl AsRef<Path>,
+    options: IndexOptions,
+    collection: &(impl Vectors<O::Vector> + Collection + Sync),
+) -> Vamana<O, Q> {
+    create_dir(path.as_ref()).unwrap();
+    let VamanaIndexingOptions {
+        degree,
+        build_search_list_size,
+        alpha,
+        quantization: quantization_options,
+    } = options.indexing.clone().unwrap_vamana();
+    let dist = |u: u32, v: u32| O::distance(collection.vector(u), collection.vector(v));
+    let n = collection.len();
+    let s = medoid(dist, n);
+    let mut g = (0..n).map(|_| RwLock::new(Vec::new())).collect::<Vec<_>>();
+    if let Some(s) = s {
+        // the first pass builds a sparse graph, the second one adds long-range edges
+        for alpha in [1.0, alpha] {
+            patch_insertions(dist, n, s, build_search_list_size, alpha, degree, &g);
+            rayon::check();
+        }
+    }
+    finish(&mut g, degree);
+    let storage = O::Storage::create(path.as_ref().join("storage"), collection);
+    rayon::check();
+    let quantization = Quantization::<O, Q>::create(
+        path.as_ref().join("quantization"),
+        options.vector,
+        quantization_options,
+        collection,
+        |vector| vector.own(),
+    );
+    rayon::check();
+    let payloads = MmapArray::create(
+        path.as_ref().join("payloads"),
+        (0..n).map(|i| collection.payload(i)),
+    );
+    rayon::check();
+    let graph_outs = MmapArray::create(
+        path.as_ref().join("graph_outs"),
+        g.iter_mut()
+            .flat_map(|u| u.get_mut())
+            .map(|&mut (_0, _1)| _1),
+    );
+    rayon::check();
+    let degree = Json::create(path.as_ref().join("degree"), degree);
+    let s = Json::create(path.as_ref().join("s"), s);
+    Vamana {
+        storage,
+        quantization,
+        payloads,
+        graph_outs,
+        degree,
+        s,
+        visited: VisitedPool::new(n),
+    }
+}
+
+fn open<O: OperatorVamana, Q: Quantizer<O>>(path: impl AsRef<Path>) -> Vamana<O, Q> {
+    let storage = O::Storage::open(path.as_ref().join("storage"));
+    let quantization = Quantization::open(path.as_ref().join("quantization"));
+    let payloads = MmapArray::open(path.as_ref().join("payloads"));
+    let graph_outs = MmapArray::open(path.as_ref().join("graph_outs"));
+    let degree = Json::open(path.as_ref().join("degree"));
+    let s = Json::open(path.as_ref().join("s"));
+    let n = storage.len();
+    Vamana {
+        storage,
+        quantization,
+        payloads,
+        graph_outs,
+        degree,
+        s,
+        visited: VisitedPool::new(n),
+    }
+}
+
+fn medoid(dist: impl Fn(u32, u32) -> Distance + Sync, n: u32) -> Option<u32> {
+    const SAMPLES: u32 = 1024;
+    if n == 0 {
+        return None;
+    }
+    let step = n.div_ceil(SAMPLES);
+    let samples = (0..n).step_by(step as usize).collect::<Vec<_>>();
+    samples
+        .clone()
+        .into_par_iter()
+        .map(|u| {
+            let sum = samples
+                .iter()
+                .map(|&v| f32::from(dist(u, v)) as f64)
+                .sum::<f64>();
+            (sum, u)
+        })
+        .min_by(|(x, _), (y, _)| x.total_cmp(y))
+        .map(|(_, u)| u)
+}
+
+fn patch_insertions(
+    dist: impl Fn(u32, u32) -> Distance + Copy + Sync,
+    n: u32,
+    s: u32,
+    search_list_size: u32,
+    alpha: f32,
+    degree: u32,
+    g: &[RwLock<Vec<(Distance, u32)>>],
+) {
+    let visited = VisitedPool::new(n);
+    (0..n).into_par_iter().for_each(|u| {
+        rayon::check();
+        let mut visited = visited.fetch_guard();
+        let (_, trace) = graph::search::search_returning_trace(
+            |v| dist(u, v),
+            |v| g[v as usize].read().clone().into_iter().map(|(_, x)| x),
+            &mut visited,
+            s,
+            search_list_size,
+        );
+        let mut edges = g[u as usize].read().clone();
+        graph::prune::robust_prune(dist, u, &mut edges, &trace, alpha, degree);
+        g[u as usize].write().clone_from(&edges);
+        for (dis_v, v) in edges {
+            let mut lock = g[v as usize].write();
+            if lock.iter().any(|(_, k)| *k == u) {
+                continue;
+            }
+            if lock.len() < degree as usize {
+                let (Ok(index) | Err(index)) = lock.binary_search(&(dis_v, u));
+                lock.insert(index, (dis_v, u));
+                continue;
+            }
+            graph::prune::robust_prune(dist, v, &mut lock, &[(dis_v, u)], alpha, degree);
+        }
+    });
+}
+
+fn finish(g: &mut [RwLock<Vec<(Distance, u32)>>], degree: u32) {
+    for u in g.iter_mut() {
+        u.get_mut()
+            .resize(degree as usize, (Distance::INFINITY, u32::MAX));
+    }
+}
+
+fn outs<O: OperatorVamana, Q: Quantizer<O>>(
+    vamana: &Vamana<O, Q>,
+    u: u32,
+) -> impl Iterator<Item = u32> + '_ {
+    let degree = *vamana.degree;
+    let offset = degree as usize * u as usize;
+    vamana.graph_outs[offset..offset + degree as usize]
+        .iter()
+        .take_while(|v| **v != u32::MAX)
+        .copied()
+}
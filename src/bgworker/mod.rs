@@ -73,6 +73,7 @@ extern "C" fn _vectors_main(_arg: pgrx::pg_sys::Datum) {
     let path = Path::new("pg_vectors");
     if path.try_exists().unwrap() {
         let worker = Worker::open(path.to_owned());
+        Version::write(path.join("VERSION"));
         normal::normal(worker);
     } else {
         let worker = Worker::create(path.to_owned());
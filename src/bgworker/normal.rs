@@ -37,6 +37,13 @@ pub fn normal(worker: Arc<Worker>) {
                 }
             }
         });
+        scope.spawn({
+            let worker = worker.clone();
+            move || loop {
+                worker.promote();
+                std::thread::sleep(std::time::Duration::from_secs(1));
+            }
+        });
         loop {
             let mut sig: i32 = 0;
             unsafe {
@@ -93,12 +100,53 @@ fn session(worker: Arc<Worker>, handler: ServerRpcHandler) -> Result<Infallible,
             } => {
                 handler = x.leave(worker.insert(handle, vector, pointer))?;
             }
+            ServerRpcHandle::InsertMany { handle, vectors, x } => {
+                handler = x.leave(worker.insert_many(handle, vectors))?;
+            }
+            ServerRpcHandle::Build { handle, vectors, x } => {
+                handler = x.leave(worker.build(handle, vectors))?;
+            }
             ServerRpcHandle::Delete { handle, pointer, x } => {
                 handler = x.leave(worker.delete(handle, pointer))?;
             }
+            ServerRpcHandle::Replay {
+                handle,
+                expected,
+                records,
+                next,
+                x,
+            } => {
+                handler = x.leave(worker.replay(handle, expected, records, next))?;
+            }
+            ServerRpcHandle::Snapshot { handle, dest, x } => {
+                handler = x.leave(worker.snapshot(handle, dest))?;
+            }
+            ServerRpcHandle::Restore { handle, source, x } => {
+                handler = x.leave(worker.restore(handle, source))?;
+            }
+            ServerRpcHandle::Attach {
+                handle,
+                source,
+                labels,
+                x,
+            } => {
+                let labels = std::fs::read(labels)
+                    .map_err(|e| e.to_string())
+                    .and_then(|x| bincode::deserialize(&x).map_err(|e| e.to_string()));
+                handler = x.leave(match labels {
+                    Ok(labels) => worker.attach(handle, source, labels),
+                    Err(reason) => Err(base::index::AttachError::Read { reason }),
+                })?;
+            }
+            ServerRpcHandle::Upgrade { handle, options, x } => {
+                handler = x.leave(worker.upgrade(handle, options))?;
+            }
             ServerRpcHandle::Stat { handle, x } => {
                 handler = x.leave(worker.stat(handle))?;
             }
+            ServerRpcHandle::Verify { handle, x } => {
+                handler = x.leave(worker.verify(handle))?;
+            }
             ServerRpcHandle::Alter {
                 handle,
                 key,
@@ -111,6 +159,7 @@ fn session(worker: Arc<Worker>, handler: ServerRpcHandler) -> Result<Infallible,
                 handle,
                 vector,
                 opts,
+                filter,
                 x,
             } => {
                 let v = match worker.view_vbase(handle) {
@@ -120,14 +169,16 @@ fn session(worker: Arc<Worker>, handler: ServerRpcHandler) -> Result<Infallible,
                         continue;
                     }
                 };
-                match v.vbase(&vector, &opts) {
+                let vector = v.preprocess(vector);
+                match v.vbase(&vector, &opts, filter.as_ref()) {
                     Ok(mut iter) => {
                         use crate::ipc::ServerVbaseHandle;
                         let mut x = x.error_ok()?;
                         loop {
                             match x.handle()? {
                                 ServerVbaseHandle::Next { x: y } => {
-                                    x = y.leave(iter.next())?;
+                                    let size = y.size();
+                                    x = y.leave(iter.by_ref().take(size).collect())?;
                                 }
                                 ServerVbaseHandle::Leave { x } => {
                                     handler = x;
@@ -139,6 +190,44 @@ fn session(worker: Arc<Worker>, handler: ServerRpcHandler) -> Result<Infallible,
                     Err(e) => handler = x.error_err(e)?,
                 };
             }
+            ServerRpcHandle::VbaseMulti {
+                handle,
+                vectors,
+                opts,
+                filter,
+                x,
+            } => {
+                let v = match worker.view_vbase(handle) {
+                    Ok(x) => x,
+                    Err(e) => {
+                        handler = x.error_err(e)?;
+                        continue;
+                    }
+                };
+                let vectors = vectors
+                    .into_iter()
+                    .map(|vector| v.preprocess(vector))
+                    .collect::<Vec<_>>();
+                match v.vbase_multi(&vectors, &opts, filter.as_ref()) {
+                    Ok(mut iter) => {
+                        use crate::ipc::ServerVbaseMultiHandle;
+                        let mut x = x.error_ok()?;
+                        loop {
+                            match x.handle()? {
+                                ServerVbaseMultiHandle::Next { x: y } => {
+                                    let size = y.size();
+                                    x = y.leave(iter.by_ref().take(size).collect())?;
+                                }
+                                ServerVbaseMultiHandle::Leave { x } => {
+                                    handler = x;
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => handler = x.error_err(e)?,
+                };
+            }
             ServerRpcHandle::List { handle, x } => {
                 let v = match worker.view_list(handle) {
                     Ok(x) => x,
@@ -154,7 +243,8 @@ fn session(worker: Arc<Worker>, handler: ServerRpcHandler) -> Result<Infallible,
                         loop {
                             match x.handle()? {
                                 ServerListHandle::Next { x: y } => {
-                                    x = y.leave(iter.next())?;
+                                    let size = y.size();
+                                    x = y.leave(iter.by_ref().take(size).collect())?;
                                 }
                                 ServerListHandle::Leave { x } => {
                                     handler = x;
@@ -0,0 +1,52 @@
+use super::binary::Bytea;
+use super::memory_veci8::{Veci8Input, Veci8Output};
+use base::vector::{Veci8Borrowed, VectorBorrowed};
+use pgrx::datum::Internal;
+use pgrx::datum::IntoDatum;
+use pgrx::pg_sys::Oid;
+use std::ffi::c_char;
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_send(vector: Veci8Input<'_>) -> Bytea {
+    use pgrx::pg_sys::StringInfoData;
+    unsafe {
+        let mut buf = StringInfoData::default();
+        let vector = vector.as_borrowed();
+        let dims = vector.dims();
+        let internal_dims = dims as u16;
+        let alpha = vector.alpha();
+        let offset = vector.offset();
+        let b_slice = size_of::<i8>() * dims as usize;
+        pgrx::pg_sys::pq_begintypsend(&mut buf);
+        pgrx::pg_sys::pq_sendbytes(&mut buf, (&internal_dims) as *const u16 as _, 2);
+        pgrx::pg_sys::pq_sendbytes(&mut buf, (&alpha) as *const f32 as _, 4);
+        pgrx::pg_sys::pq_sendbytes(&mut buf, (&offset) as *const f32 as _, 4);
+        pgrx::pg_sys::pq_sendbytes(&mut buf, vector.data().as_ptr() as _, b_slice as _);
+        Bytea::new(pgrx::pg_sys::pq_endtypsend(&mut buf))
+    }
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_recv(internal: Internal, oid: Oid, typmod: i32) -> Veci8Output {
+    let _ = (oid, typmod);
+    use pgrx::pg_sys::StringInfo;
+    unsafe {
+        let buf: StringInfo = internal.into_datum().unwrap().cast_mut_ptr();
+        let internal_dims = (pgrx::pg_sys::pq_getmsgbytes(buf, 2) as *const u16).read_unaligned();
+        let dims = internal_dims as u32;
+        let alpha = (pgrx::pg_sys::pq_getmsgbytes(buf, 4) as *const f32).read_unaligned();
+        let offset = (pgrx::pg_sys::pq_getmsgbytes(buf, 4) as *const f32).read_unaligned();
+
+        let b_slice = size_of::<i8>() * dims as usize;
+        let p_slice = pgrx::pg_sys::pq_getmsgbytes(buf, b_slice as _);
+        let mut slice = Vec::<i8>::with_capacity(dims as usize);
+        std::ptr::copy(p_slice, slice.as_mut_ptr().cast::<c_char>(), b_slice);
+        slice.set_len(dims as usize);
+
+        if let Some(x) = Veci8Borrowed::new_checked(&slice, alpha, offset) {
+            Veci8Output::new(x)
+        } else {
+            pgrx::error!("detect data corruption");
+        }
+    }
+}
@@ -1,11 +1,13 @@
 use crate::datatype::memory_bvector::{BVectorInput, BVectorOutput};
 use crate::datatype::memory_svecf32::{SVecf32Input, SVecf32Output};
+use crate::datatype::memory_vecbf16::{Vecbf16Input, Vecbf16Output};
 use crate::datatype::memory_vecf16::{Vecf16Input, Vecf16Output};
 use crate::datatype::memory_vecf32::{Vecf32Input, Vecf32Output};
+use crate::datatype::memory_veci8::{Veci8Input, Veci8Output};
 use crate::error::*;
 use base::scalar::*;
 use base::vector::*;
-use half::f16;
+use half::{bf16, f16};
 
 #[pgrx::pg_extern(immutable, strict, parallel_safe)]
 fn _vectors_cast_array_to_vecf32(
@@ -52,6 +54,54 @@ fn _vectors_cast_vecf16_to_vecf32(
     Vecf32Output::new(VectBorrowed::new(&slice))
 }
 
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_cast_vecf32_to_vecbf16(
+    vector: Vecf32Input<'_>,
+    _typmod: i32,
+    _explicit: bool,
+) -> Vecbf16Output {
+    let slice: Vec<bf16> = bf16::vector_from_f32(vector.slice());
+    Vecbf16Output::new(VectBorrowed::new(&slice))
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_cast_vecbf16_to_vecf32(
+    vector: Vecbf16Input<'_>,
+    _typmod: i32,
+    _explicit: bool,
+) -> Vecf32Output {
+    let slice: Vec<f32> = bf16::vector_to_f32(vector.slice());
+    Vecf32Output::new(VectBorrowed::new(&slice))
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_cast_vecf16_to_vecbf16(
+    vector: Vecf16Input<'_>,
+    _typmod: i32,
+    _explicit: bool,
+) -> Vecbf16Output {
+    let slice: Vec<bf16> = vector
+        .slice()
+        .iter()
+        .map(|x| bf16::from_f32(x.to_f32()))
+        .collect();
+    Vecbf16Output::new(VectBorrowed::new(&slice))
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_cast_vecbf16_to_vecf16(
+    vector: Vecbf16Input<'_>,
+    _typmod: i32,
+    _explicit: bool,
+) -> Vecf16Output {
+    let slice: Vec<f16> = vector
+        .slice()
+        .iter()
+        .map(|x| f16::from_f32(x.to_f32()))
+        .collect();
+    Vecf16Output::new(VectBorrowed::new(&slice))
+}
+
 #[pgrx::pg_extern(immutable, strict, parallel_safe)]
 fn _vectors_cast_vecf32_to_svecf32(
     vector: Vecf32Input<'_>,
@@ -118,3 +168,25 @@ fn _vectors_cast_bvector_to_vecf32(
         .collect();
     Vecf32Output::new(VectBorrowed::new(&data))
 }
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_cast_vecf32_to_veci8(
+    vector: Vecf32Input<'_>,
+    _typmod: i32,
+    _explicit: bool,
+) -> Veci8Output {
+    if vector.slice().iter().any(|x| !x.is_finite()) {
+        pgrx::error!("veci8 does not support infinite or NaN values");
+    }
+    Veci8Output::new(Veci8Owned::from_f32(vector.slice()).as_borrowed())
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_cast_veci8_to_vecf32(
+    vector: Veci8Input<'_>,
+    _typmod: i32,
+    _explicit: bool,
+) -> Vecf32Output {
+    let slice = vector.as_borrowed().to_f32();
+    Vecf32Output::new(VectBorrowed::new(&slice))
+}
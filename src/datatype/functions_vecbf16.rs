@@ -0,0 +1,18 @@
+use crate::datatype::memory_vecbf16::*;
+use base::scalar::*;
+use base::vector::*;
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_dims(vector: Vecbf16Input<'_>) -> i32 {
+    vector.as_borrowed().dims() as i32
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_norm(vector: Vecbf16Input<'_>) -> f32 {
+    vector.as_borrowed().norm().to_f32()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_normalize(vector: Vecbf16Input<'_>) -> Vecbf16Output {
+    Vecbf16Output::new(vector.as_borrowed().function_normalize().as_borrowed())
+}
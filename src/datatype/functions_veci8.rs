@@ -0,0 +1,43 @@
+use crate::datatype::memory_veci8::*;
+use crate::error::*;
+use base::vector::*;
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_dims(vector: Veci8Input<'_>) -> i32 {
+    vector.as_borrowed().dims() as i32
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_norm(vector: Veci8Input<'_>) -> f32 {
+    vector.as_borrowed().norm()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_normalize(vector: Veci8Input<'_>) -> Veci8Output {
+    Veci8Output::new(vector.as_borrowed().function_normalize().as_borrowed())
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_to_veci8(
+    len: i32,
+    alpha: f32,
+    offset: f32,
+    values: pgrx::datum::Array<i32>,
+) -> Veci8Output {
+    let dims = u32::try_from(len).expect("dimensions must be non-negative");
+    check_value_dims_65535(dims);
+    if values.len() != dims as usize {
+        bad_literal("length of values does not match dimensions");
+    }
+    let mut data = Vec::with_capacity(dims as usize);
+    for x in values.iter() {
+        match x.map(i8::try_from) {
+            Some(Ok(x)) => data.push(x),
+            _ => bad_literal("values must be integers in range [-128, 127]"),
+        }
+    }
+    match Veci8Borrowed::new_checked(&data, alpha, offset) {
+        Some(vector) => Veci8Output::new(vector),
+        None => bad_literal("alpha and offset must be finite"),
+    }
+}
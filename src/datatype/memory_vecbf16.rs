@@ -0,0 +1,223 @@
+use base::vector::*;
+use half::bf16;
+use pgrx::datum::FromDatum;
+use pgrx::datum::IntoDatum;
+use pgrx::pg_sys::Datum;
+use pgrx::pg_sys::Oid;
+use pgrx::pgrx_sql_entity_graph::metadata::ArgumentError;
+use pgrx::pgrx_sql_entity_graph::metadata::Returns;
+use pgrx::pgrx_sql_entity_graph::metadata::ReturnsError;
+use pgrx::pgrx_sql_entity_graph::metadata::SqlMapping;
+use pgrx::pgrx_sql_entity_graph::metadata::SqlTranslatable;
+use std::alloc::Layout;
+use std::ops::Deref;
+use std::ptr::NonNull;
+
+pub const HEADER_MAGIC: u16 = 5;
+
+#[repr(C, align(8))]
+pub struct Vecbf16Header {
+    varlena: u32,
+    dims: u16,
+    magic: u16,
+    phantom: [bf16; 0],
+}
+
+impl Vecbf16Header {
+    fn varlena(size: usize) -> u32 {
+        (size << 2) as u32
+    }
+    fn layout(len: usize) -> Layout {
+        u16::try_from(len).expect("Vector is too large.");
+        let layout_alpha = Layout::new::<Vecbf16Header>();
+        let layout_beta = Layout::array::<bf16>(len).unwrap();
+        let layout = layout_alpha.extend(layout_beta).unwrap().0;
+        layout.pad_to_align()
+    }
+    pub fn dims(&self) -> u32 {
+        self.dims as u32
+    }
+    pub fn slice(&self) -> &[bf16] {
+        unsafe { std::slice::from_raw_parts(self.phantom.as_ptr(), self.dims as usize) }
+    }
+    pub fn as_borrowed(&self) -> VectBorrowed<'_, bf16> {
+        unsafe { VectBorrowed::new_unchecked(self.slice()) }
+    }
+}
+
+impl Deref for Vecbf16Header {
+    type Target = [bf16];
+
+    fn deref(&self) -> &Self::Target {
+        self.slice()
+    }
+}
+
+pub enum Vecbf16Input<'a> {
+    Owned(Vecbf16Output),
+    Borrowed(&'a Vecbf16Header),
+}
+
+impl<'a> Vecbf16Input<'a> {
+    unsafe fn new(p: NonNull<Vecbf16Header>) -> Self {
+        let q = unsafe {
+            NonNull::new(pgrx::pg_sys::pg_detoast_datum(p.cast().as_ptr()).cast()).unwrap()
+        };
+        if p != q {
+            Vecbf16Input::Owned(Vecbf16Output(q))
+        } else {
+            unsafe { Vecbf16Input::Borrowed(p.as_ref()) }
+        }
+    }
+}
+
+impl Deref for Vecbf16Input<'_> {
+    type Target = Vecbf16Header;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Vecbf16Input::Owned(x) => x,
+            Vecbf16Input::Borrowed(x) => x,
+        }
+    }
+}
+
+pub struct Vecbf16Output(NonNull<Vecbf16Header>);
+
+impl Vecbf16Output {
+    pub fn new(vector: VectBorrowed<'_, bf16>) -> Vecbf16Output {
+        unsafe {
+            let slice = vector.slice();
+            let layout = Vecbf16Header::layout(slice.len());
+            let dims = vector.dims();
+            let internal_dims = dims as u16;
+            let ptr = pgrx::pg_sys::palloc(layout.size()) as *mut Vecbf16Header;
+            ptr.cast::<u8>().add(layout.size() - 8).write_bytes(0, 8);
+            (&raw mut (*ptr).varlena).write(Vecbf16Header::varlena(layout.size()));
+            (&raw mut (*ptr).magic).write(HEADER_MAGIC);
+            (&raw mut (*ptr).dims).write(internal_dims);
+            std::ptr::copy_nonoverlapping(slice.as_ptr(), (*ptr).phantom.as_mut_ptr(), slice.len());
+            Vecbf16Output(NonNull::new(ptr).unwrap())
+        }
+    }
+    pub fn into_raw(self) -> *mut Vecbf16Header {
+        let result = self.0.as_ptr();
+        std::mem::forget(self);
+        result
+    }
+}
+
+impl Deref for Vecbf16Output {
+    type Target = Vecbf16Header;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.0.as_ref() }
+    }
+}
+
+impl Drop for Vecbf16Output {
+    fn drop(&mut self) {
+        unsafe {
+            pgrx::pg_sys::pfree(self.0.as_ptr() as _);
+        }
+    }
+}
+
+impl<'a> FromDatum for Vecbf16Input<'a> {
+    unsafe fn from_polymorphic_datum(datum: Datum, is_null: bool, _typoid: Oid) -> Option<Self> {
+        if is_null {
+            None
+        } else {
+            let ptr = NonNull::new(datum.cast_mut_ptr::<Vecbf16Header>()).unwrap();
+            unsafe { Some(Vecbf16Input::new(ptr)) }
+        }
+    }
+}
+
+impl IntoDatum for Vecbf16Output {
+    fn into_datum(self) -> Option<Datum> {
+        Some(Datum::from(self.into_raw() as *mut ()))
+    }
+
+    fn type_oid() -> Oid {
+        let namespace =
+            pgrx::pg_catalog::PgNamespace::search_namespacename(crate::SCHEMA_C_STR).unwrap();
+        let namespace = namespace.get().expect("pgvecto.rs is not installed.");
+        let t = pgrx::pg_catalog::PgType::search_typenamensp(c"vecbf16", namespace.oid()).unwrap();
+        let t = t.get().expect("pg_catalog is broken.");
+        t.oid()
+    }
+}
+
+impl FromDatum for Vecbf16Output {
+    unsafe fn from_polymorphic_datum(datum: Datum, is_null: bool, _typoid: Oid) -> Option<Self> {
+        if is_null {
+            None
+        } else {
+            let p = NonNull::new(datum.cast_mut_ptr::<Vecbf16Header>())?;
+            let q =
+                unsafe { NonNull::new(pgrx::pg_sys::pg_detoast_datum(p.cast().as_ptr()).cast())? };
+            if p != q {
+                Some(Vecbf16Output(q))
+            } else {
+                let header = p.as_ptr();
+                let vector = unsafe { (*header).as_borrowed() };
+                Some(Vecbf16Output::new(vector))
+            }
+        }
+    }
+}
+
+unsafe impl pgrx::datum::UnboxDatum for Vecbf16Output {
+    type As<'src> = Vecbf16Output;
+    #[inline]
+    unsafe fn unbox<'src>(d: pgrx::datum::Datum<'src>) -> Self::As<'src>
+    where
+        Self: 'src,
+    {
+        let p = NonNull::new(d.sans_lifetime().cast_mut_ptr::<Vecbf16Header>()).unwrap();
+        let q = unsafe {
+            NonNull::new(pgrx::pg_sys::pg_detoast_datum(p.cast().as_ptr()).cast()).unwrap()
+        };
+        if p != q {
+            Vecbf16Output(q)
+        } else {
+            let header = p.as_ptr();
+            let vector = unsafe { (*header).as_borrowed() };
+            Vecbf16Output::new(vector)
+        }
+    }
+}
+
+unsafe impl SqlTranslatable for Vecbf16Input<'_> {
+    fn argument_sql() -> Result<SqlMapping, ArgumentError> {
+        Ok(SqlMapping::As(String::from("vecbf16")))
+    }
+    fn return_sql() -> Result<Returns, ReturnsError> {
+        Ok(Returns::One(SqlMapping::As(String::from("vecbf16"))))
+    }
+}
+
+unsafe impl SqlTranslatable for Vecbf16Output {
+    fn argument_sql() -> Result<SqlMapping, ArgumentError> {
+        Ok(SqlMapping::As(String::from("vecbf16")))
+    }
+    fn return_sql() -> Result<Returns, ReturnsError> {
+        Ok(Returns::One(SqlMapping::As(String::from("vecbf16"))))
+    }
+}
+
+unsafe impl<'fcx> pgrx::callconv::ArgAbi<'fcx> for Vecbf16Input<'fcx> {
+    unsafe fn unbox_arg_unchecked(arg: pgrx::callconv::Arg<'_, 'fcx>) -> Self {
+        unsafe { arg.unbox_arg_using_from_datum().unwrap() }
+    }
+}
+
+unsafe impl pgrx::callconv::BoxRet for Vecbf16Output {
+    unsafe fn box_into<'fcx>(
+        self,
+        fcinfo: &mut pgrx::callconv::FcInfo<'fcx>,
+    ) -> pgrx::datum::Datum<'fcx> {
+        unsafe { fcinfo.return_raw_datum(Datum::from(self.into_raw() as *mut ())) }
+    }
+}
@@ -0,0 +1,230 @@
+use base::vector::*;
+use pgrx::datum::FromDatum;
+use pgrx::datum::IntoDatum;
+use pgrx::pg_sys::Datum;
+use pgrx::pg_sys::Oid;
+use pgrx::pgrx_sql_entity_graph::metadata::ArgumentError;
+use pgrx::pgrx_sql_entity_graph::metadata::Returns;
+use pgrx::pgrx_sql_entity_graph::metadata::ReturnsError;
+use pgrx::pgrx_sql_entity_graph::metadata::SqlMapping;
+use pgrx::pgrx_sql_entity_graph::metadata::SqlTranslatable;
+use std::alloc::Layout;
+use std::ops::Deref;
+use std::ptr::NonNull;
+
+pub const HEADER_MAGIC: u16 = 4;
+
+#[repr(C, align(8))]
+pub struct Veci8Header {
+    varlena: u32,
+    dims: u16,
+    magic: u16,
+    alpha: f32,
+    offset: f32,
+    sum: f32,
+    l2_norm: f32,
+    phantom: [i8; 0],
+}
+
+impl Veci8Header {
+    fn varlena(size: usize) -> u32 {
+        (size << 2) as u32
+    }
+    fn layout(len: usize) -> Layout {
+        u16::try_from(len).expect("Vector is too large.");
+        let layout_alpha = Layout::new::<Veci8Header>();
+        let layout_beta = Layout::array::<i8>(len).unwrap();
+        let layout = layout_alpha.extend(layout_beta).unwrap().0;
+        layout.pad_to_align()
+    }
+    pub fn dims(&self) -> u32 {
+        self.dims as u32
+    }
+    pub fn data(&self) -> &[i8] {
+        unsafe { std::slice::from_raw_parts(self.phantom.as_ptr(), self.dims as usize) }
+    }
+    pub fn as_borrowed(&self) -> Veci8Borrowed<'_> {
+        unsafe {
+            Veci8Borrowed::new_unchecked(
+                self.data(),
+                self.alpha,
+                self.offset,
+                self.sum,
+                self.l2_norm,
+            )
+        }
+    }
+}
+
+pub enum Veci8Input<'a> {
+    Owned(Veci8Output),
+    Borrowed(&'a Veci8Header),
+}
+
+impl<'a> Veci8Input<'a> {
+    unsafe fn new(p: NonNull<Veci8Header>) -> Self {
+        let q = unsafe {
+            NonNull::new(pgrx::pg_sys::pg_detoast_datum(p.cast().as_ptr()).cast()).unwrap()
+        };
+        if p != q {
+            Veci8Input::Owned(Veci8Output(q))
+        } else {
+            unsafe { Veci8Input::Borrowed(p.as_ref()) }
+        }
+    }
+}
+
+impl Deref for Veci8Input<'_> {
+    type Target = Veci8Header;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Veci8Input::Owned(x) => x,
+            Veci8Input::Borrowed(x) => x,
+        }
+    }
+}
+
+pub struct Veci8Output(NonNull<Veci8Header>);
+
+impl Veci8Output {
+    pub fn new(vector: Veci8Borrowed<'_>) -> Veci8Output {
+        unsafe {
+            let data = vector.data();
+            let layout = Veci8Header::layout(data.len());
+            let dims = vector.dims();
+            let internal_dims = dims as u16;
+            let ptr = pgrx::pg_sys::palloc(layout.size()) as *mut Veci8Header;
+            ptr.cast::<u8>().add(layout.size() - 8).write_bytes(0, 8);
+            (&raw mut (*ptr).varlena).write(Veci8Header::varlena(layout.size()));
+            (&raw mut (*ptr).magic).write(HEADER_MAGIC);
+            (&raw mut (*ptr).dims).write(internal_dims);
+            (&raw mut (*ptr).alpha).write(vector.alpha());
+            (&raw mut (*ptr).offset).write(vector.offset());
+            (&raw mut (*ptr).sum).write(vector.sum());
+            (&raw mut (*ptr).l2_norm).write(vector.l2_norm());
+            std::ptr::copy_nonoverlapping(data.as_ptr(), (*ptr).phantom.as_mut_ptr(), data.len());
+            Veci8Output(NonNull::new(ptr).unwrap())
+        }
+    }
+    pub fn into_raw(self) -> *mut Veci8Header {
+        let result = self.0.as_ptr();
+        std::mem::forget(self);
+        result
+    }
+}
+
+impl Deref for Veci8Output {
+    type Target = Veci8Header;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.0.as_ref() }
+    }
+}
+
+impl Drop for Veci8Output {
+    fn drop(&mut self) {
+        unsafe {
+            pgrx::pg_sys::pfree(self.0.as_ptr() as _);
+        }
+    }
+}
+
+impl<'a> FromDatum for Veci8Input<'a> {
+    unsafe fn from_polymorphic_datum(datum: Datum, is_null: bool, _typoid: Oid) -> Option<Self> {
+        if is_null {
+            None
+        } else {
+            let ptr = NonNull::new(datum.cast_mut_ptr::<Veci8Header>()).unwrap();
+            unsafe { Some(Veci8Input::new(ptr)) }
+        }
+    }
+}
+
+impl IntoDatum for Veci8Output {
+    fn into_datum(self) -> Option<Datum> {
+        Some(Datum::from(self.into_raw() as *mut ()))
+    }
+
+    fn type_oid() -> Oid {
+        let namespace =
+            pgrx::pg_catalog::PgNamespace::search_namespacename(crate::SCHEMA_C_STR).unwrap();
+        let namespace = namespace.get().expect("pgvecto.rs is not installed.");
+        let t = pgrx::pg_catalog::PgType::search_typenamensp(c"veci8", namespace.oid()).unwrap();
+        let t = t.get().expect("pg_catalog is broken.");
+        t.oid()
+    }
+}
+
+impl FromDatum for Veci8Output {
+    unsafe fn from_polymorphic_datum(datum: Datum, is_null: bool, _typoid: Oid) -> Option<Self> {
+        if is_null {
+            None
+        } else {
+            let p = NonNull::new(datum.cast_mut_ptr::<Veci8Header>())?;
+            let q =
+                unsafe { NonNull::new(pgrx::pg_sys::pg_detoast_datum(p.cast().as_ptr()).cast())? };
+            if p != q {
+                Some(Veci8Output(q))
+            } else {
+                let header = p.as_ptr();
+                let vector = unsafe { (*header).as_borrowed() };
+                Some(Veci8Output::new(vector))
+            }
+        }
+    }
+}
+
+unsafe impl pgrx::datum::UnboxDatum for Veci8Output {
+    type As<'src> = Veci8Output;
+    #[inline]
+    unsafe fn unbox<'src>(d: pgrx::datum::Datum<'src>) -> Self::As<'src>
+    where
+        Self: 'src,
+    {
+        let p = NonNull::new(d.sans_lifetime().cast_mut_ptr::<Veci8Header>()).unwrap();
+        let q = unsafe {
+            NonNull::new(pgrx::pg_sys::pg_detoast_datum(p.cast().as_ptr()).cast()).unwrap()
+        };
+        if p != q {
+            Veci8Output(q)
+        } else {
+            let header = p.as_ptr();
+            let vector = unsafe { (*header).as_borrowed() };
+            Veci8Output::new(vector)
+        }
+    }
+}
+
+unsafe impl SqlTranslatable for Veci8Input<'_> {
+    fn argument_sql() -> Result<SqlMapping, ArgumentError> {
+        Ok(SqlMapping::As(String::from("veci8")))
+    }
+    fn return_sql() -> Result<Returns, ReturnsError> {
+        Ok(Returns::One(SqlMapping::As(String::from("veci8"))))
+    }
+}
+
+unsafe impl SqlTranslatable for Veci8Output {
+    fn argument_sql() -> Result<SqlMapping, ArgumentError> {
+        Ok(SqlMapping::As(String::from("veci8")))
+    }
+    fn return_sql() -> Result<Returns, ReturnsError> {
+        Ok(Returns::One(SqlMapping::As(String::from("veci8"))))
+    }
+}
+
+unsafe impl<'fcx> pgrx::callconv::ArgAbi<'fcx> for Veci8Input<'fcx> {
+    unsafe fn unbox_arg_unchecked(arg: pgrx::callconv::Arg<'_, 'fcx>) -> Self {
+        unsafe { arg.unbox_arg_using_from_datum().unwrap() }
+    }
+}
+
+unsafe impl pgrx::callconv::BoxRet for Veci8Output {
+    unsafe fn box_into<'fcx>(
+        self,
+        fcinfo: &mut pgrx::callconv::FcInfo<'fcx>,
+    ) -> pgrx::datum::Datum<'fcx> {
+        unsafe { fcinfo.return_raw_datum(Datum::from(self.into_raw() as *mut ())) }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::datatype::memory_vecbf16::{Vecbf16Input, Vecbf16Output};
+use crate::error::*;
+use base::vector::*;
+use std::num::NonZero;
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_operator_add(lhs: Vecbf16Input<'_>, rhs: Vecbf16Input<'_>) -> Vecbf16Output {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    Vecbf16Output::new(
+        lhs.as_borrowed()
+            .operator_add(rhs.as_borrowed())
+            .as_borrowed(),
+    )
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_operator_sub(lhs: Vecbf16Input<'_>, rhs: Vecbf16Input<'_>) -> Vecbf16Output {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    Vecbf16Output::new(
+        lhs.as_borrowed()
+            .operator_sub(rhs.as_borrowed())
+            .as_borrowed(),
+    )
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_operator_mul(lhs: Vecbf16Input<'_>, rhs: Vecbf16Input<'_>) -> Vecbf16Output {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    Vecbf16Output::new(
+        lhs.as_borrowed()
+            .operator_mul(rhs.as_borrowed())
+            .as_borrowed(),
+    )
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_operator_lt(lhs: Vecbf16Input<'_>, rhs: Vecbf16Input<'_>) -> bool {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    lhs.as_borrowed() < rhs.as_borrowed()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_operator_lte(lhs: Vecbf16Input<'_>, rhs: Vecbf16Input<'_>) -> bool {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    lhs.as_borrowed() <= rhs.as_borrowed()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_operator_gt(lhs: Vecbf16Input<'_>, rhs: Vecbf16Input<'_>) -> bool {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    lhs.as_borrowed() > rhs.as_borrowed()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_operator_gte(lhs: Vecbf16Input<'_>, rhs: Vecbf16Input<'_>) -> bool {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    lhs.as_borrowed() >= rhs.as_borrowed()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_operator_eq(lhs: Vecbf16Input<'_>, rhs: Vecbf16Input<'_>) -> bool {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    lhs.as_borrowed() == rhs.as_borrowed()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_operator_neq(lhs: Vecbf16Input<'_>, rhs: Vecbf16Input<'_>) -> bool {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    lhs.as_borrowed() != rhs.as_borrowed()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_operator_dot(lhs: Vecbf16Input<'_>, rhs: Vecbf16Input<'_>) -> f32 {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    VectBorrowed::operator_dot(lhs.as_borrowed(), rhs.as_borrowed()).to_f32()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_operator_l2(lhs: Vecbf16Input<'_>, rhs: Vecbf16Input<'_>) -> f32 {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    VectBorrowed::operator_l2(lhs.as_borrowed(), rhs.as_borrowed()).to_f32()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_operator_cos(lhs: Vecbf16Input<'_>, rhs: Vecbf16Input<'_>) -> f32 {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    VectBorrowed::operator_cos(lhs.as_borrowed(), rhs.as_borrowed()).to_f32()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_sphere_dot_in(
+    lhs: Vecbf16Input<'_>,
+    rhs: pgrx::composite_type!("sphere_vecbf16"),
+) -> bool {
+    let center: Vecbf16Output = match rhs.get_by_index(NonZero::new(1).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty center at sphere"),
+        Err(_) => unreachable!(),
+    };
+    check_matched_dims(lhs.dims(), center.dims());
+    let radius: f32 = match rhs.get_by_index(NonZero::new(2).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty radius at sphere"),
+        Err(_) => unreachable!(),
+    };
+    VectBorrowed::operator_dot(lhs.as_borrowed(), center.as_borrowed()).to_f32() < radius
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_sphere_l2_in(
+    lhs: Vecbf16Input<'_>,
+    rhs: pgrx::composite_type!("sphere_vecbf16"),
+) -> bool {
+    let center: Vecbf16Output = match rhs.get_by_index(NonZero::new(1).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty center at sphere"),
+        Err(_) => unreachable!(),
+    };
+    check_matched_dims(lhs.dims(), center.dims());
+    let radius: f32 = match rhs.get_by_index(NonZero::new(2).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty radius at sphere"),
+        Err(_) => unreachable!(),
+    };
+    VectBorrowed::operator_l2(lhs.as_borrowed(), center.as_borrowed()).to_f32() < radius
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_sphere_cos_in(
+    lhs: Vecbf16Input<'_>,
+    rhs: pgrx::composite_type!("sphere_vecbf16"),
+) -> bool {
+    let center: Vecbf16Output = match rhs.get_by_index(NonZero::new(1).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty center at sphere"),
+        Err(_) => unreachable!(),
+    };
+    check_matched_dims(lhs.dims(), center.dims());
+    let radius: f32 = match rhs.get_by_index(NonZero::new(2).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty radius at sphere"),
+        Err(_) => unreachable!(),
+    };
+    VectBorrowed::operator_cos(lhs.as_borrowed(), center.as_borrowed()).to_f32() < radius
+}
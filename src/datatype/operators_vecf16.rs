@@ -1,6 +1,9 @@
 use crate::datatype::memory_vecf16::{Vecf16Input, Vecf16Output};
+use crate::datatype::memory_vecf32::Vecf32Output;
 use crate::error::*;
+use base::operator::*;
 use base::vector::*;
+use half::f16;
 use std::num::NonZero;
 
 #[pgrx::pg_extern(immutable, strict, parallel_safe)]
@@ -87,6 +90,37 @@ fn _vectors_vecf16_operator_cos(lhs: Vecf16Input<'_>, rhs: Vecf16Input<'_>) -> f
     VectBorrowed::operator_cos(lhs.as_borrowed(), rhs.as_borrowed()).to_f32()
 }
 
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecf16_operator_l1(lhs: Vecf16Input<'_>, rhs: Vecf16Input<'_>) -> f32 {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    VectL1::<f16>::distance(lhs.as_borrowed(), rhs.as_borrowed()).to_f32()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecf16_operator_weighted_l2(
+    lhs: Vecf16Input<'_>,
+    rhs: pgrx::composite_type!("weighted_vecf16"),
+) -> f32 {
+    let center: Vecf16Output = match rhs.get_by_index(NonZero::new(1).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty center at weighted vector"),
+        Err(_) => unreachable!(),
+    };
+    check_matched_dims(lhs.dims(), center.dims());
+    let weights: Vecf32Output = match rhs.get_by_index(NonZero::new(2).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty weights at weighted vector"),
+        Err(_) => unreachable!(),
+    };
+    check_matched_dims(lhs.dims(), weights.dims());
+    let mut result = 0.0f32;
+    for ((x, y), w) in lhs.slice().iter().zip(center.slice()).zip(weights.slice()) {
+        let d = x.to_f32() - y.to_f32();
+        result += w * d * d;
+    }
+    result
+}
+
 #[pgrx::pg_extern(immutable, strict, parallel_safe)]
 fn _vectors_vecf16_sphere_dot_in(
     lhs: Vecf16Input<'_>,
@@ -143,3 +177,22 @@ fn _vectors_vecf16_sphere_cos_in(
     };
     VectBorrowed::operator_cos(lhs.as_borrowed(), center.as_borrowed()).to_f32() < radius
 }
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecf16_sphere_l1_in(
+    lhs: Vecf16Input<'_>,
+    rhs: pgrx::composite_type!("sphere_vecf16"),
+) -> bool {
+    let center: Vecf16Output = match rhs.get_by_index(NonZero::new(1).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty center at sphere"),
+        Err(_) => unreachable!(),
+    };
+    check_matched_dims(lhs.dims(), center.dims());
+    let radius: f32 = match rhs.get_by_index(NonZero::new(2).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty radius at sphere"),
+        Err(_) => unreachable!(),
+    };
+    VectL1::<f16>::distance(lhs.as_borrowed(), center.as_borrowed()).to_f32() < radius
+}
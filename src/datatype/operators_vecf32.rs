@@ -1,5 +1,6 @@
 use crate::datatype::memory_vecf32::{Vecf32Input, Vecf32Output};
 use crate::error::*;
+use base::operator::*;
 use base::vector::*;
 use std::num::NonZero;
 
@@ -87,6 +88,37 @@ fn _vectors_vecf32_operator_cos(lhs: Vecf32Input<'_>, rhs: Vecf32Input<'_>) -> f
     VectBorrowed::operator_cos(lhs.as_borrowed(), rhs.as_borrowed()).to_f32()
 }
 
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecf32_operator_l1(lhs: Vecf32Input<'_>, rhs: Vecf32Input<'_>) -> f32 {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    VectL1::<f32>::distance(lhs.as_borrowed(), rhs.as_borrowed()).to_f32()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecf32_operator_weighted_l2(
+    lhs: Vecf32Input<'_>,
+    rhs: pgrx::composite_type!("weighted_vector"),
+) -> f32 {
+    let center: Vecf32Output = match rhs.get_by_index(NonZero::new(1).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty center at weighted vector"),
+        Err(_) => unreachable!(),
+    };
+    check_matched_dims(lhs.dims(), center.dims());
+    let weights: Vecf32Output = match rhs.get_by_index(NonZero::new(2).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty weights at weighted vector"),
+        Err(_) => unreachable!(),
+    };
+    check_matched_dims(lhs.dims(), weights.dims());
+    let mut result = 0.0f32;
+    for ((x, y), w) in lhs.slice().iter().zip(center.slice()).zip(weights.slice()) {
+        let d = x - y;
+        result += w * d * d;
+    }
+    result
+}
+
 #[pgrx::pg_extern(immutable, strict, parallel_safe)]
 fn _vectors_vecf32_sphere_dot_in(
     lhs: Vecf32Input<'_>,
@@ -143,3 +175,22 @@ fn _vectors_vecf32_sphere_cos_in(
     };
     VectBorrowed::operator_cos(lhs.as_borrowed(), center.as_borrowed()).to_f32() < radius
 }
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecf32_sphere_l1_in(
+    lhs: Vecf32Input<'_>,
+    rhs: pgrx::composite_type!("sphere_vector"),
+) -> bool {
+    let center: Vecf32Output = match rhs.get_by_index(NonZero::new(1).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty center at sphere"),
+        Err(_) => unreachable!(),
+    };
+    check_matched_dims(lhs.dims(), center.dims());
+    let radius: f32 = match rhs.get_by_index(NonZero::new(2).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty radius at sphere"),
+        Err(_) => unreachable!(),
+    };
+    VectL1::<f32>::distance(lhs.as_borrowed(), center.as_borrowed()).to_f32() < radius
+}
@@ -0,0 +1,57 @@
+use crate::datatype::memory_vecf32::Vecf32Output;
+use crate::error::*;
+use base::vector::*;
+use pgrx::datum::Array;
+
+/// Sums the distances from each vector of `rhs` to its nearest vector of `lhs`.
+fn maxsim(
+    lhs: Array<'_, Vecf32Output>,
+    rhs: Array<'_, Vecf32Output>,
+    f: impl Fn(VectBorrowed<'_, f32>, VectBorrowed<'_, f32>) -> f32,
+) -> f32 {
+    let lhs = lhs
+        .iter()
+        .map(|x| x.unwrap_or_else(|| bad_multivector()))
+        .collect::<Vec<_>>();
+    let rhs = rhs
+        .iter()
+        .map(|x| x.unwrap_or_else(|| bad_multivector()))
+        .collect::<Vec<_>>();
+    if lhs.is_empty() || rhs.is_empty() {
+        bad_multivector();
+    }
+    let mut result = 0.0f32;
+    for r in rhs.iter() {
+        let mut nearest = f32::INFINITY;
+        for l in lhs.iter() {
+            check_matched_dims(l.dims(), r.dims());
+            nearest = nearest.min(f(l.as_borrowed(), r.as_borrowed()));
+        }
+        result += nearest;
+    }
+    result
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecf32_array_operator_dot(
+    lhs: Array<'_, Vecf32Output>,
+    rhs: Array<'_, Vecf32Output>,
+) -> f32 {
+    maxsim(lhs, rhs, |l, r| VectBorrowed::operator_dot(l, r).to_f32())
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecf32_array_operator_l2(
+    lhs: Array<'_, Vecf32Output>,
+    rhs: Array<'_, Vecf32Output>,
+) -> f32 {
+    maxsim(lhs, rhs, |l, r| VectBorrowed::operator_l2(l, r).to_f32())
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecf32_array_operator_cos(
+    lhs: Array<'_, Vecf32Output>,
+    rhs: Array<'_, Vecf32Output>,
+) -> f32 {
+    maxsim(lhs, rhs, |l, r| VectBorrowed::operator_cos(l, r).to_f32())
+}
@@ -0,0 +1,145 @@
+use crate::datatype::memory_veci8::{Veci8Input, Veci8Output};
+use crate::error::*;
+use base::vector::*;
+use std::num::NonZero;
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_operator_add(lhs: Veci8Input<'_>, rhs: Veci8Input<'_>) -> Veci8Output {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    Veci8Output::new(
+        lhs.as_borrowed()
+            .operator_add(rhs.as_borrowed())
+            .as_borrowed(),
+    )
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_operator_sub(lhs: Veci8Input<'_>, rhs: Veci8Input<'_>) -> Veci8Output {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    Veci8Output::new(
+        lhs.as_borrowed()
+            .operator_sub(rhs.as_borrowed())
+            .as_borrowed(),
+    )
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_operator_mul(lhs: Veci8Input<'_>, rhs: Veci8Input<'_>) -> Veci8Output {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    Veci8Output::new(
+        lhs.as_borrowed()
+            .operator_mul(rhs.as_borrowed())
+            .as_borrowed(),
+    )
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_operator_lt(lhs: Veci8Input<'_>, rhs: Veci8Input<'_>) -> bool {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    lhs.as_borrowed() < rhs.as_borrowed()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_operator_lte(lhs: Veci8Input<'_>, rhs: Veci8Input<'_>) -> bool {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    lhs.as_borrowed() <= rhs.as_borrowed()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_operator_gt(lhs: Veci8Input<'_>, rhs: Veci8Input<'_>) -> bool {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    lhs.as_borrowed() > rhs.as_borrowed()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_operator_gte(lhs: Veci8Input<'_>, rhs: Veci8Input<'_>) -> bool {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    lhs.as_borrowed() >= rhs.as_borrowed()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_operator_eq(lhs: Veci8Input<'_>, rhs: Veci8Input<'_>) -> bool {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    lhs.as_borrowed() == rhs.as_borrowed()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_operator_neq(lhs: Veci8Input<'_>, rhs: Veci8Input<'_>) -> bool {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    lhs.as_borrowed() != rhs.as_borrowed()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_operator_dot(lhs: Veci8Input<'_>, rhs: Veci8Input<'_>) -> f32 {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    Veci8Borrowed::operator_dot(lhs.as_borrowed(), rhs.as_borrowed()).to_f32()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_operator_l2(lhs: Veci8Input<'_>, rhs: Veci8Input<'_>) -> f32 {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    Veci8Borrowed::operator_l2(lhs.as_borrowed(), rhs.as_borrowed()).to_f32()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_operator_cos(lhs: Veci8Input<'_>, rhs: Veci8Input<'_>) -> f32 {
+    check_matched_dims(lhs.dims(), rhs.dims());
+    Veci8Borrowed::operator_cos(lhs.as_borrowed(), rhs.as_borrowed()).to_f32()
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_sphere_dot_in(
+    lhs: Veci8Input<'_>,
+    rhs: pgrx::composite_type!("sphere_veci8"),
+) -> bool {
+    let center: Veci8Output = match rhs.get_by_index(NonZero::new(1).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty center at sphere"),
+        Err(_) => unreachable!(),
+    };
+    check_matched_dims(lhs.dims(), center.dims());
+    let radius: f32 = match rhs.get_by_index(NonZero::new(2).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty radius at sphere"),
+        Err(_) => unreachable!(),
+    };
+    Veci8Borrowed::operator_dot(lhs.as_borrowed(), center.as_borrowed()).to_f32() < radius
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_sphere_l2_in(
+    lhs: Veci8Input<'_>,
+    rhs: pgrx::composite_type!("sphere_veci8"),
+) -> bool {
+    let center: Veci8Output = match rhs.get_by_index(NonZero::new(1).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty center at sphere"),
+        Err(_) => unreachable!(),
+    };
+    check_matched_dims(lhs.dims(), center.dims());
+    let radius: f32 = match rhs.get_by_index(NonZero::new(2).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty radius at sphere"),
+        Err(_) => unreachable!(),
+    };
+    Veci8Borrowed::operator_l2(lhs.as_borrowed(), center.as_borrowed()).to_f32() < radius
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_sphere_cos_in(
+    lhs: Veci8Input<'_>,
+    rhs: pgrx::composite_type!("sphere_veci8"),
+) -> bool {
+    let center: Veci8Output = match rhs.get_by_index(NonZero::new(1).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty center at sphere"),
+        Err(_) => unreachable!(),
+    };
+    check_matched_dims(lhs.dims(), center.dims());
+    let radius: f32 = match rhs.get_by_index(NonZero::new(2).unwrap()) {
+        Ok(Some(s)) => s,
+        Ok(None) => pgrx::error!("Bad input: empty radius at sphere"),
+        Err(_) => unreachable!(),
+    };
+    Veci8Borrowed::operator_cos(lhs.as_borrowed(), center.as_borrowed()).to_f32() < radius
+}
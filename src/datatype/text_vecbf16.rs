@@ -0,0 +1,42 @@
+use super::memory_vecbf16::Vecbf16Output;
+use crate::datatype::memory_vecbf16::Vecbf16Input;
+use crate::datatype::typmod::Typmod;
+use crate::error::*;
+use base::vector::*;
+use pgrx::pg_sys::Oid;
+use std::ffi::{CStr, CString};
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_in(input: &CStr, _oid: Oid, typmod: i32) -> Vecbf16Output {
+    use crate::utils::parse::parse_vector;
+    let reserve = Typmod::parse_from_i32(typmod)
+        .unwrap()
+        .dims()
+        .map(|x| x.get())
+        .unwrap_or(0);
+    let v = parse_vector(input.to_bytes(), reserve as usize, |s| s.parse().ok());
+    match v {
+        Err(e) => {
+            bad_literal(&e.to_string());
+        }
+        Ok(vector) => {
+            let dims = u32::try_from(vector.len()).expect("input is too large");
+            check_value_dims_65535(dims);
+            Vecbf16Output::new(VectBorrowed::new(&vector))
+        }
+    }
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_vecbf16_out(vector: Vecbf16Input<'_>) -> CString {
+    let mut buffer = String::new();
+    buffer.push('[');
+    if let Some(&x) = vector.slice().first() {
+        buffer.push_str(format!("{}", x).as_str());
+    }
+    for &x in vector.slice().iter().skip(1) {
+        buffer.push_str(format!(", {}", x).as_str());
+    }
+    buffer.push(']');
+    CString::new(buffer).unwrap()
+}
@@ -0,0 +1,48 @@
+use super::memory_veci8::Veci8Output;
+use crate::datatype::memory_veci8::Veci8Input;
+use crate::datatype::typmod::Typmod;
+use crate::error::*;
+use base::vector::*;
+use pgrx::pg_sys::Oid;
+use std::ffi::{CStr, CString};
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_in(input: &CStr, _oid: Oid, typmod: i32) -> Veci8Output {
+    use crate::utils::parse::parse_vector;
+    let reserve = Typmod::parse_from_i32(typmod)
+        .unwrap()
+        .dims()
+        .map(|x| x.get())
+        .unwrap_or(0);
+    let v = parse_vector(input.to_bytes(), reserve as usize, |s| {
+        s.parse::<f32>().ok()
+    });
+    match v {
+        Err(e) => {
+            bad_literal(&e.to_string());
+        }
+        Ok(vector) => {
+            let dims = u32::try_from(vector.len()).expect("input is too large");
+            check_value_dims_65535(dims);
+            if vector.iter().any(|x| !x.is_finite()) {
+                bad_literal("veci8 does not support infinite or NaN values");
+            }
+            Veci8Output::new(Veci8Owned::from_f32(&vector).as_borrowed())
+        }
+    }
+}
+
+#[pgrx::pg_extern(immutable, strict, parallel_safe)]
+fn _vectors_veci8_out(vector: Veci8Input<'_>) -> CString {
+    let mut buffer = String::new();
+    buffer.push('[');
+    let slice = vector.as_borrowed().to_f32();
+    if let Some(&x) = slice.first() {
+        buffer.push_str(format!("{}", x).as_str());
+    }
+    for &x in slice.iter().skip(1) {
+        buffer.push_str(format!(", {}", x).as_str());
+    }
+    buffer.push(']');
+    CString::new(buffer).unwrap()
+}
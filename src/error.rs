@@ -135,9 +135,25 @@ INFORMATION: reason = {reason:?}"
     )
 }
 
+pub fn bad_relation_log_full() -> ! {
+    error!(
+        "\
+pgvecto.rs: The log of the index is full.
+ADVICE: Rebuild the index with `REINDEX` to start a new log, or raise `vectors.relation_log_limit`."
+    )
+}
+
 pub fn bad_service_invalid_vector() -> ! {
     error!(
         "\
 pgvecto.rs: The dimension of a vector does not matched that in a vector index column."
     )
 }
+
+pub fn bad_multivector() -> ! {
+    error!(
+        "\
+pgvecto.rs: An array of vectors is empty or contains nulls.
+ADVICE: Check if the array of vectors has at least one element and no null elements."
+    )
+}
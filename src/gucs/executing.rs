@@ -22,6 +22,31 @@ static IVF_NPROBE: GucSetting<i32> =
 static HNSW_EF_SEARCH: GucSetting<i32> =
     GucSetting::<i32>::new(SearchOptions::default_hnsw_ef_search() as i32);
 
+static HNSW_FILTERED_TRAVERSAL: GucSetting<bool> =
+    GucSetting::<bool>::new(SearchOptions::default_hnsw_filtered_traversal());
+
+static HNSW_BRUTE_FORCE_SELECTIVITY: GucSetting<f64> =
+    GucSetting::<f64>::new(SearchOptions::default_hnsw_brute_force_selectivity() as f64);
+
+static VAMANA_SEARCH_LIST_SIZE: GucSetting<i32> =
+    GucSetting::<i32>::new(SearchOptions::default_vamana_search_list_size() as i32);
+
+static SPARSE_INVERTED_INDEX_PRUNING_FACTOR: GucSetting<f64> =
+    GucSetting::<f64>::new(SearchOptions::default_sparse_inverted_index_pruning_factor() as f64);
+
+static SPARSE_INVERTED_INDEX_RERANK_SIZE: GucSetting<i32> =
+    GucSetting::<i32>::new(SearchOptions::default_sparse_inverted_index_rerank_size() as i32);
+
+static MAXSIM_TOKEN_LIMIT: GucSetting<i32> =
+    GucSetting::<i32>::new(SearchOptions::default_maxsim_token_limit() as i32);
+
+static MAXSIM_REFINE: GucSetting<i32> =
+    GucSetting::<i32>::new(SearchOptions::default_maxsim_refine() as i32);
+
+static STREAM_BATCH_SIZE: GucSetting<i32> = GucSetting::<i32>::new(32);
+
+static ENABLE_PREFILTER: GucSetting<bool> = GucSetting::<bool>::new(false);
+
 pub unsafe fn init() {
     GucRegistry::define_int_guc(
         "vectors.sq_rerank_size",
@@ -87,6 +112,92 @@ pub unsafe fn init() {
         GucContext::Userset,
         GucFlags::default(),
     );
+    GucRegistry::define_bool_guc(
+        "vectors.hnsw_filtered_traversal",
+        "Traverses two-hop neighbours of filtered-out vertices in HNSW algorithm or not.",
+        "https://docs.pgvecto.rs/usage/search.html",
+        &HNSW_FILTERED_TRAVERSAL,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+    GucRegistry::define_float_guc(
+        "vectors.hnsw_brute_force_selectivity",
+        "Selectivity of filters below which HNSW algorithm falls back to brute force.",
+        "https://docs.pgvecto.rs/usage/search.html",
+        &HNSW_BRUTE_FORCE_SELECTIVITY,
+        0.0,
+        1.0,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+    GucRegistry::define_int_guc(
+        "vectors.vamana_search_list_size",
+        "`search_list_size` argument of Vamana algorithm.",
+        "https://docs.pgvecto.rs/usage/search.html",
+        &VAMANA_SEARCH_LIST_SIZE,
+        1,
+        u16::MAX as _,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+    GucRegistry::define_float_guc(
+        "vectors.sparse_inverted_index_pruning_factor",
+        "Pruning factor of sparse inverted index. `1.0` means exact search.",
+        "https://docs.pgvecto.rs/usage/search.html",
+        &SPARSE_INVERTED_INDEX_PRUNING_FACTOR,
+        1.0,
+        16.0,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+    GucRegistry::define_int_guc(
+        "vectors.sparse_inverted_index_rerank_size",
+        "Sparse inverted index reranker size.",
+        "https://docs.pgvecto.rs/usage/search.html",
+        &SPARSE_INVERTED_INDEX_RERANK_SIZE,
+        0,
+        65535,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+    GucRegistry::define_int_guc(
+        "vectors.maxsim_token_limit",
+        "Number of nearest token vectors fetched for each query vector in MaxSim search.",
+        "https://docs.pgvecto.rs/usage/search.html",
+        &MAXSIM_TOKEN_LIMIT,
+        1,
+        65535,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+    GucRegistry::define_int_guc(
+        "vectors.maxsim_refine",
+        "Number of candidate documents rescored with exact MaxSim.",
+        "https://docs.pgvecto.rs/usage/search.html",
+        &MAXSIM_REFINE,
+        0,
+        65535,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+    GucRegistry::define_int_guc(
+        "vectors.stream_batch_size",
+        "Size of the first batch of results fetched by an index scan.",
+        "https://docs.pgvecto.rs/usage/search.html",
+        &STREAM_BATCH_SIZE,
+        1,
+        16384,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+    GucRegistry::define_bool_guc(
+        "vectors.enable_prefilter",
+        "Enables or disables evaluating the WHERE clause over the table before vector search.",
+        "https://docs.pgvecto.rs/usage/search.html",
+        &ENABLE_PREFILTER,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
 }
 
 pub fn search_options() -> SearchOptions {
@@ -98,5 +209,22 @@ pub fn search_options() -> SearchOptions {
         rq_fast_scan: RQ_FAST_SCAN.get(),
         ivf_nprobe: IVF_NPROBE.get() as u32,
         hnsw_ef_search: HNSW_EF_SEARCH.get() as u32,
+        hnsw_filtered_traversal: HNSW_FILTERED_TRAVERSAL.get(),
+        hnsw_brute_force_selectivity: HNSW_BRUTE_FORCE_SELECTIVITY.get() as f32,
+        vamana_search_list_size: VAMANA_SEARCH_LIST_SIZE.get() as u32,
+        sparse_inverted_index_pruning_factor: SPARSE_INVERTED_INDEX_PRUNING_FACTOR.get() as f32,
+        sparse_inverted_index_rerank_size: SPARSE_INVERTED_INDEX_RERANK_SIZE.get() as u32,
+        maxsim_token_limit: MAXSIM_TOKEN_LIMIT.get() as u32,
+        maxsim_refine: MAXSIM_REFINE.get() as u32,
+        radius: SearchOptions::default_radius(),
+        limit: SearchOptions::default_limit(),
     }
 }
+
+pub fn stream_batch_size() -> u32 {
+    STREAM_BATCH_SIZE.get() as u32
+}
+
+pub fn enable_prefilter() -> bool {
+    ENABLE_PREFILTER.get()
+}
@@ -10,6 +10,9 @@ pub enum Transport {
 
 pub static TRANSPORT: GucSetting<Transport> = GucSetting::<Transport>::new(Transport::mmap);
 
+/// 1 GiB with 8 KiB blocks.
+pub static RELATION_LOG_LIMIT: GucSetting<i32> = GucSetting::<i32>::new(131072);
+
 pub unsafe fn init() {
     GucRegistry::define_enum_guc(
         "vectors.internal_transport",
@@ -18,5 +21,15 @@ pub unsafe fn init() {
         &TRANSPORT,
         GucContext::Userset,
         GucFlags::default(),
+    );
+    GucRegistry::define_int_guc(
+        "vectors.relation_log_limit",
+        "Maximum size of the log of an index stored in its relation, past which inserts fail.",
+        "https://docs.pgvecto.rs/usage/indexing.html",
+        &RELATION_LOG_LIMIT,
+        2,
+        i32::MAX,
+        GucContext::Userset,
+        GucFlags::UNIT_BLOCKS,
     )
 }
@@ -1,21 +1,29 @@
+use super::am_cost;
 use super::am_options;
 use super::am_options::Opfamily;
 use super::am_scan;
+use super::pushdown;
+use super::relation;
 use crate::error::*;
 use crate::gucs::planning::ENABLE_INDEX;
-use crate::index::am_scan::Scanner;
+use crate::index::am_scan::{Query, Scanner};
 use crate::index::catalog::{on_index_build, on_index_write};
 use crate::index::utils::from_oid_to_handle;
 use crate::index::utils::{ctid_to_pointer, pointer_to_ctid};
 use crate::ipc::{client, ClientRpc};
-use crate::utils::cells::PgCell;
-use am_options::Reloption;
+use crate::utils::cells::{PgCell, PgRefCell};
+use am_options::{PgDistanceKind, Reloption, Storage};
+use base::distance::DistanceKind;
 use base::index::*;
+use base::search::Pointer;
+use base::vector::OwnedVector;
 use pgrx::datum::Internal;
 use pgrx::pg_sys::Datum;
 
 static RELOPT_KIND_VECTORS: PgCell<pgrx::pg_sys::relopt_kind::Type> = unsafe { PgCell::new(0) };
 
+static UPGRADED: PgRefCell<Vec<Handle>> = unsafe { PgRefCell::new(Vec::new()) };
+
 pub unsafe fn init() {
     unsafe {
         RELOPT_KIND_VECTORS.set(pgrx::pg_sys::add_reloption_kind());
@@ -104,9 +112,9 @@ pub unsafe extern "C" fn amoptions(reloptions: Datum, validate: bool) -> *mut pg
 
 #[pgrx::pg_guard]
 pub unsafe extern "C" fn amcostestimate(
-    _root: *mut pgrx::pg_sys::PlannerInfo,
+    root: *mut pgrx::pg_sys::PlannerInfo,
     path: *mut pgrx::pg_sys::IndexPath,
-    _loop_count: f64,
+    loop_count: f64,
     index_startup_cost: *mut pgrx::pg_sys::Cost,
     index_total_cost: *mut pgrx::pg_sys::Cost,
     index_selectivity: *mut pgrx::pg_sys::Selectivity,
@@ -124,11 +132,23 @@ pub unsafe extern "C" fn amcostestimate(
             *index_pages = 0.0;
             return;
         }
-        *index_startup_cost = 0.0;
-        *index_total_cost = 0.0;
-        *index_selectivity = 1.0;
+        let mut costs = pgrx::pg_sys::GenericCosts::default();
+        pgrx::pg_sys::genericcostestimate(root, path, loop_count, &mut costs);
+        *index_selectivity = costs.indexSelectivity;
         *index_correlation = 1.0;
-        *index_pages = 0.0;
+        *index_pages = costs.numIndexPages;
+        let handle = from_oid_to_handle((*(*path).indexinfo).indexoid);
+        let limit = Some((*root).limit_tuples).filter(|&x| x > 0.0);
+        let Some(cost) = am_cost::estimate_index(handle, limit) else {
+            *index_startup_cost = 0.0;
+            *index_total_cost = 0.0;
+            return;
+        };
+        let cpu_operator_cost = pgrx::pg_sys::cpu_operator_cost;
+        let cpu_index_tuple_cost = pgrx::pg_sys::cpu_index_tuple_cost;
+        *index_startup_cost = cost.startup * cpu_operator_cost;
+        *index_total_cost = *index_startup_cost
+            + costs.numIndexTuples * (cost.per_tuple * cpu_operator_cost + cpu_index_tuple_cost);
     }
 }
 
@@ -140,13 +160,19 @@ pub unsafe extern "C" fn ambuild(
 ) -> *mut pgrx::pg_sys::IndexBuildResult {
     pub struct Builder {
         pub opfamily: Opfamily,
+        pub relation: bool,
         pub rpc: ClientRpc,
         pub result: *mut pgrx::pg_sys::IndexBuildResult,
+        pub buffer: Vec<(OwnedVector, Pointer)>,
+        pub buffer_bytes: usize,
     }
+    // keep batches well below the capacity of the mmap transport
+    const BUFFER_BYTES: usize = 512 * 1024;
     let oid = unsafe { (*index).rd_id };
     let handle = from_oid_to_handle(oid);
     let (options, alterable_options) = unsafe { am_options::options(index) };
     let opfamily = unsafe { am_options::opfamily(index) };
+    let relation = unsafe { am_options::storage(index) } == Storage::Relation;
     let mut rpc = check_client(client());
     match rpc.create(handle, options, alterable_options) {
         Ok(()) => (),
@@ -159,11 +185,17 @@ pub unsafe extern "C" fn ambuild(
         Ok(()) => (),
         Err(StopError::NotExist) => pgrx::error!("internal error"),
     }
+    if relation {
+        unsafe { relation::create(index, &mut rpc) };
+    }
     let result = unsafe { pgrx::pgbox::PgBox::<pgrx::pg_sys::IndexBuildResult>::alloc0() };
     let mut builder = Builder {
         opfamily,
+        relation,
         rpc,
         result: result.as_ptr(),
+        buffer: Vec::new(),
+        buffer_bytes: 0,
     };
     let table_am = unsafe { &*(*heap).rd_tableam };
     unsafe {
@@ -191,19 +223,33 @@ pub unsafe extern "C" fn ambuild(
         state: *mut std::os::raw::c_void,
     ) {
         let state = unsafe { &mut *state.cast::<Builder>() };
-        let vector = unsafe {
+        let vectors = unsafe {
             state
                 .opfamily
-                .datum_to_vector(*values.add(0), *is_null.add(0))
+                .datum_to_vectors(*values.add(0), *is_null.add(0))
         };
-        if let Some(vector) = vector {
-            let oid = unsafe { (*index).rd_id };
-            let handle = from_oid_to_handle(oid);
+        if let Some(vectors) = vectors {
             let pointer = ctid_to_pointer(unsafe { ctid.read() });
-            match state.rpc.insert(handle, vector, pointer) {
-                Ok(()) => (),
-                Err(InsertError::NotExist) => bad_service_not_exist(),
-                Err(InsertError::InvalidVector) => bad_service_invalid_vector(),
+            for vector in vectors {
+                let bytes = bincode::serialized_size(&vector).unwrap_or(0) as usize;
+                if !state.buffer.is_empty() && state.buffer_bytes + bytes > BUFFER_BYTES {
+                    let oid = unsafe { (*index).rd_id };
+                    let handle = from_oid_to_handle(oid);
+                    let vectors = std::mem::take(&mut state.buffer);
+                    state.buffer_bytes = 0;
+                    if state.relation {
+                        let record = LogRecord::Insert(vectors);
+                        unsafe { relation::write(index, &mut state.rpc, record) };
+                    } else {
+                        match state.rpc.build(handle, vectors) {
+                            Ok(()) => (),
+                            Err(InsertError::NotExist) => bad_service_not_exist(),
+                            Err(InsertError::InvalidVector) => bad_service_invalid_vector(),
+                        }
+                    }
+                }
+                state.buffer.push((vector, pointer));
+                state.buffer_bytes += bytes;
             }
             unsafe {
                 (*state.result).index_tuples += 1.0;
@@ -214,6 +260,18 @@ pub unsafe extern "C" fn ambuild(
         }
     }
     let mut rpc = builder.rpc;
+    if !builder.buffer.is_empty() {
+        if relation {
+            let record = LogRecord::Insert(builder.buffer);
+            unsafe { relation::write(index, &mut rpc, record) };
+        } else {
+            match rpc.build(handle, builder.buffer) {
+                Ok(()) => (),
+                Err(InsertError::NotExist) => bad_service_not_exist(),
+                Err(InsertError::InvalidVector) => bad_service_invalid_vector(),
+            }
+        }
+    }
     match rpc.start(handle) {
         Ok(()) => (),
         Err(StartError::NotExist) => pgrx::error!("internal error"),
@@ -262,15 +320,29 @@ pub unsafe extern "C" fn aminsert(
     let opfamily = unsafe { am_options::opfamily(index) };
     let oid = unsafe { (*index).rd_id };
     let handle = from_oid_to_handle(oid);
-    let vector = unsafe { opfamily.datum_to_vector(*values.add(0), *is_null.add(0)) };
-    if let Some(vector) = vector {
+    let vectors = unsafe { opfamily.datum_to_vectors(*values.add(0), *is_null.add(0)) };
+    if let Some(mut vectors) = vectors {
         let pointer = ctid_to_pointer(unsafe { heap_tid.read() });
 
         on_index_write(handle);
 
+        unsafe { upgrade(index) };
+
         let mut rpc = check_client(client());
 
-        match rpc.insert(handle, vector, pointer) {
+        if unsafe { relation::is_relation(index) } {
+            let vectors = vectors.into_iter().map(|x| (x, pointer)).collect();
+            unsafe { relation::write(index, &mut rpc, LogRecord::Insert(vectors)) };
+            return false;
+        }
+
+        let result = if vectors.len() == 1 {
+            rpc.insert(handle, vectors.pop().unwrap(), pointer)
+        } else {
+            let vectors = vectors.into_iter().map(|x| (x, pointer)).collect();
+            rpc.insert_many(handle, vectors)
+        };
+        match result {
             Ok(()) => (),
             Err(InsertError::NotExist) => bad_service_not_exist(),
             Err(InsertError::InvalidVector) => bad_service_invalid_vector(),
@@ -289,7 +361,7 @@ pub unsafe extern "C" fn ambeginscan(
 
     let scan = unsafe { pgrx::pg_sys::RelationGetIndexScan(index, n_keys, n_orderbys) };
     unsafe {
-        let scanner = am_scan::scan_make(None, None, false);
+        let scanner = am_scan::scan_make(None, None, false, None, None);
         (*scan).opaque = CurrentMemoryContext.leak_and_drop_on_delete(scanner).cast();
     }
     scan
@@ -311,6 +383,11 @@ pub unsafe extern "C" fn amrescan(
             std::ptr::copy(orderbys, (*scan).orderByData, (*scan).numberOfOrderBys as _);
         }
         let opfamily = am_options::opfamily((*scan).indexRelation);
+        upgrade((*scan).indexRelation);
+        if relation::is_relation((*scan).indexRelation) {
+            // see records written by other backends, or by the primary on a standby
+            relation::sync((*scan).indexRelation, &mut check_client(client()));
+        }
         let (orderbys, spheres) = {
             let mut orderbys = Vec::new();
             let mut spheres = Vec::new();
@@ -324,7 +401,16 @@ pub unsafe extern "C" fn amrescan(
                 let value = (*data).sk_argument;
                 let is_null = ((*data).sk_flags & pgrx::pg_sys::SK_ISNULL as i32) != 0;
                 match (*data).sk_strategy {
-                    1 => orderbys.push(opfamily.datum_to_vector(value, is_null)),
+                    1 if opfamily.is_weighted() => {
+                        let (center, weights) = opfamily.datum_to_weighted(value, is_null);
+                        // vectors are weighted by the weights of the index
+                        if center.is_some() && weights != am_options::weights((*scan).indexRelation)
+                        {
+                            pgrx::error!("the weights of the query do not match the index");
+                        }
+                        orderbys.push(center.map(Query::Vector))
+                    }
+                    1 => orderbys.push(opfamily.datum_to_query(value, is_null)),
                     _ => unreachable!(),
                 }
             }
@@ -333,15 +419,26 @@ pub unsafe extern "C" fn amrescan(
                 let value = (*data).sk_argument;
                 let is_null = ((*data).sk_flags & pgrx::pg_sys::SK_ISNULL as i32) != 0;
                 match (*data).sk_strategy {
-                    2 => spheres.push(opfamily.datum_to_sphere(value, is_null)),
+                    2 => {
+                        let (center, radius) = opfamily.datum_to_sphere(value, is_null);
+                        spheres.push((center.map(Query::Vector), radius))
+                    }
                     _ => unreachable!(),
                 }
             }
             (orderbys, spheres)
         };
-        let (vector, threshold, recheck) = am_scan::scan_build(orderbys, spheres, opfamily);
+        let (vector, threshold, recheck) = am_scan::scan_build(orderbys, spheres);
+        let filter = if vector.is_some() {
+            pushdown::filter(scan)
+        } else {
+            None
+        };
         let scanner = (*scan).opaque.cast::<Scanner>().as_mut().unwrap_unchecked();
-        let scanner = std::mem::replace(scanner, am_scan::scan_make(vector, threshold, recheck));
+        let scanner = std::mem::replace(
+            scanner,
+            am_scan::scan_make(vector, threshold, recheck, filter, pushdown::limit(scan)),
+        );
         am_scan::scan_release(scanner);
     }
 }
@@ -382,7 +479,7 @@ pub unsafe extern "C" fn amgettuple(
 pub unsafe extern "C" fn amendscan(scan: pgrx::pg_sys::IndexScanDesc) {
     unsafe {
         let scanner = (*scan).opaque.cast::<Scanner>().as_mut().unwrap_unchecked();
-        let scanner = std::mem::replace(scanner, am_scan::scan_make(None, None, false));
+        let scanner = std::mem::replace(scanner, am_scan::scan_make(None, None, false, None, None));
         am_scan::scan_release(scanner);
     }
 }
@@ -405,6 +502,30 @@ pub unsafe extern "C" fn ambulkdelete(
     if let Some(callback) = callback {
         on_index_write(handle);
 
+        let index = unsafe { (*info).index };
+        if unsafe { relation::is_relation(index) } {
+            let mut rpc = check_client(client());
+            unsafe { relation::sync(index, &mut rpc) };
+            let mut x = match rpc.list(handle) {
+                Ok(x) => x,
+                Err((_, ListError::NotExist)) => bad_service_not_exist(),
+            };
+            let mut pointers = Vec::new();
+            while let Some(pointer) = x.next() {
+                let mut ctid = pointer_to_ctid(pointer);
+                if unsafe { callback(&mut ctid, callback_state) } {
+                    pointers.push(pointer);
+                }
+            }
+            let mut rpc = x.leave();
+            // keep batches well below the capacity of the mmap transport
+            for pointers in pointers.chunks(65536) {
+                let record = LogRecord::Delete(pointers.to_vec());
+                unsafe { relation::write(index, &mut rpc, record) };
+            }
+            return stats;
+        }
+
         let mut x = match check_client(client()).list(handle) {
             Ok(x) => x,
             Err((_, ListError::NotExist)) => bad_service_not_exist(),
@@ -431,3 +552,32 @@ pub unsafe extern "C" fn amvacuumcleanup(
 ) -> *mut pgrx::pg_sys::IndexBulkDeleteResult {
     std::ptr::null_mut()
 }
+
+/// Indexes of cos operator classes used to be stored as dot indexes of normalized vectors, so
+/// they are converted to cos indexes before a backend uses them for the first time.
+unsafe fn upgrade(index: pgrx::pg_sys::Relation) {
+    let opfamily = unsafe { (*index).rd_opfamily.read() };
+    if !matches!(
+        am_options::convert_opfamily_to_vd(opfamily),
+        Some((_, PgDistanceKind::Cos, _))
+    ) {
+        return;
+    }
+    let handle = from_oid_to_handle(unsafe { (*index).rd_id });
+    if UPGRADED.borrow().contains(&handle) {
+        return;
+    }
+    let mut rpc = check_client(client());
+    match rpc.stat(handle) {
+        Ok(stat) if stat.options.vector.d == DistanceKind::Dot => {
+            let (options, _) = unsafe { am_options::options(index) };
+            if let Err(e) = rpc.upgrade(handle, options) {
+                pgrx::error!("{}", e.to_string());
+            }
+        }
+        Ok(_) => (),
+        // the index is created by a build, or by the replay of its log
+        Err(StatError::NotExist) => return,
+    }
+    UPGRADED.borrow_mut().push(handle);
+}
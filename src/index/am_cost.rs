@@ -0,0 +1,123 @@
+use crate::gucs::executing::search_options;
+use crate::ipc::client;
+use crate::utils::cells::PgRefCell;
+use base::index::*;
+use base::search::Handle;
+use std::time::{Duration, Instant};
+
+const GROWING_GRAPH_M: f64 = 16.0;
+const GROWING_GRAPH_THRESHOLD: f64 = 1024.0;
+
+// queries are planned far more often than segments change, so statistics are fetched from the
+// background worker at most once a while
+const STAT_TTL: Duration = Duration::from_secs(10);
+
+static STATS: PgRefCell<Vec<(Handle, Instant, IndexStat)>> = unsafe { PgRefCell::new(Vec::new()) };
+
+pub struct Cost {
+    pub startup: f64,
+    pub per_tuple: f64,
+}
+
+/// Estimates the cost of a search of an index with its cached statistics, or returns `None` if
+/// the background worker is not available.
+pub fn estimate_index(handle: Handle, limit: Option<f64>) -> Option<Cost> {
+    let now = Instant::now();
+    {
+        let mut stats = STATS.borrow_mut();
+        stats.retain(|(_, instant, _)| now.duration_since(*instant) < STAT_TTL);
+        if let Some((_, _, stat)) = stats.iter().find(|(x, _, _)| *x == handle) {
+            return Some(estimate(stat, limit));
+        }
+    }
+    let stat = client().and_then(|mut rpc| rpc.stat(handle).ok())?;
+    let cost = estimate(&stat, limit);
+    STATS.borrow_mut().push((handle, now, stat));
+    Some(cost)
+}
+
+/// Estimates the number of distance computations of a vector search, given the
+/// statistics of an index and the number of tuples the query is expected to need.
+pub fn estimate(stat: &IndexStat, limit: Option<f64>) -> Cost {
+    let opts = search_options();
+    let limit = limit.unwrap_or(0.0);
+    let mut cost = Cost {
+        startup: 0.0,
+        per_tuple: 0.0,
+    };
+    for segment in stat.segments.iter() {
+        let n = segment.length as f64;
+        if n == 0.0 {
+            continue;
+        }
+        let (startup, per_tuple) = match segment.r#type.as_str() {
+            "sealed" => sealed(&stat.options.indexing, &opts, n, limit),
+            // sealed segments of an outdated layout, which are scanned until they're rebuilt
+            "stored" => sealed(&IndexingOptions::Flat(Default::default()), &opts, n, limit),
+            _ if n > GROWING_GRAPH_THRESHOLD => {
+                let ef = (opts.hnsw_ef_search as f64).max(limit).min(n);
+                (ef * GROWING_GRAPH_M, 1.0)
+            }
+            _ => (n, 0.0),
+        };
+        cost.startup += startup;
+        cost.per_tuple += per_tuple;
+    }
+    cost
+}
+
+fn sealed(indexing: &IndexingOptions, opts: &SearchOptions, n: f64, limit: f64) -> (f64, f64) {
+    match indexing {
+        IndexingOptions::Flat(x) => {
+            let q = quantization(&x.quantization, opts);
+            (n * q + rerank(&x.quantization, opts), 0.0)
+        }
+        IndexingOptions::Ivf(x) => {
+            let q = quantization(&x.quantization, opts);
+            let nlist = (x.nlist as f64).min(n);
+            let nprobe = (opts.ivf_nprobe as f64).min(nlist);
+            let startup = nlist + n * nprobe / nlist * q + rerank(&x.quantization, opts);
+            (startup, q)
+        }
+        IndexingOptions::Hnsw(x) => {
+            let q = quantization(&x.quantization, opts);
+            let m = x.m as f64;
+            let ef = (opts.hnsw_ef_search as f64).max(limit).min(n);
+            let upper = n.log2().max(1.0) * m;
+            let startup = upper + ef * 2.0 * m * q + rerank(&x.quantization, opts);
+            (startup, q)
+        }
+        IndexingOptions::Vamana(x) => {
+            let q = quantization(&x.quantization, opts);
+            let degree = x.degree as f64;
+            let l = (opts.vamana_search_list_size as f64).max(limit).min(n);
+            let startup = l * degree * q + rerank(&x.quantization, opts);
+            (startup, q)
+        }
+        IndexingOptions::SparseInvertedIndex(x) if x.quantized_weights => {
+            (n + opts.sparse_inverted_index_rerank_size as f64, 0.0)
+        }
+        IndexingOptions::SparseInvertedIndex(_) => (n, 0.0),
+    }
+}
+
+fn quantization(quantization: &Option<QuantizationOptions>, opts: &SearchOptions) -> f64 {
+    match quantization {
+        None => 1.0,
+        Some(QuantizationOptions::Scalar(_)) if opts.sq_fast_scan => 0.125,
+        Some(QuantizationOptions::Scalar(_)) => 0.25,
+        Some(QuantizationOptions::Product(_)) if opts.pq_fast_scan => 0.0625,
+        Some(QuantizationOptions::Product(_)) => 0.125,
+        Some(QuantizationOptions::Rabitq(_)) if opts.rq_fast_scan => 0.0625,
+        Some(QuantizationOptions::Rabitq(x)) => 0.125 * x.bits as f64,
+    }
+}
+
+fn rerank(quantization: &Option<QuantizationOptions>, opts: &SearchOptions) -> f64 {
+    match quantization {
+        None => 0.0,
+        Some(QuantizationOptions::Scalar(_)) => opts.sq_rerank_size as f64,
+        Some(QuantizationOptions::Product(_)) => opts.pq_rerank_size as f64,
+        Some(QuantizationOptions::Rabitq(_)) => 0.0,
+    }
+}
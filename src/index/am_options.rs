@@ -2,15 +2,21 @@ use crate::datatype::memory_bvector::BVectorInput;
 use crate::datatype::memory_bvector::BVectorOutput;
 use crate::datatype::memory_svecf32::SVecf32Input;
 use crate::datatype::memory_svecf32::SVecf32Output;
+use crate::datatype::memory_vecbf16::Vecbf16Input;
+use crate::datatype::memory_vecbf16::Vecbf16Output;
 use crate::datatype::memory_vecf16::Vecf16Input;
 use crate::datatype::memory_vecf16::Vecf16Output;
 use crate::datatype::memory_vecf32::Vecf32Input;
 use crate::datatype::memory_vecf32::Vecf32Output;
+use crate::datatype::memory_veci8::Veci8Input;
+use crate::datatype::memory_veci8::Veci8Output;
 use crate::datatype::typmod::Typmod;
 use crate::error::*;
+use crate::index::am_scan::Query;
 use base::distance::*;
 use base::index::*;
 use base::vector::*;
+use pgrx::datum::Array;
 use pgrx::datum::FromDatum;
 use pgrx::heap_tuple::PgHeapTuple;
 use serde::Deserialize;
@@ -48,6 +54,8 @@ pub enum PgDistanceKind {
     Cos,
     Hamming,
     Jaccard,
+    L1,
+    WeightedL2,
 }
 
 impl PgDistanceKind {
@@ -55,16 +63,18 @@ impl PgDistanceKind {
         match self {
             PgDistanceKind::L2 => DistanceKind::L2,
             PgDistanceKind::Dot => DistanceKind::Dot,
-            PgDistanceKind::Cos => DistanceKind::Dot,
+            PgDistanceKind::Cos => DistanceKind::Cos,
             PgDistanceKind::Hamming => DistanceKind::Hamming,
             PgDistanceKind::Jaccard => DistanceKind::Jaccard,
+            PgDistanceKind::L1 => DistanceKind::L1,
+            PgDistanceKind::WeightedL2 => DistanceKind::L2,
         }
     }
 }
 
 pub fn convert_opclass_to_vd(
     opclass_oid: pgrx::pg_sys::Oid,
-) -> Option<(VectorKind, PgDistanceKind)> {
+) -> Option<(VectorKind, PgDistanceKind, bool)> {
     let namespace =
         pgrx::pg_catalog::PgNamespace::search_namespacename(crate::SCHEMA_C_STR).unwrap();
     let namespace = namespace.get().expect("pgvecto.rs is not installed.");
@@ -82,7 +92,7 @@ pub fn convert_opclass_to_vd(
 
 pub fn convert_opfamily_to_vd(
     opfamily_oid: pgrx::pg_sys::Oid,
-) -> Option<(VectorKind, PgDistanceKind)> {
+) -> Option<(VectorKind, PgDistanceKind, bool)> {
     let namespace =
         pgrx::pg_catalog::PgNamespace::search_namespacename(crate::SCHEMA_C_STR).unwrap();
     let namespace = namespace.get().expect("pgvecto.rs is not installed.");
@@ -98,46 +108,89 @@ pub fn convert_opfamily_to_vd(
     None
 }
 
-fn convert_name_to_vd(name: &str) -> Option<(VectorKind, PgDistanceKind)> {
+/// Returns the vector kind, the distance kind and whether columns are arrays of vectors.
+fn convert_name_to_vd(name: &str) -> Option<(VectorKind, PgDistanceKind, bool)> {
     match name.strip_suffix("_ops") {
-        Some("vector_l2") => Some((VectorKind::Vecf32, PgDistanceKind::L2)),
-        Some("vector_dot") => Some((VectorKind::Vecf32, PgDistanceKind::Dot)),
-        Some("vector_cos") => Some((VectorKind::Vecf32, PgDistanceKind::Cos)),
-        Some("vecf16_l2") => Some((VectorKind::Vecf16, PgDistanceKind::L2)),
-        Some("vecf16_dot") => Some((VectorKind::Vecf16, PgDistanceKind::Dot)),
-        Some("vecf16_cos") => Some((VectorKind::Vecf16, PgDistanceKind::Cos)),
-        Some("svector_l2") => Some((VectorKind::SVecf32, PgDistanceKind::L2)),
-        Some("svector_dot") => Some((VectorKind::SVecf32, PgDistanceKind::Dot)),
-        Some("svector_cos") => Some((VectorKind::SVecf32, PgDistanceKind::Cos)),
-        Some("bvector_hamming") => Some((VectorKind::BVector, PgDistanceKind::Hamming)),
-        Some("bvector_dot") => Some((VectorKind::BVector, PgDistanceKind::Dot)),
-        Some("bvector_jaccard") => Some((VectorKind::BVector, PgDistanceKind::Jaccard)),
+        Some("vector_l2") => Some((VectorKind::Vecf32, PgDistanceKind::L2, false)),
+        Some("vector_dot") => Some((VectorKind::Vecf32, PgDistanceKind::Dot, false)),
+        Some("vector_cos") => Some((VectorKind::Vecf32, PgDistanceKind::Cos, false)),
+        Some("vector_l1") => Some((VectorKind::Vecf32, PgDistanceKind::L1, false)),
+        Some("vector_weighted_l2") => Some((VectorKind::Vecf32, PgDistanceKind::WeightedL2, false)),
+        Some("vecf16_l2") => Some((VectorKind::Vecf16, PgDistanceKind::L2, false)),
+        Some("vecf16_dot") => Some((VectorKind::Vecf16, PgDistanceKind::Dot, false)),
+        Some("vecf16_cos") => Some((VectorKind::Vecf16, PgDistanceKind::Cos, false)),
+        Some("vecf16_l1") => Some((VectorKind::Vecf16, PgDistanceKind::L1, false)),
+        Some("vecf16_weighted_l2") => Some((VectorKind::Vecf16, PgDistanceKind::WeightedL2, false)),
+        Some("vecbf16_l2") => Some((VectorKind::Vecbf16, PgDistanceKind::L2, false)),
+        Some("vecbf16_dot") => Some((VectorKind::Vecbf16, PgDistanceKind::Dot, false)),
+        Some("vecbf16_cos") => Some((VectorKind::Vecbf16, PgDistanceKind::Cos, false)),
+        Some("svector_l2") => Some((VectorKind::SVecf32, PgDistanceKind::L2, false)),
+        Some("svector_dot") => Some((VectorKind::SVecf32, PgDistanceKind::Dot, false)),
+        Some("svector_cos") => Some((VectorKind::SVecf32, PgDistanceKind::Cos, false)),
+        Some("bvector_hamming") => Some((VectorKind::BVector, PgDistanceKind::Hamming, false)),
+        Some("bvector_dot") => Some((VectorKind::BVector, PgDistanceKind::Dot, false)),
+        Some("bvector_jaccard") => Some((VectorKind::BVector, PgDistanceKind::Jaccard, false)),
+        Some("veci8_l2") => Some((VectorKind::Veci8, PgDistanceKind::L2, false)),
+        Some("veci8_dot") => Some((VectorKind::Veci8, PgDistanceKind::Dot, false)),
+        Some("veci8_cos") => Some((VectorKind::Veci8, PgDistanceKind::Cos, false)),
+        Some("vector_array_l2") => Some((VectorKind::Vecf32, PgDistanceKind::L2, true)),
+        Some("vector_array_dot") => Some((VectorKind::Vecf32, PgDistanceKind::Dot, true)),
+        Some("vector_array_cos") => Some((VectorKind::Vecf32, PgDistanceKind::Cos, true)),
         _ => None,
     }
 }
 
-unsafe fn convert_reloptions_to_options(
-    reloptions: *const pgrx::pg_sys::varlena,
-) -> (IndexingOptions, IndexAlterableOptions) {
-    #[derive(Debug, Clone, Deserialize, Default)]
-    #[serde(deny_unknown_fields)]
-    struct Parsed {
-        #[serde(default)]
-        indexing: IndexingOptions,
-        #[serde(flatten)]
-        alterable: IndexAlterableOptions,
-    }
+/// Where the data of an index is kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Storage {
+    /// Only in the background worker.
+    #[default]
+    Worker,
+    /// In the index relation, replayed by the background worker.
+    Relation,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct Parsed {
+    #[serde(default)]
+    weights: Option<Vec<f32>>,
+    #[serde(default)]
+    storage: Storage,
+    #[serde(default)]
+    indexing: IndexingOptions,
+    #[serde(flatten)]
+    alterable: IndexAlterableOptions,
+}
+
+unsafe fn convert_reloptions_to_options(reloptions: *const pgrx::pg_sys::varlena) -> Parsed {
     let reloption = reloptions as *const Reloption;
     if reloption.is_null() || unsafe { (*reloption).options == 0 } {
         return Default::default();
     }
     let s = unsafe { (*reloption).options() }.to_string_lossy();
     match toml::from_str::<Parsed>(&s) {
-        Ok(p) => (p.indexing, p.alterable),
+        Ok(p) => p,
         Err(e) => pgrx::error!("failed to parse options: {}", e),
     }
 }
 
+pub unsafe fn storage(index: pgrx::pg_sys::Relation) -> Storage {
+    unsafe { convert_reloptions_to_options((*index).rd_options) }.storage
+}
+
+pub unsafe fn weights(index: pgrx::pg_sys::Relation) -> Option<Vec<f32>> {
+    unsafe { convert_reloptions_to_options((*index).rd_options) }.weights
+}
+
+pub unsafe fn dims(index: pgrx::pg_sys::Relation) -> u32 {
+    let att = unsafe { &mut *(*index).rd_att };
+    let atts = unsafe { att.attrs.as_slice(att.natts as _) };
+    let typmod = Typmod::parse_from_i32(atts[0].type_mod()).unwrap();
+    check_column_dims(typmod.dims()).get()
+}
+
 pub unsafe fn options(index: pgrx::pg_sys::Relation) -> (IndexOptions, IndexAlterableOptions) {
     let opfamily = unsafe { (*index).rd_opfamily.read() };
     let att = unsafe { &mut *(*index).rd_att };
@@ -152,24 +205,79 @@ pub unsafe fn options(index: pgrx::pg_sys::Relation) -> (IndexOptions, IndexAlte
     let typmod = Typmod::parse_from_i32(atts[0].type_mod()).unwrap();
     let dims = check_column_dims(typmod.dims()).get();
     // get v, d
-    let (v, pg_d) = convert_opfamily_to_vd(opfamily).unwrap();
+    let (v, pg_d, _) = convert_opfamily_to_vd(opfamily).unwrap();
+    // get indexing, segment, optimizing
+    let Parsed {
+        weights,
+        indexing,
+        alterable,
+        ..
+    } = unsafe { convert_reloptions_to_options((*index).rd_options) };
+    // only weighted operators compute the distances that weighted indexes are ordered by
+    match (pg_d, weights.is_some()) {
+        (PgDistanceKind::WeightedL2, false) => {
+            pgrx::error!("weights are required by weighted operator classes")
+        }
+        (PgDistanceKind::WeightedL2, true) | (_, false) => (),
+        (_, true) => pgrx::error!("weights are only supported by weighted operator classes"),
+    }
     let vector = VectorOptions {
         dims,
         v,
         d: pg_d.to_distance(),
+        weights,
     };
-    // get indexing, segment, optimizing
-    let (indexing, alterable) = unsafe { convert_reloptions_to_options((*index).rd_options) };
     (IndexOptions { vector, indexing }, alterable)
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct Opfamily {
     vector: VectorKind,
-    pg_distance: PgDistanceKind,
+    weighted: bool,
+    multivector: bool,
 }
 
 impl Opfamily {
+    /// Returns all vectors of a value, which is a single vector unless columns are arrays of vectors.
+    pub unsafe fn datum_to_vectors(
+        self,
+        datum: pgrx::pg_sys::Datum,
+        is_null: bool,
+    ) -> Option<Vec<OwnedVector>> {
+        if !self.multivector {
+            return unsafe { self.datum_to_vector(datum, is_null) }.map(|vector| vec![vector]);
+        }
+        if is_null || datum.is_null() {
+            return None;
+        }
+        let vectors = match self.vector {
+            VectorKind::Vecf32 => {
+                let array = unsafe { Array::<Vecf32Output>::from_datum(datum, false).unwrap() };
+                array
+                    .iter()
+                    .map(|vector| match vector {
+                        Some(vector) => {
+                            self.preprocess(BorrowedVector::Vecf32(vector.as_borrowed()))
+                        }
+                        None => bad_multivector(),
+                    })
+                    .collect()
+            }
+            _ => unreachable!(),
+        };
+        Some(vectors)
+    }
+    pub unsafe fn datum_to_query(self, datum: pgrx::pg_sys::Datum, is_null: bool) -> Option<Query> {
+        if self.multivector {
+            let vectors = unsafe { self.datum_to_vectors(datum, is_null) }?;
+            if vectors.is_empty() {
+                bad_multivector();
+            }
+            Some(Query::MultiVector(vectors))
+        } else {
+            unsafe { self.datum_to_vector(datum, is_null) }.map(Query::Vector)
+        }
+    }
     pub unsafe fn datum_to_vector(
         self,
         datum: pgrx::pg_sys::Datum,
@@ -187,6 +295,10 @@ impl Opfamily {
                 let vector = unsafe { Vecf16Input::from_datum(datum, false).unwrap() };
                 self.preprocess(BorrowedVector::Vecf16(vector.as_borrowed()))
             }
+            VectorKind::Vecbf16 => {
+                let vector = unsafe { Vecbf16Input::from_datum(datum, false).unwrap() };
+                self.preprocess(BorrowedVector::Vecbf16(vector.as_borrowed()))
+            }
             VectorKind::SVecf32 => {
                 let vector = unsafe { SVecf32Input::from_datum(datum, false).unwrap() };
                 self.preprocess(BorrowedVector::SVecf32(vector.as_borrowed()))
@@ -195,6 +307,10 @@ impl Opfamily {
                 let vector = unsafe { BVectorInput::from_datum(datum, false).unwrap() };
                 self.preprocess(BorrowedVector::BVector(vector.as_borrowed()))
             }
+            VectorKind::Veci8 => {
+                let vector = unsafe { Veci8Input::from_datum(datum, false).unwrap() };
+                self.preprocess(BorrowedVector::Veci8(vector.as_borrowed()))
+            }
         };
         Some(vector)
     }
@@ -216,6 +332,10 @@ impl Opfamily {
                 .get_by_index::<Vecf16Output>(NonZero::new(1).unwrap())
                 .unwrap()
                 .map(|vector| self.preprocess(BorrowedVector::Vecf16(vector.as_borrowed()))),
+            VectorKind::Vecbf16 => tuple
+                .get_by_index::<Vecbf16Output>(NonZero::new(1).unwrap())
+                .unwrap()
+                .map(|vector| self.preprocess(BorrowedVector::Vecbf16(vector.as_borrowed()))),
             VectorKind::SVecf32 => tuple
                 .get_by_index::<SVecf32Output>(NonZero::new(1).unwrap())
                 .unwrap()
@@ -224,36 +344,64 @@ impl Opfamily {
                 .get_by_index::<BVectorOutput>(NonZero::new(1).unwrap())
                 .unwrap()
                 .map(|vector| self.preprocess(BorrowedVector::BVector(vector.as_borrowed()))),
+            VectorKind::Veci8 => tuple
+                .get_by_index::<Veci8Output>(NonZero::new(1).unwrap())
+                .unwrap()
+                .map(|vector| self.preprocess(BorrowedVector::Veci8(vector.as_borrowed()))),
         };
         let radius = tuple.get_by_index::<f32>(NonZero::new(2).unwrap()).unwrap();
         (center, radius)
     }
+    pub fn is_weighted(self) -> bool {
+        self.weighted
+    }
+    /// Returns the center and the weights of a query of a weighted operator.
+    pub unsafe fn datum_to_weighted(
+        self,
+        datum: pgrx::pg_sys::Datum,
+        is_null: bool,
+    ) -> (Option<OwnedVector>, Option<Vec<f32>>) {
+        if is_null || datum.is_null() {
+            return (None, None);
+        }
+        let tuple = unsafe { PgHeapTuple::from_composite_datum(datum) };
+        let center = match self.vector {
+            VectorKind::Vecf32 => tuple
+                .get_by_index::<Vecf32Output>(NonZero::new(1).unwrap())
+                .unwrap()
+                .map(|vector| self.preprocess(BorrowedVector::Vecf32(vector.as_borrowed()))),
+            VectorKind::Vecf16 => tuple
+                .get_by_index::<Vecf16Output>(NonZero::new(1).unwrap())
+                .unwrap()
+                .map(|vector| self.preprocess(BorrowedVector::Vecf16(vector.as_borrowed()))),
+            _ => unreachable!(),
+        };
+        let weights = tuple
+            .get_by_index::<Vecf32Output>(NonZero::new(2).unwrap())
+            .unwrap()
+            .map(|weights| weights.slice().to_vec());
+        (center, weights)
+    }
     pub fn preprocess(self, vector: BorrowedVector<'_>) -> OwnedVector {
         use BorrowedVector as B;
         use OwnedVector as O;
-        match (vector, self.pg_distance) {
-            (B::Vecf32(x), PgDistanceKind::Cos) => O::Vecf32(x.function_normalize()),
-            (B::Vecf32(x), _) => O::Vecf32(x.own()),
-            (B::Vecf16(x), PgDistanceKind::Cos) => O::Vecf16(x.function_normalize()),
-            (B::Vecf16(x), _) => O::Vecf16(x.own()),
-            (B::SVecf32(x), PgDistanceKind::Cos) => O::SVecf32(x.function_normalize()),
-            (B::SVecf32(x), _) => O::SVecf32(x.own()),
-            (B::BVector(x), _) => O::BVector(x.own()),
-        }
-    }
-    pub fn process(self, x: Distance) -> f32 {
-        match self.pg_distance {
-            PgDistanceKind::Cos => f32::from(x) + 1.0f32,
-            _ => f32::from(x),
+        match vector {
+            B::Vecf32(x) => O::Vecf32(x.own()),
+            B::Vecf16(x) => O::Vecf16(x.own()),
+            B::Vecbf16(x) => O::Vecbf16(x.own()),
+            B::SVecf32(x) => O::SVecf32(x.own()),
+            B::BVector(x) => O::BVector(x.own()),
+            B::Veci8(x) => O::Veci8(x.own()),
         }
     }
 }
 
 pub unsafe fn opfamily(index: pgrx::pg_sys::Relation) -> Opfamily {
     let opfamily = unsafe { (*index).rd_opfamily.read() };
-    let (vector, pg_distance) = convert_opfamily_to_vd(opfamily).unwrap();
+    let (vector, pg_d, multivector) = convert_opfamily_to_vd(opfamily).unwrap();
     Opfamily {
         vector,
-        pg_distance,
+        weighted: pg_d == PgDistanceKind::WeightedL2,
+        multivector,
     }
 }
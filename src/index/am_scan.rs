@@ -1,33 +1,59 @@
-use super::am_options::Opfamily;
 use crate::error::*;
 use crate::gucs::executing::search_options;
 use crate::gucs::planning::Mode;
 use crate::gucs::planning::SEARCH_MODE;
-use crate::ipc::{client, ClientVbase};
+use crate::ipc::{client, ClientRpc, ClientVbase, ClientVbaseMulti};
+use base::distance::Distance;
 use base::index::*;
 use base::search::*;
 use base::vector::*;
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    Vector(OwnedVector),
+    MultiVector(Vec<OwnedVector>),
+}
+
+pub enum Stream {
+    Vbase(ClientVbase),
+    VbaseMulti(ClientVbaseMulti),
+}
+
+impl Stream {
+    fn next(&mut self) -> Option<(Distance, Pointer)> {
+        match self {
+            Stream::Vbase(x) => x.next(),
+            Stream::VbaseMulti(x) => x.next(),
+        }
+    }
+    fn leave(self) -> ClientRpc {
+        match self {
+            Stream::Vbase(x) => x.leave(),
+            Stream::VbaseMulti(x) => x.leave(),
+        }
+    }
+}
+
 pub enum Scanner {
     Initial {
-        vector: Option<(OwnedVector, Opfamily)>,
+        vector: Option<Query>,
         threshold: Option<f32>,
         recheck: bool,
+        filter: Option<AllowList>,
+        limit: Option<u32>,
     },
     Vbase {
-        vbase: ClientVbase,
+        vbase: Stream,
         threshold: Option<f32>,
         recheck: bool,
-        opfamily: Opfamily,
     },
     Empty {},
 }
 
 pub fn scan_build(
-    orderbys: Vec<Option<OwnedVector>>,
-    spheres: Vec<(Option<OwnedVector>, Option<f32>)>,
-    opfamily: Opfamily,
-) -> (Option<(OwnedVector, Opfamily)>, Option<f32>, bool) {
+    orderbys: Vec<Option<Query>>,
+    spheres: Vec<(Option<Query>, Option<f32>)>,
+) -> (Option<Query>, Option<f32>, bool) {
     let mut pair = None;
     let mut threshold = None;
     let mut recheck = false;
@@ -51,18 +77,22 @@ pub fn scan_build(
             break;
         }
     }
-    (pair.map(|x| (x, opfamily)), threshold, recheck)
+    (pair, threshold, recheck)
 }
 
 pub fn scan_make(
-    vector: Option<(OwnedVector, Opfamily)>,
+    vector: Option<Query>,
     threshold: Option<f32>,
     recheck: bool,
+    filter: Option<AllowList>,
+    limit: Option<u32>,
 ) -> Scanner {
     Scanner::Initial {
         vector,
         threshold,
         recheck,
+        filter,
+        limit,
     }
 }
 
@@ -71,28 +101,41 @@ pub fn scan_next(scanner: &mut Scanner, handle: Handle) -> Option<(Pointer, bool
         vector,
         threshold,
         recheck,
+        filter,
+        limit,
     } = scanner
     {
-        if let Some((vector, opfamily)) = vector.as_ref() {
+        if let Some(vector) = vector.as_ref() {
             let rpc = check_client(client());
 
-            match SEARCH_MODE.get() {
-                Mode::basic | Mode::vbase => {
-                    let opts = search_options();
-                    let vbase = match rpc.vbase(handle, vector.clone(), opts) {
-                        Ok(x) => x,
-                        Err((_, VbaseError::NotExist)) => bad_service_not_exist(),
-                        Err((_, VbaseError::InvalidVector)) => bad_service_invalid_vector(),
-                        Err((_, VbaseError::InvalidSearchOptions { reason: _ })) => unreachable!(),
-                    };
-                    *scanner = Scanner::Vbase {
-                        vbase,
-                        threshold: *threshold,
-                        recheck: *recheck,
-                        opfamily: *opfamily,
-                    };
-                }
+            let mut opts = search_options();
+            if let Some(threshold) = threshold {
+                opts.radius = *threshold;
+            }
+            // a hint of how many results are fetched first, while rows that are deleted or
+            // invisible to the snapshot are skipped by fetching more
+            if let (Mode::basic, Some(limit), false) = (SEARCH_MODE.get(), *limit, *recheck) {
+                opts.limit = limit.max(1);
             }
+            let vbase = match vector.clone() {
+                Query::Vector(vector) => rpc
+                    .vbase(handle, vector, opts, filter.take())
+                    .map(Stream::Vbase),
+                Query::MultiVector(vectors) => rpc
+                    .vbase_multi(handle, vectors, opts, filter.take())
+                    .map(Stream::VbaseMulti),
+            };
+            let vbase = match vbase {
+                Ok(x) => x,
+                Err((_, VbaseError::NotExist)) => bad_service_not_exist(),
+                Err((_, VbaseError::InvalidVector)) => bad_service_invalid_vector(),
+                Err((_, VbaseError::InvalidSearchOptions { reason: _ })) => unreachable!(),
+            };
+            *scanner = Scanner::Vbase {
+                vbase,
+                threshold: *threshold,
+                recheck: *recheck,
+            };
         } else {
             *scanner = Scanner::Empty {};
         }
@@ -103,11 +146,7 @@ pub fn scan_next(scanner: &mut Scanner, handle: Handle) -> Option<(Pointer, bool
             vbase,
             threshold,
             recheck,
-            opfamily,
-        } => match (
-            vbase.next().map(|(d, p)| (opfamily.process(d), p)),
-            threshold,
-        ) {
+        } => match (vbase.next().map(|(d, p)| (f32::from(d), p)), threshold) {
             (Some((_, ptr)), None) => Some((ptr, *recheck)),
             (Some((distance, ptr)), Some(t)) if distance < *t => Some((ptr, *recheck)),
             _ => {
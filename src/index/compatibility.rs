@@ -142,6 +142,7 @@ unsafe fn rewrite_opclass(istmt: *mut pgrx::pg_sys::IndexStmt) {
                 Ok("vector_l2_ops") => "vector_l2_ops",
                 Ok("vector_ip_ops") => "vector_dot_ops",
                 Ok("vector_cosine_ops") => "vector_cos_ops",
+                Ok("vector_l1_ops") => "vector_l1_ops",
                 Ok(_) | Err(_) => {
                     return;
                 }
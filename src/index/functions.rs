@@ -1,8 +1,9 @@
-use super::utils::from_oid_to_handle;
+use super::utils::{ctid_to_pointer, from_oid_to_handle};
 use crate::error::check_client;
 use crate::ipc::client;
 use base::index::StatError;
 use pgrx::pg_sys::Oid;
+use std::path::PathBuf;
 
 #[pgrx::pg_extern(volatile, strict, parallel_safe)]
 fn _vectors_pgvectors_upgrade() {
@@ -39,3 +40,58 @@ fn _vectors_fence_vector_index(oid: Oid) {
         }
     }
 }
+
+/// Writes a snapshot of a vector index to a new directory on the server. Files of sealed segments
+/// are hard-linked, so the directory must be on the same file system as `pg_vectors`.
+#[pgrx::pg_extern(volatile, strict)]
+fn _vectors_snapshot_vector_index(oid: Oid, path: String) {
+    let handle = from_oid_to_handle(oid);
+    let mut rpc = check_client(client());
+    if let Err(e) = rpc.snapshot(handle, PathBuf::from(path)) {
+        pgrx::error!("{}", e.to_string());
+    }
+}
+
+/// Replaces the contents of a vector index with a snapshot. The snapshot must be taken from an
+/// index with the same options.
+#[pgrx::pg_extern(volatile, strict)]
+fn _vectors_restore_vector_index(oid: Oid, path: String) {
+    let handle = from_oid_to_handle(oid);
+    let mut rpc = check_client(client());
+    if let Err(e) = rpc.restore(handle, PathBuf::from(path)) {
+        pgrx::error!("{}", e.to_string());
+    }
+}
+
+/// Installs a sealed index built offline by `pyvectors` or the CLI as a sealed segment of a vector
+/// index. The prebuilt index refers to rows by labels, the row of `labels[i]` is `ctids[i]`.
+#[pgrx::pg_extern(volatile, strict)]
+fn _vectors_attach_vector_index(
+    oid: Oid,
+    path: String,
+    labels: pgrx::datum::Array<i64>,
+    ctids: pgrx::datum::Array<pgrx::pg_sys::ItemPointerData>,
+) {
+    let handle = from_oid_to_handle(oid);
+    if labels.len() != ctids.len() {
+        pgrx::error!("labels and ctids are of different lengths");
+    }
+    let mut pairs = Vec::with_capacity(labels.len());
+    for (label, ctid) in labels.iter().zip(ctids.iter()) {
+        let (Some(label), Some(ctid)) = (label, ctid) else {
+            pgrx::error!("labels and ctids must not contain nulls");
+        };
+        pairs.push((label, ctid_to_pointer(ctid)));
+    }
+    let mut rpc = check_client(client());
+    // labels may not fit in a packet, so they are passed in a file
+    let file = PathBuf::from(format!("pg_vectors/attach_{}", std::process::id()));
+    if let Err(e) = std::fs::write(&file, bincode::serialize(&pairs).unwrap()) {
+        pgrx::error!("failed to write labels: {}", e);
+    }
+    let result = rpc.attach(handle, PathBuf::from(path), file.clone());
+    let _ = std::fs::remove_file(file);
+    if let Err(e) = result {
+        pgrx::error!("{}", e.to_string());
+    }
+}
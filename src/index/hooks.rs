@@ -1,4 +1,5 @@
 static mut PREV_EXECUTOR_START: pgrx::pg_sys::ExecutorStart_hook_type = None;
+static mut PREV_EXECUTOR_END: pgrx::pg_sys::ExecutorEnd_hook_type = None;
 static mut PREV_PROCESS_UTILITY: pgrx::pg_sys::ProcessUtility_hook_type = None;
 static mut NEXT_OBJECT_ACCESS_HOOK: pgrx::pg_sys::object_access_hook_type = None;
 
@@ -13,6 +14,19 @@ unsafe extern "C" fn vectors_executor_start(
         } else {
             pgrx::pg_sys::standard_ExecutorStart(query_desc, eflags);
         }
+        super::pushdown::on_executor_start(query_desc);
+    }
+}
+
+#[pgrx::pg_guard]
+unsafe extern "C" fn vectors_executor_end(query_desc: *mut pgrx::pg_sys::QueryDesc) {
+    super::pushdown::on_executor_end(query_desc);
+    unsafe {
+        if let Some(prev_executor_end) = PREV_EXECUTOR_END {
+            prev_executor_end(query_desc);
+        } else {
+            pgrx::pg_sys::standard_ExecutorEnd(query_desc);
+        }
     }
 }
 
@@ -82,19 +96,35 @@ unsafe extern "C" fn xact_callback(
         pgrx::pg_sys::XactEvent::XACT_EVENT_PRE_COMMIT
         | pgrx::pg_sys::XactEvent::XACT_EVENT_PARALLEL_PRE_COMMIT => unsafe {
             super::catalog::on_commit();
+            super::pushdown::on_xact_end();
         },
         pgrx::pg_sys::XactEvent::XACT_EVENT_ABORT
         | pgrx::pg_sys::XactEvent::XACT_EVENT_PARALLEL_ABORT => unsafe {
             super::catalog::on_abort();
+            super::pushdown::on_xact_end();
         },
         _ => {}
     }
 }
 
+#[pgrx::pg_guard]
+unsafe extern "C" fn subxact_callback(
+    event: pgrx::pg_sys::SubXactEvent::Type,
+    my_subid: pgrx::pg_sys::SubTransactionId,
+    _parent_subid: pgrx::pg_sys::SubTransactionId,
+    _data: pgrx::void_mut_ptr,
+) {
+    if event == pgrx::pg_sys::SubXactEvent::SUBXACT_EVENT_ABORT_SUB {
+        super::pushdown::on_subxact_abort(my_subid);
+    }
+}
+
 pub unsafe fn init() {
     unsafe {
         PREV_EXECUTOR_START = pgrx::pg_sys::ExecutorStart_hook;
         pgrx::pg_sys::ExecutorStart_hook = Some(vectors_executor_start);
+        PREV_EXECUTOR_END = pgrx::pg_sys::ExecutorEnd_hook;
+        pgrx::pg_sys::ExecutorEnd_hook = Some(vectors_executor_end);
         PREV_PROCESS_UTILITY = pgrx::pg_sys::ProcessUtility_hook;
         pgrx::pg_sys::ProcessUtility_hook = Some(vectors_process_utility);
         NEXT_OBJECT_ACCESS_HOOK = pgrx::pg_sys::object_access_hook;
@@ -102,5 +132,6 @@ pub unsafe fn init() {
     }
     unsafe {
         pgrx::pg_sys::RegisterXactCallback(Some(xact_callback), std::ptr::null_mut());
+        pgrx::pg_sys::RegisterSubXactCallback(Some(subxact_callback), std::ptr::null_mut());
     }
 }
@@ -1,10 +1,13 @@
 mod am;
+mod am_cost;
 mod am_options;
 mod am_scan;
 mod catalog;
 mod compatibility;
 mod functions;
 mod hooks;
+mod pushdown;
+mod relation;
 mod utils;
 mod views;
 
@@ -0,0 +1,198 @@
+use crate::gucs::executing::enable_prefilter;
+use crate::index::utils::ctid_to_pointer;
+use crate::utils::cells::PgRefCell;
+use base::search::AllowList;
+use pgrx::pg_sys::{NodeTag, PlanState};
+
+struct Entry {
+    query_desc: usize,
+    subxid: pgrx::pg_sys::SubTransactionId,
+    state: usize,
+    limit: Option<u32>,
+}
+
+/// Pointers are sent to the background worker with the search request, so if more rows pass the
+/// filter, it's left to the executor, which keeps the request within the size of a packet.
+const MAX_POINTERS: usize = 65536;
+
+static SCANS: PgRefCell<Vec<Entry>> = unsafe { PgRefCell::new(Vec::new()) };
+
+pub unsafe fn on_executor_start(query_desc: *mut pgrx::pg_sys::QueryDesc) {
+    unsafe {
+        let planstate = (*query_desc).planstate;
+        if planstate.is_null() {
+            return;
+        }
+        let subxid = pgrx::pg_sys::GetCurrentSubTransactionId();
+        let mut scans = SCANS.borrow_mut();
+        walk(planstate, None, &mut |state, limit| {
+            scans.push(Entry {
+                query_desc: query_desc as usize,
+                subxid,
+                state: state as usize,
+                limit,
+            });
+        });
+    }
+}
+
+pub fn on_executor_end(query_desc: *mut pgrx::pg_sys::QueryDesc) {
+    let mut scans = SCANS.borrow_mut();
+    scans.retain(|x| x.query_desc != query_desc as usize);
+}
+
+pub fn on_subxact_abort(subxid: pgrx::pg_sys::SubTransactionId) {
+    let mut scans = SCANS.borrow_mut();
+    scans.retain(|x| x.subxid != subxid);
+}
+
+pub fn on_xact_end() {
+    SCANS.borrow_mut().clear();
+}
+
+unsafe fn find(scan: pgrx::pg_sys::IndexScanDesc) -> Option<(*mut PlanState, Option<u32>)> {
+    let scans = SCANS.borrow();
+    scans.iter().rev().find_map(|x| unsafe {
+        let state = x.state as *mut pgrx::pg_sys::IndexScanState;
+        ((*state).iss_ScanDesc == scan).then_some((state.cast(), x.limit))
+    })
+}
+
+/// Returns the number of tuples a query needs from an index scan, if the executor knows it.
+pub unsafe fn limit(scan: pgrx::pg_sys::IndexScanDesc) -> Option<u32> {
+    unsafe { find(scan)?.1 }
+}
+
+/// Returns pointers of the rows that pass the filter the executor applies to the results of an
+/// index scan, which is evaluated over the whole table, so vector search skips other rows. It
+/// returns `None` if more than `MAX_POINTERS` rows pass the filter.
+pub unsafe fn filter(scan: pgrx::pg_sys::IndexScanDesc) -> Option<AllowList> {
+    use pgrx::pg_sys::ScanOptions::*;
+    if !enable_prefilter() {
+        return None;
+    }
+    unsafe {
+        let (state, _) = find(scan)?;
+        let qual = (*state).qual;
+        if qual.is_null() {
+            return None;
+        }
+        // a volatile filter may not give the same result when the executor evaluates it again
+        if pgrx::pg_sys::contain_volatile_functions((*(*state).plan).qual.cast()) {
+            return None;
+        }
+        let econtext = (*state).ps_ExprContext;
+        let heap = (*scan).heapRelation;
+        let table_am = (*heap).rd_tableam;
+        let slot = pgrx::pg_sys::table_slot_create(heap, std::ptr::null_mut());
+        let table_scan = (*table_am).scan_begin.unwrap()(
+            heap,
+            (*scan).xs_snapshot,
+            0,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            SO_TYPE_SEQSCAN | SO_ALLOW_STRAT | SO_ALLOW_SYNC | SO_ALLOW_PAGEMODE,
+        );
+        let scantuple = (*econtext).ecxt_scantuple;
+        (*econtext).ecxt_scantuple = slot;
+        let mut pointers = Vec::new();
+        let mut overflow = false;
+        let forward = pgrx::pg_sys::ScanDirection::ForwardScanDirection;
+        while (*table_am).scan_getnextslot.unwrap()(table_scan, forward, slot) {
+            pgrx::check_for_interrupts!();
+            let memory = pgrx::pg_sys::MemoryContextSwitchTo((*econtext).ecxt_per_tuple_memory);
+            let mut is_null = false;
+            let result = (*qual).evalfunc.unwrap()(qual, econtext, &mut is_null);
+            pgrx::pg_sys::MemoryContextSwitchTo(memory);
+            if !is_null && result.value() != 0 {
+                pointers.push(ctid_to_pointer((*slot).tts_tid));
+            }
+            pgrx::pg_sys::MemoryContextReset((*econtext).ecxt_per_tuple_memory);
+            if pointers.len() > MAX_POINTERS {
+                overflow = true;
+                break;
+            }
+        }
+        (*econtext).ecxt_scantuple = scantuple;
+        (*table_am).scan_end.unwrap()(table_scan);
+        pgrx::pg_sys::ExecDropSingleTupleTableSlot(slot);
+        if overflow {
+            return None;
+        }
+        Some(AllowList::new(pointers))
+    }
+}
+
+unsafe fn walk(
+    node: *mut PlanState,
+    limit: Option<u32>,
+    f: &mut impl FnMut(*mut PlanState, Option<u32>),
+) {
+    unsafe {
+        if node.is_null() {
+            return;
+        }
+        if (*node).type_ == NodeTag::T_IndexScanState {
+            // rows that the executor filters out are not counted towards the limit
+            f(node, limit.filter(|_| (*node).qual.is_null()));
+        }
+        let outer = if (*node).type_ == NodeTag::T_LimitState {
+            limit_of((*node).plan.cast())
+        } else {
+            None
+        };
+        walk((*node).lefttree, outer, f);
+        walk((*node).righttree, None, f);
+        for list in [(*node).initPlan, (*node).subPlan] {
+            for subplan in pgrx::PgList::<pgrx::pg_sys::SubPlanState>::from_pg(list).iter_ptr() {
+                walk((*subplan).planstate, None, f);
+            }
+        }
+        let children = match (*node).type_ {
+            NodeTag::T_AppendState => {
+                let node = node.cast::<pgrx::pg_sys::AppendState>();
+                ((*node).appendplans, (*node).as_nplans)
+            }
+            NodeTag::T_MergeAppendState => {
+                let node = node.cast::<pgrx::pg_sys::MergeAppendState>();
+                ((*node).mergeplans, (*node).ms_nplans)
+            }
+            NodeTag::T_SubqueryScanState => {
+                let node = node.cast::<pgrx::pg_sys::SubqueryScanState>();
+                walk((*node).subplan, None, f);
+                (std::ptr::null_mut(), 0)
+            }
+            _ => (std::ptr::null_mut(), 0),
+        };
+        for i in 0..children.1 {
+            walk(*children.0.add(i as usize), None, f);
+        }
+    }
+}
+
+unsafe fn limit_of(plan: *mut pgrx::pg_sys::Limit) -> Option<u32> {
+    unsafe fn constant(node: *mut pgrx::pg_sys::Node) -> Option<i64> {
+        unsafe {
+            if node.is_null() || (*node).type_ != NodeTag::T_Const {
+                return None;
+            }
+            let node = node.cast::<pgrx::pg_sys::Const>();
+            if (*node).constisnull {
+                return None;
+            }
+            Some((*node).constvalue.value() as i64)
+        }
+    }
+    unsafe {
+        if (*plan).limitOption != pgrx::pg_sys::LimitOption::LIMIT_OPTION_COUNT {
+            return None;
+        }
+        let count = constant((*plan).limitCount)?;
+        let offset = if (*plan).limitOffset.is_null() {
+            0
+        } else {
+            constant((*plan).limitOffset)?
+        };
+        u32::try_from(count.checked_add(offset.max(0))?).ok()
+    }
+}
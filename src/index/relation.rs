@@ -0,0 +1,425 @@
+//! Relation-backed storage of a vector index.
+//!
+//! Block 0 of the index relation is a metapage, and the following blocks are an append-only
+//! log of [`LogRecord`]s, written through generic WAL records. The background worker's index
+//! is kept as a replay of the log, so a physical replica or a restored base backup rebuilds it
+//! from the relation. A base backup doesn't need `pg_vectors`, and a copy of it taken while the
+//! background worker was running is better removed before starting the restored server.
+//!
+//! Only the log is stored in the relation, so sealed segments are built again by the background
+//! worker of each server. Storing sealed segments, so that the log could be truncated after each
+//! seal, is not supported.
+//!
+//! The log is never truncated, since it's the only copy of the index that replicas and restored
+//! servers have. It grows with every insert and delete, including rows that are deleted later,
+//! so inserts fail once it reaches `vectors.relation_log_limit` blocks, until the index is rebuilt
+//! by `REINDEX`, which starts a new log. Deletes are still written, so `VACUUM` keeps working on a
+//! full log. A background worker that is restored from a snapshot replays the log from the
+//! position of the snapshot.
+
+use super::am_options;
+use crate::error::*;
+use crate::gucs::internal::RELATION_LOG_LIMIT;
+use crate::index::utils::from_oid_to_handle;
+use crate::ipc::ClientRpc;
+use base::index::*;
+use base::search::Handle;
+use base::vector::*;
+use pgrx::pg_sys::{Buffer, Page, Relation};
+
+const MAGIC: u64 = u64::from_le_bytes(*b"vectors\0");
+
+// keep batches well below the capacity of the mmap transport
+const REPLAY_BYTES: usize = 512 * 1024;
+
+// a chunk is `[length: u16][last: u8][bytes]`
+const CHUNK_HEADER: usize = 3;
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct Meta {
+    magic: u64,
+    log: u64,
+    block: u32,
+    offset: u16,
+}
+
+pub unsafe fn is_relation(index: Relation) -> bool {
+    unsafe {
+        pgrx::pg_sys::RelationGetNumberOfBlocksInFork(index, pgrx::pg_sys::ForkNumber::MAIN_FORKNUM)
+            > 0
+    }
+}
+
+/// Initializes an empty log in a newly built index and binds the background worker's index to it.
+pub unsafe fn create(index: Relation, rpc: &mut ClientRpc) {
+    let handle = handle(index);
+    let start = unsafe { init(index) };
+    match rpc.replay(handle, None, Vec::new(), start) {
+        Ok(()) => (),
+        Err(ReplayError::NotExist) => bad_service_not_exist(),
+        Err(ReplayError::InvalidVector | ReplayError::Conflict) => pgrx::error!("internal error"),
+    }
+}
+
+/// Appends a record to the log and applies it to the background worker's index.
+pub unsafe fn write(index: Relation, rpc: &mut ClientRpc, record: LogRecord) {
+    let handle = handle(index);
+    if let LogRecord::Insert(vectors) = &record {
+        let dims = unsafe { am_options::dims(index) };
+        if vectors
+            .iter()
+            .any(|(vector, _)| vector_dims(vector) != dims)
+        {
+            bad_service_invalid_vector();
+        }
+        if unsafe { tail(index) }.block >= RELATION_LOG_LIMIT.get() as u32 {
+            bad_relation_log_full();
+        }
+    }
+    let (start, end) = unsafe { append(index, &record) };
+    match rpc.replay(handle, Some(start), vec![record], end) {
+        Ok(()) => (),
+        Err(ReplayError::InvalidVector) => bad_service_invalid_vector(),
+        // other records were appended before this one, or the index is missing
+        Err(ReplayError::NotExist | ReplayError::Conflict) => unsafe { sync(index, rpc) },
+    }
+}
+
+/// Catches the background worker's index up with the log. The index is created if it's
+/// missing, and rebuilt if it doesn't hold a prefix of the log.
+pub unsafe fn sync(index: Relation, rpc: &mut ClientRpc) {
+    let handle = handle(index);
+    loop {
+        let end = unsafe { tail(index) };
+        let position = match rpc.stat(handle) {
+            Ok(stat) => stat.position,
+            Err(StatError::NotExist) => {
+                unsafe { recreate(index, rpc, false) };
+                None
+            }
+        };
+        let from = match position {
+            Some(position) if position == end => return,
+            // the index is behind the log
+            Some(position)
+                if position.log == end.log
+                    && (position.block, position.offset) < (end.block, end.offset) =>
+            {
+                Some(position)
+            }
+            // the index was built from another log, or from a later point of this log, which
+            // happens if the relation is restored to an earlier point
+            Some(_) => {
+                unsafe { recreate(index, rpc, true) };
+                None
+            }
+            None => None,
+        };
+        match unsafe { replay(index, rpc, from, end) } {
+            Ok(()) => return,
+            Err(ReplayError::InvalidVector) => bad_service_invalid_vector(),
+            Err(ReplayError::NotExist | ReplayError::Conflict) => continue,
+        }
+    }
+}
+
+unsafe fn recreate(index: Relation, rpc: &mut ClientRpc, drop: bool) {
+    let handle = handle(index);
+    if drop {
+        let _ = rpc.drop(handle);
+    }
+    let (options, alterable_options) = unsafe { am_options::options(index) };
+    match rpc.create(handle, options, alterable_options) {
+        Ok(()) => (),
+        Err(CreateError::InvalidIndexOptions { reason }) => {
+            bad_service_invalid_index_options(&reason);
+        }
+    }
+}
+
+unsafe fn replay(
+    index: Relation,
+    rpc: &mut ClientRpc,
+    from: Option<LogPosition>,
+    end: LogPosition,
+) -> Result<(), ReplayError> {
+    let handle = handle(index);
+    let start = LogPosition {
+        log: end.log,
+        block: 1,
+        offset: header_size(),
+    };
+    let mut expected = from;
+    let mut records = Vec::new();
+    let mut bytes = 0;
+    unsafe {
+        read(
+            index,
+            from.unwrap_or(start),
+            end,
+            |record, size, position| {
+                records.push(record);
+                bytes += size;
+                if bytes >= REPLAY_BYTES {
+                    rpc.replay(handle, expected, std::mem::take(&mut records), position)?;
+                    expected = Some(position);
+                    bytes = 0;
+                }
+                Ok(())
+            },
+        )?;
+    }
+    if expected != Some(end) {
+        rpc.replay(handle, expected, records, end)?;
+    }
+    Ok(())
+}
+
+fn handle(index: Relation) -> Handle {
+    from_oid_to_handle(unsafe { (*index).rd_id })
+}
+
+fn vector_dims(vector: &OwnedVector) -> u32 {
+    match vector.as_borrowed() {
+        BorrowedVector::Vecf32(x) => x.dims(),
+        BorrowedVector::Vecf16(x) => x.dims(),
+        BorrowedVector::Vecbf16(x) => x.dims(),
+        BorrowedVector::SVecf32(x) => x.dims(),
+        BorrowedVector::BVector(x) => x.dims(),
+        BorrowedVector::Veci8(x) => x.dims(),
+    }
+}
+
+fn header_size() -> u16 {
+    std::mem::offset_of!(pgrx::pg_sys::PageHeaderData, pd_linp) as u16
+}
+
+unsafe fn header(page: Page) -> *mut pgrx::pg_sys::PageHeaderData {
+    page.cast()
+}
+
+unsafe fn meta(page: Page) -> *mut Meta {
+    unsafe { page.add(header_size() as usize).cast() }
+}
+
+/// Mirrors `BufferGetPage`.
+unsafe fn buffer_page(buffer: Buffer) -> Page {
+    unsafe {
+        if buffer < 0 {
+            pgrx::pg_sys::LocalBufferBlockPointers
+                .add((-buffer - 1) as usize)
+                .read()
+                .cast()
+        } else {
+            pgrx::pg_sys::BufferBlocks.add((buffer - 1) as usize * pgrx::pg_sys::BLCKSZ as usize)
+        }
+    }
+}
+
+unsafe fn extend(index: Relation) -> Buffer {
+    unsafe {
+        let lockmode = pgrx::pg_sys::ExclusiveLock as pgrx::pg_sys::LOCKMODE;
+        pgrx::pg_sys::LockRelationForExtension(index, lockmode);
+        // `P_NEW`
+        let buffer = pgrx::pg_sys::ReadBuffer(index, pgrx::pg_sys::BlockNumber::MAX);
+        pgrx::pg_sys::UnlockRelationForExtension(index, lockmode);
+        buffer
+    }
+}
+
+unsafe fn init(index: Relation) -> LogPosition {
+    let exclusive = pgrx::pg_sys::BUFFER_LOCK_EXCLUSIVE as i32;
+    let full_image = pgrx::pg_sys::GENERIC_XLOG_FULL_IMAGE as i32;
+    let log = rand::random::<u64>();
+    unsafe {
+        let meta_buffer = extend(index);
+        let data_buffer = extend(index);
+        assert_eq!(pgrx::pg_sys::BufferGetBlockNumber(meta_buffer), 0);
+        assert_eq!(pgrx::pg_sys::BufferGetBlockNumber(data_buffer), 1);
+        pgrx::pg_sys::LockBuffer(meta_buffer, exclusive);
+        pgrx::pg_sys::LockBuffer(data_buffer, exclusive);
+        let state = pgrx::pg_sys::GenericXLogStart(index);
+        let meta_page = pgrx::pg_sys::GenericXLogRegisterBuffer(state, meta_buffer, full_image);
+        let data_page = pgrx::pg_sys::GenericXLogRegisterBuffer(state, data_buffer, full_image);
+        pgrx::pg_sys::PageInit(meta_page, pgrx::pg_sys::BLCKSZ as _, 0);
+        pgrx::pg_sys::PageInit(data_page, pgrx::pg_sys::BLCKSZ as _, 0);
+        meta(meta_page).write(Meta {
+            magic: MAGIC,
+            log,
+            block: 1,
+            offset: header_size(),
+        });
+        (*header(meta_page)).pd_lower = header_size() + size_of::<Meta>() as u16;
+        pgrx::pg_sys::GenericXLogFinish(state);
+        pgrx::pg_sys::UnlockReleaseBuffer(data_buffer);
+        pgrx::pg_sys::UnlockReleaseBuffer(meta_buffer);
+    }
+    LogPosition {
+        log,
+        block: 1,
+        offset: header_size(),
+    }
+}
+
+/// Returns the end of the log.
+unsafe fn tail(index: Relation) -> LogPosition {
+    unsafe {
+        let buffer = pgrx::pg_sys::ReadBuffer(index, 0);
+        pgrx::pg_sys::LockBuffer(buffer, pgrx::pg_sys::BUFFER_LOCK_SHARE as i32);
+        let meta = meta(buffer_page(buffer)).read();
+        pgrx::pg_sys::UnlockReleaseBuffer(buffer);
+        if meta.magic != MAGIC {
+            pgrx::error!("pgvecto.rs: The metapage of the index is corrupted.");
+        }
+        LogPosition {
+            log: meta.log,
+            block: meta.block,
+            offset: meta.offset,
+        }
+    }
+}
+
+/// Appends a record to the log, returning its start and its end. The metapage is only
+/// updated by the last WAL record, so a record interrupted by a crash is overwritten.
+unsafe fn append(index: Relation, record: &LogRecord) -> (LogPosition, LogPosition) {
+    // a generic WAL record holds at most 4 pages, which are the metapage and 3 log pages
+    const PAGES: usize = 3;
+    let exclusive = pgrx::pg_sys::BUFFER_LOCK_EXCLUSIVE as i32;
+    let full_image = pgrx::pg_sys::GENERIC_XLOG_FULL_IMAGE as i32;
+    let blcksz = pgrx::pg_sys::BLCKSZ as usize;
+    let bytes = bincode::serialize(record).unwrap();
+    unsafe {
+        let meta_buffer = pgrx::pg_sys::ReadBuffer(index, 0);
+        pgrx::pg_sys::LockBuffer(meta_buffer, exclusive);
+        let start = {
+            let meta = meta(buffer_page(meta_buffer)).read();
+            if meta.magic != MAGIC {
+                pgrx::error!("pgvecto.rs: The metapage of the index is corrupted.");
+            }
+            LogPosition {
+                log: meta.log,
+                block: meta.block,
+                offset: meta.offset,
+            }
+        };
+        let mut nblocks = pgrx::pg_sys::RelationGetNumberOfBlocksInFork(
+            index,
+            pgrx::pg_sys::ForkNumber::MAIN_FORKNUM,
+        );
+        let mut state = pgrx::pg_sys::GenericXLogStart(index);
+        let mut meta_page = pgrx::pg_sys::GenericXLogRegisterBuffer(state, meta_buffer, 0);
+        let mut block = start.block;
+        let mut buffer = pgrx::pg_sys::ReadBuffer(index, block);
+        pgrx::pg_sys::LockBuffer(buffer, exclusive);
+        let mut page = pgrx::pg_sys::GenericXLogRegisterBuffer(state, buffer, 0);
+        // drop anything left by an interrupted append
+        (*header(page)).pd_lower = start.offset;
+        let mut buffers = vec![buffer];
+        let mut rest = bytes.as_slice();
+        loop {
+            let lower = (*header(page)).pd_lower as usize;
+            if blcksz - lower > CHUNK_HEADER {
+                let n = std::cmp::min(blcksz - lower - CHUNK_HEADER, rest.len());
+                let last = n == rest.len();
+                let chunk = page.add(lower).cast::<u8>();
+                chunk.copy_from_nonoverlapping((n as u16).to_le_bytes().as_ptr(), 2);
+                chunk.add(2).write(last as u8);
+                chunk
+                    .add(CHUNK_HEADER)
+                    .copy_from_nonoverlapping(rest.as_ptr(), n);
+                (*header(page)).pd_lower = (lower + CHUNK_HEADER + n) as u16;
+                rest = &rest[n..];
+                if last {
+                    break;
+                }
+            }
+            if buffers.len() == PAGES {
+                pgrx::pg_sys::GenericXLogFinish(state);
+                for buffer in buffers.drain(..) {
+                    pgrx::pg_sys::UnlockReleaseBuffer(buffer);
+                }
+                state = pgrx::pg_sys::GenericXLogStart(index);
+                meta_page = pgrx::pg_sys::GenericXLogRegisterBuffer(state, meta_buffer, 0);
+            }
+            block += 1;
+            buffer = if block < nblocks {
+                // left by an interrupted append
+                pgrx::pg_sys::ReadBuffer(index, block)
+            } else {
+                nblocks += 1;
+                extend(index)
+            };
+            pgrx::pg_sys::LockBuffer(buffer, exclusive);
+            page = pgrx::pg_sys::GenericXLogRegisterBuffer(state, buffer, full_image);
+            pgrx::pg_sys::PageInit(page, blcksz, 0);
+            buffers.push(buffer);
+        }
+        let end = LogPosition {
+            log: start.log,
+            block,
+            offset: (*header(page)).pd_lower,
+        };
+        (*meta(meta_page)).block = end.block;
+        (*meta(meta_page)).offset = end.offset;
+        pgrx::pg_sys::GenericXLogFinish(state);
+        for buffer in buffers {
+            pgrx::pg_sys::UnlockReleaseBuffer(buffer);
+        }
+        pgrx::pg_sys::UnlockReleaseBuffer(meta_buffer);
+        (start, end)
+    }
+}
+
+/// Reads records from `from` to `end`, passing each of them with its size and its end.
+unsafe fn read<E>(
+    index: Relation,
+    from: LogPosition,
+    end: LogPosition,
+    mut f: impl FnMut(LogRecord, usize, LogPosition) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut block = from.block;
+    let mut offset = from.offset;
+    let mut bytes = Vec::new();
+    while (block, offset) < (end.block, end.offset) {
+        let mut records = Vec::new();
+        unsafe {
+            let buffer = pgrx::pg_sys::ReadBuffer(index, block);
+            pgrx::pg_sys::LockBuffer(buffer, pgrx::pg_sys::BUFFER_LOCK_SHARE as i32);
+            let page = buffer_page(buffer);
+            let limit = if block == end.block {
+                end.offset
+            } else {
+                (*header(page)).pd_lower
+            };
+            while offset < limit {
+                let chunk = page.add(offset as usize).cast::<u8>();
+                let n = u16::from_le_bytes([chunk.read(), chunk.add(1).read()]) as usize;
+                let last = chunk.add(2).read() != 0;
+                bytes.extend_from_slice(std::slice::from_raw_parts(chunk.add(CHUNK_HEADER), n));
+                offset += (CHUNK_HEADER + n) as u16;
+                if last {
+                    let record = bincode::deserialize::<LogRecord>(&bytes).unwrap();
+                    let position = LogPosition {
+                        log: end.log,
+                        block,
+                        offset,
+                    };
+                    records.push((record, bytes.len(), position));
+                    bytes.clear();
+                }
+            }
+            pgrx::pg_sys::UnlockReleaseBuffer(buffer);
+        }
+        // call back without holding the buffer lock
+        for (record, size, position) in records {
+            f(record, size, position)?;
+        }
+        if block == end.block {
+            break;
+        }
+        block += 1;
+        offset = header_size();
+    }
+    Ok(())
+}
@@ -29,6 +29,7 @@ fn _vectors_index_stat(
             indexing,
             options,
             segments,
+            ..
         }) => {
             res.set_by_name("idx_status", "NORMAL").unwrap();
             res.set_by_name("idx_indexing", indexing).unwrap();
@@ -41,7 +42,7 @@ fn _vectors_index_stat(
                 "idx_sealed",
                 segments
                     .iter()
-                    .filter(|x| x.r#type == "sealed")
+                    .filter(|x| x.r#type == "sealed" || x.r#type == "stored")
                     .map(|x| x.length as i64)
                     .collect::<Vec<_>>(),
             )
@@ -78,3 +79,28 @@ fn _vectors_index_stat(
         }
     }
 }
+
+#[pgrx::pg_extern(volatile, strict)]
+fn _vectors_verify_vector_index(
+    oid: pgrx::pg_sys::Oid,
+) -> pgrx::iter::TableIterator<
+    'static,
+    (
+        pgrx::name!(location, String),
+        pgrx::name!(kind, String),
+        pgrx::name!(error, Option<String>),
+    ),
+> {
+    let handle = from_oid_to_handle(oid);
+    let mut rpc = check_client(client());
+    match rpc.verify(handle) {
+        Ok(checks) => pgrx::iter::TableIterator::new(checks.into_iter().map(
+            |VerifyCheck {
+                 location,
+                 kind,
+                 error,
+             }| (location, kind, error),
+        )),
+        Err(VerifyError::NotExist) => bad_service_not_exist(),
+    }
+}
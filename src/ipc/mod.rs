@@ -11,6 +11,10 @@ use base::index::*;
 use base::search::*;
 use base::vector::*;
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+const MAX_BATCH_SIZE: u32 = 16384;
 
 #[derive(Debug, Clone)]
 pub enum ConnectionError {
@@ -122,13 +126,13 @@ macro_rules! define_packets {
 
             #[derive(Debug, Serialize, Deserialize)]
             pub enum [<Packet $name:camel>] {
-                Next {},
+                Next { size: u32 },
                 Leave {},
             }
 
             #[derive(Debug, Serialize, Deserialize)]
             pub struct [<Packet $name:camel 1>] {
-                pub p: Option<$r>,
+                pub p: Vec<$r>,
             }
 
             #[derive(Debug, Serialize, Deserialize)]
@@ -161,7 +165,10 @@ macro_rules! define_client_stuffs {
                         Err((self, e))
                     } else {
                         Ok([<Client $name:camel>] {
-                            socket: self.socket.take()
+                            socket: self.socket.take(),
+                            buffer: VecDeque::new(),
+                            size: crate::gucs::executing::stream_batch_size(),
+                            finished: false,
                         })
                     }
                 }
@@ -169,6 +176,9 @@ macro_rules! define_client_stuffs {
 
             pub struct [<Client $name:camel>] {
                 socket: Option<ClientSocket>,
+                buffer: VecDeque<$r>,
+                size: u32,
+                finished: bool,
             }
 
             impl [<Client $name:camel>] {
@@ -182,10 +192,16 @@ macro_rules! define_client_stuffs {
 
             impl [<Client $name:camel>] {
                 pub fn next(&mut self) -> Option<$r> {
-                    let packet = [<Packet $name:camel>]::Next {};
-                    check_connection(self._ok(packet));
-                    let [<Packet $name:camel 1>] { p } = check_connection(self._recv());
-                    p
+                    if self.buffer.is_empty() && !self.finished {
+                        let size = self.size;
+                        let packet = [<Packet $name:camel>]::Next { size };
+                        check_connection(self._ok(packet));
+                        let [<Packet $name:camel 1>] { p } = check_connection(self._recv());
+                        self.finished = p.len() < size as usize;
+                        self.buffer.extend(p);
+                        self.size = std::cmp::min(size.saturating_mul(2), MAX_BATCH_SIZE);
+                    }
+                    self.buffer.pop_front()
                 }
                 pub fn leave(mut self) -> ClientRpc {
                     let packet = [<Packet $name:camel>]::Leave {};
@@ -244,9 +260,10 @@ macro_rules! define_server_stuffs {
             impl [<Server $name:camel Handler>] {
                 pub fn handle(mut self) -> Result<[<Server $name:camel Handle>], ConnectionError> {
                     Ok(match self.socket.recv::<[<Packet $name:camel>]>()? {
-                        [<Packet $name:camel>]::Next {} => [<Server $name:camel Handle>]::Next {
+                        [<Packet $name:camel>]::Next { size } => [<Server $name:camel Handle>]::Next {
                             x: [<Server $name:camel Next>] {
                                 socket: self.socket,
+                                size,
                             },
                         },
                         [<Packet $name:camel>]::Leave {} => {
@@ -268,10 +285,14 @@ macro_rules! define_server_stuffs {
 
             pub struct [<Server $name:camel Next>] {
                 socket: ServerSocket,
+                size: u32,
             }
 
             impl [<Server $name:camel Next>] {
-                pub fn leave(mut self, p: Option<$r>) -> Result<[<Server $name:camel Handler>], ConnectionError> {
+                pub fn size(&self) -> usize {
+                    self.size as usize
+                }
+                pub fn leave(mut self, p: Vec<$r>) -> Result<[<Server $name:camel Handler>], ConnectionError> {
                     let packet = [<Packet $name:camel 1>] { p };
                     self.socket.ok(packet)?;
                     Ok([<Server $name:camel Handler>] {
@@ -325,11 +346,20 @@ defines! {
     unary drop(handle: Handle) -> ();
     unary flush(handle: Handle) -> ();
     unary insert(handle: Handle, vector: OwnedVector, pointer: Pointer) -> ();
+    unary insert_many(handle: Handle, vectors: Vec<(OwnedVector, Pointer)>) -> ();
+    unary build(handle: Handle, vectors: Vec<(OwnedVector, Pointer)>) -> ();
     unary delete(handle: Handle, pointer: Pointer) -> ();
-    stream vbase(handle: Handle, vector: OwnedVector, opts: SearchOptions) -> (Distance, Pointer);
+    unary replay(handle: Handle, expected: Option<LogPosition>, records: Vec<LogRecord>, next: LogPosition) -> ();
+    stream vbase(handle: Handle, vector: OwnedVector, opts: SearchOptions, filter: Option<AllowList>) -> (Distance, Pointer);
+    stream vbase_multi(handle: Handle, vectors: Vec<OwnedVector>, opts: SearchOptions, filter: Option<AllowList>) -> (Distance, Pointer);
     stream list(handle: Handle) -> Pointer;
     unary stat(handle: Handle) -> IndexStat;
+    unary verify(handle: Handle) -> Vec<VerifyCheck>;
     unary alter(handle: Handle, key: String, value: String) -> ();
+    unary snapshot(handle: Handle, dest: PathBuf) -> ();
+    unary restore(handle: Handle, source: PathBuf) -> ();
+    unary attach(handle: Handle, source: PathBuf, labels: PathBuf) -> ();
+    unary upgrade(handle: Handle, options: IndexOptions) -> ();
     unary stop(handle: Handle) -> ();
     unary start(handle: Handle) -> ();
 }
@@ -6,6 +6,8 @@ CREATE TYPE vector;
 CREATE TYPE vecf16;
 CREATE TYPE svector;
 CREATE TYPE bvector;
+CREATE TYPE veci8;
+CREATE TYPE vecbf16;
 
 CREATE TYPE vector_index_stat;
 
@@ -13,5 +15,10 @@ CREATE TYPE sphere_vector;
 CREATE TYPE sphere_vecf16;
 CREATE TYPE sphere_svector;
 CREATE TYPE sphere_bvector;
+CREATE TYPE sphere_veci8;
+CREATE TYPE sphere_vecbf16;
+
+CREATE TYPE weighted_vector;
+CREATE TYPE weighted_vecf16;
 
 -- bootstrap end
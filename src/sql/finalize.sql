@@ -54,6 +54,32 @@ CREATE TYPE bvector (
     ALIGNMENT = double
 );
 
+CREATE TYPE veci8 (
+    INPUT = _vectors_veci8_in,
+    OUTPUT = _vectors_veci8_out,
+    RECEIVE = _vectors_veci8_recv,
+    SEND = _vectors_veci8_send,
+    SUBSCRIPT = _vectors_veci8_subscript,
+    TYPMOD_IN = _vectors_typmod_in_65535,
+    TYPMOD_OUT = _vectors_typmod_out,
+    STORAGE = EXTERNAL,
+    INTERNALLENGTH = VARIABLE,
+    ALIGNMENT = double
+);
+
+CREATE TYPE vecbf16 (
+    INPUT = _vectors_vecbf16_in,
+    OUTPUT = _vectors_vecbf16_out,
+    RECEIVE = _vectors_vecbf16_recv,
+    SEND = _vectors_vecbf16_send,
+    SUBSCRIPT = _vectors_vecbf16_subscript,
+    TYPMOD_IN = _vectors_typmod_in_65535,
+    TYPMOD_OUT = _vectors_typmod_out,
+    STORAGE = EXTERNAL,
+    INTERNALLENGTH = VARIABLE,
+    ALIGNMENT = double
+);
+
 CREATE TYPE vector_index_stat AS (
     idx_status TEXT,
     idx_indexing BOOL,
@@ -85,6 +111,26 @@ CREATE TYPE sphere_bvector AS (
     radius REAL
 );
 
+CREATE TYPE sphere_veci8 AS (
+    center veci8,
+    radius REAL
+);
+
+CREATE TYPE sphere_vecbf16 AS (
+    center vecbf16,
+    radius REAL
+);
+
+CREATE TYPE weighted_vector AS (
+    center vector,
+    weights vector
+);
+
+CREATE TYPE weighted_vecf16 AS (
+    center vecf16,
+    weights vector
+);
+
 -- List of operators
 
 CREATE OPERATOR + (
@@ -108,6 +154,20 @@ CREATE OPERATOR + (
     COMMUTATOR = +
 );
 
+CREATE OPERATOR + (
+    PROCEDURE = _vectors_veci8_operator_add,
+    LEFTARG = veci8,
+    RIGHTARG = veci8,
+    COMMUTATOR = +
+);
+
+CREATE OPERATOR + (
+    PROCEDURE = _vectors_vecbf16_operator_add,
+    LEFTARG = vecbf16,
+    RIGHTARG = vecbf16,
+    COMMUTATOR = +
+);
+
 CREATE OPERATOR - (
     PROCEDURE = _vectors_vecf32_operator_sub,
     LEFTARG = vector,
@@ -126,6 +186,18 @@ CREATE OPERATOR - (
     RIGHTARG = svector
 );
 
+CREATE OPERATOR - (
+    PROCEDURE = _vectors_veci8_operator_sub,
+    LEFTARG = veci8,
+    RIGHTARG = veci8
+);
+
+CREATE OPERATOR - (
+    PROCEDURE = _vectors_vecbf16_operator_sub,
+    LEFTARG = vecbf16,
+    RIGHTARG = vecbf16
+);
+
 CREATE OPERATOR * (
     PROCEDURE = _vectors_vecf32_operator_mul,
     LEFTARG = vector,
@@ -147,6 +219,20 @@ CREATE OPERATOR * (
     COMMUTATOR = *
 );
 
+CREATE OPERATOR * (
+    PROCEDURE = _vectors_veci8_operator_mul,
+    LEFTARG = veci8,
+    RIGHTARG = veci8,
+    COMMUTATOR = *
+);
+
+CREATE OPERATOR * (
+    PROCEDURE = _vectors_vecbf16_operator_mul,
+    LEFTARG = vecbf16,
+    RIGHTARG = vecbf16,
+    COMMUTATOR = *
+);
+
 CREATE OPERATOR & (
     PROCEDURE = _vectors_bvector_operator_and,
     LEFTARG = bvector,
@@ -205,6 +291,26 @@ CREATE OPERATOR = (
     JOIN = eqjoinsel
 );
 
+CREATE OPERATOR = (
+    PROCEDURE = _vectors_veci8_operator_eq,
+    LEFTARG = veci8,
+    RIGHTARG = veci8,
+    COMMUTATOR = =,
+    NEGATOR = <>,
+    RESTRICT = eqsel,
+    JOIN = eqjoinsel
+);
+
+CREATE OPERATOR = (
+    PROCEDURE = _vectors_vecbf16_operator_eq,
+    LEFTARG = vecbf16,
+    RIGHTARG = vecbf16,
+    COMMUTATOR = =,
+    NEGATOR = <>,
+    RESTRICT = eqsel,
+    JOIN = eqjoinsel
+);
+
 CREATE OPERATOR <> (
     PROCEDURE = _vectors_vecf32_operator_neq,
     LEFTARG = vector,
@@ -245,6 +351,26 @@ CREATE OPERATOR <> (
     JOIN = eqjoinsel
 );
 
+CREATE OPERATOR <> (
+    PROCEDURE = _vectors_veci8_operator_neq,
+    LEFTARG = veci8,
+    RIGHTARG = veci8,
+    COMMUTATOR = <>,
+    NEGATOR = =,
+    RESTRICT = eqsel,
+    JOIN = eqjoinsel
+);
+
+CREATE OPERATOR <> (
+    PROCEDURE = _vectors_vecbf16_operator_neq,
+    LEFTARG = vecbf16,
+    RIGHTARG = vecbf16,
+    COMMUTATOR = <>,
+    NEGATOR = =,
+    RESTRICT = eqsel,
+    JOIN = eqjoinsel
+);
+
 CREATE OPERATOR < (
     PROCEDURE = _vectors_vecf32_operator_lt,
     LEFTARG = vector,
@@ -285,6 +411,26 @@ CREATE OPERATOR < (
     JOIN = scalarltjoinsel
 );
 
+CREATE OPERATOR < (
+    PROCEDURE = _vectors_veci8_operator_lt,
+    LEFTARG = veci8,
+    RIGHTARG = veci8,
+    COMMUTATOR = >,
+    NEGATOR = >=,
+    RESTRICT = scalarltsel,
+    JOIN = scalarltjoinsel
+);
+
+CREATE OPERATOR < (
+    PROCEDURE = _vectors_vecbf16_operator_lt,
+    LEFTARG = vecbf16,
+    RIGHTARG = vecbf16,
+    COMMUTATOR = >,
+    NEGATOR = >=,
+    RESTRICT = scalarltsel,
+    JOIN = scalarltjoinsel
+);
+
 CREATE OPERATOR > (
     PROCEDURE = _vectors_vecf32_operator_gt,
     LEFTARG = vector,
@@ -325,6 +471,26 @@ CREATE OPERATOR > (
     JOIN = scalargtjoinsel
 );
 
+CREATE OPERATOR > (
+    PROCEDURE = _vectors_veci8_operator_gt,
+    LEFTARG = veci8,
+    RIGHTARG = veci8,
+    COMMUTATOR = <,
+    NEGATOR = <=,
+    RESTRICT = scalargtsel,
+    JOIN = scalargtjoinsel
+);
+
+CREATE OPERATOR > (
+    PROCEDURE = _vectors_vecbf16_operator_gt,
+    LEFTARG = vecbf16,
+    RIGHTARG = vecbf16,
+    COMMUTATOR = <,
+    NEGATOR = <=,
+    RESTRICT = scalargtsel,
+    JOIN = scalargtjoinsel
+);
+
 CREATE OPERATOR <= (
     PROCEDURE = _vectors_vecf32_operator_lte,
     LEFTARG = vector,
@@ -365,6 +531,26 @@ CREATE OPERATOR <= (
     JOIN = scalarltjoinsel
 );
 
+CREATE OPERATOR <= (
+    PROCEDURE = _vectors_veci8_operator_lte,
+    LEFTARG = veci8,
+    RIGHTARG = veci8,
+    COMMUTATOR = >=,
+    NEGATOR = >,
+    RESTRICT = scalarltsel,
+    JOIN = scalarltjoinsel
+);
+
+CREATE OPERATOR <= (
+    PROCEDURE = _vectors_vecbf16_operator_lte,
+    LEFTARG = vecbf16,
+    RIGHTARG = vecbf16,
+    COMMUTATOR = >=,
+    NEGATOR = >,
+    RESTRICT = scalarltsel,
+    JOIN = scalarltjoinsel
+);
+
 CREATE OPERATOR >= (
     PROCEDURE = _vectors_vecf32_operator_gte,
     LEFTARG = vector,
@@ -405,6 +591,26 @@ CREATE OPERATOR >= (
     JOIN = scalargtjoinsel
 );
 
+CREATE OPERATOR >= (
+    PROCEDURE = _vectors_veci8_operator_gte,
+    LEFTARG = veci8,
+    RIGHTARG = veci8,
+    COMMUTATOR = <=,
+    NEGATOR = <,
+    RESTRICT = scalargtsel,
+    JOIN = scalargtjoinsel
+);
+
+CREATE OPERATOR >= (
+    PROCEDURE = _vectors_vecbf16_operator_gte,
+    LEFTARG = vecbf16,
+    RIGHTARG = vecbf16,
+    COMMUTATOR = <=,
+    NEGATOR = <,
+    RESTRICT = scalargtsel,
+    JOIN = scalargtjoinsel
+);
+
 CREATE OPERATOR <-> (
     PROCEDURE = _vectors_vecf32_operator_l2,
     LEFTARG = vector,
@@ -433,6 +639,20 @@ CREATE OPERATOR <-> (
     COMMUTATOR = <->
 );
 
+CREATE OPERATOR <-> (
+    PROCEDURE = _vectors_veci8_operator_l2,
+    LEFTARG = veci8,
+    RIGHTARG = veci8,
+    COMMUTATOR = <->
+);
+
+CREATE OPERATOR <-> (
+    PROCEDURE = _vectors_vecbf16_operator_l2,
+    LEFTARG = vecbf16,
+    RIGHTARG = vecbf16,
+    COMMUTATOR = <->
+);
+
 CREATE OPERATOR <#> (
     PROCEDURE = _vectors_vecf32_operator_dot,
     LEFTARG = vector,
@@ -461,6 +681,20 @@ CREATE OPERATOR <#> (
     COMMUTATOR = <#>
 );
 
+CREATE OPERATOR <#> (
+    PROCEDURE = _vectors_veci8_operator_dot,
+    LEFTARG = veci8,
+    RIGHTARG = veci8,
+    COMMUTATOR = <#>
+);
+
+CREATE OPERATOR <#> (
+    PROCEDURE = _vectors_vecbf16_operator_dot,
+    LEFTARG = vecbf16,
+    RIGHTARG = vecbf16,
+    COMMUTATOR = <#>
+);
+
 CREATE OPERATOR <=> (
     PROCEDURE = _vectors_vecf32_operator_cos,
     LEFTARG = vector,
@@ -482,6 +716,46 @@ CREATE OPERATOR <=> (
     COMMUTATOR = <=>
 );
 
+CREATE OPERATOR <=> (
+    PROCEDURE = _vectors_veci8_operator_cos,
+    LEFTARG = veci8,
+    RIGHTARG = veci8,
+    COMMUTATOR = <=>
+);
+
+CREATE OPERATOR <=> (
+    PROCEDURE = _vectors_vecbf16_operator_cos,
+    LEFTARG = vecbf16,
+    RIGHTARG = vecbf16,
+    COMMUTATOR = <=>
+);
+
+CREATE OPERATOR <+> (
+    PROCEDURE = _vectors_vecf32_operator_l1,
+    LEFTARG = vector,
+    RIGHTARG = vector,
+    COMMUTATOR = <+>
+);
+
+CREATE OPERATOR <+> (
+    PROCEDURE = _vectors_vecf16_operator_l1,
+    LEFTARG = vecf16,
+    RIGHTARG = vecf16,
+    COMMUTATOR = <+>
+);
+
+CREATE OPERATOR <-> (
+    PROCEDURE = _vectors_vecf32_operator_weighted_l2,
+    LEFTARG = vector,
+    RIGHTARG = weighted_vector
+);
+
+CREATE OPERATOR <-> (
+    PROCEDURE = _vectors_vecf16_operator_weighted_l2,
+    LEFTARG = vecf16,
+    RIGHTARG = weighted_vecf16
+);
+
 CREATE OPERATOR <~> (
     PROCEDURE = _vectors_bvector_operator_jaccard,
     LEFTARG = bvector,
@@ -489,6 +763,24 @@ CREATE OPERATOR <~> (
     COMMUTATOR = <~>
 );
 
+CREATE OPERATOR <-> (
+    PROCEDURE = _vectors_vecf32_array_operator_l2,
+    LEFTARG = vector[],
+    RIGHTARG = vector[]
+);
+
+CREATE OPERATOR <#> (
+    PROCEDURE = _vectors_vecf32_array_operator_dot,
+    LEFTARG = vector[],
+    RIGHTARG = vector[]
+);
+
+CREATE OPERATOR <=> (
+    PROCEDURE = _vectors_vecf32_array_operator_cos,
+    LEFTARG = vector[],
+    RIGHTARG = vector[]
+);
+
 CREATE OPERATOR <<->> (
     PROCEDURE = _vectors_vecf32_sphere_l2_in,
     LEFTARG = vector,
@@ -517,6 +809,20 @@ CREATE OPERATOR <<->> (
     COMMUTATOR = <<->>
 );
 
+CREATE OPERATOR <<->> (
+    PROCEDURE = _vectors_veci8_sphere_l2_in,
+    LEFTARG = veci8,
+    RIGHTARG = sphere_veci8,
+    COMMUTATOR = <<->>
+);
+
+CREATE OPERATOR <<->> (
+    PROCEDURE = _vectors_vecbf16_sphere_l2_in,
+    LEFTARG = vecbf16,
+    RIGHTARG = sphere_vecbf16,
+    COMMUTATOR = <<->>
+);
+
 CREATE OPERATOR <<#>> (
     PROCEDURE = _vectors_vecf32_sphere_dot_in,
     LEFTARG = vector,
@@ -545,6 +851,20 @@ CREATE OPERATOR <<#>> (
     COMMUTATOR = <<#>>
 );
 
+CREATE OPERATOR <<#>> (
+    PROCEDURE = _vectors_veci8_sphere_dot_in,
+    LEFTARG = veci8,
+    RIGHTARG = sphere_veci8,
+    COMMUTATOR = <<#>>
+);
+
+CREATE OPERATOR <<#>> (
+    PROCEDURE = _vectors_vecbf16_sphere_dot_in,
+    LEFTARG = vecbf16,
+    RIGHTARG = sphere_vecbf16,
+    COMMUTATOR = <<#>>
+);
+
 CREATE OPERATOR <<=>> (
     PROCEDURE = _vectors_vecf32_sphere_cos_in,
     LEFTARG = vector,
@@ -566,6 +886,34 @@ CREATE OPERATOR <<=>> (
     COMMUTATOR = <<=>>
 );
 
+CREATE OPERATOR <<=>> (
+    PROCEDURE = _vectors_veci8_sphere_cos_in,
+    LEFTARG = veci8,
+    RIGHTARG = sphere_veci8,
+    COMMUTATOR = <<=>>
+);
+
+CREATE OPERATOR <<=>> (
+    PROCEDURE = _vectors_vecbf16_sphere_cos_in,
+    LEFTARG = vecbf16,
+    RIGHTARG = sphere_vecbf16,
+    COMMUTATOR = <<=>>
+);
+
+CREATE OPERATOR <<+>> (
+    PROCEDURE = _vectors_vecf32_sphere_l1_in,
+    LEFTARG = vector,
+    RIGHTARG = sphere_vector,
+    COMMUTATOR = <<+>>
+);
+
+CREATE OPERATOR <<+>> (
+    PROCEDURE = _vectors_vecf16_sphere_l1_in,
+    LEFTARG = vecf16,
+    RIGHTARG = sphere_vecf16,
+    COMMUTATOR = <<+>>
+);
+
 CREATE OPERATOR <<~>> (
     PROCEDURE = _vectors_bvector_sphere_jaccard_in,
     LEFTARG = bvector,
@@ -598,6 +946,23 @@ STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_alter_vector_ind
 CREATE FUNCTION fence_vector_index(oid) RETURNS void
 STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_fence_vector_index_wrapper';
 
+CREATE FUNCTION snapshot_vector_index("index" OID, "path" TEXT) RETURNS void
+STRICT LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_snapshot_vector_index_wrapper';
+
+CREATE FUNCTION restore_vector_index("index" OID, "path" TEXT) RETURNS void
+STRICT LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_restore_vector_index_wrapper';
+
+CREATE FUNCTION attach_vector_index("index" OID, "path" TEXT, "labels" BIGINT[], "ctids" TID[]) RETURNS void
+STRICT LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_attach_vector_index_wrapper';
+
+CREATE FUNCTION verify_vector_index("index" OID) RETURNS TABLE(location TEXT, kind TEXT, error TEXT)
+STRICT LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_verify_vector_index_wrapper';
+
+-- they read and write files of the server
+REVOKE ALL ON FUNCTION snapshot_vector_index(OID, TEXT) FROM PUBLIC;
+REVOKE ALL ON FUNCTION restore_vector_index(OID, TEXT) FROM PUBLIC;
+REVOKE ALL ON FUNCTION attach_vector_index(OID, TEXT, BIGINT[], TID[]) FROM PUBLIC;
+
 CREATE FUNCTION vector_dims(vector) RETURNS INT
 IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_vecf32_dims_wrapper';
 
@@ -610,6 +975,12 @@ IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_svecf3
 CREATE FUNCTION vector_dims(bvector) RETURNS INT
 IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_bvector_dims_wrapper';
 
+CREATE FUNCTION vector_dims(veci8) RETURNS INT
+IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_veci8_dims_wrapper';
+
+CREATE FUNCTION vector_dims(vecbf16) RETURNS INT
+IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_vecbf16_dims_wrapper';
+
 CREATE FUNCTION vector_norm(vector) RETURNS real
 IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_vecf32_norm_wrapper';
 
@@ -622,6 +993,12 @@ IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_svecf3
 CREATE FUNCTION vector_norm(bvector) RETURNS real
 IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_bvector_norm_wrapper';
 
+CREATE FUNCTION vector_norm(veci8) RETURNS real
+IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_veci8_norm_wrapper';
+
+CREATE FUNCTION vector_norm(vecbf16) RETURNS real
+IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_vecbf16_norm_wrapper';
+
 CREATE FUNCTION vector_normalize(vector) RETURNS vector
 IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_vecf32_normalize_wrapper';
 
@@ -631,9 +1008,18 @@ IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_vecf16
 CREATE FUNCTION vector_normalize(svector) RETURNS svector
 IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_svecf32_normalize_wrapper';
 
+CREATE FUNCTION vector_normalize(veci8) RETURNS veci8
+IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_veci8_normalize_wrapper';
+
+CREATE FUNCTION vector_normalize(vecbf16) RETURNS vecbf16
+IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_vecbf16_normalize_wrapper';
+
 CREATE FUNCTION to_svector("dims" INT, "indexes" INT[], "values" real[]) RETURNS svector
 IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_to_svector_wrapper';
 
+CREATE FUNCTION to_veci8("len" INT, "alpha" real, "offset" real, "values" INT[]) RETURNS veci8
+IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_to_veci8_wrapper';
+
 CREATE FUNCTION binarize("vector" vector) RETURNS bvector
 IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_vectors_binarize_wrapper';
 
@@ -649,6 +1035,18 @@ IMMUTABLE PARALLEL SAFE LANGUAGE sql AS 'SELECT ROW($1, $2)';
 CREATE FUNCTION sphere(bvector, real) RETURNS sphere_bvector
 IMMUTABLE PARALLEL SAFE LANGUAGE sql AS 'SELECT ROW($1, $2)';
 
+CREATE FUNCTION sphere(veci8, real) RETURNS sphere_veci8
+IMMUTABLE PARALLEL SAFE LANGUAGE sql AS 'SELECT ROW($1, $2)';
+
+CREATE FUNCTION sphere(vecbf16, real) RETURNS sphere_vecbf16
+IMMUTABLE PARALLEL SAFE LANGUAGE sql AS 'SELECT ROW($1, $2)';
+
+CREATE FUNCTION weighted(vector, vector) RETURNS weighted_vector
+IMMUTABLE PARALLEL SAFE LANGUAGE sql AS 'SELECT ROW($1, $2)';
+
+CREATE FUNCTION weighted(vecf16, vector) RETURNS weighted_vecf16
+IMMUTABLE PARALLEL SAFE LANGUAGE sql AS 'SELECT ROW($1, $2)';
+
 -- List of aggregates
 
 CREATE AGGREGATE avg(vector) (
@@ -697,6 +1095,18 @@ CREATE CAST (vector AS vecf16)
 CREATE CAST (vecf16 AS vector)
     WITH FUNCTION _vectors_cast_vecf16_to_vecf32(vecf16, integer, boolean);
 
+CREATE CAST (vector AS vecbf16)
+    WITH FUNCTION _vectors_cast_vecf32_to_vecbf16(vector, integer, boolean);
+
+CREATE CAST (vecbf16 AS vector)
+    WITH FUNCTION _vectors_cast_vecbf16_to_vecf32(vecbf16, integer, boolean);
+
+CREATE CAST (vecf16 AS vecbf16)
+    WITH FUNCTION _vectors_cast_vecf16_to_vecbf16(vecf16, integer, boolean);
+
+CREATE CAST (vecbf16 AS vecf16)
+    WITH FUNCTION _vectors_cast_vecbf16_to_vecf16(vecbf16, integer, boolean);
+
 CREATE CAST (vector AS svector)
     WITH FUNCTION _vectors_cast_vecf32_to_svecf32(vector, integer, boolean);
 
@@ -709,6 +1119,12 @@ CREATE CAST (vector AS bvector)
 CREATE CAST (bvector AS vector)
     WITH FUNCTION _vectors_cast_bvector_to_vecf32(bvector, integer, boolean);
 
+CREATE CAST (vector AS veci8)
+    WITH FUNCTION _vectors_cast_vecf32_to_veci8(vector, integer, boolean);
+
+CREATE CAST (veci8 AS vector)
+    WITH FUNCTION _vectors_cast_veci8_to_vecf32(veci8, integer, boolean);
+
 -- List of access methods
 
 CREATE ACCESS METHOD vectors TYPE INDEX HANDLER _vectors_amhandler;
@@ -722,12 +1138,20 @@ CREATE OPERATOR FAMILY vector_dot_ops USING vectors;
 
 CREATE OPERATOR FAMILY vector_cos_ops USING vectors;
 
+CREATE OPERATOR FAMILY vector_l1_ops USING vectors;
+
+CREATE OPERATOR FAMILY vector_weighted_l2_ops USING vectors;
+
 CREATE OPERATOR FAMILY vecf16_l2_ops USING vectors;
 
 CREATE OPERATOR FAMILY vecf16_dot_ops USING vectors;
 
 CREATE OPERATOR FAMILY vecf16_cos_ops USING vectors;
 
+CREATE OPERATOR FAMILY vecf16_l1_ops USING vectors;
+
+CREATE OPERATOR FAMILY vecf16_weighted_l2_ops USING vectors;
+
 CREATE OPERATOR FAMILY svector_l2_ops USING vectors;
 
 CREATE OPERATOR FAMILY svector_dot_ops USING vectors;
@@ -740,6 +1164,24 @@ CREATE OPERATOR FAMILY bvector_dot_ops USING vectors;
 
 CREATE OPERATOR FAMILY bvector_jaccard_ops USING vectors;
 
+CREATE OPERATOR FAMILY veci8_l2_ops USING vectors;
+
+CREATE OPERATOR FAMILY veci8_dot_ops USING vectors;
+
+CREATE OPERATOR FAMILY veci8_cos_ops USING vectors;
+
+CREATE OPERATOR FAMILY vecbf16_l2_ops USING vectors;
+
+CREATE OPERATOR FAMILY vecbf16_dot_ops USING vectors;
+
+CREATE OPERATOR FAMILY vecbf16_cos_ops USING vectors;
+
+CREATE OPERATOR FAMILY vector_array_l2_ops USING vectors;
+
+CREATE OPERATOR FAMILY vector_array_dot_ops USING vectors;
+
+CREATE OPERATOR FAMILY vector_array_cos_ops USING vectors;
+
 -- List of operator classes
 
 CREATE OPERATOR CLASS vector_l2_ops
@@ -757,6 +1199,15 @@ CREATE OPERATOR CLASS vector_cos_ops
     OPERATOR 1 <=> (vector, vector) FOR ORDER BY float_ops,
     OPERATOR 2 <<=>> (vector, sphere_vector) FOR SEARCH;
 
+CREATE OPERATOR CLASS vector_l1_ops
+    FOR TYPE vector USING vectors FAMILY vector_l1_ops AS
+    OPERATOR 1 <+> (vector, vector) FOR ORDER BY float_ops,
+    OPERATOR 2 <<+>> (vector, sphere_vector) FOR SEARCH;
+
+CREATE OPERATOR CLASS vector_weighted_l2_ops
+    FOR TYPE vector USING vectors FAMILY vector_weighted_l2_ops AS
+    OPERATOR 1 <-> (vector, weighted_vector) FOR ORDER BY float_ops;
+
 CREATE OPERATOR CLASS vecf16_l2_ops
     FOR TYPE vecf16 USING vectors FAMILY vecf16_l2_ops AS
     OPERATOR 1 <-> (vecf16, vecf16) FOR ORDER BY float_ops,
@@ -772,6 +1223,15 @@ CREATE OPERATOR CLASS vecf16_cos_ops
     OPERATOR 1 <=> (vecf16, vecf16) FOR ORDER BY float_ops,
     OPERATOR 2 <<=>> (vecf16, sphere_vecf16) FOR SEARCH;
 
+CREATE OPERATOR CLASS vecf16_l1_ops
+    FOR TYPE vecf16 USING vectors FAMILY vecf16_l1_ops AS
+    OPERATOR 1 <+> (vecf16, vecf16) FOR ORDER BY float_ops,
+    OPERATOR 2 <<+>> (vecf16, sphere_vecf16) FOR SEARCH;
+
+CREATE OPERATOR CLASS vecf16_weighted_l2_ops
+    FOR TYPE vecf16 USING vectors FAMILY vecf16_weighted_l2_ops AS
+    OPERATOR 1 <-> (vecf16, weighted_vecf16) FOR ORDER BY float_ops;
+
 CREATE OPERATOR CLASS svector_l2_ops
     FOR TYPE svector USING vectors FAMILY svector_l2_ops AS
     OPERATOR 1 <-> (svector, svector) FOR ORDER BY float_ops,
@@ -802,6 +1262,48 @@ CREATE OPERATOR CLASS bvector_jaccard_ops
     OPERATOR 1 <~> (bvector, bvector) FOR ORDER BY float_ops,
     OPERATOR 2 <<~>> (bvector, sphere_bvector) FOR SEARCH;
 
+CREATE OPERATOR CLASS veci8_l2_ops
+    FOR TYPE veci8 USING vectors FAMILY veci8_l2_ops AS
+    OPERATOR 1 <-> (veci8, veci8) FOR ORDER BY float_ops,
+    OPERATOR 2 <<->> (veci8, sphere_veci8) FOR SEARCH;
+
+CREATE OPERATOR CLASS veci8_dot_ops
+    FOR TYPE veci8 USING vectors FAMILY veci8_dot_ops AS
+    OPERATOR 1 <#> (veci8, veci8) FOR ORDER BY float_ops,
+    OPERATOR 2 <<#>> (veci8, sphere_veci8) FOR SEARCH;
+
+CREATE OPERATOR CLASS veci8_cos_ops
+    FOR TYPE veci8 USING vectors FAMILY veci8_cos_ops AS
+    OPERATOR 1 <=> (veci8, veci8) FOR ORDER BY float_ops,
+    OPERATOR 2 <<=>> (veci8, sphere_veci8) FOR SEARCH;
+
+CREATE OPERATOR CLASS vecbf16_l2_ops
+    FOR TYPE vecbf16 USING vectors FAMILY vecbf16_l2_ops AS
+    OPERATOR 1 <-> (vecbf16, vecbf16) FOR ORDER BY float_ops,
+    OPERATOR 2 <<->> (vecbf16, sphere_vecbf16) FOR SEARCH;
+
+CREATE OPERATOR CLASS vecbf16_dot_ops
+    FOR TYPE vecbf16 USING vectors FAMILY vecbf16_dot_ops AS
+    OPERATOR 1 <#> (vecbf16, vecbf16) FOR ORDER BY float_ops,
+    OPERATOR 2 <<#>> (vecbf16, sphere_vecbf16) FOR SEARCH;
+
+CREATE OPERATOR CLASS vecbf16_cos_ops
+    FOR TYPE vecbf16 USING vectors FAMILY vecbf16_cos_ops AS
+    OPERATOR 1 <=> (vecbf16, vecbf16) FOR ORDER BY float_ops,
+    OPERATOR 2 <<=>> (vecbf16, sphere_vecbf16) FOR SEARCH;
+
+CREATE OPERATOR CLASS vector_array_l2_ops
+    FOR TYPE vector[] USING vectors FAMILY vector_array_l2_ops AS
+    OPERATOR 1 <-> (vector[], vector[]) FOR ORDER BY float_ops;
+
+CREATE OPERATOR CLASS vector_array_dot_ops
+    FOR TYPE vector[] USING vectors FAMILY vector_array_dot_ops AS
+    OPERATOR 1 <#> (vector[], vector[]) FOR ORDER BY float_ops;
+
+CREATE OPERATOR CLASS vector_array_cos_ops
+    FOR TYPE vector[] USING vectors FAMILY vector_array_cos_ops AS
+    OPERATOR 1 <=> (vector[], vector[]) FOR ORDER BY float_ops;
+
 -- List of views
 
 CREATE VIEW pg_vector_index_stat AS